@@ -0,0 +1,29 @@
+#![cfg(all(test, feature = "std", feature = "tokio"))]
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+fn noop_raw_waker() -> RawWaker {
+    fn clone(_: *const ()) -> RawWaker {
+        noop_raw_waker()
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    RawWaker::new(::std::ptr::null(), &VTABLE)
+}
+
+// Polls `f` until it is ready, without a runtime
+pub fn poll_until_ready<T, F: FnMut(&mut Context) -> Poll<T>>(mut f: F) -> T {
+    let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(t) = f(&mut cx) {
+            return t;
+        }
+    }
+}
+
+// Runs the future `f` to completion
+pub fn block_on<F: Future + Unpin>(mut f: F) -> F::Output {
+    poll_until_ready(|cx| Pin::new(&mut f).poll(cx))
+}
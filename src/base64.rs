@@ -1,12 +1,26 @@
 //! Libsodium Base64 encoding/decoding helper functions
+//!
+//! Unlike most Base64 codecs, these functions run in constant time for a
+//! given length, so they can be used to encode and decode keys and tokens.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::base64::{self, Variant};
+//!
+//! let token = [0xfb, 0xff, 0x00];
+//! let encoded = base64::encode(&token, Variant::UrlSafeNoPadding);
+//! assert_eq!(encoded, "-_8A");
+//! assert_eq!(base64::decode(&encoded, Variant::UrlSafeNoPadding), Ok(token.to_vec()));
+//! ```
 use ffi;
 #[cfg(not(feature = "std"))]
 use prelude::*;
 use std::ptr;
+use Error;
 
 /// Supported variants of Base64 encoding/decoding
 #[repr(u32)]
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum Variant {
     /// Base64 as defined in RFC 4648 §4
     Original = ffi::sodium_base64_VARIANT_ORIGINAL,
@@ -43,11 +57,11 @@ pub fn encode<T: AsRef<[u8]>>(bin: T, variant: Variant) -> String {
 
 /// Decodes a Base64 string into a byte sequence using the given variant.
 ///
-/// Fails if the decoded length overflows
+/// Fails with `Error::InvalidEncoding` if the decoded length overflows
 /// or if `b64` contains invalid characters.
-pub fn decode<T: AsRef<[u8]>>(b64: T, variant: Variant) -> Result<Vec<u8>, ()> {
+pub fn decode<T: AsRef<[u8]>>(b64: T, variant: Variant) -> Result<Vec<u8>, Error> {
     let b64 = b64.as_ref();
-    let mut bin = vec![0; decoded_len(b64.len()).ok_or(())?];
+    let mut bin = vec![0; decoded_len(b64.len()).ok_or(Error::InvalidEncoding)?];
     let mut bin_len = 0;
 
     // SAFETY: `decoded_len` ensures space for 3 bytes
@@ -67,7 +81,50 @@ pub fn decode<T: AsRef<[u8]>>(b64: T, variant: Variant) -> Result<Vec<u8>, ()> {
             variant as _,
         );
         if rc != 0 {
-            return Err(());
+            return Err(Error::InvalidEncoding);
+        }
+        bin.truncate(bin_len);
+        Ok(bin)
+    }
+}
+
+/// Decodes a Base64 string into a byte sequence using the given variant,
+/// skipping any of the characters in `ignore`, e.g. `" \r\n"` for wrapped
+/// output.
+///
+/// Fails like `decode()`, and with `Error::InvalidParameter` if `ignore`
+/// contains a nul byte.
+pub fn decode_ignore<T: AsRef<[u8]>, I: AsRef<[u8]>>(
+    b64: T,
+    ignore: I,
+    variant: Variant,
+) -> Result<Vec<u8>, Error> {
+    let b64 = b64.as_ref();
+    let ignore = ignore.as_ref();
+    if ignore.contains(&0) {
+        return Err(Error::InvalidParameter);
+    }
+    let mut ignore_nul = Vec::with_capacity(ignore.len() + 1);
+    ignore_nul.extend_from_slice(ignore);
+    ignore_nul.push(0);
+
+    let mut bin = vec![0; decoded_len(b64.len()).ok_or(Error::InvalidEncoding)?];
+    let mut bin_len = 0;
+
+    // SAFETY: as for `decode()`; in addition `ignore_nul` is nul-terminated.
+    unsafe {
+        let rc = ffi::sodium_base642bin(
+            bin.as_mut_ptr(),
+            bin.len(),
+            b64.as_ptr() as *const _,
+            b64.len(),
+            ignore_nul.as_ptr() as *const _,
+            &mut bin_len,
+            ptr::null_mut(),
+            variant as _,
+        );
+        if rc != 0 {
+            return Err(Error::InvalidEncoding);
         }
         bin.truncate(bin_len);
         Ok(bin)
@@ -112,4 +169,70 @@ mod tests {
             decode("Zm9vYmFy", Variant::Original)
         );
     }
+
+    #[test]
+    fn test_variants() {
+        let bin = [0xfb, 0xff];
+        let cases = [
+            (Variant::Original, "+/8="),
+            (Variant::OriginalNoPadding, "+/8"),
+            (Variant::UrlSafe, "-_8="),
+            (Variant::UrlSafeNoPadding, "-_8"),
+        ];
+        for &(variant, b64) in cases.iter() {
+            assert_eq!(b64.to_string(), encode(bin, variant));
+            assert_eq!(Ok(bin.to_vec()), decode(b64, variant));
+        }
+
+        // each variant rejects the alphabet or padding of the others
+        assert_eq!(
+            Err(Error::InvalidEncoding),
+            decode("-_8=", Variant::Original)
+        );
+        assert_eq!(
+            Err(Error::InvalidEncoding),
+            decode("+/8=", Variant::UrlSafe)
+        );
+        assert_eq!(
+            Err(Error::InvalidEncoding),
+            decode("+/8", Variant::Original)
+        );
+        assert_eq!(
+            Err(Error::InvalidEncoding),
+            decode("+/8=", Variant::OriginalNoPadding)
+        );
+    }
+
+    #[test]
+    fn test_decode_ignore() {
+        assert_eq!(
+            Ok(b"foobar".to_vec()),
+            decode_ignore("Zm9v\r\nYmFy\r\n", "\r\n", Variant::Original)
+        );
+        assert_eq!(
+            Err(Error::InvalidEncoding),
+            decode_ignore("Zm9v\nYmFy", "", Variant::Original)
+        );
+        assert_eq!(
+            Err(Error::InvalidParameter),
+            decode_ignore("Zm9vYmFy", "\0", Variant::Original)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        use randombytes::randombytes;
+        let variants = [
+            Variant::Original,
+            Variant::OriginalNoPadding,
+            Variant::UrlSafe,
+            Variant::UrlSafeNoPadding,
+        ];
+        for i in 0..64usize {
+            let bin = randombytes(i);
+            for &variant in variants.iter() {
+                assert_eq!(Ok(bin.clone()), decode(encode(&bin, variant), variant));
+            }
+        }
+    }
 }
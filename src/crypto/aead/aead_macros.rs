@@ -5,12 +5,14 @@ macro_rules! aead_module (($seal_name:ident,
                            $keybytes:expr,
                            $noncebytes:expr,
                            $tagbytes:expr,
+                           $messagebytes_max:ident,
                            $test_gate:expr) => (
 
 #[cfg(not(feature = "std"))] use prelude::*;
 use libc::c_ulonglong;
 use std::ptr;
 use randombytes::randombytes_into;
+use Error;
 
 /// Number of bytes in a `Key`.
 pub const KEYBYTES: usize = $keybytes;
@@ -21,6 +23,12 @@ pub const NONCEBYTES: usize = $noncebytes;
 /// Number of bytes in an authentication `Tag`.
 pub const TAGBYTES: usize = $tagbytes;
 
+/// Returns the maximum length of a message, which depends on the platform and
+/// can be smaller than what fits in memory.
+pub fn messagebytes_max() -> usize {
+    unsafe { $messagebytes_max() }
+}
+
 new_type! {
     /// `Key` for symmetric authenticated encryption with additional data.
     ///
@@ -53,7 +61,13 @@ pub fn gen_key() -> Key {
 
 /// `seal()` encrypts and authenticates a message `m` together with optional plaintext data `ad`
 /// using a secret key `k` and a nonce `n`. It returns a ciphertext `c`.
+///
+/// # Panics
+///
+/// Panics if `m` is longer than `messagebytes_max()`, as libsodium would
+/// otherwise abort the process.
 pub fn seal(m: &[u8], ad: Option<&[u8]>, n: &Nonce, k: &Key) -> Vec<u8> {
+    assert!(m.len() <= messagebytes_max(), "message too long");
     let (ad_p, ad_len) = ad.map(|ad| (ad.as_ptr(), ad.len() as c_ulonglong)).unwrap_or((ptr::null(), 0));
     let mut c = Vec::with_capacity(m.len() + TAGBYTES);
     let mut clen = c.len() as c_ulonglong;
@@ -79,7 +93,12 @@ pub fn seal(m: &[u8], ad: Option<&[u8]>, n: &Nonce, k: &Key) -> Vec<u8> {
 /// `ad` using a secret key `k` and a nonce `n`.
 /// `m` is encrypted in place, so after this function returns it will contain the ciphertext.
 /// The detached authentication tag is returned by value.
+///
+/// # Panics
+///
+/// Panics if `m` is longer than `messagebytes_max()`.
 pub fn seal_detached(m: &mut [u8], ad: Option<&[u8]>, n: &Nonce, k: &Key) -> Tag {
+    assert!(m.len() <= messagebytes_max(), "message too long");
     let (ad_p, ad_len) = ad.map(|ad| (ad.as_ptr(), ad.len() as c_ulonglong)).unwrap_or((ptr::null(), 0));
     let mut tag = Tag([0u8; TAGBYTES]);
     let mut maclen = TAGBYTES as c_ulonglong;
@@ -103,10 +122,16 @@ pub fn seal_detached(m: &mut [u8], ad: Option<&[u8]>, n: &Nonce, k: &Key) -> Tag
 /// `open()` verifies and decrypts a ciphertext `c` together with optional plaintext data `ad`
 /// using a secret key `k` and a nonce `n`.
 /// It returns a plaintext `Ok(m)`.
-/// If the ciphertext fails verification, `open()` returns `Err(())`.
-pub fn open(c: &[u8], ad: Option<&[u8]>, n: &Nonce, k: &Key) -> Result<Vec<u8>, ()> {
+/// If the ciphertext fails verification, `open()` returns
+/// `Err(Error::VerificationFailed)`, or `Err(Error::InvalidLength)` if it is
+/// too short to hold an authentication tag. If the plaintext would be longer
+/// than `messagebytes_max()`, it returns `Err(Error::MessageTooLong)`.
+pub fn open(c: &[u8], ad: Option<&[u8]>, n: &Nonce, k: &Key) -> Result<Vec<u8>, Error> {
     if c.len() < TAGBYTES {
-        return Err(());
+        return Err(Error::InvalidLength);
+    }
+    if c.len() - TAGBYTES > messagebytes_max() {
+        return Err(Error::MessageTooLong);
     }
     let (ad_p, ad_len) = ad.map(|ad| (ad.as_ptr(), ad.len() as c_ulonglong)).unwrap_or((ptr::null(), 0));
     let mut m = Vec::with_capacity(c.len() - TAGBYTES);
@@ -126,7 +151,7 @@ pub fn open(c: &[u8], ad: Option<&[u8]>, n: &Nonce, k: &Key) -> Result<Vec<u8>,
                 k.0.as_ptr()
             );
         if ret != 0 {
-            return Err(());
+            return Err(Error::VerificationFailed);
         }
         m.set_len(mlen as usize);
     }
@@ -135,9 +160,13 @@ pub fn open(c: &[u8], ad: Option<&[u8]>, n: &Nonce, k: &Key) -> Result<Vec<u8>,
 /// `open_detached()` verifies and decrypts a ciphertext `c` toghether with optional plaintext data
 /// `ad` and and authentication tag `tag`, using a secret key `k` and a nonce `n`.
 /// `c` is decrypted in place, so if this function is successful it will contain the plaintext.
-/// If the ciphertext fails verification, `open_detached()` returns `Err(())`,
-/// and the ciphertext is not modified.
-pub fn open_detached(c: &mut [u8], ad: Option<&[u8]>, t: &Tag, n: &Nonce, k: &Key) -> Result<(), ()> {
+/// If the ciphertext fails verification, `open_detached()` returns `Err(Error::VerificationFailed)`,
+/// and the ciphertext is not modified. If `c` is longer than `messagebytes_max()`, it returns
+/// `Err(Error::MessageTooLong)`.
+pub fn open_detached(c: &mut [u8], ad: Option<&[u8]>, t: &Tag, n: &Nonce, k: &Key) -> Result<(), Error> {
+    if c.len() > messagebytes_max() {
+        return Err(Error::MessageTooLong);
+    }
     let (ad_p, ad_len) = ad.map(|ad| (ad.as_ptr(), ad.len() as c_ulonglong)).unwrap_or((ptr::null(), 0));
     let ret = unsafe {
         $open_detached_name(
@@ -155,7 +184,7 @@ pub fn open_detached(c: &mut [u8], ad: Option<&[u8]>, t: &Tag, n: &Nonce, k: &Ke
     if ret == 0 {
         Ok(())
     } else {
-        Err(())
+        Err(Error::VerificationFailed)
     }
 }
 
@@ -286,3 +315,133 @@ mod test_m {
 }
 
 ));
+
+/// Implements the traits of the RustCrypto `aead` crate on a `$cipher` type
+/// holding a `Key`, for modules defined with `aead_module!`.
+macro_rules! aead_traits (($cipher:ident, $nonce_size:ident) => (
+
+/// A cipher holding a `Key`, which implements the `KeyInit`, `AeadCore` and
+/// `AeadInPlace` traits of the RustCrypto
+/// [aead](https://crates.io/crates/aead) crate, and through them `Aead`, so it
+/// can be used by code that is generic over AEAD ciphers.
+///
+/// Additional data is always authenticated, an empty `associated_data` is the
+/// same as passing `None` to `seal()`.
+#[cfg(feature = "aead-traits")]
+#[derive(Clone, Debug)]
+pub struct $cipher(Key);
+
+#[cfg(feature = "aead-traits")]
+impl From<Key> for $cipher {
+    fn from(k: Key) -> $cipher {
+        $cipher(k)
+    }
+}
+
+#[cfg(feature = "aead-traits")]
+impl ::aead_traits::KeySizeUser for $cipher {
+    type KeySize = ::aead_traits::consts::U32;
+}
+
+#[cfg(feature = "aead-traits")]
+impl ::aead_traits::KeyInit for $cipher {
+    fn new(key: &::aead_traits::Key<Self>) -> $cipher {
+        let mut k = Key([0; KEYBYTES]);
+        k.0.copy_from_slice(key);
+        $cipher(k)
+    }
+}
+
+#[cfg(feature = "aead-traits")]
+impl ::aead_traits::AeadCore for $cipher {
+    type NonceSize = ::aead_traits::consts::$nonce_size;
+    type TagSize = ::aead_traits::consts::U16;
+    type CiphertextOverhead = ::aead_traits::consts::U0;
+}
+
+#[cfg(feature = "aead-traits")]
+impl ::aead_traits::AeadInPlace for $cipher {
+    fn encrypt_in_place_detached(
+        &self,
+        nonce: &::aead_traits::Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<::aead_traits::Tag<Self>, ::aead_traits::Error> {
+        if buffer.len() > messagebytes_max() {
+            return Err(::aead_traits::Error);
+        }
+        let mut n = Nonce([0; NONCEBYTES]);
+        n.0.copy_from_slice(nonce);
+        let tag = seal_detached(buffer, Some(associated_data), &n, &self.0);
+        Ok(::aead_traits::Tag::<Self>::clone_from_slice(&tag.0))
+    }
+
+    fn decrypt_in_place_detached(
+        &self,
+        nonce: &::aead_traits::Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+        tag: &::aead_traits::Tag<Self>,
+    ) -> Result<(), ::aead_traits::Error> {
+        let mut n = Nonce([0; NONCEBYTES]);
+        n.0.copy_from_slice(nonce);
+        let mut t = Tag([0; TAGBYTES]);
+        t.0.copy_from_slice(tag);
+        open_detached(buffer, Some(associated_data), &t, &n, &self.0)
+            .map_err(|_| ::aead_traits::Error)
+    }
+}
+
+#[cfg(feature = "aead-traits")]
+#[cfg(test)]
+mod test_aead_traits {
+    use super::*;
+    use aead_traits::{Aead, AeadInPlace, KeyInit, Payload};
+    use crypto::nonce::gen_random_nonce;
+    use randombytes::randombytes;
+
+    #[test]
+    fn test_aead() {
+        for i in 0..64usize {
+            let k = gen_key();
+            let n = gen_random_nonce::<Nonce>();
+            let m = randombytes(i);
+            let ad = randombytes(i % 5);
+            let cipher = $cipher::new_from_slice(&k.0).unwrap();
+            let nonce = ::aead_traits::Nonce::<$cipher>::from_slice(&n.0);
+
+            let c = cipher.encrypt(nonce, Payload { msg: &m, aad: &ad }).unwrap();
+            assert_eq!(c, seal(&m, Some(&ad), &n, &k));
+            let m2 = cipher.decrypt(nonce, Payload { msg: &c, aad: &ad }).unwrap();
+            assert_eq!(m2, m);
+            let mut bad_ad = ad.clone();
+            bad_ad.push(0);
+            assert!(cipher.decrypt(nonce, Payload { msg: &c, aad: &bad_ad }).is_err());
+        }
+    }
+
+    #[test]
+    fn test_aead_in_place() {
+        let k = gen_key();
+        let n = gen_random_nonce::<Nonce>();
+        let cipher = $cipher::from(k.clone());
+        let nonce = ::aead_traits::Nonce::<$cipher>::from_slice(&n.0);
+
+        let mut buf = b"some data".to_vec();
+        let tag = cipher.encrypt_in_place_detached(nonce, b"ad", &mut buf).unwrap();
+        let mut c = buf.clone();
+        c.extend_from_slice(&tag);
+        assert_eq!(c, seal(b"some data", Some(b"ad"), &n, &k));
+
+        cipher.decrypt_in_place(nonce, b"ad", &mut c).unwrap();
+        assert_eq!(c, b"some data");
+
+        let mut bad_tag = tag;
+        bad_tag[0] ^= 1;
+        assert!(cipher
+            .decrypt_in_place_detached(nonce, b"ad", &mut buf, &bad_tag)
+            .is_err());
+    }
+}
+
+));
@@ -19,15 +19,15 @@ mod aes_impl {
         crypto_aead_aes256gcm_ABYTES, crypto_aead_aes256gcm_KEYBYTES,
         crypto_aead_aes256gcm_NPUBBYTES, crypto_aead_aes256gcm_decrypt,
         crypto_aead_aes256gcm_decrypt_detached, crypto_aead_aes256gcm_encrypt,
-        crypto_aead_aes256gcm_encrypt_detached,
+        crypto_aead_aes256gcm_encrypt_detached, crypto_aead_aes256gcm_messagebytes_max,
     };
 
     /// `is_available` returns true if the current CPU supports aes256gcm and false otherwise.
     ///
-    /// WARNING: You must call [init](crate::init) before calling this function; if you do not
-    /// `is_available` will always return false even if the runtime supports aes256gcm.
+    /// CPU features are only detected by [init](crate::init), so `is_available` calls it
+    /// first. It returns false if initialization fails.
     pub fn is_available() -> bool {
-        unsafe { ffi::crypto_aead_aes256gcm_is_available() == 1 }
+        crate::init().is_ok() && unsafe { ffi::crypto_aead_aes256gcm_is_available() == 1 }
     }
 
     aead_module!(
@@ -38,7 +38,8 @@ mod aes_impl {
         crypto_aead_aes256gcm_KEYBYTES as usize,
         crypto_aead_aes256gcm_NPUBBYTES as usize,
         crypto_aead_aes256gcm_ABYTES as usize,
-        crate::init().is_ok() && is_available()
+        crypto_aead_aes256gcm_messagebytes_max,
+        is_available()
     );
 
     #[cfg(test)]
@@ -80,8 +81,12 @@ mod aes_impl {
 mod aes_api {
     use super::aes_impl;
     use crypto::nonce::gen_random_nonce;
+    use ffi;
+    use libc::c_ulonglong;
     #[cfg(not(feature = "std"))]
     use prelude::Vec;
+    use std::{fmt, mem, ptr};
+    use Error;
 
     /// The Aes256Gcm struct encapsulates the crypto_aead_aes256gcm_* family of
     /// functions in a way that ensures safe usage of the API at runtime
@@ -91,16 +96,17 @@ mod aes_api {
 
     impl Aes256Gcm {
         /// Returns an `Ok` of [Aes256Gcm](self::Aes256Gcm) if the runtime
-        /// supports AES and an `Err(_)` if it does not.
+        /// supports AES and an `Err(Error::Unsupported)` if it does not, in which case callers
+        /// should fall back to another AEAD construction such as
+        /// [xchacha20poly1305_ietf](crate::crypto::aead::xchacha20poly1305_ietf).
         ///
-        /// You must call [init](crate::init) before calling this function. Failure
-        /// to do so will result in `Err(_)` being returned even if the runtime
-        /// hardware supports AES.
-        pub fn new() -> Result<Self, ()> {
-            if unsafe { ffi::crypto_aead_aes256gcm_is_available() } == 1 {
+        /// Like [is_available](aes_impl::is_available) this calls [init](crate::init)
+        /// to detect the CPU features.
+        pub fn new() -> Result<Self, Error> {
+            if aes_impl::is_available() {
                 Ok(Self)
             } else {
-                Err(())
+                Err(Error::Unsupported)
             }
         }
 
@@ -131,21 +137,21 @@ mod aes_api {
         /// `open()` verifies and decrypts a ciphertext `c` together with optional plaintext data `ad`
         /// using a secret key `k` and a nonce `n`.
         /// It returns a plaintext `Ok(m)`.
-        /// If the ciphertext fails verification, `open()` returns `Err(())`.
+        /// If the ciphertext fails verification, `open()` returns `Err(Error::VerificationFailed)`.
         pub fn open(
             &self,
             c: &[u8],
             ad: Option<&[u8]>,
             n: &aes_impl::Nonce,
             k: &aes_impl::Key,
-        ) -> Result<Vec<u8>, ()> {
+        ) -> Result<Vec<u8>, Error> {
             aes_impl::open(c, ad, n, k)
         }
 
         /// `open_detached()` verifies and decrypts a ciphertext `c` toghether with optional plaintext data
         /// `ad` and and authentication tag `tag`, using a secret key `k` and a nonce `n`.
         /// `c` is decrypted in place, so if this function is successful it will contain the plaintext.
-        /// If the ciphertext fails verification, `open_detached()` returns `Err(())`,
+        /// If the ciphertext fails verification, `open_detached()` returns `Err(Error::VerificationFailed)`,
         /// and the ciphertext is not modified.
         pub fn open_detached(
             &self,
@@ -154,7 +160,7 @@ mod aes_api {
             t: &aes_impl::Tag,
             n: &aes_impl::Nonce,
             k: &aes_impl::Key,
-        ) -> Result<(), ()> {
+        ) -> Result<(), Error> {
             aes_impl::open_detached(c, ad, t, n, k)
         }
 
@@ -183,6 +189,192 @@ mod aes_api {
         ) -> aes_impl::Tag {
             aes_impl::seal_detached(m, ad, n, k)
         }
+
+        /// `precompute()` expands the key `k` once into a
+        /// [PrecomputedKey](self::PrecomputedKey), which encrypts and decrypts faster than
+        /// passing `k` to every call when many messages use the same key.
+        pub fn precompute(&self, k: &aes_impl::Key) -> PrecomputedKey {
+            // the state is opaque and may contain bytes beforenm does not write
+            let mut state = mem::MaybeUninit::<ffi::crypto_aead_aes256gcm_state>::zeroed();
+            let state = unsafe {
+                ffi::crypto_aead_aes256gcm_beforenm(state.as_mut_ptr(), k.0.as_ptr());
+                state.assume_init()
+            };
+            PrecomputedKey(state)
+        }
+    }
+
+    fn ad_ptr(ad: Option<&[u8]>) -> (*const u8, c_ulonglong) {
+        ad.map(|ad| (ad.as_ptr(), ad.len() as c_ulonglong))
+            .unwrap_or((ptr::null(), 0))
+    }
+
+    /// `PrecomputedKey` holds an expanded AES256-GCM key, created with
+    /// [Aes256Gcm::precompute](self::Aes256Gcm::precompute). Its methods use
+    /// libsodium's `_afternm` functions and produce the same ciphertexts as the
+    /// corresponding methods of [Aes256Gcm](self::Aes256Gcm).
+    ///
+    /// When a `PrecomputedKey` goes out of scope its contents will be zeroed out.
+    pub struct PrecomputedKey(ffi::crypto_aead_aes256gcm_state);
+
+    impl PrecomputedKey {
+        /// `seal()` encrypts and authenticates a message `m` together with optional plaintext data `ad`
+        /// using the precomputed key and a nonce `n`. It returns a ciphertext `c`.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `m` is longer than `messagebytes_max()`.
+        pub fn seal(&self, m: &[u8], ad: Option<&[u8]>, n: &aes_impl::Nonce) -> Vec<u8> {
+            assert!(m.len() <= aes_impl::messagebytes_max(), "message too long");
+            let (ad_p, ad_len) = ad_ptr(ad);
+            let mut c = Vec::with_capacity(m.len() + aes_impl::TAGBYTES);
+            let mut clen = c.len() as c_ulonglong;
+            unsafe {
+                ffi::crypto_aead_aes256gcm_encrypt_afternm(
+                    c.as_mut_ptr(),
+                    &mut clen,
+                    m.as_ptr(),
+                    m.len() as c_ulonglong,
+                    ad_p,
+                    ad_len,
+                    ptr::null(),
+                    n.0.as_ptr(),
+                    &self.0,
+                );
+                c.set_len(clen as usize);
+            }
+            c
+        }
+
+        /// `seal_detached()` encrypts and authenticates a message `m` together with optional
+        /// plaintext data `ad` using the precomputed key and a nonce `n`.
+        /// `m` is encrypted in place, and the detached authentication tag is returned by value.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `m` is longer than `messagebytes_max()`.
+        pub fn seal_detached(
+            &self,
+            m: &mut [u8],
+            ad: Option<&[u8]>,
+            n: &aes_impl::Nonce,
+        ) -> aes_impl::Tag {
+            assert!(m.len() <= aes_impl::messagebytes_max(), "message too long");
+            let (ad_p, ad_len) = ad_ptr(ad);
+            let mut tag = aes_impl::Tag([0u8; aes_impl::TAGBYTES]);
+            let mut maclen = aes_impl::TAGBYTES as c_ulonglong;
+            unsafe {
+                ffi::crypto_aead_aes256gcm_encrypt_detached_afternm(
+                    m.as_mut_ptr(),
+                    tag.0.as_mut_ptr(),
+                    &mut maclen,
+                    m.as_ptr(),
+                    m.len() as c_ulonglong,
+                    ad_p,
+                    ad_len,
+                    ptr::null(),
+                    n.0.as_ptr(),
+                    &self.0,
+                );
+            }
+            tag
+        }
+
+        /// `open()` verifies and decrypts a ciphertext `c` together with optional plaintext data
+        /// `ad` using the precomputed key and a nonce `n`. It returns a plaintext `Ok(m)`.
+        /// If the ciphertext fails verification, `open()` returns
+        /// `Err(Error::VerificationFailed)`, or `Err(Error::InvalidLength)` if it is too short
+        /// to hold an authentication tag. If the plaintext would be longer than
+        /// `messagebytes_max()`, it returns `Err(Error::MessageTooLong)`.
+        pub fn open(
+            &self,
+            c: &[u8],
+            ad: Option<&[u8]>,
+            n: &aes_impl::Nonce,
+        ) -> Result<Vec<u8>, Error> {
+            if c.len() < aes_impl::TAGBYTES {
+                return Err(Error::InvalidLength);
+            }
+            if c.len() - aes_impl::TAGBYTES > aes_impl::messagebytes_max() {
+                return Err(Error::MessageTooLong);
+            }
+            let (ad_p, ad_len) = ad_ptr(ad);
+            let mut m = Vec::with_capacity(c.len() - aes_impl::TAGBYTES);
+            let mut mlen = m.len() as c_ulonglong;
+            unsafe {
+                let ret = ffi::crypto_aead_aes256gcm_decrypt_afternm(
+                    m.as_mut_ptr(),
+                    &mut mlen,
+                    ptr::null_mut(),
+                    c.as_ptr(),
+                    c.len() as c_ulonglong,
+                    ad_p,
+                    ad_len,
+                    n.0.as_ptr(),
+                    &self.0,
+                );
+                if ret != 0 {
+                    return Err(Error::VerificationFailed);
+                }
+                m.set_len(mlen as usize);
+            }
+            Ok(m)
+        }
+
+        /// `open_detached()` verifies and decrypts a ciphertext `c` together with optional
+        /// plaintext data `ad` and an authentication tag `t`, using the precomputed key and
+        /// a nonce `n`. `c` is decrypted in place. If the ciphertext fails verification,
+        /// `open_detached()` returns `Err(Error::VerificationFailed)`, and the ciphertext is
+        /// not modified. If `c` is longer than `messagebytes_max()`, it returns
+        /// `Err(Error::MessageTooLong)`.
+        pub fn open_detached(
+            &self,
+            c: &mut [u8],
+            ad: Option<&[u8]>,
+            t: &aes_impl::Tag,
+            n: &aes_impl::Nonce,
+        ) -> Result<(), Error> {
+            if c.len() > aes_impl::messagebytes_max() {
+                return Err(Error::MessageTooLong);
+            }
+            let (ad_p, ad_len) = ad_ptr(ad);
+            let ret = unsafe {
+                ffi::crypto_aead_aes256gcm_decrypt_detached_afternm(
+                    c.as_mut_ptr(),
+                    ptr::null_mut(),
+                    c.as_ptr(),
+                    c.len() as c_ulonglong,
+                    t.0.as_ptr(),
+                    ad_p,
+                    ad_len,
+                    n.0.as_ptr(),
+                    &self.0,
+                )
+            };
+            if ret == 0 {
+                Ok(())
+            } else {
+                Err(Error::VerificationFailed)
+            }
+        }
+    }
+
+    impl Drop for PrecomputedKey {
+        fn drop(&mut self) {
+            unsafe {
+                ffi::sodium_memzero(
+                    &mut self.0 as *mut ffi::crypto_aead_aes256gcm_state as *mut _,
+                    ffi::crypto_aead_aes256gcm_statebytes(),
+                );
+            }
+        }
+    }
+
+    impl fmt::Debug for PrecomputedKey {
+        fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            // Hide the expanded key from debug output.
+            write!(formatter, "PrecomputedKey(****)")
+        }
     }
 
     #[cfg(test)]
@@ -193,10 +385,10 @@ mod aes_api {
         #[cfg(feature = "std")]
         #[test]
         fn test_is_available() {
-            init().unwrap();
             let is_available_feat_check =
                 is_x86_feature_detected!("aes") && is_x86_feature_detected!("pclmulqdq");
             assert_eq!(aes_impl::is_available(), is_available_feat_check);
+            assert_eq!(Aes256Gcm::new().is_ok(), is_available_feat_check);
         }
 
         #[test]
@@ -231,8 +423,52 @@ mod aes_api {
                 assert_eq!(m, m2);
             }
         }
+
+        #[test]
+        fn test_precomputed_key() {
+            init().unwrap();
+            use randombytes::randombytes;
+            let aes = Aes256Gcm::new().unwrap();
+            for i in 0..256usize {
+                let k = aes.gen_key();
+                let pk = aes.precompute(&k);
+                let n = gen_random_nonce();
+                let ad = randombytes(i);
+                let m = randombytes(i);
+
+                let c = pk.seal(&m, Some(&ad), &n);
+                assert_eq!(c, aes.seal(&m, Some(&ad), &n, &k));
+                assert_eq!(pk.open(&c, Some(&ad), &n), Ok(m.clone()));
+                assert_eq!(aes.open(&c, Some(&ad), &n, &k), Ok(m.clone()));
+
+                let mut buf = m.clone();
+                let t = pk.seal_detached(&mut buf, Some(&ad), &n);
+                assert_eq!(&buf[..], &c[..m.len()]);
+                pk.open_detached(&mut buf, Some(&ad), &t, &n).unwrap();
+                assert_eq!(buf, m);
+            }
+        }
+
+        #[test]
+        fn test_precomputed_key_tamper() {
+            init().unwrap();
+            let aes = Aes256Gcm::new().unwrap();
+            let pk = aes.precompute(&aes.gen_key());
+            let other = aes.precompute(&aes.gen_key());
+            let n = gen_random_nonce();
+            let mut c = pk.seal(b"some data", None, &n);
+            assert_eq!(other.open(&c, None, &n), Err(Error::VerificationFailed));
+            assert_eq!(pk.open(&c, Some(b"ad"), &n), Err(Error::VerificationFailed));
+            c[0] ^= 1;
+            assert_eq!(pk.open(&c, None, &n), Err(Error::VerificationFailed));
+            assert_eq!(
+                pk.open(&c[..aes_impl::TAGBYTES - 1], None, &n),
+                Err(Error::InvalidLength)
+            );
+            assert_eq!(format!("{:?}", pk), "PrecomputedKey(****)");
+        }
     }
 }
 
-pub use self::aes_api::Aes256Gcm;
+pub use self::aes_api::{Aes256Gcm, PrecomputedKey};
 pub use self::aes_impl::{is_available, Key, Nonce, Tag, KEYBYTES, NONCEBYTES, TAGBYTES};
@@ -6,7 +6,7 @@ use ffi::{
     crypto_aead_chacha20poly1305_ABYTES, crypto_aead_chacha20poly1305_KEYBYTES,
     crypto_aead_chacha20poly1305_NPUBBYTES, crypto_aead_chacha20poly1305_decrypt,
     crypto_aead_chacha20poly1305_decrypt_detached, crypto_aead_chacha20poly1305_encrypt,
-    crypto_aead_chacha20poly1305_encrypt_detached,
+    crypto_aead_chacha20poly1305_encrypt_detached, crypto_aead_chacha20poly1305_messagebytes_max,
 };
 aead_module!(
     crypto_aead_chacha20poly1305_encrypt,
@@ -16,9 +16,12 @@ aead_module!(
     crypto_aead_chacha20poly1305_KEYBYTES as usize,
     crypto_aead_chacha20poly1305_NPUBBYTES as usize,
     crypto_aead_chacha20poly1305_ABYTES as usize,
+    crypto_aead_chacha20poly1305_messagebytes_max,
     true
 );
 
+aead_traits!(ChaCha20Poly1305, U8);
+
 #[cfg(test)]
 mod test {
     use super::*;
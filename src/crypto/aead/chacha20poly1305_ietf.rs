@@ -2,11 +2,13 @@
 //! practically unlimited number of messages, but individual messages cannot
 //! exceed 64*(2^32)-64 bytes (approximatively 256 GB).
 
+use crypto::nonce::gen_random_nonce;
 use ffi::{
     crypto_aead_chacha20poly1305_ietf_ABYTES, crypto_aead_chacha20poly1305_ietf_KEYBYTES,
     crypto_aead_chacha20poly1305_ietf_NPUBBYTES, crypto_aead_chacha20poly1305_ietf_decrypt,
     crypto_aead_chacha20poly1305_ietf_decrypt_detached, crypto_aead_chacha20poly1305_ietf_encrypt,
     crypto_aead_chacha20poly1305_ietf_encrypt_detached,
+    crypto_aead_chacha20poly1305_ietf_messagebytes_max,
 };
 aead_module!(
     crypto_aead_chacha20poly1305_ietf_encrypt,
@@ -16,13 +18,49 @@ aead_module!(
     crypto_aead_chacha20poly1305_ietf_KEYBYTES as usize,
     crypto_aead_chacha20poly1305_ietf_NPUBBYTES as usize,
     crypto_aead_chacha20poly1305_ietf_ABYTES as usize,
+    crypto_aead_chacha20poly1305_ietf_messagebytes_max,
     true
 );
 
+aead_traits!(ChaCha20Poly1305Ietf, U12);
+
+/// `gen_nonce()` randomly generates a nonce
+///
+/// WARNING: nonces of this construction are only 96 bits long, so the probability
+/// of a collision between random nonces becomes non-negligible after about 2^32
+/// messages encrypted with the same key. When encrypting many messages under one
+/// key, use a counter (see `Nonce::increment_le()`) or prefer
+/// `xchacha20poly1305_ietf`, whose nonces are large enough to be chosen randomly.
+///
+/// THREAD SAFETY: `gen_nonce()` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+pub fn gen_nonce() -> Nonce {
+    gen_random_nonce()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_messagebytes_max() {
+        // the 32-bit block counter limits messages to 2^32 - 1 blocks
+        assert_eq!(messagebytes_max(), 64 * ((1 << 32) - 1));
+    }
+
+    #[test]
+    fn test_gen_nonce_seal_open() {
+        let k = gen_key();
+        let n1 = gen_nonce();
+        let n2 = gen_nonce();
+        assert_ne!(n1, n2);
+        let c = seal(b"some data", Some(b"ad"), &n1, &k);
+        assert_eq!(open(&c, Some(b"ad"), &n1, &k), Ok(b"some data".to_vec()));
+        assert!(open(&c, Some(b"ad"), &n2, &k).is_err());
+    }
+
     #[test]
     fn test_vector_1() {
         // Test vector from https://tools.ietf.org/html/rfc7539#section-2.8.1
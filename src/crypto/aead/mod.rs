@@ -7,8 +7,27 @@
 //!
 //! # Selected primitive
 //! `seal()`, `seal_detached()`, `open()` and `open_detached()` are currently
-//! an implementation of `chacha20poly1305_ietf`, i.e. the IETF construction defined in
-//! <https://tools.ietf.org/html/rfc7539>.
+//! an implementation of `xchacha20poly1305_ietf`, i.e. the XChaCha20-Poly1305
+//! construction defined in <https://tools.ietf.org/html/draft-irtf-cfrg-xchacha>.
+//! Its 192-bit nonces can safely be generated randomly with `gen_nonce()`.
+//!
+//! # Alternate primitives
+//! All modules share the same API, generated from a common macro:
+//!
+//! ------------------------------------------------------------------
+//! |module                     |KEYBYTES|NONCEBYTES|TAGBYTES|
+//! |---------------------------|--------|----------|--------|
+//! |`xchacha20poly1305_ietf`   |32      |24        |16      |
+//! |`chacha20poly1305_ietf`    |32      |12        |16      |
+//! |`chacha20poly1305`         |32      |8         |16      |
+//! |`aes256gcm`                |32      |12        |16      |
+//!
+//! `aes256gcm` is only available on CPUs with hardware AES support, see
+//! `aes256gcm::is_available()`.
+//!
+//! NOTE: the AEGIS-128L and AEGIS-256 constructions were added in libsodium
+//! 1.0.19. They are not bound yet since the bundled libsodium (and the
+//! generated `libsodium-sys` bindings) are still at version 1.0.18.
 //!
 //! # Example (combined mode)
 //! ```
@@ -40,6 +59,24 @@
 //!
 //! assert_eq!(m, m2);
 //! ```
+//!
+//! # Example (random nonce)
+//! `seal_combined()` picks a random nonce and prepends it to the ciphertext,
+//! so that callers cannot reuse one by mistake.
+//! ```
+//! use sodiumoxide::crypto::aead;
+//!
+//! let k = aead::gen_key();
+//! let c = aead::seal_combined(b"Some plaintext", Some(b"Some additional data"), &k);
+//! let m = aead::open_combined(&c, Some(b"Some additional data"), &k).unwrap();
+//! assert_eq!(m, b"Some plaintext");
+//! ```
+//!
+//! # RustCrypto traits
+//! With the `aead-traits` feature, the `ChaCha20Poly1305`,
+//! `ChaCha20Poly1305Ietf` and `XChaCha20Poly1305Ietf` types of the
+//! corresponding modules implement the traits of the
+//! [aead](https://crates.io/crates/aead) crate.
 
 pub use self::xchacha20poly1305_ietf::*;
 #[macro_use]
@@ -15,6 +15,7 @@ use ffi::{
     crypto_aead_xchacha20poly1305_ietf_decrypt_detached,
     crypto_aead_xchacha20poly1305_ietf_encrypt,
     crypto_aead_xchacha20poly1305_ietf_encrypt_detached,
+    crypto_aead_xchacha20poly1305_ietf_messagebytes_max,
 };
 
 aead_module!(
@@ -25,9 +26,12 @@ aead_module!(
     crypto_aead_xchacha20poly1305_ietf_KEYBYTES as usize,
     crypto_aead_xchacha20poly1305_ietf_NPUBBYTES as usize,
     crypto_aead_xchacha20poly1305_ietf_ABYTES as usize,
+    crypto_aead_xchacha20poly1305_ietf_messagebytes_max,
     true
 );
 
+aead_traits!(XChaCha20Poly1305Ietf, U24);
+
 /// `gen_nonce` randomly generates a nonce
 ///
 /// THREAD SAFETY: `gen_nonce()` is thread-safe provided that you have
@@ -37,6 +41,47 @@ pub fn gen_nonce() -> Nonce {
     gen_random_nonce()
 }
 
+/// Number of additional bytes in a ciphertext produced by `seal_combined()`
+/// compared to the corresponding plaintext.
+pub const COMBINEDBYTES: usize = NONCEBYTES + TAGBYTES;
+
+/// `seal_combined()` encrypts and authenticates a message `m` together with
+/// optional plaintext data `ad` using a secret key `k` and a random nonce.
+/// The nonce is prepended to the ciphertext, so the result is
+/// `COMBINEDBYTES + m.len()` bytes long and can be passed to
+/// `open_combined()` as is.
+///
+/// Since the nonces are 192 bits long, choosing them at random is safe for a
+/// practically unlimited number of messages, and callers cannot reuse one by
+/// mistake.
+///
+/// # Panics
+///
+/// Panics if `m` is longer than `messagebytes_max()`.
+pub fn seal_combined(m: &[u8], ad: Option<&[u8]>, k: &Key) -> Vec<u8> {
+    let n = gen_nonce();
+    let c = seal(m, ad, &n, k);
+    let mut out = Vec::with_capacity(NONCEBYTES + c.len());
+    out.extend_from_slice(&n.0);
+    out.extend_from_slice(&c);
+    out
+}
+
+/// `open_combined()` verifies and decrypts a ciphertext `c` produced by
+/// `seal_combined()`, together with optional plaintext data `ad`, using a
+/// secret key `k`. It returns a plaintext `Ok(m)`.
+///
+/// It returns `Err(Error::InvalidLength)` if `c` is too short to hold a nonce
+/// and an authentication tag, and fails like `open()` otherwise.
+pub fn open_combined(c: &[u8], ad: Option<&[u8]>, k: &Key) -> Result<Vec<u8>, Error> {
+    if c.len() < COMBINEDBYTES {
+        return Err(Error::InvalidLength);
+    }
+    let (n, c) = c.split_at(NONCEBYTES);
+    let n = Nonce::from_slice(n).ok_or(Error::InvalidLength)?;
+    open(c, ad, &n, k)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -81,6 +126,50 @@ mod test {
         assert_eq!(&c[..], &c_expected[..]);
     }
 
+    #[test]
+    fn test_seal_open_combined() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let k = gen_key();
+            let m = randombytes(i);
+            let ad = randombytes(i % 7);
+            let c = seal_combined(&m, Some(&ad), &k);
+            assert_eq!(c.len(), m.len() + COMBINEDBYTES);
+            assert_eq!(open_combined(&c, Some(&ad), &k), Ok(m.clone()));
+            let n = Nonce::from_slice(&c[..NONCEBYTES]).unwrap();
+            assert_eq!(open(&c[NONCEBYTES..], Some(&ad), &n, &k), Ok(m));
+        }
+    }
+
+    #[test]
+    fn test_seal_combined_random_nonce() {
+        let k = gen_key();
+        let c1 = seal_combined(b"some data", None, &k);
+        let c2 = seal_combined(b"some data", None, &k);
+        assert!(c1[..NONCEBYTES] != c2[..NONCEBYTES]);
+        assert!(c1 != c2);
+    }
+
+    #[test]
+    fn test_open_combined_invalid() {
+        let k = gen_key();
+        let mut c = seal_combined(b"some data", Some(b"ad"), &k);
+        assert_eq!(
+            open_combined(&c[..COMBINEDBYTES - 1], Some(b"ad"), &k),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(open_combined(&c, None, &k), Err(Error::VerificationFailed));
+        assert_eq!(
+            open_combined(&c, Some(b"ad"), &gen_key()),
+            Err(Error::VerificationFailed)
+        );
+        for i in 0..c.len() {
+            c[i] ^= 0x01;
+            assert!(open_combined(&c, Some(b"ad"), &k).is_err());
+            c[i] ^= 0x01;
+        }
+    }
+
     #[test]
     fn test_nonce_length() {
         assert_eq!(192 / 8, gen_nonce().as_ref().len());
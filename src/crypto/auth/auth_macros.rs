@@ -1,10 +1,10 @@
 macro_rules! auth_module (($auth_name:ident,
                            $verify_name:ident,
+                           $keygen_name:ident,
                            $keybytes:expr,
                            $tagbytes:expr) => (
 
 use libc::c_ulonglong;
-use randombytes::randombytes_into;
 
 /// Number of bytes in a `Key`.
 pub const KEYBYTES: usize = $keybytes;
@@ -34,9 +34,11 @@ new_type! {
 /// called `sodiumoxide::init()` once before using any other function
 /// from sodiumoxide.
 pub fn gen_key() -> Key {
-    let mut k = [0; KEYBYTES];
-    randombytes_into(&mut k);
-    Key(k)
+    let mut k = Key([0; KEYBYTES]);
+    unsafe {
+        $keygen_name(k.0.as_mut_ptr());
+    }
+    k
 }
 
 /// `authenticate()` authenticates a message `m` using a secret key `k`.
@@ -53,6 +55,24 @@ pub fn authenticate(m: &[u8],
     }
 }
 
+/// `authenticate_into()` authenticates a message `m` using a secret key `k`, and writes the
+/// authenticator tag to the start of `out`. It returns the number of bytes written, i.e.
+/// `TAGBYTES`, or `Err(Error::InvalidLength)` if `out` is shorter than that.
+pub fn authenticate_into(out: &mut [u8],
+                         m: &[u8],
+                         k: &Key) -> Result<usize, ::Error> {
+    if out.len() < TAGBYTES {
+        return Err(::Error::InvalidLength);
+    }
+    unsafe {
+        $auth_name(out.as_mut_ptr(),
+                   m.as_ptr(),
+                   m.len() as c_ulonglong,
+                   k.0.as_ptr());
+    }
+    Ok(TAGBYTES)
+}
+
 /// `verify()` returns `true` if `tag` is a correct authenticator of message `m`
 /// under a secret key `k`. Otherwise it returns false.
 pub fn verify(tag: &Tag, m: &[u8],
@@ -65,6 +85,23 @@ pub fn verify(tag: &Tag, m: &[u8],
     }
 }
 
+/// `verify_with_any()` checks `tag` against message `m` under each of the
+/// secret keys in `keys`, e.g. the current and the previous key during a key
+/// rotation. It returns `Some(i)` where `keys[i]` is the first key under which
+/// `tag` is correct, or `None` if there is no such key.
+///
+/// Every key is tried, in order, whichever one matches, so that the time
+/// taken does not reveal which key was used.
+pub fn verify_with_any(keys: &[Key], tag: &Tag, m: &[u8]) -> Option<usize> {
+    let mut found = None;
+    for (i, k) in keys.iter().enumerate() {
+        if verify(tag, m, k) && found.is_none() {
+            found = Some(i);
+        }
+    }
+    found
+}
+
 #[cfg(test)]
 mod test_m {
     use super::*;
@@ -100,6 +137,49 @@ mod test_m {
         }
     }
 
+    #[test]
+    fn test_verify_with_any() {
+        let old = gen_key();
+        let new = gen_key();
+        let other = gen_key();
+        let tag = authenticate(b"rotate", &old);
+        let keys = [new.clone(), old.clone()];
+        assert_eq!(verify_with_any(&keys, &tag, b"rotate"), Some(1));
+        assert_eq!(verify_with_any(&keys[..1], &tag, b"rotate"), None);
+        assert_eq!(verify_with_any(&[], &tag, b"rotate"), None);
+        assert_eq!(verify_with_any(&keys, &tag, b"rotatf"), None);
+        let keys = [old.clone(), other, old];
+        assert_eq!(verify_with_any(&keys, &tag, b"rotate"), Some(0));
+    }
+
+    #[test]
+    fn test_authenticate_into() {
+        let k = gen_key();
+        let mut out = [0; TAGBYTES + 1];
+        assert_eq!(authenticate_into(&mut out, b"into", &k), Ok(TAGBYTES));
+        assert_eq!(&out[..TAGBYTES], authenticate(b"into", &k).as_ref());
+        assert_eq!(out[TAGBYTES], 0);
+        assert_eq!(
+            authenticate_into(&mut out[..TAGBYTES - 1], b"into", &k),
+            Err(::Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_tag_borrow() {
+        use std::borrow::Borrow;
+        use std::collections::HashSet;
+        let k = gen_key();
+        let tag = authenticate(b"borrow", &k);
+        let wire = tag.0.to_vec();
+        let mut seen = HashSet::new();
+        seen.insert(tag);
+        assert!(seen.contains(&wire[..]));
+        let borrowed: &[u8] = tag.borrow();
+        assert_eq!(borrowed, tag.as_ref());
+        assert_eq!(k.expose_bytes(), &k.0);
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn test_serialisation() {
@@ -113,6 +193,16 @@ mod test_m {
             round_trip(tag);
         }
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialisation_rejects_wrong_length() {
+        use test_utils::rejects_length;
+        rejects_length::<Key>(KEYBYTES - 1);
+        rejects_length::<Key>(KEYBYTES + 1);
+        rejects_length::<Tag>(0);
+        rejects_length::<Tag>(TAGBYTES + 1);
+    }
 }
 
 #[cfg(feature = "benchmarks")]
@@ -156,3 +246,87 @@ mod bench_m {
 }
 
 ));
+
+/// Macro for defining functions that work with tags truncated to a prefix,
+/// on top of the items generated by `auth_module!`.
+#[allow(unused_macros)]
+macro_rules! auth_truncated (() => (
+
+/// Minimum number of bytes a tag can be truncated to.
+///
+/// Shorter tags can be forged by guessing, and a zero-length tag would verify
+/// for every message.
+pub const TAGBYTES_TRUNCATED_MIN: usize = 8;
+
+/// `authenticate_truncated()` authenticates a message `m` using a secret key
+/// `k`, and returns the first `len` bytes of the authenticator tag, as used by
+/// many wire protocols.
+///
+/// It returns `Err(Error::InvalidLength)` unless `len` lies between
+/// `TAGBYTES_TRUNCATED_MIN` and `TAGBYTES`.
+pub fn authenticate_truncated(m: &[u8], k: &Key, len: usize) -> Result<Vec<u8>, ::Error> {
+    if !(TAGBYTES_TRUNCATED_MIN..=TAGBYTES).contains(&len) {
+        return Err(::Error::InvalidLength);
+    }
+    Ok(authenticate(m, k).0[..len].to_vec())
+}
+
+/// `verify_truncated()` returns `true` if `tag_bytes` is a correct
+/// authenticator of message `m` under a secret key `k`, truncated to
+/// `tag_bytes.len()` bytes. Otherwise it returns false.
+///
+/// The prefix is compared in constant time. It returns false if
+/// `tag_bytes.len()` is not between `TAGBYTES_TRUNCATED_MIN` and `TAGBYTES`.
+pub fn verify_truncated(tag_bytes: &[u8], m: &[u8], k: &Key) -> bool {
+    let len = tag_bytes.len();
+    if !(TAGBYTES_TRUNCATED_MIN..=TAGBYTES).contains(&len) {
+        return false;
+    }
+    let mut expected = authenticate(m, k);
+    let r = ::utils::memcmp(&expected.0[..len], tag_bytes);
+    ::utils::memzero(&mut expected.0);
+    r
+}
+
+#[cfg(test)]
+mod test_truncated {
+    use super::*;
+
+    #[test]
+    fn test_truncated() {
+        let k = gen_key();
+        let tag = authenticate(b"message", &k);
+        for len in TAGBYTES_TRUNCATED_MIN..TAGBYTES + 1 {
+            let t = authenticate_truncated(b"message", &k, len).unwrap();
+            assert_eq!(&t[..], &tag.0[..len]);
+            assert!(verify_truncated(&t, b"message", &k));
+            assert!(!verify_truncated(&t, b"messagf", &k));
+            let mut bad = t.clone();
+            bad[len - 1] ^= 0x01;
+            assert!(!verify_truncated(&bad, b"message", &k));
+        }
+    }
+
+    #[test]
+    fn test_truncated_length() {
+        let k = gen_key();
+        let tag = authenticate(b"message", &k);
+        for &len in [0, TAGBYTES_TRUNCATED_MIN - 1, TAGBYTES + 1].iter() {
+            assert_eq!(
+                authenticate_truncated(b"message", &k, len),
+                Err(::Error::InvalidLength)
+            );
+        }
+        assert!(!verify_truncated(&[], b"message", &k));
+        assert!(!verify_truncated(
+            &tag.0[..TAGBYTES_TRUNCATED_MIN - 1],
+            b"message",
+            &k
+        ));
+        let mut long = tag.0.to_vec();
+        long.push(0);
+        assert!(!verify_truncated(&long, b"message", &k));
+    }
+}
+
+));
@@ -5,19 +5,43 @@
 ///               SAFETY NOTE: This needs to be a type that does not define a `Drop`
 ///               implementation, otherwise undefined behaviour will occur.
 /// $init_name - A function `f(s: *mut $state_name, k: *u8, klen: size_t)` that initializes
-///              a state with a key.
+///              a state with a key, and returns 0 on success.
 /// $update_name - A function `f(s: *mut $state_name, m: *u8, mlen: size_t)` that updates
 ///                a state with a message chunk.
 /// $final_name - A function `f(s: *mut $state_name, t: *u8)` that computes an authenticator tag of length $tagbytes from a $state_name.
 /// $tagbytes   - The number of bytes in an authenticator tag.
+/// $valid_name - Optional function `f(s: &$state_name) -> bool` that checks a raw state
+///               restored by `State::import()`. It must reject every state that would make
+///               $update_name or $final_name access memory out of bounds. It can be omitted
+///               for states that libsodium only ever indexes with masked counters.
 macro_rules! auth_state (($state_name:ident,
                           $init_name:ident,
                           $update_name:ident,
                           $final_name:ident,
                           $tagbytes:expr) => (
+auth_state!($state_name, $init_name, $update_name, $final_name, $tagbytes, |_| true);
+);
+                         ($state_name:ident,
+                          $init_name:ident,
+                          $update_name:ident,
+                          $final_name:ident,
+                          $tagbytes:expr,
+                          $valid_name:expr) => (
 
+#[cfg(not(feature = "std"))] use prelude::*;
 use std::mem;
+use std::ptr;
 use ffi;
+use version::{version_major, version_minor};
+
+/// Magic bytes at the start of an exported `State`.
+const STATE_MAGIC: [u8; 4] = *b"SOAS";
+
+/// Version of the exported `State` format.
+const STATE_FORMAT_VERSION: u8 = 1;
+
+/// Number of bytes in the header of an exported `State`.
+const STATE_HEADERBYTES: usize = 12;
 
 /// Authentication `State`
 ///
@@ -44,15 +68,56 @@ impl Drop for State {
     }
 }
 
+// No `Zeroize`: `update()` cannot fail, so a zeroized state would silently
+// keep hashing. `Drop` wipes it.
+#[cfg(feature = "zeroize")]
+impl ::zeroize::ZeroizeOnDrop for State {}
+
+/// Feeds everything written into `update()`, e.g. to authenticate a stream
+/// with `io::copy()`.
+#[cfg(feature = "std")]
+impl ::std::io::Write for State {
+    fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> ::std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl ::std::fmt::Debug for State {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        // The state is derived from the key, hide it from debug output.
+        write!(formatter, "State(****)")
+    }
+}
+
 impl State {
+    /// `try_init()` initializes an authentication structure using a secret key 'k'.
+    ///
+    /// It returns `Err(Error::InitFailed)` if the primitive rejects the key, e.g. a
+    /// `BLAKE2b` key shorter than `KEYBYTES_MIN` or longer than `KEYBYTES_MAX` bytes.
+    pub fn try_init(k: &[u8]) -> Result<State, ::Error> {
+        // The init functions do not write every byte of the state, which
+        // `export()` copies as a whole, so start from zeroes.
+        let mut s = mem::MaybeUninit::zeroed();
+        unsafe {
+            if $init_name(s.as_mut_ptr(), k.as_ptr(), k.len()) != 0 {
+                return Err(::Error::InitFailed);
+            }
+            Ok(State(s.assume_init()))
+        }
+    }
+
     /// `init()` initializes an authentication structure using a secret key 'k'.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the primitive rejects the key, see `try_init()`.
     pub fn init(k: &[u8]) -> State {
-        let mut s = mem::MaybeUninit::uninit();
-        let state = unsafe {
-            $init_name(s.as_mut_ptr(), k.as_ptr(), k.len());
-            s.assume_init() // s is definitely initialized
-        };
-        State(state)
+        State::try_init(k).expect("invalid authentication key")
     }
 
     /// `update()` can be called more than once in order to compute the authenticator
@@ -72,12 +137,123 @@ impl State {
             Tag(tag)
         }
     }
+
+    /// `finalize_verify()` finalizes the authenticator computation and returns
+    /// `true` if `tag` is the correct authenticator of the message, and `false`
+    /// otherwise.
+    ///
+    /// The comparison is done in constant time using `sodium_memcmp`, so this
+    /// should be used instead of comparing the result of `finalize()` by hand.
+    /// Like `finalize()`, `finalize_verify()` consumes the `State`.
+    pub fn finalize_verify(mut self, tag: &Tag) -> bool {
+        unsafe {
+            let mut expected = [0u8; $tagbytes];
+            $final_name(&mut self.0, expected.as_mut_ptr());
+            let r = ffi::sodium_memcmp(
+                expected.as_ptr() as *const _,
+                tag.0.as_ptr() as *const _,
+                $tagbytes,
+            );
+            ffi::sodium_memzero(expected.as_mut_ptr() as *mut _, $tagbytes);
+            r == 0
+        }
+    }
+
+    fn export_header() -> [u8; STATE_HEADERBYTES] {
+        let state_len = mem::size_of::<$state_name>() as u16;
+        let major = version_major() as u16;
+        let minor = version_minor() as u16;
+        let mut header = [0u8; STATE_HEADERBYTES];
+        header[..4].copy_from_slice(&STATE_MAGIC);
+        header[4] = STATE_FORMAT_VERSION;
+        header[5] = $tagbytes as u8;
+        header[6..8].copy_from_slice(&state_len.to_le_bytes());
+        header[8..10].copy_from_slice(&major.to_le_bytes());
+        header[10..12].copy_from_slice(&minor.to_le_bytes());
+        header
+    }
+
+    /// `export()` serializes the intermediate state so that the computation
+    /// can be resumed later, possibly in another process, using `import()`.
+    ///
+    /// The blob consists of a 12 byte header (the magic bytes `SOAS`, a format
+    /// version, the tag length, the size of the raw state and the libsodium
+    /// library version) followed by the raw libsodium state.
+    ///
+    /// WARNING: the exported state is equivalent to the key. Anybody who gets
+    /// hold of it can compute valid tags for any message having the already
+    /// processed data as a prefix, so it must be stored encrypted and zeroed
+    /// out after use.
+    ///
+    /// NOTE: the raw state is tied to the libsodium version and ABI it was
+    /// produced with. `import()` refuses blobs from a different library version.
+    pub fn export(&self) -> Vec<u8> {
+        let state_len = mem::size_of::<$state_name>();
+        let mut out = Vec::with_capacity(STATE_HEADERBYTES + state_len);
+        out.extend_from_slice(&State::export_header());
+        let raw = unsafe {
+            ::std::slice::from_raw_parts(&self.0 as *const $state_name as *const u8, state_len)
+        };
+        out.extend_from_slice(raw);
+        out
+    }
+
+    /// `import()` restores a `State` previously serialized with `export()`.
+    ///
+    /// It returns `Err(Error::InvalidEncoding)` if the blob is truncated, its
+    /// header is corrupted, it was produced by another primitive or
+    /// libsodium version, or its raw state is out of range.
+    ///
+    /// Only the header and the fields libsodium uses as buffer offsets are
+    /// checked. A modified blob that passes these checks computes a wrong tag,
+    /// but cannot make `update()` or `finalize()` access memory out of bounds.
+    pub fn import(blob: &[u8]) -> Result<State, ::Error> {
+        let state_len = mem::size_of::<$state_name>();
+        if blob.len() != STATE_HEADERBYTES + state_len
+            || blob[..STATE_HEADERBYTES] != State::export_header()
+        {
+            return Err(::Error::InvalidEncoding);
+        }
+        let mut s = mem::MaybeUninit::<$state_name>::uninit();
+        let state = unsafe {
+            ptr::copy_nonoverlapping(
+                blob[STATE_HEADERBYTES..].as_ptr(),
+                s.as_mut_ptr() as *mut u8,
+                state_len,
+            );
+            s.assume_init() // every byte of s has been written
+        };
+        let valid: fn(&$state_name) -> bool = $valid_name;
+        if !valid(&state) {
+            return Err(::Error::InvalidEncoding);
+        }
+        Ok(State(state))
+    }
 }
 
 #[cfg(test)]
 mod test_s {
     use super::*;
 
+    #[test]
+    fn test_debug_redacted() {
+        let k = gen_key();
+        assert_eq!(format!("{:?}", k), "Key(****)");
+        assert_eq!(format!("{:?}", State::init(k.as_ref())), "State(****)");
+        assert_eq!(
+            format!("{:?}", Tag([0xab; TAGBYTES])),
+            format!("Tag({})", "ab".repeat(TAGBYTES))
+        );
+    }
+
+    #[test]
+    fn test_init_fully_initializes() {
+        let k = gen_key();
+        let a = State::try_init(k.as_ref()).unwrap();
+        let b = State::init(k.as_ref());
+        assert_eq!(a.export(), b.export());
+    }
+
     #[test]
     fn test_auth_eq_auth_state() {
         use randombytes::randombytes;
@@ -92,6 +268,21 @@ mod test_s {
         }
     }
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_state_io_write() {
+        use randombytes::randombytes;
+        use std::io::{self, Write};
+        let k = gen_key();
+        let m = randombytes(1000);
+        let mut state = State::init(k.as_ref());
+        io::copy(&mut &m[..], &mut state).unwrap();
+        write!(state, "{}", 42).unwrap();
+        let mut expected = m.clone();
+        expected.extend_from_slice(b"42");
+        assert_eq!(state.finalize(), authenticate(&expected, &k));
+    }
+
     #[test]
     fn test_auth_eq_auth_state_chunked() {
         use randombytes::randombytes;
@@ -107,5 +298,130 @@ mod test_s {
             assert_eq!(tag, tag2);
         }
     }
+
+    #[test]
+    fn test_finalize_verify() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let k = gen_key();
+            let m = randombytes(i);
+            let tag = authenticate(&m, &k);
+
+            let mut state = State::init(k.as_ref());
+            state.update(&m);
+            assert!(state.finalize_verify(&tag));
+
+            let mut bad_tag = tag;
+            bad_tag.0[i % TAGBYTES] ^= 0x20;
+            let mut state = State::init(k.as_ref());
+            state.update(&m);
+            assert!(!state.finalize_verify(&bad_tag));
+
+            let mut state = State::init(k.as_ref());
+            state.update(&m);
+            state.update(b"x");
+            assert!(!state.finalize_verify(&tag));
+        }
+    }
+
+    #[test]
+    fn test_state_export_import() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let k = gen_key();
+            let m = randombytes(i);
+            let tag = authenticate(&m, &k);
+            let (m1, m2) = m.split_at(i / 3);
+            let mut state = State::init(k.as_ref());
+            state.update(m1);
+            let blob = state.export();
+            drop(state);
+            let mut state = State::import(&blob).unwrap();
+            state.update(m2);
+            let tag2 = state.finalize();
+            assert_eq!(tag, tag2);
+        }
+    }
+
+    #[test]
+    fn test_state_import_rejects_corruption() {
+        let k = gen_key();
+        let mut state = State::init(k.as_ref());
+        state.update(b"some data");
+        let mut blob = state.export();
+        assert!(State::import(&blob).is_ok());
+        assert!(State::import(&blob[..blob.len() - 1]).is_err());
+        assert!(State::import(&[]).is_err());
+        let mut longer = blob.clone();
+        longer.push(0);
+        assert!(State::import(&longer).is_err());
+        for i in 0..STATE_HEADERBYTES {
+            blob[i] ^= 0x01;
+            assert!(State::import(&blob).is_err());
+            blob[i] ^= 0x01;
+        }
+    }
+}
+));
+
+/// Macro for defining simple-interface functions that take keys of any
+/// length, for primitives whose `State::init()` accepts every key, i.e. the
+/// `HMAC` variants.
+macro_rules! auth_varkey (() => (
+
+/// `authenticate_with_key()` authenticates a message `m` using the secret key
+/// `k`, which can have any length, e.g. a 20 or 64 byte `HMAC` key shared
+/// with another system. It returns the same tag as `authenticate()` when `k`
+/// is `KEYBYTES` long.
+///
+/// As with the streaming interface, the caller is responsible for zeroing
+/// out `k` after use.
+pub fn authenticate_with_key(m: &[u8], k: &[u8]) -> Tag {
+    let mut state = State::init(k);
+    state.update(m);
+    state.finalize()
+}
+
+/// `verify_with_key()` returns `true` if `tag` is a correct authenticator of
+/// message `m` under the secret key `k` of any length. Otherwise it returns
+/// false.
+///
+/// The comparison is done in constant time.
+pub fn verify_with_key(tag: &Tag, m: &[u8], k: &[u8]) -> bool {
+    let mut state = State::init(k);
+    state.update(m);
+    state.finalize_verify(tag)
+}
+
+#[cfg(test)]
+mod test_varkey {
+    use super::*;
+    use randombytes::randombytes;
+
+    #[test]
+    fn test_authenticate_with_key() {
+        for &len in [0, 1, 20, KEYBYTES, 64, 200].iter() {
+            let k = randombytes(len);
+            let m = randombytes(len + 3);
+            let tag = authenticate_with_key(&m, &k);
+            assert!(verify_with_key(&tag, &m, &k));
+            assert!(!verify_with_key(&tag, &m[1..], &k));
+            assert!(!verify_with_key(&tag, &m, &randombytes(len + 1)));
+        }
+    }
+
+    #[test]
+    fn test_authenticate_with_key_matches_fixed() {
+        let k = gen_key();
+        let tag = authenticate_with_key(b"message", k.as_ref());
+        assert_eq!(tag, authenticate(b"message", &k));
+        assert!(verify(&tag, b"message", &k));
+    }
+
+    #[test]
+    fn test_gen_key() {
+        assert!(gen_key() != gen_key());
+    }
 }
+
 ));
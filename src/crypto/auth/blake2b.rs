@@ -0,0 +1,203 @@
+//! `BLAKE2b` in keyed mode, as specified in RFC 7693. Keyed `BLAKE2b` is a
+//! PRF and is conjectured to meet the standard notion of unforgeability.
+//!
+//! The tag length is fixed at 32 bytes; `blake2b_auth!` can be used to
+//! instantiate the same API with a different tag length.
+//!
+//! NOTE: this primitive is backed by `crypto_generichash_blake2b` and does not
+//! exist in `NaCl`.
+
+use ffi::{
+    crypto_generichash_blake2b, crypto_generichash_blake2b_BYTES,
+    crypto_generichash_blake2b_KEYBYTES, crypto_generichash_blake2b_KEYBYTES_MAX,
+    crypto_generichash_blake2b_KEYBYTES_MIN, crypto_generichash_blake2b_final,
+    crypto_generichash_blake2b_init, crypto_generichash_blake2b_state,
+    crypto_generichash_blake2b_update,
+};
+
+/// Minimum number of bytes in a key accepted by `State::init()`. Shorter
+/// keys, including the empty one, would silently compute an unkeyed hash.
+pub const KEYBYTES_MIN: usize = crypto_generichash_blake2b_KEYBYTES_MIN as usize;
+
+/// Maximum number of bytes in a key accepted by `State::init()`.
+pub const KEYBYTES_MAX: usize = crypto_generichash_blake2b_KEYBYTES_MAX as usize;
+
+const BLAKE2B_BLOCKBYTES: usize = 128;
+const BLAKE2B_BUFLEN_OFFSET: usize = 8 * 8 + 2 * 8 + 2 * 8 + 2 * BLAKE2B_BLOCKBYTES;
+
+/// Generates the `auth_module!` and `auth_state!` APIs on top of keyed
+/// `BLAKE2b` with a tag length of `$tagbytes`.
+///
+/// `$tagbytes` must lie between `crypto_generichash_blake2b_BYTES_MIN` and
+/// `crypto_generichash_blake2b_BYTES_MAX`.
+macro_rules! blake2b_auth (($tagbytes:expr) => (
+
+unsafe fn blake2b_authenticate(
+    tag: *mut u8,
+    m: *const u8,
+    mlen: c_ulonglong,
+    k: *const u8,
+) -> i32 {
+    crypto_generichash_blake2b(tag, TAGBYTES, m, mlen, k, KEYBYTES)
+}
+
+unsafe fn blake2b_verify(
+    tag: *const u8,
+    m: *const u8,
+    mlen: c_ulonglong,
+    k: *const u8,
+) -> i32 {
+    let mut expected = [0u8; TAGBYTES];
+    if blake2b_authenticate(expected.as_mut_ptr(), m, mlen, k) != 0 {
+        return -1;
+    }
+    let r = ffi::sodium_memcmp(expected.as_ptr() as *const _, tag as *const _, TAGBYTES);
+    ffi::sodium_memzero(expected.as_mut_ptr() as *mut _, TAGBYTES);
+    r
+}
+
+unsafe fn blake2b_keygen(k: *mut u8) {
+    ffi::randombytes_buf(k as *mut _, KEYBYTES);
+}
+
+unsafe fn blake2b_init(
+    s: *mut crypto_generichash_blake2b_state,
+    k: *const u8,
+    klen: usize,
+) -> i32 {
+    if !(KEYBYTES_MIN..=KEYBYTES_MAX).contains(&klen) {
+        return -1;
+    }
+    crypto_generichash_blake2b_init(s, k, klen, $tagbytes)
+}
+
+unsafe fn blake2b_update(
+    s: *mut crypto_generichash_blake2b_state,
+    m: *const u8,
+    mlen: c_ulonglong,
+) -> i32 {
+    crypto_generichash_blake2b_update(s, m, mlen)
+}
+
+unsafe fn blake2b_final(s: *mut crypto_generichash_blake2b_state, tag: *mut u8) -> i32 {
+    crypto_generichash_blake2b_final(s, tag, $tagbytes)
+}
+
+// libsodium uses `buflen`, which follows the chaining value, the counters,
+// the flags and the two block buffer, as an offset into that buffer without
+// checking it.
+fn blake2b_valid_state(s: &crypto_generichash_blake2b_state) -> bool {
+    let mut buflen = [0u8; ::std::mem::size_of::<usize>()];
+    let len = buflen.len();
+    buflen.copy_from_slice(&s.opaque[BLAKE2B_BUFLEN_OFFSET..BLAKE2B_BUFLEN_OFFSET + len]);
+    usize::from_ne_bytes(buflen) <= 2 * BLAKE2B_BLOCKBYTES
+}
+
+auth_module!(
+    blake2b_authenticate,
+    blake2b_verify,
+    blake2b_keygen,
+    crypto_generichash_blake2b_KEYBYTES as usize,
+    $tagbytes
+);
+
+auth_state!(
+    crypto_generichash_blake2b_state,
+    blake2b_init,
+    blake2b_update,
+    blake2b_final,
+    $tagbytes,
+    blake2b_valid_state
+);
+
+auth_truncated!();
+
+auth_mac!(Blake2b);
+
+));
+
+blake2b_auth!(crypto_generichash_blake2b_BYTES as usize);
+
+#[cfg(all(test, feature = "generichash"))]
+mod test {
+    use super::*;
+    use crypto::generichash;
+
+    #[test]
+    fn test_eq_generichash() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let k = gen_key();
+            let m = randombytes(i);
+            let tag = authenticate(&m, &k);
+            let digest = generichash::hash(&m, Some(TAGBYTES), Some(k.as_ref())).unwrap();
+            assert_eq!(tag.as_ref(), digest.as_ref());
+        }
+    }
+
+    #[test]
+    fn test_state_variable_length_keys() {
+        use randombytes::randombytes;
+        for klen in generichash::KEY_MIN..=KEYBYTES_MAX {
+            let k = randombytes(klen);
+            let m = randombytes(klen);
+            let mut state = State::init(&k);
+            state.update(&m);
+            let tag = state.finalize();
+            let digest = generichash::hash(&m, Some(TAGBYTES), Some(&k)).unwrap();
+            assert_eq!(tag.as_ref(), digest.as_ref());
+        }
+    }
+
+    #[test]
+    fn test_state_try_init_key_too_long() {
+        let k = [0u8; KEYBYTES_MAX + 1];
+        assert!(State::try_init(&k[..KEYBYTES_MAX]).is_ok());
+        assert_eq!(State::try_init(&k).err(), Some(::Error::InitFailed));
+    }
+
+    #[test]
+    fn test_import_buflen_out_of_range() {
+        let k = gen_key();
+        let mut state = State::init(k.as_ref());
+        state.update(&[0x41; 200]);
+        let blob = state.export();
+        assert!(State::import(&blob).is_ok());
+
+        // `buflen` sits after the 12 byte header of the blob
+        let offset = 12 + BLAKE2B_BUFLEN_OFFSET;
+        let len = ::std::mem::size_of::<usize>();
+        assert_eq!(&blob[offset..offset + len], &200usize.to_ne_bytes()[..]);
+        for &buflen in [2 * BLAKE2B_BLOCKBYTES + 1, 1 << 20, !0].iter() {
+            let mut bad = blob.clone();
+            bad[offset..offset + len].copy_from_slice(&buflen.to_ne_bytes());
+            assert_eq!(State::import(&bad).err(), Some(::Error::InvalidEncoding));
+        }
+        let mut full = blob;
+        full[offset..offset + len].copy_from_slice(&(2 * BLAKE2B_BLOCKBYTES).to_ne_bytes());
+        assert!(State::import(&full).is_ok());
+    }
+
+    #[test]
+    fn test_state_try_init_key_too_short() {
+        let k = [0u8; KEYBYTES_MIN];
+        assert!(State::try_init(&k).is_ok());
+        for len in 0..KEYBYTES_MIN {
+            assert_eq!(State::try_init(&k[..len]).err(), Some(::Error::InitFailed));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_state_empty_key() {
+        use super::super::MacState;
+        let _ = <State as MacState>::init(&[]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_state_key_too_long() {
+        let k = [0u8; KEYBYTES_MAX + 1];
+        let _ = State::init(&k);
+    }
+}
@@ -3,13 +3,14 @@
 
 use ffi::{
     crypto_auth_hmacsha256, crypto_auth_hmacsha256_BYTES, crypto_auth_hmacsha256_KEYBYTES,
-    crypto_auth_hmacsha256_final, crypto_auth_hmacsha256_init, crypto_auth_hmacsha256_state,
-    crypto_auth_hmacsha256_update, crypto_auth_hmacsha256_verify,
+    crypto_auth_hmacsha256_final, crypto_auth_hmacsha256_init, crypto_auth_hmacsha256_keygen,
+    crypto_auth_hmacsha256_state, crypto_auth_hmacsha256_update, crypto_auth_hmacsha256_verify,
 };
 
 auth_module!(
     crypto_auth_hmacsha256,
     crypto_auth_hmacsha256_verify,
+    crypto_auth_hmacsha256_keygen,
     crypto_auth_hmacsha256_KEYBYTES as usize,
     crypto_auth_hmacsha256_BYTES as usize
 );
@@ -22,6 +23,12 @@ auth_state!(
     crypto_auth_hmacsha256_BYTES as usize
 );
 
+auth_truncated!();
+
+auth_varkey!();
+
+auth_mac!(HmacSha256);
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -3,13 +3,14 @@
 
 use ffi::{
     crypto_auth_hmacsha512, crypto_auth_hmacsha512_BYTES, crypto_auth_hmacsha512_KEYBYTES,
-    crypto_auth_hmacsha512_final, crypto_auth_hmacsha512_init, crypto_auth_hmacsha512_state,
-    crypto_auth_hmacsha512_update, crypto_auth_hmacsha512_verify,
+    crypto_auth_hmacsha512_final, crypto_auth_hmacsha512_init, crypto_auth_hmacsha512_keygen,
+    crypto_auth_hmacsha512_state, crypto_auth_hmacsha512_update, crypto_auth_hmacsha512_verify,
 };
 
 auth_module!(
     crypto_auth_hmacsha512,
     crypto_auth_hmacsha512_verify,
+    crypto_auth_hmacsha512_keygen,
     crypto_auth_hmacsha512_KEYBYTES as usize,
     crypto_auth_hmacsha512_BYTES as usize
 );
@@ -22,6 +23,12 @@ auth_state!(
     crypto_auth_hmacsha512_BYTES as usize
 );
 
+auth_truncated!();
+
+auth_varkey!();
+
+auth_mac!(HmacSha512);
+
 #[cfg(test)]
 mod test {
     use super::*;
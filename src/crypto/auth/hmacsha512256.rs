@@ -5,13 +5,14 @@
 use ffi::{
     crypto_auth_hmacsha512256, crypto_auth_hmacsha512256_BYTES, crypto_auth_hmacsha512256_KEYBYTES,
     crypto_auth_hmacsha512256_final, crypto_auth_hmacsha512256_init,
-    crypto_auth_hmacsha512256_state, crypto_auth_hmacsha512256_update,
-    crypto_auth_hmacsha512256_verify,
+    crypto_auth_hmacsha512256_keygen, crypto_auth_hmacsha512256_state,
+    crypto_auth_hmacsha512256_update, crypto_auth_hmacsha512256_verify,
 };
 
 auth_module!(
     crypto_auth_hmacsha512256,
     crypto_auth_hmacsha512256_verify,
+    crypto_auth_hmacsha512256_keygen,
     crypto_auth_hmacsha512256_KEYBYTES as usize,
     crypto_auth_hmacsha512256_BYTES as usize
 );
@@ -24,6 +25,12 @@ auth_state!(
     crypto_auth_hmacsha512256_BYTES as usize
 );
 
+auth_truncated!();
+
+auth_varkey!();
+
+auth_mac!(HmacSha512256);
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -32,6 +32,22 @@
 //! |`crypto_auth_hmacsha512256` |`HMAC_SHA-512-256` |32   |32      |
 //! |`crypto_auth_hmacsha512`    |`HMAC_SHA-512`     |64   |32      |
 //!
+//! The `HMAC` modules also provide `authenticate_with_key()` and
+//! `verify_with_key()`, which accept keys of any length, for interoperability
+//! with systems using e.g. 20 or 64 byte keys.
+//!
+//! Every primitive provides `authenticate_truncated()` and
+//! `verify_truncated()` for protocols that send a prefix of the tag. The
+//! prefix is compared in constant time, which slicing a `Tag` and using `==`
+//! on the slices does not do.
+//!
+//! `sodiumoxide` additionally provides keyed `BLAKE2b` as an authenticator:
+//!
+//! -----------------------------------------------------------------
+//! |module                      |primitive          |BYTES|KEYBYTES|
+//! |----------------------------|-------------------|-----|--------|
+//! |`auth::blake2b`             |keyed `BLAKE2b`    |32   |32      |
+//!
 //! # Example (simple interface)
 //! ```
 //! use sodiumoxide::crypto::auth;
@@ -63,13 +79,294 @@
 //! state.update(data_2_part_2);
 //! let tag2 = state.finalize();
 //! assert_eq!(tag1, tag2);
+//!
+//! // verify a tag received from somebody else in constant time
+//! let mut state = auth::State::init(&key);
+//! state.update(b"some datasome other data");
+//! assert!(state.finalize_verify(&tag1));
+//! ```
+
+//!
+//! # Example (generic and runtime selected primitives)
 //! ```
+//! use sodiumoxide::crypto::auth::{self, hmacsha256, Algorithm, Mac};
+//!
+//! fn sign_webhook<M: Mac>(body: &[u8], key: &M::Key) -> M::Tag {
+//!     M::authenticate(body, key)
+//! }
+//!
+//! let key = hmacsha256::gen_key();
+//! let tag = sign_webhook::<hmacsha256::HmacSha256>(b"payload", &key);
+//! assert!(hmacsha256::verify(&tag, b"payload", &key));
+//!
+//! let alg = Algorithm::HmacSha256;
+//! let tag2 = alg.authenticate_dyn(b"payload", key.as_ref()).unwrap();
+//! assert_eq!(tag.as_ref(), &tag2[..]);
+//! assert!(alg.verify_dyn(&tag2, b"payload", key.as_ref()));
+//! ```
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+
+/// `Mac` abstracts over the secret-key authentication primitives in this
+/// module, so that code can be written once for all of them.
+///
+/// Every primitive module defines a marker type implementing `Mac` whose
+/// methods are equivalent to the module's free functions.
+pub trait Mac {
+    /// Authentication key type.
+    type Key: AsRef<[u8]>;
+    /// Authenticator tag type.
+    type Tag: AsRef<[u8]> + PartialEq;
+    /// Streaming authentication state type.
+    type State: MacState<Tag = Self::Tag>;
+
+    /// Number of bytes in a `Key`.
+    const KEYBYTES: usize;
+    /// Number of bytes in a `Tag`.
+    const TAGBYTES: usize;
+
+    /// `gen_key()` randomly generates a key for authentication.
+    fn gen_key() -> Self::Key;
+
+    /// `authenticate()` authenticates a message `m` using a secret key `k`.
+    fn authenticate(m: &[u8], k: &Self::Key) -> Self::Tag;
+
+    /// `verify()` returns `true` if `tag` is a correct authenticator of
+    /// message `m` under a secret key `k`.
+    fn verify(tag: &Self::Tag, m: &[u8], k: &Self::Key) -> bool;
+}
+
+/// `MacState` abstracts over the streaming authentication states of the
+/// primitives implementing `Mac`.
+pub trait MacState: Sized {
+    /// Authenticator tag type.
+    type Tag;
+
+    /// `init()` initializes an authentication structure using a secret key `k`.
+    fn init(k: &[u8]) -> Self;
+
+    /// `update()` adds a chunk of the message to the computation.
+    fn update(&mut self, m: &[u8]);
+
+    /// `finalize()` finalizes the authenticator computation and returns a `Tag`.
+    fn finalize(self) -> Self::Tag;
+
+    /// `finalize_verify()` finalizes the authenticator computation and
+    /// compares the result with `tag` in constant time.
+    fn finalize_verify(self, tag: &Self::Tag) -> bool;
+}
+
+/// Defines the marker type `$mac_name` implementing `Mac` and implements
+/// `MacState` for `State`, both in terms of the items generated by
+/// `auth_module!` and `auth_state!`.
+macro_rules! auth_mac (($mac_name:ident) => (
+
+/// Marker type implementing [`Mac`](super::Mac) for this primitive.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct $mac_name;
+
+impl super::Mac for $mac_name {
+    type Key = Key;
+    type Tag = Tag;
+    type State = State;
+
+    const KEYBYTES: usize = KEYBYTES;
+    const TAGBYTES: usize = TAGBYTES;
+
+    fn gen_key() -> Key {
+        gen_key()
+    }
+
+    fn authenticate(m: &[u8], k: &Key) -> Tag {
+        authenticate(m, k)
+    }
+
+    fn verify(tag: &Tag, m: &[u8], k: &Key) -> bool {
+        verify(tag, m, k)
+    }
+}
+
+impl super::MacState for State {
+    type Tag = Tag;
+
+    fn init(k: &[u8]) -> State {
+        State::init(k)
+    }
+
+    fn update(&mut self, m: &[u8]) {
+        State::update(self, m)
+    }
+
+    fn finalize(self) -> Tag {
+        State::finalize(self)
+    }
+
+    fn finalize_verify(self, tag: &Tag) -> bool {
+        State::finalize_verify(self, tag)
+    }
+}
+
+));
 
 pub use self::hmacsha512256::*;
 #[macro_use]
 mod auth_macros;
 #[macro_use]
 mod auth_state_macros;
+pub mod blake2b;
 pub mod hmacsha256;
 pub mod hmacsha512;
 pub mod hmacsha512256;
+
+/// `Algorithm` selects one of the authentication primitives at runtime, e.g.
+/// from configuration. Keys and tags are passed as byte slices.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    /// `HMAC-SHA-256`, see [`hmacsha256`].
+    HmacSha256,
+    /// `HMAC-SHA-512`, see [`hmacsha512`].
+    HmacSha512,
+    /// `HMAC-SHA-512-256`, see [`hmacsha512256`].
+    HmacSha512256,
+    /// Keyed `BLAKE2b`, see [`blake2b`].
+    Blake2b,
+}
+
+macro_rules! dispatch (($alg:expr, $m:ident => $e:expr) => (
+    match $alg {
+        Algorithm::HmacSha256 => { use self::hmacsha256 as $m; $e }
+        Algorithm::HmacSha512 => { use self::hmacsha512 as $m; $e }
+        Algorithm::HmacSha512256 => { use self::hmacsha512256 as $m; $e }
+        Algorithm::Blake2b => { use self::blake2b as $m; $e }
+    }
+));
+
+// `dispatch!` qualifies paths for every primitive, including the glob-imported default.
+#[allow(unused_qualifications)]
+impl Algorithm {
+    /// `key_len()` returns the number of bytes in a key for this algorithm.
+    pub fn key_len(self) -> usize {
+        dispatch!(self, m => m::KEYBYTES)
+    }
+
+    /// `tag_len()` returns the number of bytes in a tag for this algorithm.
+    pub fn tag_len(self) -> usize {
+        dispatch!(self, m => m::TAGBYTES)
+    }
+
+    /// `authenticate_dyn()` authenticates a message `m` using the secret key
+    /// `k` and returns the tag bytes.
+    ///
+    /// It returns `Err(Error::InvalidLength)` if `k` is not `key_len()` bytes
+    /// long.
+    pub fn authenticate_dyn(self, m: &[u8], k: &[u8]) -> Result<Vec<u8>, ::Error> {
+        dispatch!(self, m => {
+            let k = m::Key::from_slice(k).ok_or(::Error::InvalidLength)?;
+            Ok(m::authenticate(m, &k).as_ref().to_vec())
+        })
+    }
+
+    /// `verify_dyn()` returns `true` if `tag` is a correct authenticator of
+    /// message `m` under the secret key `k`, comparing in constant time.
+    ///
+    /// It returns `false` if `tag` or `k` have the wrong length.
+    pub fn verify_dyn(self, tag: &[u8], m: &[u8], k: &[u8]) -> bool {
+        dispatch!(self, m => {
+            match (m::Tag::from_slice(tag), m::Key::from_slice(k)) {
+                (Some(tag), Some(k)) => m::verify(&tag, m, &k),
+                _ => false,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check_mac<M: Mac>() {
+        use randombytes::randombytes;
+        for i in 0..64usize {
+            let k = M::gen_key();
+            assert_eq!(k.as_ref().len(), M::KEYBYTES);
+            let m = randombytes(i);
+            let tag = M::authenticate(&m, &k);
+            assert_eq!(tag.as_ref().len(), M::TAGBYTES);
+            assert!(M::verify(&tag, &m, &k));
+
+            let mut state = <M::State as MacState>::init(k.as_ref());
+            for c in m.chunks(3) {
+                state.update(c);
+            }
+            assert!(state.finalize() == tag);
+
+            let mut state = <M::State as MacState>::init(k.as_ref());
+            state.update(&m);
+            assert!(state.finalize_verify(&tag));
+
+            let mut m2 = m.clone();
+            m2.push(0);
+            assert!(!M::verify(&tag, &m2, &k));
+        }
+    }
+
+    #[test]
+    fn test_mac_generic() {
+        check_mac::<hmacsha256::HmacSha256>();
+        check_mac::<hmacsha512::HmacSha512>();
+        check_mac::<HmacSha512256>();
+        check_mac::<blake2b::Blake2b>();
+    }
+
+    #[test]
+    fn test_mac_eq_free_functions() {
+        let k = hmacsha512::gen_key();
+        assert_eq!(
+            <hmacsha512::HmacSha512 as Mac>::authenticate(b"data", &k),
+            hmacsha512::authenticate(b"data", &k)
+        );
+        let k = gen_key();
+        assert_eq!(
+            <HmacSha512256 as Mac>::authenticate(b"data", &k),
+            authenticate(b"data", &k)
+        );
+    }
+
+    #[test]
+    fn test_algorithm_dyn() {
+        use randombytes::randombytes;
+        let algs = [
+            Algorithm::HmacSha256,
+            Algorithm::HmacSha512,
+            Algorithm::HmacSha512256,
+            Algorithm::Blake2b,
+        ];
+        for &alg in algs.iter() {
+            let k = randombytes(alg.key_len());
+            for i in 0..64usize {
+                let m = randombytes(i);
+                let tag = alg.authenticate_dyn(&m, &k).unwrap();
+                assert_eq!(tag.len(), alg.tag_len());
+                assert!(alg.verify_dyn(&tag, &m, &k));
+                assert!(!alg.verify_dyn(&tag[1..], &m, &k));
+                assert!(!alg.verify_dyn(&tag, &m, &k[1..]));
+                let mut tag2 = tag.clone();
+                tag2[0] ^= 0x20;
+                assert!(!alg.verify_dyn(&tag2, &m, &k));
+            }
+            assert!(alg.authenticate_dyn(b"data", &k[1..]).is_err());
+        }
+
+        let k = hmacsha256::gen_key();
+        assert_eq!(
+            Algorithm::HmacSha256.authenticate_dyn(b"data", k.as_ref()),
+            Ok(hmacsha256::authenticate(b"data", &k).as_ref().to_vec())
+        );
+        let k = hmacsha512::gen_key();
+        assert_eq!(
+            Algorithm::HmacSha512.authenticate_dyn(b"data", k.as_ref()),
+            Ok(hmacsha512::authenticate(b"data", &k).as_ref().to_vec())
+        );
+    }
+}
@@ -0,0 +1,81 @@
+/// Macro for defining `seal_with_nonce_prefix()` and
+/// `open_with_nonce_prefix()` on top of the `seal()`, `open()` and
+/// `gen_nonce()` of a box construction.
+macro_rules! box_nonce_prefix (() => (
+
+/// `seal_with_nonce_prefix()` encrypts and authenticates a message `m` using the sender's
+/// secret key `sk`, the receiver's public key `pk` and a freshly generated random nonce. The
+/// nonce is prepended to the ciphertext, so the result is `NONCEBYTES + MACBYTES + m.len()`
+/// bytes long and can be passed to `open_with_nonce_prefix()` as is.
+pub fn seal_with_nonce_prefix(m: &[u8], pk: &PublicKey, sk: &SecretKey) -> Vec<u8> {
+    let n = gen_nonce();
+    let sealed = seal(m, &n, pk, sk);
+    let mut c = Vec::with_capacity(NONCEBYTES + sealed.len());
+    c.extend_from_slice(&n.0);
+    c.extend_from_slice(&sealed);
+    c
+}
+
+/// `open_with_nonce_prefix()` verifies and decrypts a ciphertext `c` produced by
+/// `seal_with_nonce_prefix()`, using the receiver's secret key `sk` and the sender's public
+/// key `pk`. It returns a plaintext `Ok(m)`.
+///
+/// It returns `Err(Error::InvalidLength)` if `c` is too short to hold a nonce and an
+/// authentication tag, and fails like `open()` otherwise.
+pub fn open_with_nonce_prefix(
+    c: &[u8],
+    pk: &PublicKey,
+    sk: &SecretKey,
+) -> Result<Vec<u8>, ::Error> {
+    if c.len() < NONCEBYTES + MACBYTES {
+        return Err(::Error::InvalidLength);
+    }
+    let (n, c) = c.split_at(NONCEBYTES);
+    let n = Nonce::from_slice(n).ok_or(::Error::InvalidLength)?;
+    open(c, &n, pk, sk)
+}
+
+#[cfg(test)]
+mod test_nonce_prefix {
+    use super::*;
+
+    #[test]
+    fn test_seal_open_with_nonce_prefix() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let (pk1, sk1) = gen_keypair();
+            let (pk2, sk2) = gen_keypair();
+            let m = randombytes(i);
+            let c = seal_with_nonce_prefix(&m, &pk1, &sk2);
+            assert_eq!(c.len(), NONCEBYTES + MACBYTES + m.len());
+            assert_eq!(open_with_nonce_prefix(&c, &pk2, &sk1), Ok(m.clone()));
+            let n = Nonce::from_slice(&c[..NONCEBYTES]).unwrap();
+            assert_eq!(open(&c[NONCEBYTES..], &n, &pk2, &sk1), Ok(m));
+        }
+    }
+
+    #[test]
+    fn test_open_with_nonce_prefix_invalid() {
+        let (pk1, sk1) = gen_keypair();
+        let (pk2, sk2) = gen_keypair();
+        let mut c = seal_with_nonce_prefix(b"some data", &pk1, &sk2);
+        assert_eq!(
+            open_with_nonce_prefix(&c[..NONCEBYTES + MACBYTES - 1], &pk2, &sk1),
+            Err(::Error::InvalidLength)
+        );
+        assert_eq!(
+            open_with_nonce_prefix(&c, &pk1, &sk1),
+            Err(::Error::VerificationFailed)
+        );
+        for i in 0..c.len() {
+            c[i] ^= 0x01;
+            assert_eq!(
+                open_with_nonce_prefix(&c, &pk2, &sk1),
+                Err(::Error::VerificationFailed)
+            );
+            c[i] ^= 0x01;
+        }
+    }
+}
+
+));
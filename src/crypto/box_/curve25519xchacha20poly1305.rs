@@ -0,0 +1,645 @@
+//! `crypto_box_curve25519xchacha20poly1305`, a variant of `crypto_box` using
+//! the `XChaCha20` stream cipher instead of `XSalsa20`.
+//!
+//! Key pairs are the same `Curve25519` key pairs as for
+//! [`curve25519xsalsa20poly1305`](super::curve25519xsalsa20poly1305), so the
+//! key types are shared, but ciphertexts and precomputed keys of the two
+//! constructions are not interchangeable.
+//!
+//! This function is conjectured to meet the standard notions of privacy and
+//! third-party unforgeability.
+
+use crypto::nonce::gen_random_nonce;
+use ffi;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use Error;
+
+pub use super::curve25519xsalsa20poly1305::{
+    gen_keypair, keypair_from_seed, PublicKey, SecretKey, Seed, PUBLICKEYBYTES, SECRETKEYBYTES,
+    SEEDBYTES,
+};
+
+/// Number of bytes in a `Nonce`.
+pub const NONCEBYTES: usize = ffi::crypto_box_curve25519xchacha20poly1305_NONCEBYTES as usize;
+
+/// Number of bytes in a `PrecomputedKey`.
+pub const PRECOMPUTEDKEYBYTES: usize =
+    ffi::crypto_box_curve25519xchacha20poly1305_BEFORENMBYTES as usize;
+
+/// Number of bytes in the authenticator tag of an encrypted message
+/// i.e. the number of bytes by which the ciphertext is larger than the
+/// plaintext.
+pub const MACBYTES: usize = ffi::crypto_box_curve25519xchacha20poly1305_MACBYTES as usize;
+
+new_type! {
+    /// Authentication `Tag` for the detached encryption mode
+    ///
+    /// In the combined mode, the tag occupies the first MACBYTES bytes of the ciphertext.
+    public Tag(MACBYTES);
+}
+
+new_type! {
+    /// `Nonce` for asymmetric authenticated encryption
+    nonce Nonce(NONCEBYTES);
+}
+
+/// `gen_nonce()` randomly generates a nonce
+///
+/// THREAD SAFETY: `gen_nonce()` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+pub fn gen_nonce() -> Nonce {
+    gen_random_nonce()
+}
+
+/// `seal()` encrypts and authenticates a message `m` using the senders secret key `sk`,
+/// the receivers public key `pk` and a nonce `n`. It returns a ciphertext `c`.
+pub fn seal(m: &[u8], n: &Nonce, pk: &PublicKey, sk: &SecretKey) -> Vec<u8> {
+    let clen = m.len() + MACBYTES;
+    let mut c = vec![0u8; clen];
+    unsafe {
+        ffi::crypto_box_curve25519xchacha20poly1305_easy(
+            c.as_mut_ptr(),
+            m.as_ptr(),
+            m.len() as u64,
+            n.0.as_ptr(),
+            pk.0.as_ptr(),
+            sk.0.as_ptr(),
+        );
+    }
+    c
+}
+
+/// `seal_detached()` encrypts and authenticates a message `m` using the senders secret key `sk`,
+/// the receivers public key `pk` and a nonce `n`. `m` is encrypted in place, so after this
+/// function returns it will contain the ciphertext. The detached authentication tag is returned by
+/// value.
+pub fn seal_detached(m: &mut [u8], n: &Nonce, pk: &PublicKey, sk: &SecretKey) -> Tag {
+    let mut tag = [0; MACBYTES];
+    unsafe {
+        ffi::crypto_box_curve25519xchacha20poly1305_detached(
+            m.as_mut_ptr(),
+            tag.as_mut_ptr(),
+            m.as_ptr(),
+            m.len() as u64,
+            n.0.as_ptr(),
+            pk.0.as_ptr(),
+            sk.0.as_ptr(),
+        );
+    };
+    Tag(tag)
+}
+
+/// `open()` verifies and decrypts a ciphertext `c` using the receiver's secret key `sk`,
+/// the senders public key `pk`, and a nonce `n`. It returns a plaintext `Ok(m)`.
+/// If the ciphertext fails verification, `open()` returns
+/// `Err(Error::VerificationFailed)`, or `Err(Error::InvalidLength)` if it is
+/// too short to hold an authentication tag.
+pub fn open(c: &[u8], n: &Nonce, pk: &PublicKey, sk: &SecretKey) -> Result<Vec<u8>, Error> {
+    if c.len() < MACBYTES {
+        return Err(Error::InvalidLength);
+    }
+    let mlen = c.len() - MACBYTES;
+    let mut m = vec![0u8; mlen];
+    let ret = unsafe {
+        ffi::crypto_box_curve25519xchacha20poly1305_open_easy(
+            m.as_mut_ptr(),
+            c.as_ptr(),
+            c.len() as u64,
+            n.0.as_ptr(),
+            pk.0.as_ptr(),
+            sk.0.as_ptr(),
+        )
+    };
+    if ret == 0 {
+        Ok(m)
+    } else {
+        Err(Error::VerificationFailed)
+    }
+}
+
+/// `open_detached()` verifies and decrypts a ciphertext `c` using the receiver's secret key `sk`,
+/// the senders public key `pk`, and a nonce `n`. `c` is decrypted in place, so if this function is
+/// successful it will contain the plaintext. If the ciphertext fails verification,
+/// `open_detached()` returns `Err(Error::VerificationFailed)`, and the ciphertext is not
+/// modified.
+pub fn open_detached(
+    c: &mut [u8],
+    mac: &Tag,
+    n: &Nonce,
+    pk: &PublicKey,
+    sk: &SecretKey,
+) -> Result<(), Error> {
+    let ret = unsafe {
+        ffi::crypto_box_curve25519xchacha20poly1305_open_detached(
+            c.as_mut_ptr(),
+            c.as_ptr(),
+            mac.0.as_ptr(),
+            c.len() as u64,
+            n.0.as_ptr(),
+            pk.0.as_ptr(),
+            sk.0.as_ptr(),
+        )
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(Error::VerificationFailed)
+    }
+}
+
+box_nonce_prefix!();
+
+new_type! {
+    /// Applications that send several messages to the same receiver can gain speed by
+    /// splitting `seal()` into two steps, `precompute()` and `seal_precomputed()`.
+    /// Similarly, applications that receive several messages from the same sender can gain
+    /// speed by splitting `open()` into two steps, `precompute()` and `open_precomputed()`.
+    ///
+    /// When a `PrecomputedKey` goes out of scope its contents will be zeroed out
+    secret PrecomputedKey(PRECOMPUTEDKEYBYTES);
+}
+
+/// `precompute()` computes an intermediate key that can be used by `seal_precomputed()`
+/// and `open_precomputed()`
+pub fn precompute(pk: &PublicKey, sk: &SecretKey) -> PrecomputedKey {
+    let mut k = PrecomputedKey([0u8; PRECOMPUTEDKEYBYTES]);
+    unsafe {
+        ffi::crypto_box_curve25519xchacha20poly1305_beforenm(
+            k.0.as_mut_ptr(),
+            pk.0.as_ptr(),
+            sk.0.as_ptr(),
+        );
+    }
+    k
+}
+
+/// `seal_precomputed()` encrypts and authenticates a message `m` using a precomputed key `k`,
+/// and a nonce `n`. It returns a ciphertext `c`.
+pub fn seal_precomputed(m: &[u8], n: &Nonce, k: &PrecomputedKey) -> Vec<u8> {
+    let clen = m.len() + MACBYTES;
+    let mut c = vec![0u8; clen];
+    unsafe {
+        ffi::crypto_box_curve25519xchacha20poly1305_easy_afternm(
+            c.as_mut_ptr(),
+            m.as_ptr(),
+            m.len() as u64,
+            n.0.as_ptr(),
+            k.0.as_ptr(),
+        );
+    }
+    c
+}
+
+/// `seal_detached_precomputed()` encrypts and authenticates a message `m` using a precomputed key
+/// `k` and a nonce `n`. `m` is encrypted in place, so after this function returns it will contain
+/// the ciphertext. The detached authentication tag is returned by value.
+pub fn seal_detached_precomputed(m: &mut [u8], n: &Nonce, k: &PrecomputedKey) -> Tag {
+    let mut tag = [0; MACBYTES];
+    unsafe {
+        ffi::crypto_box_curve25519xchacha20poly1305_detached_afternm(
+            m.as_mut_ptr(),
+            tag.as_mut_ptr(),
+            m.as_ptr(),
+            m.len() as u64,
+            n.0.as_ptr(),
+            k.0.as_ptr(),
+        );
+    };
+    Tag(tag)
+}
+
+/// `open_precomputed()` verifies and decrypts a ciphertext `c` using a precomputed
+/// key `k` and a nonce `n`. It returns a plaintext `Ok(m)`.
+/// If the ciphertext fails verification, `open_precomputed()` returns
+/// `Err(Error::VerificationFailed)`, or `Err(Error::InvalidLength)` if it is
+/// too short to hold an authentication tag.
+pub fn open_precomputed(c: &[u8], n: &Nonce, k: &PrecomputedKey) -> Result<Vec<u8>, Error> {
+    if c.len() < MACBYTES {
+        return Err(Error::InvalidLength);
+    }
+    let mlen = c.len() - MACBYTES;
+    let mut m = vec![0u8; mlen];
+    unsafe {
+        let ret = ffi::crypto_box_curve25519xchacha20poly1305_open_easy_afternm(
+            m.as_mut_ptr(),
+            c.as_ptr(),
+            c.len() as u64,
+            n.0.as_ptr(),
+            k.0.as_ptr(),
+        );
+        if ret == 0 {
+            Ok(m)
+        } else {
+            Err(Error::VerificationFailed)
+        }
+    }
+}
+
+/// `open_detached_precomputed()` verifies and decrypts a ciphertext `c` using a precomputed key
+/// `k` and a nonce `n`. `c` is decrypted in place, so if this function is successful it will
+/// contain the plaintext. If the ciphertext fails verification, `open_detached()` returns
+/// `Err(Error::VerificationFailed)`, and the ciphertext is not modified.
+pub fn open_detached_precomputed(
+    c: &mut [u8],
+    mac: &Tag,
+    n: &Nonce,
+    k: &PrecomputedKey,
+) -> Result<(), Error> {
+    let ret = unsafe {
+        ffi::crypto_box_curve25519xchacha20poly1305_open_detached_afternm(
+            c.as_mut_ptr(),
+            c.as_ptr(),
+            mac.0.as_ptr(),
+            c.len() as u64,
+            n.0.as_ptr(),
+            k.0.as_ptr(),
+        )
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(Error::VerificationFailed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex;
+
+    #[test]
+    fn test_seal_open() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let (pk1, sk1) = gen_keypair();
+            let (pk2, sk2) = gen_keypair();
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let c = seal(&m, &n, &pk1, &sk2);
+            let opened = open(&c, &n, &pk2, &sk1);
+            assert!(Ok(m) == opened);
+        }
+    }
+
+    #[test]
+    fn test_seal_open_precomputed() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let (pk1, sk1) = gen_keypair();
+            let (pk2, sk2) = gen_keypair();
+            let k1 = precompute(&pk1, &sk2);
+            let PrecomputedKey(k1buf) = k1;
+            let k2 = precompute(&pk2, &sk1);
+            let PrecomputedKey(k2buf) = k2;
+            assert!(k1buf == k2buf);
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let c = seal_precomputed(&m, &n, &k1);
+            let opened = open_precomputed(&c, &n, &k2);
+            assert!(Ok(m) == opened);
+        }
+    }
+
+    #[test]
+    fn test_seal_open_tamper() {
+        use randombytes::randombytes;
+        for i in 0..32usize {
+            let (pk1, sk1) = gen_keypair();
+            let (pk2, sk2) = gen_keypair();
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let mut c = seal(&m, &n, &pk1, &sk2);
+            for j in 0..c.len() {
+                c[j] ^= 0x20;
+                assert!(Err(Error::VerificationFailed) == open(&c, &n, &pk2, &sk1));
+                c[j] ^= 0x20;
+            }
+        }
+    }
+
+    #[test]
+    fn test_seal_open_precomputed_tamper() {
+        use randombytes::randombytes;
+        for i in 0..32usize {
+            let (pk1, sk1) = gen_keypair();
+            let (pk2, sk2) = gen_keypair();
+            let k1 = precompute(&pk1, &sk2);
+            let k2 = precompute(&pk2, &sk1);
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let mut c = seal_precomputed(&m, &n, &k1);
+            for j in 0..c.len() {
+                c[j] ^= 0x20;
+                assert!(Err(Error::VerificationFailed) == open_precomputed(&c, &n, &k2));
+                c[j] ^= 0x20;
+            }
+        }
+    }
+
+    #[test]
+    fn test_seal_open_seed() {
+        use randombytes::{randombytes, randombytes_into};
+        for i in 0..256usize {
+            let mut seedbuf = [0; 32];
+            randombytes_into(&mut seedbuf);
+            let seed = Seed(seedbuf);
+            let (pk1, sk1) = keypair_from_seed(&seed);
+            let (pk2, sk2) = gen_keypair();
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let c = seal(&m, &n, &pk1, &sk2);
+            let opened = open(&c, &n, &pk2, &sk1);
+            assert!(Ok(m) == opened);
+        }
+    }
+
+    #[test]
+    fn test_seal_open_seed_tamper() {
+        use randombytes::{randombytes, randombytes_into};
+        for i in 0..32usize {
+            let mut seedbuf = [0; 32];
+            randombytes_into(&mut seedbuf);
+            let seed = Seed(seedbuf);
+            let (pk1, sk1) = keypair_from_seed(&seed);
+            let (pk2, sk2) = gen_keypair();
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let mut c = seal(&m, &n, &pk1, &sk2);
+            for j in 0..c.len() {
+                c[j] ^= 0x20;
+                assert!(Err(Error::VerificationFailed) == open(&c, &n, &pk2, &sk1));
+                c[j] ^= 0x20;
+            }
+        }
+    }
+
+    #[test]
+    fn test_seal_open_detached() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let (pk1, sk1) = gen_keypair();
+            let (pk2, sk2) = gen_keypair();
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let mut buf = m.clone();
+            let tag = seal_detached(&mut buf, &n, &pk1, &sk2);
+            open_detached(&mut buf, &tag, &n, &pk2, &sk1).unwrap();
+            assert_eq!(m, buf);
+        }
+    }
+
+    #[test]
+    fn test_seal_combined_then_open_detached() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let (pk1, sk1) = gen_keypair();
+            let (pk2, sk2) = gen_keypair();
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let mut c = seal(&m, &n, &pk1, &sk2);
+            let tag = Tag::from_slice(&c[..MACBYTES]).unwrap();
+            let buf = &mut c[MACBYTES..];
+            open_detached(buf, &tag, &n, &pk2, &sk1).unwrap();
+            assert_eq!(buf, &*m);
+        }
+    }
+
+    #[test]
+    fn test_seal_detached_then_open_combined() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let (pk1, sk1) = gen_keypair();
+            let (pk2, sk2) = gen_keypair();
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let mut buf = vec![0; MACBYTES];
+            buf.extend_from_slice(&m);
+            let tag = seal_detached(&mut buf[MACBYTES..], &n, &pk1, &sk2);
+            buf[..MACBYTES].copy_from_slice(&tag.0[..]);
+            let opened = open(&buf, &n, &pk2, &sk1);
+            assert_eq!(Ok(m), opened);
+        }
+    }
+
+    #[test]
+    fn test_seal_open_detached_tamper() {
+        use randombytes::randombytes;
+        for i in 0..32usize {
+            let (pk1, sk1) = gen_keypair();
+            let (pk2, sk2) = gen_keypair();
+            let mut m = randombytes(i);
+            let n = gen_nonce();
+            let mut tag = seal_detached(&mut m, &n, &pk1, &sk2);
+            for j in 0..m.len() {
+                m[j] ^= 0x20;
+                assert_eq!(
+                    Err(Error::VerificationFailed),
+                    open_detached(&mut m, &tag, &n, &pk2, &sk1)
+                );
+                m[j] ^= 0x20;
+            }
+            for j in 0..tag.0.len() {
+                tag.0[j] ^= 0x20;
+                assert_eq!(
+                    Err(Error::VerificationFailed),
+                    open_detached(&mut m, &tag, &n, &pk2, &sk1)
+                );
+                tag.0[j] ^= 0x20;
+            }
+        }
+    }
+
+    #[test]
+    fn test_open_detached_failure_does_not_modify() {
+        let mut buf = b"hello world".to_vec();
+        let (pk1, sk1) = gen_keypair();
+        let (pk2, sk2) = gen_keypair();
+        let n = gen_nonce();
+        let tag = seal_detached(&mut buf, &n, &pk1, &sk2);
+        // Flip the last bit in the ciphertext, to break authentication.
+        *buf.last_mut().unwrap() ^= 1;
+        // Make a copy that we can compare against after the failure below.
+        let copy = buf.clone();
+        // Now try to open the message. This will fail.
+        let failure = open_detached(&mut buf, &tag, &n, &pk2, &sk1);
+        assert!(failure.is_err());
+        // Make sure the input hasn't been touched.
+        assert_eq!(
+            buf, copy,
+            "input should not be modified if authentication fails"
+        );
+    }
+
+    #[test]
+    fn test_seal_open_detached_precomputed() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let (pk1, sk1) = gen_keypair();
+            let (pk2, sk2) = gen_keypair();
+            let k1 = precompute(&pk1, &sk2);
+            let k2 = precompute(&pk2, &sk1);
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let mut buf = m.clone();
+            let tag = seal_detached_precomputed(&mut buf, &n, &k1);
+            open_detached_precomputed(&mut buf, &tag, &n, &k2).unwrap();
+            assert_eq!(m, buf);
+        }
+    }
+
+    #[test]
+    fn test_seal_combined_then_open_detached_precomputed() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let (pk1, sk1) = gen_keypair();
+            let (pk2, sk2) = gen_keypair();
+            let k1 = precompute(&pk1, &sk2);
+            let k2 = precompute(&pk2, &sk1);
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let mut c = seal_precomputed(&m, &n, &k1);
+            let tag = Tag::from_slice(&c[..MACBYTES]).unwrap();
+            let buf = &mut c[MACBYTES..];
+            open_detached_precomputed(buf, &tag, &n, &k2).unwrap();
+            assert_eq!(buf, &*m);
+        }
+    }
+
+    #[test]
+    fn test_seal_detached_precomputed_then_open_combined() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let (pk1, sk1) = gen_keypair();
+            let (pk2, sk2) = gen_keypair();
+            let k1 = precompute(&pk1, &sk2);
+            let k2 = precompute(&pk2, &sk1);
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let mut buf = vec![0; MACBYTES];
+            buf.extend_from_slice(&m);
+            let tag = seal_detached_precomputed(&mut buf[MACBYTES..], &n, &k1);
+            buf[..MACBYTES].copy_from_slice(&tag.0[..]);
+            let opened = open_precomputed(&buf, &n, &k2);
+            assert_eq!(Ok(m), opened);
+        }
+    }
+
+    #[test]
+    fn test_seal_open_detached_precomputed_tamper() {
+        use randombytes::randombytes;
+        for i in 0..32usize {
+            let (pk1, sk1) = gen_keypair();
+            let (pk2, sk2) = gen_keypair();
+            let k1 = precompute(&pk1, &sk2);
+            let k2 = precompute(&pk2, &sk1);
+            let mut m = randombytes(i);
+            let n = gen_nonce();
+            let mut tag = seal_detached_precomputed(&mut m, &n, &k1);
+            for j in 0..m.len() {
+                m[j] ^= 0x20;
+                assert_eq!(
+                    Err(Error::VerificationFailed),
+                    open_detached_precomputed(&mut m, &tag, &n, &k2)
+                );
+                m[j] ^= 0x20;
+            }
+            for j in 0..tag.0.len() {
+                tag.0[j] ^= 0x20;
+                assert_eq!(
+                    Err(Error::VerificationFailed),
+                    open_detached_precomputed(&mut m, &tag, &n, &k2)
+                );
+                tag.0[j] ^= 0x20;
+            }
+        }
+    }
+
+    #[test]
+    fn test_open_detached_precomputed_failure_does_not_modify() {
+        let mut buf = b"hello world".to_vec();
+        let (pk1, sk1) = gen_keypair();
+        let (pk2, sk2) = gen_keypair();
+        let k1 = precompute(&pk1, &sk2);
+        let k2 = precompute(&pk2, &sk1);
+        let n = gen_nonce();
+        let tag = seal_detached_precomputed(&mut buf, &n, &k1);
+        // Flip the last bit in the ciphertext, to break authentication.
+        *buf.last_mut().unwrap() ^= 1;
+        // Make a copy that we can compare against after the failure below.
+        let copy = buf.clone();
+        // Now try to open the message. This will fail.
+        let failure = open_detached_precomputed(&mut buf, &tag, &n, &k2);
+        assert!(failure.is_err());
+        // Make sure the input hasn't been touched.
+        assert_eq!(
+            buf, copy,
+            "input should not be modified if authentication fails"
+        );
+    }
+
+    #[test]
+    fn test_vector_1() {
+        // regression vector computed with libsodium 1.0.18, using the keys and
+        // nonce of the `NaCl` box test vector
+        let alicesk = SecretKey([
+            0x77, 0x07, 0x6d, 0x0a, 0x73, 0x18, 0xa5, 0x7d, 0x3c, 0x16, 0xc1, 0x72, 0x51, 0xb2,
+            0x66, 0x45, 0xdf, 0x4c, 0x2f, 0x87, 0xeb, 0xc0, 0x99, 0x2a, 0xb1, 0x77, 0xfb, 0xa5,
+            0x1d, 0xb9, 0x2c, 0x2a,
+        ]);
+        let bobpk = PublicKey([
+            0xde, 0x9e, 0xdb, 0x7d, 0x7b, 0x7d, 0xc1, 0xb4, 0xd3, 0x5b, 0x61, 0xc2, 0xec, 0xe4,
+            0x35, 0x37, 0x3f, 0x83, 0x43, 0xc8, 0x5b, 0x78, 0x67, 0x4d, 0xad, 0xfc, 0x7e, 0x14,
+            0x6f, 0x88, 0x2b, 0x4f,
+        ]);
+        let nonce = Nonce([
+            0x69, 0x69, 0x6e, 0xe9, 0x55, 0xb6, 0x2b, 0x73, 0xcd, 0x62, 0xbd, 0xa8, 0x75, 0xfc,
+            0x73, 0xd6, 0x82, 0x19, 0xe0, 0x03, 0x6b, 0x7a, 0x0b, 0x37,
+        ]);
+        let c = seal(b"xchacha20poly1305 box", &nonce, &bobpk, &alicesk);
+        assert_eq!(
+            hex::encode(&c),
+            "d6e344c4c0a06f099d1a72830f564f27294857c5e8557feb02164c2f64c52387c59cca1f30"
+        );
+    }
+
+    #[test]
+    fn test_not_interchangeable() {
+        use super::super::curve25519xsalsa20poly1305 as xsalsa;
+        let (pk1, sk1) = gen_keypair();
+        let (pk2, sk2) = gen_keypair();
+        let n = gen_nonce();
+        let c = seal(b"some data", &n, &pk1, &sk2);
+        let xsalsa_n = xsalsa::Nonce::from_slice(n.as_ref()).unwrap();
+        assert!(xsalsa::open(&c, &xsalsa_n, &pk2, &sk1).is_err());
+        assert_eq!(open(&c, &n, &pk2, &sk1), Ok(b"some data".to_vec()));
+    }
+
+    #[test]
+    fn test_public_key() {
+        for _ in 0..256usize {
+            let (pk1, sk) = gen_keypair();
+            let pk2 = sk.public_key();
+            assert_eq!(pk1, pk2);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialisation() {
+        use test_utils::round_trip;
+        for _ in 0..256usize {
+            let (pk, sk) = gen_keypair();
+            let n = gen_nonce();
+            round_trip(pk);
+            round_trip(sk);
+            round_trip(n);
+        }
+    }
+
+    #[test]
+    fn test_nonce_length() {
+        assert_eq!(192 / 8, gen_nonce().as_ref().len());
+    }
+}
@@ -9,6 +9,7 @@ use crypto::nonce::gen_random_nonce;
 use ffi;
 #[cfg(not(feature = "std"))]
 use prelude::*;
+use Error;
 
 /// Number of bytes in a `Seed`.
 pub const SEEDBYTES: usize = ffi::crypto_box_curve25519xsalsa20poly1305_SEEDBYTES as usize;
@@ -86,6 +87,7 @@ new_type! {
 /// called `sodiumoxide::init()` once before using any other function
 /// from sodiumoxide.
 pub fn gen_keypair() -> (PublicKey, SecretKey) {
+    ::ensure_init();
     unsafe {
         let mut pk = PublicKey([0u8; PUBLICKEYBYTES]);
         let mut sk = SecretKey([0u8; SECRETKEYBYTES]);
@@ -162,10 +164,12 @@ pub fn seal_detached(m: &mut [u8], n: &Nonce, pk: &PublicKey, sk: &SecretKey) ->
 
 /// `open()` verifies and decrypts a ciphertext `c` using the receiver's secret key `sk`,
 /// the senders public key `pk`, and a nonce `n`. It returns a plaintext `Ok(m)`.
-/// If the ciphertext fails verification, `open()` returns `Err(())`.
-pub fn open(c: &[u8], n: &Nonce, pk: &PublicKey, sk: &SecretKey) -> Result<Vec<u8>, ()> {
+/// If the ciphertext fails verification, `open()` returns
+/// `Err(Error::VerificationFailed)`, or `Err(Error::InvalidLength)` if it is
+/// too short to hold an authentication tag.
+pub fn open(c: &[u8], n: &Nonce, pk: &PublicKey, sk: &SecretKey) -> Result<Vec<u8>, Error> {
     if c.len() < MACBYTES {
-        return Err(());
+        return Err(Error::InvalidLength);
     }
     let mlen = c.len() - MACBYTES;
     let mut m = Vec::with_capacity(mlen);
@@ -183,21 +187,22 @@ pub fn open(c: &[u8], n: &Nonce, pk: &PublicKey, sk: &SecretKey) -> Result<Vec<u
     if ret == 0 {
         Ok(m)
     } else {
-        Err(())
+        Err(Error::VerificationFailed)
     }
 }
 
 /// `open_detached()` verifies and decrypts a ciphertext `c` using the receiver's secret key `sk`,
 /// the senders public key `pk`, and a nonce `n`. `c` is decrypted in place, so if this function is
 /// successful it will contain the plaintext. If the ciphertext fails verification,
-/// `open_detached()` returns `Err(())`, and the ciphertext is not modified.
+/// `open_detached()` returns `Err(Error::VerificationFailed)`, and the ciphertext is not
+/// modified.
 pub fn open_detached(
     c: &mut [u8],
     mac: &Tag,
     n: &Nonce,
     pk: &PublicKey,
     sk: &SecretKey,
-) -> Result<(), ()> {
+) -> Result<(), Error> {
     let ret = unsafe {
         ffi::crypto_box_open_detached(
             c.as_mut_ptr(),
@@ -212,10 +217,12 @@ pub fn open_detached(
     if ret == 0 {
         Ok(())
     } else {
-        Err(())
+        Err(Error::VerificationFailed)
     }
 }
 
+box_nonce_prefix!();
+
 new_type! {
     /// Applications that send several messages to the same receiver can gain speed by
     /// splitting `seal()` into two steps, `precompute()` and `seal_precomputed()`.
@@ -278,10 +285,12 @@ pub fn seal_detached_precomputed(m: &mut [u8], n: &Nonce, k: &PrecomputedKey) ->
 
 /// `open_precomputed()` verifies and decrypts a ciphertext `c` using a precomputed
 /// key `k` and a nonce `n`. It returns a plaintext `Ok(m)`.
-/// If the ciphertext fails verification, `open_precomputed()` returns `Err(())`.
-pub fn open_precomputed(c: &[u8], n: &Nonce, k: &PrecomputedKey) -> Result<Vec<u8>, ()> {
+/// If the ciphertext fails verification, `open_precomputed()` returns
+/// `Err(Error::VerificationFailed)`, or `Err(Error::InvalidLength)` if it is
+/// too short to hold an authentication tag.
+pub fn open_precomputed(c: &[u8], n: &Nonce, k: &PrecomputedKey) -> Result<Vec<u8>, Error> {
     if c.len() < MACBYTES {
-        return Err(());
+        return Err(Error::InvalidLength);
     }
     let mlen = c.len() - MACBYTES;
     let mut m = Vec::with_capacity(mlen);
@@ -297,7 +306,7 @@ pub fn open_precomputed(c: &[u8], n: &Nonce, k: &PrecomputedKey) -> Result<Vec<u
             m.set_len(mlen);
             Ok(m)
         } else {
-            Err(())
+            Err(Error::VerificationFailed)
         }
     }
 }
@@ -305,13 +314,13 @@ pub fn open_precomputed(c: &[u8], n: &Nonce, k: &PrecomputedKey) -> Result<Vec<u
 /// `open_detached_precomputed()` verifies and decrypts a ciphertext `c` using a precomputed key
 /// `k` and a nonce `n`. `c` is decrypted in place, so if this function is successful it will
 /// contain the plaintext. If the ciphertext fails verification, `open_detached()` returns
-/// `Err(())`, and the ciphertext is not modified.
+/// `Err(Error::VerificationFailed)`, and the ciphertext is not modified.
 pub fn open_detached_precomputed(
     c: &mut [u8],
     mac: &Tag,
     n: &Nonce,
     k: &PrecomputedKey,
-) -> Result<(), ()> {
+) -> Result<(), Error> {
     let ret = unsafe {
         ffi::crypto_box_open_detached_afternm(
             c.as_mut_ptr(),
@@ -325,7 +334,7 @@ pub fn open_detached_precomputed(
     if ret == 0 {
         Ok(())
     } else {
-        Err(())
+        Err(Error::VerificationFailed)
     }
 }
 
@@ -347,6 +356,40 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_debug_redacted() {
+        let (pk, sk) = gen_keypair();
+        let k = precompute(&pk, &sk);
+        assert_eq!(format!("{:?}", sk), "SecretKey(****)");
+        assert_eq!(format!("{:?}", k), "PrecomputedKey(****)");
+        assert_eq!(
+            format!("{:?}", PublicKey([0x0a; PUBLICKEYBYTES])),
+            format!("PublicKey({})", "0a".repeat(PUBLICKEYBYTES))
+        );
+        assert_eq!(
+            format!("{:?}", Nonce([0xff; NONCEBYTES])),
+            format!("Nonce({})", "ff".repeat(NONCEBYTES))
+        );
+    }
+
+    #[test]
+    fn test_public_key_as_map_key() {
+        use std::collections::{BTreeMap, HashMap};
+        let keys: Vec<PublicKey> = (0..16).map(|_| gen_keypair().0).collect();
+        let mut hashed = HashMap::new();
+        let mut ordered = BTreeMap::new();
+        for (i, pk) in keys.iter().enumerate() {
+            hashed.insert(*pk, i);
+            ordered.insert(*pk, i);
+        }
+        for (i, pk) in keys.iter().enumerate() {
+            assert_eq!(hashed[pk], i);
+            assert_eq!(ordered[pk], i);
+        }
+        let sorted: Vec<&PublicKey> = ordered.keys().collect();
+        assert!(sorted.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
     #[test]
     fn test_seal_open_precomputed() {
         use randombytes::randombytes;
@@ -377,7 +420,7 @@ mod test {
             let mut c = seal(&m, &n, &pk1, &sk2);
             for j in 0..c.len() {
                 c[j] ^= 0x20;
-                assert!(Err(()) == open(&c, &n, &pk2, &sk1));
+                assert!(Err(Error::VerificationFailed) == open(&c, &n, &pk2, &sk1));
                 c[j] ^= 0x20;
             }
         }
@@ -396,7 +439,7 @@ mod test {
             let mut c = seal_precomputed(&m, &n, &k1);
             for j in 0..c.len() {
                 c[j] ^= 0x20;
-                assert!(Err(()) == open_precomputed(&c, &n, &k2));
+                assert!(Err(Error::VerificationFailed) == open_precomputed(&c, &n, &k2));
                 c[j] ^= 0x20;
             }
         }
@@ -433,7 +476,7 @@ mod test {
             let mut c = seal(&m, &n, &pk1, &sk2);
             for j in 0..c.len() {
                 c[j] ^= 0x20;
-                assert!(Err(()) == open(&c, &n, &pk2, &sk1));
+                assert!(Err(Error::VerificationFailed) == open(&c, &n, &pk2, &sk1));
                 c[j] ^= 0x20;
             }
         }
@@ -498,12 +541,18 @@ mod test {
             let mut tag = seal_detached(&mut m, &n, &pk1, &sk2);
             for j in 0..m.len() {
                 m[j] ^= 0x20;
-                assert_eq!(Err(()), open_detached(&mut m, &tag, &n, &pk2, &sk1));
+                assert_eq!(
+                    Err(Error::VerificationFailed),
+                    open_detached(&mut m, &tag, &n, &pk2, &sk1)
+                );
                 m[j] ^= 0x20;
             }
             for j in 0..tag.0.len() {
                 tag.0[j] ^= 0x20;
-                assert_eq!(Err(()), open_detached(&mut m, &tag, &n, &pk2, &sk1));
+                assert_eq!(
+                    Err(Error::VerificationFailed),
+                    open_detached(&mut m, &tag, &n, &pk2, &sk1)
+                );
                 tag.0[j] ^= 0x20;
             }
         }
@@ -597,12 +646,18 @@ mod test {
             let mut tag = seal_detached_precomputed(&mut m, &n, &k1);
             for j in 0..m.len() {
                 m[j] ^= 0x20;
-                assert_eq!(Err(()), open_detached_precomputed(&mut m, &tag, &n, &k2));
+                assert_eq!(
+                    Err(Error::VerificationFailed),
+                    open_detached_precomputed(&mut m, &tag, &n, &k2)
+                );
                 m[j] ^= 0x20;
             }
             for j in 0..tag.0.len() {
                 tag.0[j] ^= 0x20;
-                assert_eq!(Err(()), open_detached_precomputed(&mut m, &tag, &n, &k2));
+                assert_eq!(
+                    Err(Error::VerificationFailed),
+                    open_detached_precomputed(&mut m, &tag, &n, &k2)
+                );
                 tag.0[j] ^= 0x20;
             }
         }
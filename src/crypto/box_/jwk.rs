@@ -0,0 +1,511 @@
+//! JSON Web Key encoding of X25519 keys.
+//!
+//! Keys are encoded as `OKP` keys on the `X25519` curve, as specified in
+//! [RFC 8037](https://tools.ietf.org/html/rfc8037) and used by JOSE
+//! libraries and WebCrypto:
+//!
+//! ```text
+//! {"kty":"OKP","crv":"X25519","x":"<public key>","d":"<secret key>"}
+//! ```
+//!
+//! with the keys in unpadded URL-safe Base64. When parsing, other members
+//! such as `kid` or `use` are ignored.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::box_;
+//!
+//! let (pk, sk) = box_::gen_keypair();
+//!
+//! let jwk = pk.to_jwk();
+//! assert!(jwk.starts_with(r#"{"kty":"OKP","crv":"X25519","x":""#));
+//! assert_eq!(box_::PublicKey::from_jwk(&jwk), Ok(pk));
+//!
+//! let jwk = sk.to_jwk();
+//! assert_eq!(box_::SecretKey::from_jwk(&jwk), Ok(sk));
+//! ```
+
+use super::curve25519xsalsa20poly1305::{PublicKey, SecretKey};
+use base64::{self, Variant};
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use utils::memzero;
+use Error;
+
+impl PublicKey {
+    /// `to_jwk()` returns the key as a JSON Web Key.
+    pub fn to_jwk(&self) -> String {
+        format!(
+            r#"{{"kty":"OKP","crv":"X25519","x":"{}"}}"#,
+            base64::encode(&self.0[..], Variant::UrlSafeNoPadding)
+        )
+    }
+
+    /// `from_jwk()` parses an X25519 JSON Web Key. A private key is accepted
+    /// too, and only its public part is returned.
+    ///
+    /// It returns `Err(Error::InvalidEncoding)` if `jwk` is not an `OKP` key
+    /// on the `X25519` curve.
+    pub fn from_jwk(jwk: &str) -> Result<PublicKey, Error> {
+        let members = parse_object(jwk)?;
+        let x = check_key_type(&members)?;
+        PublicKey::from_slice(&decode_key(x)?).ok_or(Error::InvalidEncoding)
+    }
+}
+
+impl SecretKey {
+    /// `to_jwk()` returns the key, along with its public key, as a JSON Web
+    /// Key.
+    ///
+    /// WARNING: the result holds the secret key in plain text, and the caller
+    /// is responsible for zeroing it out after use.
+    pub fn to_jwk(&self) -> String {
+        let mut d = base64::encode(&self.0[..], Variant::UrlSafeNoPadding);
+        let jwk = format!(
+            r#"{{"kty":"OKP","crv":"X25519","x":"{}","d":"{}"}}"#,
+            base64::encode(self.public_key().0, Variant::UrlSafeNoPadding),
+            d
+        );
+        unsafe {
+            memzero(d.as_bytes_mut());
+        }
+        jwk
+    }
+
+    /// `from_jwk()` parses an X25519 JSON Web Key holding a private key.
+    ///
+    /// It returns `Err(Error::InvalidEncoding)` if `jwk` is not an `OKP` key
+    /// on the `X25519` curve, has no private key, or if its public key does
+    /// not belong to the private key.
+    pub fn from_jwk(jwk: &str) -> Result<SecretKey, Error> {
+        let mut members = parse_object(jwk)?;
+        let res = secret_key_from_members(&members);
+        for (_, value) in members.iter_mut() {
+            if let Some(value) = value {
+                unsafe {
+                    memzero(value.as_bytes_mut());
+                }
+            }
+        }
+        res
+    }
+}
+
+fn secret_key_from_members(members: &[(String, Option<String>)]) -> Result<SecretKey, Error> {
+    let x = check_key_type(members)?;
+    let d = member(members, "d").ok_or(Error::InvalidEncoding)?;
+    let mut d = decode_key(d)?;
+    let sk = SecretKey::from_slice(&d);
+    memzero(&mut d);
+    let sk = sk.ok_or(Error::InvalidEncoding)?;
+    if decode_key(x)? != sk.public_key().0[..] {
+        return Err(Error::InvalidEncoding);
+    }
+    Ok(sk)
+}
+
+fn member<'a>(members: &'a [(String, Option<String>)], name: &str) -> Option<&'a str> {
+    members
+        .iter()
+        .find(|(n, _)| n == name)
+        .and_then(|(_, value)| value.as_ref().map(|v| v.as_str()))
+}
+
+/// Checks that the key is an X25519 key and returns its `x` member.
+fn check_key_type(members: &[(String, Option<String>)]) -> Result<&str, Error> {
+    if member(members, "kty") != Some("OKP") || member(members, "crv") != Some("X25519") {
+        return Err(Error::InvalidEncoding);
+    }
+    member(members, "x").ok_or(Error::InvalidEncoding)
+}
+
+fn decode_key(b64: &str) -> Result<Vec<u8>, Error> {
+    base64::decode(b64, Variant::UrlSafeNoPadding)
+}
+
+/// `parse_object()` parses a JSON object and returns its members, with the
+/// values of string members. Other values are checked for being valid JSON
+/// but otherwise skipped, as no member used here has them. Values nested
+/// more than `MAX_DEPTH` levels deep are rejected, so that untrusted input
+/// cannot exhaust the stack.
+fn parse_object(s: &str) -> Result<Vec<(String, Option<String>)>, Error> {
+    let mut p = Parser {
+        s: s.as_bytes(),
+        pos: 0,
+        depth: 0,
+    };
+    p.skip_ws();
+    let members = p.object()?;
+    p.skip_ws();
+    if p.pos != p.s.len() {
+        return Err(Error::InvalidEncoding);
+    }
+    for (i, (name, _)) in members.iter().enumerate() {
+        if members[..i].iter().any(|(n, _)| n == name) {
+            return Err(Error::InvalidEncoding);
+        }
+    }
+    Ok(members)
+}
+
+const MAX_DEPTH: usize = 16;
+
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+    // number of objects and arrays currently open
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.pos).cloned()
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\r') | Some(b'\n') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, b: u8) -> Result<(), Error> {
+        self.skip_ws();
+        if self.peek() != Some(b) {
+            return Err(Error::InvalidEncoding);
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn enter(&mut self) -> Result<(), Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::InvalidEncoding);
+        }
+        self.depth += 1;
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn object(&mut self) -> Result<Vec<(String, Option<String>)>, Error> {
+        self.skip_ws();
+        if self.peek() != Some(b'{') {
+            return Err(Error::InvalidEncoding);
+        }
+        self.enter()?;
+        let mut members = Vec::new();
+        self.skip_ws();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            self.depth -= 1;
+            return Ok(members);
+        }
+        loop {
+            self.skip_ws();
+            let name = self.string()?;
+            self.expect(b':')?;
+            self.skip_ws();
+            let value = if self.peek() == Some(b'"') {
+                Some(self.string()?)
+            } else {
+                self.skip_value()?;
+                None
+            };
+            members.push((name, value));
+            self.skip_ws();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    self.depth -= 1;
+                    return Ok(members);
+                }
+                _ => return Err(Error::InvalidEncoding),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        self.expect(b'"')?;
+        let mut out = Vec::new();
+        loop {
+            let b = self.peek().ok_or(Error::InvalidEncoding)?;
+            self.pos += 1;
+            match b {
+                b'"' => break,
+                b'\\' => {
+                    let e = self.peek().ok_or(Error::InvalidEncoding)?;
+                    self.pos += 1;
+                    let c = match e {
+                        b'"' | b'\\' | b'/' => e as char,
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(Error::InvalidEncoding),
+                    };
+                    let mut buf = [0u8; 4];
+                    out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                0x00..=0x1f => return Err(Error::InvalidEncoding),
+                _ => out.push(b),
+            }
+        }
+        String::from_utf8(out).map_err(|_| Error::InvalidEncoding)
+    }
+
+    fn hex4(&mut self) -> Result<u32, Error> {
+        let digits = self
+            .s
+            .get(self.pos..self.pos + 4)
+            .ok_or(Error::InvalidEncoding)?;
+        let digits = ::std::str::from_utf8(digits).map_err(|_| Error::InvalidEncoding)?;
+        let n = u32::from_str_radix(digits, 16).map_err(|_| Error::InvalidEncoding)?;
+        self.pos += 4;
+        Ok(n)
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, Error> {
+        let mut n = self.hex4()?;
+        if (0xd800..0xdc00).contains(&n) {
+            if self.s.get(self.pos..self.pos + 2) != Some(&b"\\u"[..]) {
+                return Err(Error::InvalidEncoding);
+            }
+            self.pos += 2;
+            let low = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(Error::InvalidEncoding);
+            }
+            n = 0x10000 + ((n - 0xd800) << 10) + (low - 0xdc00);
+        }
+        ::std::char::from_u32(n).ok_or(Error::InvalidEncoding)
+    }
+
+    fn skip_value(&mut self) -> Result<(), Error> {
+        self.skip_ws();
+        match self.peek() {
+            Some(b'"') => self.string().map(|_| ()),
+            Some(b'{') => self.object().map(|_| ()),
+            Some(b'[') => {
+                self.enter()?;
+                self.skip_ws();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    self.depth -= 1;
+                    return Ok(());
+                }
+                loop {
+                    self.skip_value()?;
+                    self.skip_ws();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            self.depth -= 1;
+                            return Ok(());
+                        }
+                        _ => return Err(Error::InvalidEncoding),
+                    }
+                }
+            }
+            Some(b't') => self.literal(b"true"),
+            Some(b'f') => self.literal(b"false"),
+            Some(b'n') => self.literal(b"null"),
+            _ => self.number(),
+        }
+    }
+
+    fn literal(&mut self, word: &[u8]) -> Result<(), Error> {
+        if self.s.get(self.pos..self.pos + word.len()) != Some(word) {
+            return Err(Error::InvalidEncoding);
+        }
+        self.pos += word.len();
+        Ok(())
+    }
+
+    // Skips the digits at the current position and returns how many there were.
+    fn digits(&mut self) -> usize {
+        let start = self.pos;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    // -?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?
+    fn number(&mut self) -> Result<(), Error> {
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => {
+                self.digits();
+            }
+            _ => return Err(Error::InvalidEncoding),
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if self.digits() == 0 {
+                return Err(Error::InvalidEncoding);
+            }
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if self.digits() == 0 {
+                return Err(Error::InvalidEncoding);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::curve25519xsalsa20poly1305::gen_keypair;
+    use super::*;
+    use hex;
+
+    #[test]
+    fn test_rfc8037() {
+        // RFC 8037, Appendix A.6, with the keys of RFC 7748, Section 6.1
+        let bob = PublicKey::from_jwk(
+            r#"{"kty":"OKP","crv":"X25519","kid":"Bob",
+                "x":"3p7bfXt9wbTTW2HC7OQ1Nz-DQ8hbeGdNrfx-FG-IK08"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(bob.0),
+            "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f"
+        );
+
+        let jwk = r#"{"kty":"OKP","crv":"X25519",
+            "d":"dwdtCnMYpX08FsFyUbJmRd9ML4frwJkqsXf7pR25LCo",
+            "x":"hSDwCYkwp1R0i33ctD73Wg2_Og0mOBr066SpjqqbTmo"}"#;
+        let alice = SecretKey::from_jwk(jwk).unwrap();
+        assert_eq!(
+            hex::encode(&alice.0),
+            "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a"
+        );
+        assert_eq!(PublicKey::from_jwk(jwk), Ok(alice.public_key()));
+        assert_eq!(
+            alice.to_jwk(),
+            r#"{"kty":"OKP","crv":"X25519","x":"hSDwCYkwp1R0i33ctD73Wg2_Og0mOBr066SpjqqbTmo","d":"dwdtCnMYpX08FsFyUbJmRd9ML4frwJkqsXf7pR25LCo"}"#
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (pk, sk) = gen_keypair();
+        assert_eq!(PublicKey::from_jwk(&pk.to_jwk()), Ok(pk));
+        assert_eq!(SecretKey::from_jwk(&sk.to_jwk()), Ok(sk.clone()));
+        assert_eq!(PublicKey::from_jwk(&sk.to_jwk()), Ok(pk));
+        assert_eq!(
+            SecretKey::from_jwk(&pk.to_jwk()),
+            Err(Error::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn test_other_members() {
+        let (pk, _) = gen_keypair();
+        let x = base64::encode(pk.0, Variant::UrlSafeNoPadding);
+        let jwk = format!(
+            r#" {{ "use" : "enc", "key_ops": ["deriveKey", {{"a": [1, -2.5e3]}}],
+                  "ext": true, "alg": null, "kid": "k\"1é😀",
+                  "kty": "OKP", "crv": "X25519", "x": "{}" }} "#,
+            x
+        );
+        assert_eq!(PublicKey::from_jwk(&jwk), Ok(pk));
+
+        for v in ["0", "-0", "12", "-1.25", "1E5", "2e-3", "0.5e+10", "false"].iter() {
+            let jwk = format!(r#"{{"kty":"OKP","crv":"X25519","x":"{}","e":{}}}"#, x, v);
+            assert_eq!(PublicKey::from_jwk(&jwk), Ok(pk), "{}", v);
+        }
+        let depth = MAX_DEPTH - 1;
+        let nested = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let jwk = format!(
+            r#"{{"kty":"OKP","crv":"X25519","x":"{}","e":{}}}"#,
+            x, nested
+        );
+        assert_eq!(PublicKey::from_jwk(&jwk), Ok(pk));
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        // must fail cleanly instead of overflowing the stack
+        let jwk = format!("{}{}", r#"{"a":"#, "[".repeat(1 << 20));
+        assert_eq!(PublicKey::from_jwk(&jwk), Err(Error::InvalidEncoding));
+        let jwk = r#"{"a":"#.repeat(1 << 20);
+        assert_eq!(PublicKey::from_jwk(&jwk), Err(Error::InvalidEncoding));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let (pk, sk) = gen_keypair();
+        let x = base64::encode(pk.0, Variant::UrlSafeNoPadding);
+        let invalid = [
+            String::new(),
+            "[]".to_string(),
+            format!(r#"{{"kty":"OKP","crv":"Ed25519","x":"{}"}}"#, x),
+            format!(r#"{{"kty":"EC","crv":"X25519","x":"{}"}}"#, x),
+            r#"{"kty":"OKP","crv":"X25519"}"#.to_string(),
+            format!(r#"{{"kty":"OKP","crv":"X25519","x":"{}="}}"#, x),
+            format!(r#"{{"kty":"OKP","crv":"X25519","x":"{}"}}"#, &x[1..]),
+            format!(r#"{{"kty":"OKP","crv":"X25519","x":"{}""#, x),
+            format!(r#"{{"kty":"OKP","crv":"X25519","x":"{}"}} x"#, x),
+            format!(r#"{{"kty":"OKP","crv":"X25519","x":"{}","x":"{}"}}"#, x, x),
+            format!(r#"{{"kty":"OKP","crv":"X25519","x":"{}",}}"#, x),
+        ];
+        let mut bad_values = vec![
+            "tru",
+            "truex",
+            "nul",
+            "undefined",
+            "--1",
+            "01",
+            "1.",
+            ".5",
+            "1e",
+            "1e+",
+            "+1",
+            "0x10",
+            "1-2",
+            "[1,]",
+            "[,]",
+            "{\"a\"}",
+            "[",
+            "]",
+        ];
+        let nested = format!("{}{}", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH));
+        bad_values.push(&nested);
+        let invalid: Vec<String> = invalid
+            .iter()
+            .cloned()
+            .chain(
+                bad_values
+                    .iter()
+                    .map(|v| format!(r#"{{"kty":"OKP","crv":"X25519","x":"{}","e":{}}}"#, x, v)),
+            )
+            .collect();
+        for jwk in invalid.iter() {
+            assert_eq!(
+                PublicKey::from_jwk(jwk),
+                Err(Error::InvalidEncoding),
+                "{}",
+                jwk
+            );
+        }
+
+        // public key of another key pair
+        let (other, _) = gen_keypair();
+        let jwk = sk
+            .to_jwk()
+            .replace(&x, &base64::encode(other.0, Variant::UrlSafeNoPadding));
+        assert_eq!(SecretKey::from_jwk(&jwk), Err(Error::InvalidEncoding));
+    }
+}
@@ -44,6 +44,10 @@
 //! This function is conjectured to meet the standard notions of privacy and
 //! third-party unforgeability.
 //!
+//! # Alternate primitives
+//! [`curve25519xchacha20poly1305`] provides the same API on top of `XChaCha20`
+//! instead of `XSalsa20`, using the same key pairs.
+//!
 //! # Example (simple interface)
 //! ```
 //! use sodiumoxide::crypto::box_;
@@ -73,6 +77,47 @@
 //!                                              &their_precomputed_key).unwrap();
 //! assert!(plaintext == &their_plaintext[..]);
 //! ```
+//! # Example (detached interface)
+//! The detached functions encrypt in place and return the authentication tag
+//! separately, e.g. for wire formats that keep it in a header. They also have
+//! `_precomputed` variants, which libsodium calls `_afternm`.
+//! ```
+//! use sodiumoxide::crypto::box_;
+//!
+//! let (ourpk, oursk) = box_::gen_keypair();
+//! let (theirpk, theirsk) = box_::gen_keypair();
+//! let nonce = box_::gen_nonce();
+//! let mut buf = b"plaintext".to_vec();
+//! let tag = box_::seal_detached(&mut buf, &nonce, &theirpk, &oursk);
+//! assert_eq!(buf.len(), b"plaintext".len());
+//! box_::open_detached(&mut buf, &tag, &nonce, &ourpk, &theirsk).unwrap();
+//! assert_eq!(buf, b"plaintext");
+//! ```
+//!
+//! # Example (nonce prefix)
+//! `seal_with_nonce_prefix()` generates a random nonce and prepends it to the
+//! ciphertext, and `open_with_nonce_prefix()` splits it off again.
+//! ```
+//! use sodiumoxide::crypto::box_;
+//!
+//! let (ourpk, oursk) = box_::gen_keypair();
+//! let (theirpk, theirsk) = box_::gen_keypair();
+//! let ciphertext = box_::seal_with_nonce_prefix(b"plaintext", &theirpk, &oursk);
+//! let plaintext = box_::open_with_nonce_prefix(&ciphertext, &ourpk, &theirsk).unwrap();
+//! assert_eq!(plaintext, b"plaintext");
+//! ```
+//!
+//! # Key formats
+//! Keys can be exchanged with other software as PKCS#8 and
+//! `SubjectPublicKeyInfo` documents as used by OpenSSL, see
+//! `SecretKey::to_pkcs8_pem()` and `PublicKey::to_public_key_pem()`, and as
+//! JSON Web Keys, see `SecretKey::to_jwk()` and `PublicKey::to_jwk()`.
 
 pub use self::curve25519xsalsa20poly1305::*;
+pub use crypto::pkcs8::{PrivateKeyDer, PrivateKeyPem};
+#[macro_use]
+mod box_macros;
+pub mod curve25519xchacha20poly1305;
 pub mod curve25519xsalsa20poly1305;
+mod jwk;
+mod pkcs8;
@@ -0,0 +1,521 @@
+//! Encrypted channels between a client and a server
+//!
+//! # Purpose
+//! A `Channel` combines a `kx` key exchange with two `secretstream`s, one
+//! for each direction, so that two parties can exchange an ordered sequence
+//! of encrypted messages over any message-oriented transport. Messages can
+//! not be modified, dropped or reordered without the receiver noticing, and
+//! every handshake uses fresh keys, so that neither messages nor whole
+//! sessions can be replayed.
+//!
+//! The transport is given as two callbacks: one that sends a message to the
+//! peer, and one that blocks until the next message from the peer arrives.
+//! The callbacks must preserve message boundaries, e.g. by length-prefixing
+//! messages on a TCP connection.
+//!
+//! # Algorithm Details
+//! The client must know the server's `kx` public key in advance. Both sides
+//! also generate an ephemeral `kx` key pair for every handshake, which
+//! consists of three messages:
+//!
+//! ```text
+//! client -> server: client_pk || client_epk
+//! server -> client: server_pk || server_epk || server_header || server_confirmation
+//! client -> server: client_header || client_confirmation
+//! ```
+//!
+//! Each side derives one pair of `kx` session keys from the static key pairs
+//! and one from the ephemeral key pairs. The key of each stream is the
+//! `BLAKE2b` hash of the ephemeral session key for that direction, keyed with
+//! the static one:
+//!
+//! ```text
+//! key = BLAKE2b(key=static_session_key, ephemeral_session_key)
+//! ```
+//!
+//! Each side then starts a `secretstream` with its `tx` key and sends the
+//! header, followed by the encryption of an empty message as confirmation.
+//! The confirmation proves that the sender knows the secret key matching its
+//! public key, and that it took part in this handshake: the ephemeral key
+//! pairs make the stream keys different for every session, so a recorded
+//! handshake does not verify when it is replayed, and recorded sessions stay
+//! secret even if the static secret keys leak later on.
+//!
+//! The client checks that the server used the expected public key; the
+//! server learns the client's public key, available through
+//! [`Channel::peer_public_key()`], and is responsible for deciding whether to
+//! trust it.
+//!
+//! Every message on an established channel is a `secretstream` chunk. Closing
+//! a channel sends an empty chunk tagged `Tag::Final`.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::channel::Channel;
+//! use sodiumoxide::crypto::kx;
+//! use std::sync::mpsc;
+//! use std::thread;
+//!
+//! let (server_pk, server_sk) = kx::gen_keypair();
+//! let (client_pk, client_sk) = kx::gen_keypair();
+//!
+//! let (to_server, server_inbox) = mpsc::channel::<Vec<u8>>();
+//! let (to_client, client_inbox) = mpsc::channel::<Vec<u8>>();
+//!
+//! let server = thread::spawn(move || {
+//!     let mut channel = Channel::server(
+//!         |m: &[u8]| to_client.send(m.to_vec()).map_err(|_| ()),
+//!         || server_inbox.recv().map_err(|_| ()),
+//!         &server_pk,
+//!         &server_sk,
+//!     )
+//!     .unwrap();
+//!     while let Some(m) = channel.recv().unwrap() {
+//!         channel.send(&m).unwrap();
+//!     }
+//! });
+//!
+//! let mut channel = Channel::client(
+//!     |m: &[u8]| to_server.send(m.to_vec()).map_err(|_| ()),
+//!     || client_inbox.recv().map_err(|_| ()),
+//!     &client_pk,
+//!     &client_sk,
+//!     &server_pk,
+//! )
+//! .unwrap();
+//! channel.send(b"echo").unwrap();
+//! assert_eq!(channel.recv().unwrap().unwrap(), b"echo");
+//! channel.close().unwrap();
+//! server.join().unwrap();
+//! ```
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+
+use crypto::kx;
+use crypto::secretstream::{Header, Key, Pull, Push, Stream, Tag, ABYTES, HEADERBYTES, KEYBYTES};
+use ffi;
+use libc::c_ulonglong;
+use std::fmt;
+use Error;
+
+/// `ChannelError` describes why a channel operation failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelError<E> {
+    /// One of the transport callbacks failed.
+    Transport(E),
+    /// The handshake or a message was invalid, or the channel was used after
+    /// it had been closed.
+    Crypto(Error),
+}
+
+impl<E> From<Error> for ChannelError<E> {
+    fn from(e: Error) -> ChannelError<E> {
+        ChannelError::Crypto(e)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for ChannelError<E> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ChannelError::Transport(ref e) => write!(formatter, "transport error: {}", e),
+            ChannelError::Crypto(ref e) => write!(formatter, "channel error: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug + fmt::Display> ::std::error::Error for ChannelError<E> {}
+
+/// `Channel` is an established encrypted channel. Use `client()` or
+/// `server()` to perform the handshake.
+///
+/// `S` is the callback sending a message to the peer and `R` the callback
+/// receiving the next message from the peer.
+pub struct Channel<S, R> {
+    send: S,
+    recv: R,
+    tx: Stream<Push>,
+    rx: Stream<Pull>,
+    peer_pk: kx::PublicKey,
+}
+
+// Derives the key of a stream from the static and the ephemeral session key
+// for its direction.
+fn stream_key(static_key: &kx::SessionKey, ephemeral_key: &kx::SessionKey) -> Key {
+    let mut key = Key([0u8; KEYBYTES]);
+    let rc = unsafe {
+        ffi::crypto_generichash(
+            key.0.as_mut_ptr(),
+            KEYBYTES,
+            ephemeral_key.0.as_ptr(),
+            ephemeral_key.0.len() as c_ulonglong,
+            static_key.0.as_ptr(),
+            static_key.0.len(),
+        )
+    };
+    // the output and key lengths are valid
+    assert_eq!(rc, 0);
+    key
+}
+
+// Starts the outgoing stream and returns it along with its header and
+// confirmation.
+fn start_push(tx: &Key) -> Result<(Stream<Push>, Vec<u8>), Error> {
+    let (mut stream, header) = Stream::init_push(tx)?;
+    let mut out = Vec::with_capacity(HEADERBYTES + ABYTES);
+    out.extend_from_slice(header.as_ref());
+    out.extend_from_slice(&stream.push(&[], None, Tag::Message)?);
+    Ok((stream, out))
+}
+
+// Starts the incoming stream from a header and confirmation.
+fn start_pull(rx: &Key, msg: &[u8]) -> Result<Stream<Pull>, Error> {
+    if msg.len() != HEADERBYTES + ABYTES {
+        return Err(Error::InvalidLength);
+    }
+    let header = Header::from_slice(&msg[..HEADERBYTES]).ok_or(Error::InvalidLength)?;
+    let mut stream = Stream::init_pull(&header, rx).map_err(|_| Error::VerificationFailed)?;
+    match stream.pull(&msg[HEADERBYTES..], None) {
+        Ok((ref m, Tag::Message)) if m.is_empty() => Ok(stream),
+        _ => Err(Error::VerificationFailed),
+    }
+}
+
+impl<S, R, E> Channel<S, R>
+where
+    S: FnMut(&[u8]) -> Result<(), E>,
+    R: FnMut() -> Result<Vec<u8>, E>,
+{
+    /// `client()` performs the client side of the handshake with the server
+    /// whose public key is `server_pk`, using the client's key pair
+    /// `(client_pk, client_sk)`.
+    ///
+    /// It fails with `ChannelError::Crypto(Error::VerificationFailed)` if the
+    /// server does not prove that it holds the secret key for `server_pk`.
+    pub fn client(
+        mut send: S,
+        mut recv: R,
+        client_pk: &kx::PublicKey,
+        client_sk: &kx::SecretKey,
+        server_pk: &kx::PublicKey,
+    ) -> Result<Channel<S, R>, ChannelError<E>> {
+        let (static_rx, static_tx) = kx::client_session_keys(client_pk, client_sk, server_pk)?;
+        let (client_epk, client_esk) = kx::gen_keypair();
+        let mut out = Vec::with_capacity(2 * kx::PUBLICKEYBYTES);
+        out.extend_from_slice(client_pk.as_ref());
+        out.extend_from_slice(client_epk.as_ref());
+        send(&out).map_err(ChannelError::Transport)?;
+
+        let msg = recv().map_err(ChannelError::Transport)?;
+        if msg.len() < 2 * kx::PUBLICKEYBYTES {
+            return Err(Error::InvalidLength.into());
+        }
+        let (pk, rest) = msg.split_at(kx::PUBLICKEYBYTES);
+        if pk != server_pk.as_ref() {
+            return Err(Error::VerificationFailed.into());
+        }
+        let (server_epk, rest) = rest.split_at(kx::PUBLICKEYBYTES);
+        let server_epk = kx::PublicKey::from_slice(server_epk).ok_or(Error::InvalidLength)?;
+        let (ephemeral_rx, ephemeral_tx) =
+            kx::client_session_keys(&client_epk, &client_esk, &server_epk)?;
+        let rx = start_pull(&stream_key(&static_rx, &ephemeral_rx), rest)?;
+        let tx = stream_key(&static_tx, &ephemeral_tx);
+
+        let (tx, out) = start_push(&tx)?;
+        send(&out).map_err(ChannelError::Transport)?;
+        Ok(Channel {
+            send,
+            recv,
+            tx,
+            rx,
+            peer_pk: *server_pk,
+        })
+    }
+
+    /// `server()` performs the server side of the handshake with a client,
+    /// using the server's key pair `(server_pk, server_sk)`.
+    ///
+    /// Any client can connect; check `peer_public_key()` to find out which
+    /// one did.
+    pub fn server(
+        mut send: S,
+        mut recv: R,
+        server_pk: &kx::PublicKey,
+        server_sk: &kx::SecretKey,
+    ) -> Result<Channel<S, R>, ChannelError<E>> {
+        let msg = recv().map_err(ChannelError::Transport)?;
+        if msg.len() != 2 * kx::PUBLICKEYBYTES {
+            return Err(Error::InvalidLength.into());
+        }
+        let (client_pk, client_epk) = msg.split_at(kx::PUBLICKEYBYTES);
+        let client_pk = kx::PublicKey::from_slice(client_pk).ok_or(Error::InvalidLength)?;
+        let client_epk = kx::PublicKey::from_slice(client_epk).ok_or(Error::InvalidLength)?;
+        let (static_rx, static_tx) = kx::server_session_keys(server_pk, server_sk, &client_pk)?;
+        let (server_epk, server_esk) = kx::gen_keypair();
+        let (ephemeral_rx, ephemeral_tx) =
+            kx::server_session_keys(&server_epk, &server_esk, &client_epk)?;
+
+        let (tx, confirmation) = start_push(&stream_key(&static_tx, &ephemeral_tx))?;
+        let mut out = Vec::with_capacity(2 * kx::PUBLICKEYBYTES + confirmation.len());
+        out.extend_from_slice(server_pk.as_ref());
+        out.extend_from_slice(server_epk.as_ref());
+        out.extend_from_slice(&confirmation);
+        send(&out).map_err(ChannelError::Transport)?;
+
+        let msg = recv().map_err(ChannelError::Transport)?;
+        let rx = start_pull(&stream_key(&static_rx, &ephemeral_rx), &msg)?;
+        Ok(Channel {
+            send,
+            recv,
+            tx,
+            rx,
+            peer_pk: client_pk,
+        })
+    }
+
+    /// `peer_public_key()` returns the `kx` public key of the peer.
+    pub fn peer_public_key(&self) -> &kx::PublicKey {
+        &self.peer_pk
+    }
+
+    /// `send()` encrypts the message `m` and sends it to the peer.
+    ///
+    /// It fails with `ChannelError::Crypto(Error::Finalized)` after `close()`.
+    pub fn send(&mut self, m: &[u8]) -> Result<(), ChannelError<E>> {
+        let c = self.tx.push(m, None, Tag::Message)?;
+        (self.send)(&c).map_err(ChannelError::Transport)
+    }
+
+    /// `recv()` receives the next message from the peer and decrypts it.
+    ///
+    /// It returns `Ok(None)` once the peer has closed the channel, and
+    /// `ChannelError::Crypto(Error::VerificationFailed)` if the message is
+    /// not the next one the peer sent.
+    pub fn recv(&mut self) -> Result<Option<Vec<u8>>, ChannelError<E>> {
+        if self.rx.is_finalized() {
+            return Ok(None);
+        }
+        let c = (self.recv)().map_err(ChannelError::Transport)?;
+        match self.rx.pull(&c, None) {
+            Ok((m, Tag::Message)) => Ok(Some(m)),
+            Ok((ref m, Tag::Final)) if m.is_empty() => Ok(None),
+            Ok(_) | Err(_) => Err(Error::VerificationFailed.into()),
+        }
+    }
+
+    /// `close()` tells the peer that no more messages will be sent. Messages
+    /// from the peer can still be received.
+    pub fn close(&mut self) -> Result<(), ChannelError<E>> {
+        let c = self.tx.push(&[], None, Tag::Final)?;
+        (self.send)(&c).map_err(ChannelError::Transport)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+
+    type Sender = Box<dyn FnMut(&[u8]) -> Result<(), ()> + Send>;
+    type Receiver = Box<dyn FnMut() -> Result<Vec<u8>, ()> + Send>;
+
+    // Returns the callbacks of both ends of an in-memory transport. Messages
+    // sent by the second end go through `tamper` first.
+    fn transport<F>(mut tamper: F) -> ((Sender, Receiver), (Sender, Receiver))
+    where
+        F: FnMut(Vec<u8>) -> Vec<u8> + Send + 'static,
+    {
+        let (a_tx, b_rx) = mpsc::channel::<Vec<u8>>();
+        let (b_tx, a_rx) = mpsc::channel::<Vec<u8>>();
+        let a: (Sender, Receiver) = (
+            Box::new(move |m: &[u8]| a_tx.send(m.to_vec()).map_err(|_| ())),
+            Box::new(move || a_rx.recv().map_err(|_| ())),
+        );
+        let b: (Sender, Receiver) = (
+            Box::new(move |m: &[u8]| b_tx.send(tamper(m.to_vec())).map_err(|_| ())),
+            Box::new(move || b_rx.recv().map_err(|_| ())),
+        );
+        (a, b)
+    }
+
+    #[test]
+    fn test_channel() {
+        let (server_pk, server_sk) = kx::gen_keypair();
+        let (client_pk, client_sk) = kx::gen_keypair();
+        let ((client_send, client_recv), (server_send, server_recv)) = transport(|m| m);
+
+        let server = thread::spawn(move || {
+            let mut channel =
+                Channel::server(server_send, server_recv, &server_pk, &server_sk).unwrap();
+            let peer_pk = *channel.peer_public_key();
+            let mut received = Vec::new();
+            while let Some(m) = channel.recv().unwrap() {
+                channel.send(&m).unwrap();
+                received.push(m);
+            }
+            channel.close().unwrap();
+            assert_eq!(
+                channel.send(b"closed"),
+                Err(ChannelError::Crypto(Error::Finalized))
+            );
+            (peer_pk, received)
+        });
+
+        let mut channel =
+            Channel::client(client_send, client_recv, &client_pk, &client_sk, &server_pk).unwrap();
+        assert_eq!(channel.peer_public_key(), &server_pk);
+        let messages: Vec<Vec<u8>> = (0..8).map(|i| vec![i as u8; i * 10]).collect();
+        for m in &messages {
+            channel.send(m).unwrap();
+            assert_eq!(channel.recv().unwrap().as_ref(), Some(m));
+        }
+        channel.close().unwrap();
+        assert_eq!(channel.recv().unwrap(), None);
+        assert_eq!(channel.recv().unwrap(), None);
+
+        let (peer_pk, received) = server.join().unwrap();
+        assert_eq!(peer_pk, client_pk);
+        assert_eq!(received, messages);
+    }
+
+    // Sends every message to `log` before passing it on to `inner`.
+    fn recording(log: mpsc::Sender<Vec<u8>>, mut inner: Sender) -> Sender {
+        Box::new(move |m: &[u8]| {
+            log.send(m.to_vec()).unwrap();
+            inner(m)
+        })
+    }
+
+    // Receives the messages of `log`, then fails.
+    fn replaying(log: Vec<Vec<u8>>) -> Receiver {
+        let mut log = log.into_iter();
+        Box::new(move || log.next().ok_or(()))
+    }
+
+    #[test]
+    fn test_replayed_session() {
+        let (server_pk, server_sk) = kx::gen_keypair();
+        let (client_pk, client_sk) = kx::gen_keypair();
+        let ((client_send, client_recv), (server_send, server_recv)) = transport(|m| m);
+        let (client_log_tx, client_log) = mpsc::channel();
+        let (server_log_tx, server_log) = mpsc::channel();
+        let client_send = recording(client_log_tx, client_send);
+        let server_send = recording(server_log_tx, server_send);
+
+        let keys = (server_pk, server_sk.clone());
+        let server = thread::spawn(move || {
+            let mut channel = Channel::server(server_send, server_recv, &keys.0, &keys.1).unwrap();
+            while let Some(m) = channel.recv().unwrap() {
+                channel.send(&m).unwrap();
+            }
+        });
+        let mut channel =
+            Channel::client(client_send, client_recv, &client_pk, &client_sk, &server_pk).unwrap();
+        channel.send(b"transfer").unwrap();
+        assert_eq!(channel.recv().unwrap(), Some(b"transfer".to_vec()));
+        channel.close().unwrap();
+        server.join().unwrap();
+        let client_log: Vec<Vec<u8>> = client_log.try_iter().collect();
+        let server_log: Vec<Vec<u8>> = server_log.try_iter().collect();
+
+        // the client's side of the session, replayed to the server
+        let sink: Sender = Box::new(|_: &[u8]| Ok(()));
+        let res = Channel::server(sink, replaying(client_log), &server_pk, &server_sk);
+        assert_eq!(
+            res.err()
+                .map(|e| e == ChannelError::Crypto(Error::VerificationFailed)),
+            Some(true)
+        );
+
+        // the server's side of the session, replayed to the client
+        let sink: Sender = Box::new(|_: &[u8]| Ok(()));
+        let res = Channel::client(
+            sink,
+            replaying(server_log),
+            &client_pk,
+            &client_sk,
+            &server_pk,
+        );
+        assert_eq!(
+            res.err()
+                .map(|e| e == ChannelError::Crypto(Error::VerificationFailed)),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_wrong_server_key() {
+        let (server_pk, server_sk) = kx::gen_keypair();
+        let (other_pk, _) = kx::gen_keypair();
+        let (client_pk, client_sk) = kx::gen_keypair();
+        let ((client_send, client_recv), (server_send, server_recv)) = transport(|m| m);
+
+        let server = thread::spawn(move || {
+            Channel::server(server_send, server_recv, &server_pk, &server_sk).is_ok()
+        });
+        let res = Channel::client(client_send, client_recv, &client_pk, &client_sk, &other_pk);
+        assert_eq!(
+            res.err()
+                .map(|e| e == ChannelError::Crypto(Error::VerificationFailed)),
+            Some(true)
+        );
+        assert!(!server.join().unwrap());
+    }
+
+    #[test]
+    fn test_impersonated_server() {
+        let (server_pk, _) = kx::gen_keypair();
+        let (fake_pk, fake_sk) = kx::gen_keypair();
+        let (client_pk, client_sk) = kx::gen_keypair();
+        // an attacker replays the real server public key while using its own
+        // key pair
+        let ((client_send, client_recv), (server_send, server_recv)) = transport(move |mut m| {
+            if m.len() > kx::PUBLICKEYBYTES {
+                m[..kx::PUBLICKEYBYTES].copy_from_slice(server_pk.as_ref());
+            }
+            m
+        });
+
+        let server = thread::spawn(move || {
+            Channel::server(server_send, server_recv, &fake_pk, &fake_sk).is_ok()
+        });
+        let res = Channel::client(client_send, client_recv, &client_pk, &client_sk, &server_pk);
+        assert_eq!(
+            res.err()
+                .map(|e| e == ChannelError::Crypto(Error::VerificationFailed)),
+            Some(true)
+        );
+        assert!(!server.join().unwrap());
+    }
+
+    #[test]
+    fn test_tampered_message() {
+        let (server_pk, server_sk) = kx::gen_keypair();
+        let (client_pk, client_sk) = kx::gen_keypair();
+        let mut count = 0;
+        let ((client_send, client_recv), (server_send, server_recv)) = transport(move |mut m| {
+            count += 1;
+            if count == 3 {
+                m[0] ^= 0x20;
+            }
+            m
+        });
+
+        let server = thread::spawn(move || {
+            let mut channel =
+                Channel::server(server_send, server_recv, &server_pk, &server_sk).unwrap();
+            channel.send(b"first").unwrap();
+            channel.send(b"second").unwrap();
+        });
+        let mut channel =
+            Channel::client(client_send, client_recv, &client_pk, &client_sk, &server_pk).unwrap();
+        assert_eq!(channel.recv().unwrap(), Some(b"first".to_vec()));
+        assert_eq!(
+            channel.recv(),
+            Err(ChannelError::Crypto(Error::VerificationFailed))
+        );
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,82 @@
+macro_rules! core_module (($core_name:ident,
+                           $fn_name:ident,
+                           $outputbytes:expr,
+                           $inputbytes:expr,
+                           $keybytes:expr,
+                           $constbytes:expr) => (
+
+use std::ptr;
+
+/// Number of bytes in an `Output`.
+pub const OUTPUTBYTES: usize = $outputbytes;
+
+/// Number of bytes in an `Input`.
+pub const INPUTBYTES: usize = $inputbytes;
+
+/// Number of bytes in a `Key`.
+pub const KEYBYTES: usize = $keybytes;
+
+/// Number of bytes in a `Constant`.
+pub const CONSTBYTES: usize = $constbytes;
+
+new_type! {
+    /// `Key` for the core function
+    ///
+    /// When a `Key` goes out of scope its contents
+    /// will be zeroed out
+    secret Key(KEYBYTES);
+}
+
+new_type! {
+    /// `Input` block of the core function, e.g. the first 16 bytes of an
+    /// extended nonce
+    public Input(INPUTBYTES);
+}
+
+new_type! {
+    /// `Constant` replacing the standard "expand 32-byte k" constant
+    public Constant(CONSTBYTES);
+}
+
+new_type! {
+    /// `Output` of the core function, typically used as a subkey
+    ///
+    /// When an `Output` goes out of scope its contents
+    /// will be zeroed out
+    secret Output(OUTPUTBYTES);
+}
+
+/// Computes the core function of the input block `inp` under the key `k`.
+///
+/// If `c` is `None`, the standard "expand 32-byte k" constant is used.
+pub fn $fn_name(inp: &Input, k: &Key, c: Option<&Constant>) -> Output {
+    let mut out = Output([0u8; OUTPUTBYTES]);
+    let c_ptr = c.map_or(ptr::null(), |c| c.0.as_ptr());
+    unsafe {
+        $core_name(out.0.as_mut_ptr(), inp.0.as_ptr(), k.0.as_ptr(), c_ptr);
+    }
+    out
+}
+
+#[cfg(test)]
+mod test_m {
+    use super::*;
+
+    #[test]
+    fn test_default_constant() {
+        let inp = Input([0x42; INPUTBYTES]);
+        let k = Key([0x24; KEYBYTES]);
+        let c = Constant(*b"expand 32-byte k");
+        assert_eq!($fn_name(&inp, &k, None), $fn_name(&inp, &k, Some(&c)));
+    }
+
+    #[test]
+    fn test_custom_constant() {
+        let inp = Input([0x42; INPUTBYTES]);
+        let k = Key([0x24; KEYBYTES]);
+        let c = Constant([0x00; CONSTBYTES]);
+        assert!($fn_name(&inp, &k, None) != $fn_name(&inp, &k, Some(&c)));
+    }
+}
+
+));
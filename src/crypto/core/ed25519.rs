@@ -0,0 +1,264 @@
+//! Arithmetic on the Ed25519 curve and its scalar field, wrapping the
+//! `crypto_core_ed25519_*` functions.
+//!
+//! WARNING: these are low-level building blocks. Ed25519 has a cofactor of 8,
+//! so points received from other parties must be checked with
+//! [`is_valid_point()`](fn.is_valid_point.html), and protocols built on top of
+//! this module must take the cofactor into account. Unless you need
+//! compatibility with existing Ed25519 keys, prefer the prime-order group in
+//! [`scalarmult::ristretto255`](../../scalarmult/ristretto255/index.html).
+//!
+//! This module is only available with the `hazmat` feature.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::core::ed25519;
+//!
+//! let p = ed25519::from_uniform(&[0x42; ed25519::UNIFORMBYTES]).unwrap();
+//! let q = ed25519::from_uniform(&[0x24; ed25519::UNIFORMBYTES]).unwrap();
+//! let r = ed25519::add(&p, &q).unwrap();
+//! assert_eq!(ed25519::sub(&r, &q), Ok(p));
+//! ```
+use ffi;
+use Error;
+
+/// Number of bytes in a `Point`.
+pub const BYTES: usize = ffi::crypto_core_ed25519_BYTES as usize;
+
+/// Number of bytes in the input of `from_uniform()`.
+pub const UNIFORMBYTES: usize = ffi::crypto_core_ed25519_UNIFORMBYTES as usize;
+
+/// Number of bytes in a `Scalar`.
+pub const SCALARBYTES: usize = ffi::crypto_core_ed25519_SCALARBYTES as usize;
+
+/// Number of bytes in the input of `scalar_reduce()`.
+pub const NONREDUCEDSCALARBYTES: usize = ffi::crypto_core_ed25519_NONREDUCEDSCALARBYTES as usize;
+
+new_type! {
+    /// `Point`, the compressed encoding of a point on the Ed25519 curve
+    public Point(BYTES);
+}
+
+new_type! {
+    /// `Scalar` value (integer in little-endian byte representation)
+    secret Scalar(SCALARBYTES);
+}
+
+/// `is_valid_point()` returns `true` if `p` is the canonical encoding of a
+/// point in the main subgroup that does not have a small order, and `false`
+/// otherwise.
+pub fn is_valid_point(p: &Point) -> bool {
+    unsafe { ffi::crypto_core_ed25519_is_valid_point(p.0.as_ptr()) == 1 }
+}
+
+/// `add()` computes the sum of the points `p` and `q`. It returns `Err(Error::InvalidEncoding)`
+/// if either of them does not decode to a point on the curve.
+///
+/// `add()` does not check that its inputs are in the main subgroup; use
+/// `is_valid_point()` for that.
+pub fn add(p: &Point, q: &Point) -> Result<Point, Error> {
+    let mut r = [0; BYTES];
+    unsafe {
+        if ffi::crypto_core_ed25519_add(r.as_mut_ptr(), p.0.as_ptr(), q.0.as_ptr()) != 0 {
+            Err(Error::InvalidEncoding)
+        } else {
+            Ok(Point(r))
+        }
+    }
+}
+
+/// `sub()` computes the difference `p - q` of the points `p` and `q`. It
+/// returns `Err(Error::InvalidEncoding)` if either of them does not decode to a point on the
+/// curve.
+pub fn sub(p: &Point, q: &Point) -> Result<Point, Error> {
+    let mut r = [0; BYTES];
+    unsafe {
+        if ffi::crypto_core_ed25519_sub(r.as_mut_ptr(), p.0.as_ptr(), q.0.as_ptr()) != 0 {
+            Err(Error::InvalidEncoding)
+        } else {
+            Ok(Point(r))
+        }
+    }
+}
+
+/// `from_uniform()` maps the `UNIFORMBYTES` byte string `r` to a point in
+/// the main subgroup using the Elligator 2 map. The discrete logarithm of the
+/// result is unknown.
+pub fn from_uniform(r: &[u8; UNIFORMBYTES]) -> Result<Point, Error> {
+    let mut p = [0; BYTES];
+    unsafe {
+        if ffi::crypto_core_ed25519_from_uniform(p.as_mut_ptr(), r.as_ptr()) != 0 {
+            Err(Error::InvalidParameter)
+        } else {
+            Ok(Point(p))
+        }
+    }
+}
+
+/// `gen_scalar()` randomly generates a non-zero scalar reduced modulo the
+/// order of the main subgroup.
+///
+/// THREAD SAFETY: `gen_scalar()` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+pub fn gen_scalar() -> Scalar {
+    ::ensure_init();
+    let mut s = [0; SCALARBYTES];
+    unsafe {
+        ffi::crypto_core_ed25519_scalar_random(s.as_mut_ptr());
+    }
+    Scalar(s)
+}
+
+/// `scalar_add()` computes `x + y` modulo the order of the main subgroup.
+pub fn scalar_add(x: &Scalar, y: &Scalar) -> Scalar {
+    let mut z = [0; SCALARBYTES];
+    unsafe {
+        ffi::crypto_core_ed25519_scalar_add(z.as_mut_ptr(), x.0.as_ptr(), y.0.as_ptr());
+    }
+    Scalar(z)
+}
+
+/// `scalar_sub()` computes `x - y` modulo the order of the main subgroup.
+pub fn scalar_sub(x: &Scalar, y: &Scalar) -> Scalar {
+    let mut z = [0; SCALARBYTES];
+    unsafe {
+        ffi::crypto_core_ed25519_scalar_sub(z.as_mut_ptr(), x.0.as_ptr(), y.0.as_ptr());
+    }
+    Scalar(z)
+}
+
+/// `scalar_mul()` computes `x * y` modulo the order of the main subgroup.
+pub fn scalar_mul(x: &Scalar, y: &Scalar) -> Scalar {
+    let mut z = [0; SCALARBYTES];
+    unsafe {
+        ffi::crypto_core_ed25519_scalar_mul(z.as_mut_ptr(), x.0.as_ptr(), y.0.as_ptr());
+    }
+    Scalar(z)
+}
+
+/// `scalar_negate()` computes `-s` modulo the order of the main subgroup.
+pub fn scalar_negate(s: &Scalar) -> Scalar {
+    let mut neg = [0; SCALARBYTES];
+    unsafe {
+        ffi::crypto_core_ed25519_scalar_negate(neg.as_mut_ptr(), s.0.as_ptr());
+    }
+    Scalar(neg)
+}
+
+/// `scalar_invert()` computes the multiplicative inverse of `s` modulo the
+/// order of the main subgroup. It returns `Err(Error::InvalidParameter)` if `s` is zero.
+pub fn scalar_invert(s: &Scalar) -> Result<Scalar, Error> {
+    let mut recip = [0; SCALARBYTES];
+    unsafe {
+        if ffi::crypto_core_ed25519_scalar_invert(recip.as_mut_ptr(), s.0.as_ptr()) != 0 {
+            Err(Error::InvalidParameter)
+        } else {
+            Ok(Scalar(recip))
+        }
+    }
+}
+
+/// `scalar_reduce()` reduces the `NONREDUCEDSCALARBYTES` byte integer `s`,
+/// e.g. the output of `SHA-512`, modulo the order of the main subgroup.
+pub fn scalar_reduce(s: &[u8; NONREDUCEDSCALARBYTES]) -> Scalar {
+    let mut r = [0; SCALARBYTES];
+    unsafe {
+        ffi::crypto_core_ed25519_scalar_reduce(r.as_mut_ptr(), s.as_ptr());
+    }
+    Scalar(r)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex;
+
+    fn small_scalar(i: u8) -> Scalar {
+        let mut s = [0; SCALARBYTES];
+        s[0] = i;
+        Scalar(s)
+    }
+
+    fn base_point() -> Point {
+        let mut b = [0x66; BYTES];
+        b[0] = 0x58;
+        Point(b)
+    }
+
+    #[test]
+    fn test_is_valid_point() {
+        assert!(is_valid_point(&base_point()));
+
+        // the identity has a small order
+        let mut identity = [0; BYTES];
+        identity[0] = 0x01;
+        assert!(!is_valid_point(&Point(identity)));
+
+        // not a canonical encoding
+        let mut non_canonical = [0xff; BYTES];
+        non_canonical[0] = 0xed;
+        non_canonical[31] = 0x7f;
+        assert!(!is_valid_point(&Point(non_canonical)));
+
+        // y = 2 is not on the curve
+        let mut bad = [0; BYTES];
+        bad[0] = 0x02;
+        assert!(!is_valid_point(&Point(bad)));
+        assert!(add(&base_point(), &Point(bad)).is_err());
+        assert!(sub(&Point(bad), &base_point()).is_err());
+    }
+
+    #[test]
+    fn test_add_sub() {
+        let b = base_point();
+        let b2 = add(&b, &b).unwrap();
+        assert_eq!(
+            hex::encode(b2),
+            "c9a3f86aae465f0e56513864510f3997561fa2c9e85ea21dc2292309f3cd6022"
+        );
+        assert_eq!(sub(&b2, &b), Ok(b));
+    }
+
+    #[test]
+    fn test_vector_from_uniform() {
+        // regression vector computed with libsodium 1.0.18
+        let mut r = [0; UNIFORMBYTES];
+        for (i, x) in r.iter_mut().enumerate() {
+            *x = i as u8;
+        }
+        let p = from_uniform(&r).unwrap();
+        assert!(is_valid_point(&p));
+        assert_eq!(
+            hex::encode(p),
+            "0691eee3cf70a0056df6bfa03120635636581b5c4ea571dfc680f78c7e0b4137"
+        );
+    }
+
+    #[test]
+    fn test_scalar_arithmetic() {
+        for _ in 0..64 {
+            let x = gen_scalar();
+            let y = gen_scalar();
+            assert_eq!(scalar_sub(&scalar_add(&x, &y), &y), x);
+            assert_eq!(scalar_add(&x, &scalar_negate(&x)), small_scalar(0));
+            let x_inv = scalar_invert(&x).unwrap();
+            assert_eq!(scalar_mul(&x, &x_inv), small_scalar(1));
+            assert_eq!(scalar_mul(&scalar_mul(&x, &y), &x_inv), y);
+        }
+        assert!(scalar_invert(&small_scalar(0)).is_err());
+    }
+
+    #[test]
+    fn test_scalar_reduce() {
+        // the group order l reduces to zero, l + 1 to one
+        let mut s = [0; NONREDUCEDSCALARBYTES];
+        s[..SCALARBYTES].copy_from_slice(
+            &hex::decode("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010")
+                .unwrap(),
+        );
+        assert_eq!(scalar_reduce(&s), small_scalar(0));
+        s[0] += 1;
+        assert_eq!(scalar_reduce(&s), small_scalar(1));
+    }
+}
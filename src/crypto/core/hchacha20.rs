@@ -0,0 +1,68 @@
+//! `crypto_core_hchacha20`, the `HChaCha20` function used to derive the
+//! subkeys of `XChaCha20` from a key and the first 16 bytes of a nonce.
+//!
+//! `HChaCha20` is not a general purpose hash function. It is only
+//! intended for building constructions such as `XChaCha20` on top of
+//! `ChaCha20`, where the key is uniformly random.
+
+use ffi::{
+    crypto_core_hchacha20, crypto_core_hchacha20_CONSTBYTES, crypto_core_hchacha20_INPUTBYTES,
+    crypto_core_hchacha20_KEYBYTES, crypto_core_hchacha20_OUTPUTBYTES,
+};
+
+core_module!(
+    crypto_core_hchacha20,
+    hchacha20,
+    crypto_core_hchacha20_OUTPUTBYTES as usize,
+    crypto_core_hchacha20_INPUTBYTES as usize,
+    crypto_core_hchacha20_KEYBYTES as usize,
+    crypto_core_hchacha20_CONSTBYTES as usize
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex;
+
+    #[test]
+    fn test_vector_1() {
+        // https://tools.ietf.org/html/draft-irtf-cfrg-xchacha-03#section-2.2.1
+        let k = Key([
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+            0x1c, 0x1d, 0x1e, 0x1f,
+        ]);
+        let inp = Input([
+            0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x4a, 0x00, 0x00, 0x00, 0x00, 0x31, 0x41,
+            0x59, 0x27,
+        ]);
+        let out = hchacha20(&inp, &k, None);
+        assert_eq!(
+            hex::encode(&out),
+            "82413b4227b27bfed30e42508a877d73a0f9e4d58a74a853c12ec41326d3ecdc"
+        );
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn test_xchacha20_subkey() {
+        use crypto::stream::{chacha20, xchacha20};
+        let k = xchacha20::gen_key();
+        let n = xchacha20::gen_nonce();
+        let subkey = hchacha20(
+            &Input::from_slice(&n.0[..16]).unwrap(),
+            &Key::from_slice(&k.0).unwrap(),
+            None,
+        );
+        let mut chacha_n = [0u8; chacha20::NONCEBYTES];
+        chacha_n.copy_from_slice(&n.0[16..]);
+        assert_eq!(
+            xchacha20::stream(256, &n, &k),
+            chacha20::stream(
+                256,
+                &chacha20::Nonce(chacha_n),
+                &chacha20::Key::from_slice(&subkey.0).unwrap()
+            )
+        );
+    }
+}
@@ -0,0 +1,66 @@
+//! `crypto_core_hsalsa20`, the `HSalsa20` function used to derive the
+//! subkeys of `XSalsa20` and the shared keys of `crypto_box` as specified in
+//! [Cryptography in `NaCl`](http://nacl.cr.yp.to/valid.html), Section 8.
+//!
+//! `HSalsa20` is not a general purpose hash function. It is only
+//! intended for building constructions such as `XSalsa20` on top of
+//! `Salsa20`, where the key is uniformly random.
+
+use ffi::{
+    crypto_core_hsalsa20, crypto_core_hsalsa20_CONSTBYTES, crypto_core_hsalsa20_INPUTBYTES,
+    crypto_core_hsalsa20_KEYBYTES, crypto_core_hsalsa20_OUTPUTBYTES,
+};
+
+core_module!(
+    crypto_core_hsalsa20,
+    hsalsa20,
+    crypto_core_hsalsa20_OUTPUTBYTES as usize,
+    crypto_core_hsalsa20_INPUTBYTES as usize,
+    crypto_core_hsalsa20_KEYBYTES as usize,
+    crypto_core_hsalsa20_CONSTBYTES as usize
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex;
+
+    #[test]
+    fn test_vector_1() {
+        // core1 from the `NaCl` tests: the first key of the box test vector
+        // derived from the Curve25519 shared secret of alice and bob
+        let shared = Key([
+            0x4a, 0x5d, 0x9d, 0x5b, 0xa4, 0xce, 0x2d, 0xe1, 0x72, 0x8e, 0x3b, 0xf4, 0x80, 0x35,
+            0x0f, 0x25, 0xe0, 0x7e, 0x21, 0xc9, 0x47, 0xd1, 0x9e, 0x33, 0x76, 0xf0, 0x9b, 0x3c,
+            0x1e, 0x16, 0x17, 0x42,
+        ]);
+        let out = hsalsa20(&Input([0; INPUTBYTES]), &shared, None);
+        assert_eq!(
+            hex::encode(&out),
+            "1b27556473e985d462cd51197a9a46c76009549eac6474f206c4ee0844f68389"
+        );
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn test_xsalsa20_subkey() {
+        use crypto::stream::{salsa20, xsalsa20};
+        let k = xsalsa20::gen_key();
+        let n = xsalsa20::gen_nonce();
+        let subkey = hsalsa20(
+            &Input::from_slice(&n.0[..16]).unwrap(),
+            &Key::from_slice(&k.0).unwrap(),
+            None,
+        );
+        let mut salsa_n = [0u8; salsa20::NONCEBYTES];
+        salsa_n.copy_from_slice(&n.0[16..]);
+        assert_eq!(
+            xsalsa20::stream(256, &n, &k),
+            salsa20::stream(
+                256,
+                &salsa20::Nonce(salsa_n),
+                &salsa20::Key::from_slice(&subkey.0).unwrap()
+            )
+        );
+    }
+}
@@ -0,0 +1,33 @@
+//! Core functions
+//!
+//! The core functions are the building blocks that libsodium uses to derive
+//! subkeys for its extended-nonce constructions. They are exposed for
+//! implementing custom constructions, e.g. deriving `XChaCha20` subkeys or
+//! interoperating with `NaCl`-style key derivation, without resorting to the
+//! raw FFI.
+//!
+//! Most users should not need this module: the constructions in
+//! [`crypto::stream`](../stream/index.html),
+//! [`crypto::secretbox`](../secretbox/index.html) and
+//! [`crypto::box_`](../box_/index.html) already apply these functions
+//! internally.
+//!
+//! With the `hazmat` feature, [`ed25519`](ed25519/index.html) additionally
+//! exposes point and scalar arithmetic on the Ed25519 curve.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::core::hchacha20;
+//!
+//! let key = hchacha20::Key([0x42; hchacha20::KEYBYTES]);
+//! let input = hchacha20::Input([0x24; hchacha20::INPUTBYTES]);
+//! let subkey = hchacha20::hchacha20(&input, &key, None);
+//! assert_eq!(subkey.as_ref().len(), hchacha20::OUTPUTBYTES);
+//! ```
+
+#[macro_use]
+mod core_macros;
+#[cfg(feature = "hazmat")]
+pub mod ed25519;
+pub mod hchacha20;
+pub mod hsalsa20;
@@ -0,0 +1,354 @@
+//! Multi-recipient sealed envelopes
+//!
+//! # Purpose
+//! An envelope encrypts a message once and lets any of a list of recipients
+//! decrypt it with their `box_` key pair. Unlike sealing the message to each
+//! recipient separately, the size of an envelope only grows by `SLOTBYTES`
+//! per recipient, however long the message is.
+//!
+//! # Algorithm Details
+//! The message is encrypted with `aead::xchacha20poly1305_ietf` under a
+//! random payload key, and that key is sealed to every recipient with
+//! `sealedbox::seal()`, giving one slot per recipient. An envelope is
+//!
+//! ```text
+//! header || aead(m, ad=header, nonce=0, key)
+//! header = MAGIC || VERSION || count || slot_1 || ... || slot_count
+//! slot_i = sealedbox(key, recipient_pk_i)
+//! ```
+//!
+//! with `count` the number of slots as a 16 bit little-endian integer. The
+//! payload key is fresh for every envelope and encrypts a single message, so
+//! the nonce can be all zeros. The whole header, slots included, is the
+//! additional data of the payload: adding, removing, replacing or reordering
+//! slots makes decryption fail for every recipient.
+//!
+//! The slots do not name their recipients, so a recipient tries every slot
+//! of the header and envelopes do not reveal who they are for. The flip side
+//! is that opening takes time proportional to the number of recipients.
+//!
+//! NOTE: an envelope does not authenticate its sender. Any recipient knows
+//! the key and can use it to forge a different message to the other
+//! recipients.
+//!
+//! # Signed messages
+//! With the `signedseal` feature, `sign_and_seal()` and `open_and_verify()`
+//! sign a message with the sender's `sign` key before sealing it to a single
+//! recipient, and check the signature after opening it. They use the format
+//! of [`signedseal`](../signedseal/index.html), where the signature also
+//! covers the recipient's public key, so that messages cannot be re-sealed to
+//! somebody else in the sender's name.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::{box_, envelope};
+//!
+//! let (alice_pk, alice_sk) = box_::gen_keypair();
+//! let (bob_pk, bob_sk) = box_::gen_keypair();
+//!
+//! let c = envelope::seal(b"some data", &[alice_pk, bob_pk]).unwrap();
+//! assert_eq!(envelope::open(&c, &alice_pk, &alice_sk).unwrap(), b"some data");
+//! assert_eq!(envelope::open(&c, &bob_pk, &bob_sk).unwrap(), b"some data");
+//! ```
+//!
+//! # Example (signed messages)
+//! ```
+//! use sodiumoxide::crypto::{box_, envelope, sign};
+//!
+//! let (sender_pk, sender_sk) = sign::gen_keypair();
+//! let (recipient_pk, recipient_sk) = box_::gen_keypair();
+//!
+//! let c = envelope::sign_and_seal(b"some data", &sender_sk, &recipient_pk);
+//! let m = envelope::open_and_verify(&c, &sender_pk, &recipient_sk).unwrap();
+//! assert_eq!(m, b"some data");
+//! ```
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+
+use crypto::aead::xchacha20poly1305_ietf as aead;
+use crypto::box_;
+use crypto::sealedbox;
+#[cfg(feature = "signedseal")]
+use crypto::{sign, signedseal};
+use utils::memzero;
+use Error;
+
+/// `MAGIC` starts every envelope, followed by `VERSION` and the slot count.
+pub const MAGIC: &[u8] = b"envelope";
+
+/// Format version of the envelopes written by `seal()`; `open()` refuses any
+/// other.
+pub const VERSION: u8 = 1;
+
+/// Number of bytes before the first recipient slot: `MAGIC`, `VERSION` and
+/// the 16 bit slot count.
+pub const HEADERBYTES: usize = MAGIC.len() + 1 + 2;
+
+/// Number of bytes in a recipient slot, i.e. the payload key sealed to one
+/// recipient with `sealedbox`.
+pub const SLOTBYTES: usize = sealedbox::SEALBYTES + aead::KEYBYTES;
+
+/// Maximum number of recipients of an envelope.
+pub const RECIPIENTS_MAX: usize = 0xffff;
+
+/// `sealbytes()` returns the number of additional bytes in an envelope for
+/// `recipients` recipients compared to the corresponding plaintext.
+pub fn sealbytes(recipients: usize) -> usize {
+    HEADERBYTES + recipients * SLOTBYTES + aead::TAGBYTES
+}
+
+/// `seal()` encrypts the message `m` so that it can be opened by any of the
+/// recipients whose public keys are `recipient_pks`. It returns a ciphertext
+/// whose length is `sealbytes(recipient_pks.len()) + m.len()`.
+///
+/// It returns `Err(Error::InvalidParameter)` if there are no recipients or
+/// more than `RECIPIENTS_MAX`.
+pub fn seal(m: &[u8], recipient_pks: &[box_::PublicKey]) -> Result<Vec<u8>, Error> {
+    let count = recipient_pks.len();
+    if count == 0 || count > RECIPIENTS_MAX {
+        return Err(Error::InvalidParameter);
+    }
+    let k = aead::gen_key();
+    let mut header = Vec::with_capacity(HEADERBYTES + count * SLOTBYTES);
+    header.extend_from_slice(MAGIC);
+    header.push(VERSION);
+    header.extend_from_slice(&(count as u16).to_le_bytes());
+    for pk in recipient_pks {
+        header.extend_from_slice(&sealedbox::seal(&k.0, pk));
+    }
+
+    let c = aead::seal(m, Some(&header), &aead::Nonce([0; aead::NONCEBYTES]), &k);
+    let mut out = header;
+    out.extend_from_slice(&c);
+    Ok(out)
+}
+
+/// `open()` looks for the slot of the envelope `c` that was sealed to the key
+/// pair `(recipient_pk, recipient_sk)`, and decrypts the message with the
+/// payload key it holds.
+///
+/// The header is checked before any slot is opened: `open()` returns
+/// `Err(Error::InvalidEncoding)` if `c` does not start with `MAGIC` and
+/// `VERSION` or announces no slots, and `Err(Error::InvalidLength)` if it is
+/// too short to hold the announced slots and the authentication tag. It
+/// returns `Err(Error::VerificationFailed)` if no slot opens with the key
+/// pair, or if the slot table or the payload were modified.
+pub fn open(
+    c: &[u8],
+    recipient_pk: &box_::PublicKey,
+    recipient_sk: &box_::SecretKey,
+) -> Result<Vec<u8>, Error> {
+    if c.len() < sealbytes(1) {
+        return Err(Error::InvalidLength);
+    }
+    let (magic, rest) = c.split_at(MAGIC.len());
+    if magic != MAGIC || rest[0] != VERSION {
+        return Err(Error::InvalidEncoding);
+    }
+    let count = usize::from(u16::from_le_bytes([rest[1], rest[2]]));
+    if count == 0 {
+        return Err(Error::InvalidEncoding);
+    }
+    if c.len() < sealbytes(count) {
+        return Err(Error::InvalidLength);
+    }
+    let (header, body) = c.split_at(HEADERBYTES + count * SLOTBYTES);
+
+    // try every slot, so that the time taken does not depend on which one
+    // belongs to the recipient
+    let mut k = None;
+    for slot in header[HEADERBYTES..].chunks(SLOTBYTES) {
+        if let Ok(mut key) = sealedbox::open(slot, recipient_pk, recipient_sk) {
+            if k.is_none() {
+                k = aead::Key::from_slice(&key);
+            }
+            memzero(&mut key);
+        }
+    }
+    let k = k.ok_or(Error::VerificationFailed)?;
+    aead::open(body, Some(header), &aead::Nonce([0; aead::NONCEBYTES]), &k)
+}
+
+/// `sign_and_seal()` signs the message `m` with the sender's secret key
+/// `sender_sk` and encrypts it together with the signature for the recipient
+/// whose public key is `recipient_pk`. It is the same as
+/// `signedseal::seal()`.
+#[cfg(feature = "signedseal")]
+pub fn sign_and_seal(
+    m: &[u8],
+    sender_sk: &sign::SecretKey,
+    recipient_pk: &box_::PublicKey,
+) -> Vec<u8> {
+    signedseal::seal(m, sender_sk, recipient_pk)
+}
+
+/// `open_and_verify()` decrypts the ciphertext `c` produced by
+/// `sign_and_seal()` using the recipient's secret key `recipient_sk`, and
+/// verifies that it was signed by the sender whose public key is `sender_pk`,
+/// for this recipient.
+///
+/// It fails like `signedseal::open()`, which it calls with the public key
+/// computed from `recipient_sk`.
+#[cfg(feature = "signedseal")]
+pub fn open_and_verify(
+    c: &[u8],
+    sender_pk: &sign::PublicKey,
+    recipient_sk: &box_::SecretKey,
+) -> Result<Vec<u8>, Error> {
+    signedseal::open(c, &recipient_sk.public_key(), recipient_sk, sender_pk)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use randombytes::randombytes;
+
+    #[test]
+    fn test_seal_open() {
+        let keypairs: Vec<_> = (0..4).map(|_| box_::gen_keypair()).collect();
+        let pks: Vec<_> = keypairs.iter().map(|&(pk, _)| pk).collect();
+        for i in 0..64usize {
+            let m = randombytes(i);
+            let c = seal(&m, &pks).unwrap();
+            assert_eq!(c.len(), m.len() + sealbytes(pks.len()));
+            for (pk, sk) in keypairs.iter() {
+                assert_eq!(open(&c, pk, sk), Ok(m.clone()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_header() {
+        let (pk, sk) = box_::gen_keypair();
+        let c = seal(b"", &[pk, pk, pk]).unwrap();
+        assert!(c.starts_with(b"envelope\x01\x03\x00"));
+        assert_eq!(c.len(), HEADERBYTES + 3 * SLOTBYTES + aead::TAGBYTES);
+        assert_eq!(open(&c, &pk, &sk), Ok(Vec::new()));
+
+        // a future version or an empty slot table is refused up front
+        let mut next_version = c.clone();
+        next_version[MAGIC.len()] = VERSION + 1;
+        assert_eq!(open(&next_version, &pk, &sk), Err(Error::InvalidEncoding));
+        let mut no_slots = c;
+        no_slots[MAGIC.len() + 1] = 0;
+        no_slots[MAGIC.len() + 2] = 0;
+        assert_eq!(open(&no_slots, &pk, &sk), Err(Error::InvalidEncoding));
+    }
+
+    #[test]
+    fn test_not_a_recipient() {
+        let (pk, _) = box_::gen_keypair();
+        let (other_pk, other_sk) = box_::gen_keypair();
+        let c = seal(b"some data", &[pk]).unwrap();
+        assert_eq!(
+            open(&c, &other_pk, &other_sk),
+            Err(Error::VerificationFailed)
+        );
+    }
+
+    #[test]
+    fn test_recipients() {
+        assert_eq!(seal(b"some data", &[]), Err(Error::InvalidParameter));
+        let (pk, _) = box_::gen_keypair();
+        let pks = vec![pk; RECIPIENTS_MAX + 1];
+        assert_eq!(seal(b"some data", &pks), Err(Error::InvalidParameter));
+    }
+
+    #[test]
+    fn test_tamper() {
+        let (pk1, sk1) = box_::gen_keypair();
+        let (pk2, _) = box_::gen_keypair();
+        let mut c = seal(b"some data", &[pk1, pk2]).unwrap();
+        for i in 0..c.len() {
+            c[i] ^= 0x01;
+            assert!(open(&c, &pk1, &sk1).is_err());
+            c[i] ^= 0x01;
+        }
+    }
+
+    #[test]
+    fn test_change_recipients() {
+        let (pk1, sk1) = box_::gen_keypair();
+        let (pk2, _) = box_::gen_keypair();
+        let c = seal(b"some data", &[pk1, pk2]).unwrap();
+        let (slot1, slot2) = c[HEADERBYTES..HEADERBYTES + 2 * SLOTBYTES].split_at(SLOTBYTES);
+
+        // other recipient removed
+        let mut removed = c[..HEADERBYTES].to_vec();
+        removed[MAGIC.len() + 1] = 1;
+        removed.extend_from_slice(slot1);
+        removed.extend_from_slice(&c[HEADERBYTES + 2 * SLOTBYTES..]);
+        assert_eq!(open(&removed, &pk1, &sk1), Err(Error::VerificationFailed));
+
+        // recipients reordered
+        let mut reordered = c[..HEADERBYTES].to_vec();
+        reordered.extend_from_slice(slot2);
+        reordered.extend_from_slice(slot1);
+        reordered.extend_from_slice(&c[HEADERBYTES + 2 * SLOTBYTES..]);
+        assert_eq!(open(&reordered, &pk1, &sk1), Err(Error::VerificationFailed));
+
+        // slot taken from another envelope to the same recipient: it opens,
+        // but its key does not match the payload
+        let other = seal(b"other data", &[pk1, pk2]).unwrap();
+        let mut replaced = c.clone();
+        replaced[HEADERBYTES..HEADERBYTES + SLOTBYTES]
+            .copy_from_slice(&other[HEADERBYTES..HEADERBYTES + SLOTBYTES]);
+        assert_eq!(open(&replaced, &pk1, &sk1), Err(Error::VerificationFailed));
+    }
+
+    #[test]
+    fn test_truncated_slot_table() {
+        let (pk, sk) = box_::gen_keypair();
+        let c = seal(b"some data", &[pk, pk, pk]).unwrap();
+        for &len in [
+            HEADERBYTES + SLOTBYTES,
+            HEADERBYTES + 2 * SLOTBYTES,
+            sealbytes(3) - 1,
+        ]
+        .iter()
+        {
+            assert_eq!(open(&c[..len], &pk, &sk), Err(Error::InvalidLength));
+        }
+
+        // a count announcing more slots than the envelope holds
+        let mut overcounted = c;
+        overcounted[MAGIC.len() + 1] = 4;
+        assert_eq!(open(&overcounted, &pk, &sk), Err(Error::InvalidLength));
+    }
+
+    #[cfg(feature = "signedseal")]
+    #[test]
+    fn test_sign_and_seal() {
+        let (sender_pk, sender_sk) = sign::gen_keypair();
+        let (recipient_pk, recipient_sk) = box_::gen_keypair();
+        for i in 0..64usize {
+            let m = randombytes(i);
+            let c = sign_and_seal(&m, &sender_sk, &recipient_pk);
+            assert_eq!(c.len(), m.len() + signedseal::SEALBYTES);
+            assert_eq!(open_and_verify(&c, &sender_pk, &recipient_sk), Ok(m));
+        }
+    }
+
+    #[cfg(feature = "signedseal")]
+    #[test]
+    fn test_open_and_verify_wrong_keys() {
+        let (sender_pk, sender_sk) = sign::gen_keypair();
+        let (other_pk, other_sk) = sign::gen_keypair();
+        let (recipient_pk, recipient_sk) = box_::gen_keypair();
+        let (_, other_recipient_sk) = box_::gen_keypair();
+        let c = sign_and_seal(b"some data", &sender_sk, &recipient_pk);
+        assert_eq!(
+            open_and_verify(&c, &other_pk, &recipient_sk),
+            Err(Error::VerificationFailed)
+        );
+        assert!(open_and_verify(&c, &sender_pk, &other_recipient_sk).is_err());
+
+        // signed by another sender, who cannot pass it off as the original one
+        let forged = sign_and_seal(b"some data", &other_sk, &recipient_pk);
+        assert_eq!(
+            open_and_verify(&forged, &sender_pk, &recipient_sk),
+            Err(Error::VerificationFailed)
+        );
+    }
+}
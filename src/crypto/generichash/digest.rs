@@ -1,8 +1,12 @@
 use ffi::crypto_generichash_BYTES_MAX;
+use ffi::crypto_generichash_BYTES_MIN;
+use std::borrow::Borrow;
 use std::cmp::{Eq, Ordering, PartialEq, PartialOrd};
-use std::fmt::{Debug, Formatter};
+use std::convert::TryFrom;
+use std::fmt::{Debug, Display, Formatter, LowerHex};
 use std::hash::{Hash, Hasher};
 use std::ops::{Index, Range, RangeFrom, RangeFull, RangeTo};
+use std::str::FromStr;
 
 /// Digest-structure
 ///
@@ -23,12 +27,61 @@ impl Digest {
     }
 }
 
+/// Creates a digest from a byte slice of between `DIGEST_MIN` and
+/// `DIGEST_MAX` bytes, and fails with `Error::InvalidLength` otherwise.
+impl<'a> TryFrom<&'a [u8]> for Digest {
+    type Error = ::Error;
+    fn try_from(bs: &'a [u8]) -> Result<Digest, ::Error> {
+        if bs.len() < crypto_generichash_BYTES_MIN as usize
+            || bs.len() > crypto_generichash_BYTES_MAX as usize
+        {
+            return Err(::Error::InvalidLength);
+        }
+        let mut res = Digest::new(bs.len());
+        res.data[..bs.len()].copy_from_slice(bs);
+        Ok(res)
+    }
+}
+
 impl Debug for Digest {
     fn fmt(&self, formatter: &mut Formatter) -> ::std::fmt::Result {
         write!(formatter, "Digest({:?})", &self[..])
     }
 }
 
+/// Formats the digest as a lower case hexadecimal string.
+impl LowerHex for Digest {
+    fn fmt(&self, formatter: &mut Formatter) -> ::std::fmt::Result {
+        let mut buf = [0u8; 2 * crypto_generichash_BYTES_MAX as usize + 1];
+        formatter.write_str(::hex::encode_to(self.as_ref(), &mut buf))
+    }
+}
+
+/// Formats the digest as a lower case hexadecimal string.
+impl Display for Digest {
+    fn fmt(&self, formatter: &mut Formatter) -> ::std::fmt::Result {
+        LowerHex::fmt(self, formatter)
+    }
+}
+
+/// Parses a digest of between `DIGEST_MIN` and `DIGEST_MAX` bytes from a
+/// hexadecimal string, in lower or upper case.
+impl FromStr for Digest {
+    type Err = ::Error;
+    fn from_str(hex: &str) -> Result<Digest, ::Error> {
+        let len = hex.len() / 2;
+        if 2 * len != hex.len()
+            || len < crypto_generichash_BYTES_MIN as usize
+            || len > crypto_generichash_BYTES_MAX as usize
+        {
+            return Err(::Error::InvalidLength);
+        }
+        let mut res = Digest::new(0);
+        res.len = ::hex::decode_to(hex.as_bytes(), &mut res.data)?;
+        Ok(res)
+    }
+}
+
 impl PartialEq for Digest {
     fn eq(&self, other: &Digest) -> bool {
         use utils::memcmp;
@@ -41,6 +94,19 @@ impl PartialEq for Digest {
 
 impl Eq for Digest {}
 
+/// Digests of different lengths are never equal. Only their contents are
+/// compared in constant time, the lengths are not secret.
+#[cfg(feature = "subtle")]
+impl ::subtle::ConstantTimeEq for Digest {
+    fn ct_eq(&self, other: &Digest) -> ::subtle::Choice {
+        use utils::memcmp;
+        if other.len != self.len {
+            return ::subtle::Choice::from(0);
+        }
+        ::subtle::Choice::from(memcmp(self.as_ref(), other.as_ref()) as u8)
+    }
+}
+
 impl AsRef<[u8]> for Digest {
     #[inline]
     fn as_ref(&self) -> &[u8] {
@@ -48,6 +114,13 @@ impl AsRef<[u8]> for Digest {
     }
 }
 
+impl Borrow<[u8]> for Digest {
+    #[inline]
+    fn borrow(&self) -> &[u8] {
+        self.as_ref()
+    }
+}
+
 impl PartialOrd for Digest {
     #[inline]
     fn partial_cmp(&self, other: &Digest) -> Option<Ordering> {
@@ -88,6 +161,86 @@ impl Hash for Digest {
     }
 }
 
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for Digest {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        if serializer.is_human_readable() {
+            let mut buf = [0u8; 2 * crypto_generichash_BYTES_MAX as usize + 1];
+            serializer.serialize_str(::hex::encode_to(self.as_ref(), &mut buf))
+        } else {
+            serializer.serialize_bytes(self.as_ref())
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> ::serde::Deserialize<'de> for Digest {
+    fn deserialize<D>(deserializer: D) -> Result<Digest, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {
+        struct DigestVisitor;
+        impl<'de> ::serde::de::Visitor<'de> for DigestVisitor {
+            type Value = Digest;
+            fn expecting(&self, formatter: &mut Formatter) -> ::std::fmt::Result {
+                write!(
+                    formatter,
+                    "Digest ({} to {} bytes, or hex)",
+                    crypto_generichash_BYTES_MIN, crypto_generichash_BYTES_MAX
+                )
+            }
+            fn visit_seq<V>(self, mut visitor: V) -> Result<Digest, V::Error>
+            where
+                V: ::serde::de::SeqAccess<'de>,
+            {
+                let mut res = Digest::new(0);
+                while let Some(value) = visitor.next_element()? {
+                    if res.len == res.data.len() {
+                        return Err(::serde::de::Error::invalid_length(res.len + 1, &self));
+                    }
+                    res.data[res.len] = value;
+                    res.len += 1;
+                }
+                if res.len < crypto_generichash_BYTES_MIN as usize {
+                    return Err(::serde::de::Error::invalid_length(res.len, &self));
+                }
+                Ok(res)
+            }
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Digest, E>
+            where
+                E: ::serde::de::Error,
+            {
+                Digest::try_from(v).map_err(|_| ::serde::de::Error::invalid_length(v.len(), &self))
+            }
+            fn visit_str<E>(self, v: &str) -> Result<Digest, E>
+            where
+                E: ::serde::de::Error,
+            {
+                let mut res = Digest::new(0);
+                match ::hex::decode_to(v.as_bytes(), &mut res.data) {
+                    Ok(len) if len >= crypto_generichash_BYTES_MIN as usize => {
+                        res.len = len;
+                        Ok(res)
+                    }
+                    Ok(len) => Err(::serde::de::Error::invalid_length(len, &self)),
+                    Err(_) => Err(::serde::de::Error::invalid_value(
+                        ::serde::de::Unexpected::Str(v),
+                        &self,
+                    )),
+                }
+            }
+        }
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(DigestVisitor)
+        } else {
+            deserializer.deserialize_bytes(DigestVisitor)
+        }
+    }
+}
+
 /// Allows a user to access the byte contents of an object as a slice.
 ///
 /// WARNING: it might be tempting to do comparisons on objects
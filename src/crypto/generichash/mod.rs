@@ -1,8 +1,31 @@
-//! `GenericHash`.
+//! `GenericHash`, a keyed or unkeyed hash function with a variable output
+//! length.
 //!
+//! The output length can be chosen between `DIGEST_MIN` and `DIGEST_MAX`
+//! bytes. An optional key of `KEY_MIN` to `KEY_MAX` bytes turns the hash into
+//! a MAC, so the same key and message always produce the same `Digest` while
+//! a different key produces an unrelated one.
+//!
+//! # Selected primitive
+//! `hash()` and `State` are currently an implementation of `BLAKE2b`.
+//!
+//! # Example (one-shot and streaming)
+//! ```
+//! use sodiumoxide::crypto::generichash;
+//!
+//! let key = [0x42; generichash::KEY_MIN];
+//! let digest = generichash::hash(b"some data", Some(64), Some(&key)).unwrap();
+//!
+//! let mut state = generichash::State::new(Some(64), Some(&key)).unwrap();
+//! state.update(b"some ").unwrap();
+//! state.update(b"data").unwrap();
+//! assert_eq!(state.finalize().unwrap(), digest);
+//! ```
 use ffi::{
     crypto_generichash, crypto_generichash_BYTES, crypto_generichash_BYTES_MAX,
     crypto_generichash_BYTES_MIN, crypto_generichash_KEYBYTES_MAX, crypto_generichash_KEYBYTES_MIN,
+    crypto_generichash_blake2b_PERSONALBYTES, crypto_generichash_blake2b_SALTBYTES,
+    crypto_generichash_blake2b_init_salt_personal, crypto_generichash_blake2b_salt_personal,
     crypto_generichash_final, crypto_generichash_init, crypto_generichash_state,
     crypto_generichash_update,
 };
@@ -10,6 +33,7 @@ use ffi::{
 use libc::c_ulonglong;
 use std::mem;
 use std::ptr;
+use Error;
 
 mod digest;
 pub use self::digest::Digest;
@@ -26,11 +50,26 @@ pub const KEY_MIN: usize = crypto_generichash_KEYBYTES_MIN as usize;
 /// Maximum of allowed bytes in a key
 pub const KEY_MAX: usize = crypto_generichash_KEYBYTES_MAX as usize;
 
+/// Maximum of allowed bytes in a salt
+pub const SALT_MAX: usize = crypto_generichash_blake2b_SALTBYTES as usize;
+
+/// Maximum of allowed bytes in a personalization string
+pub const PERSONAL_MAX: usize = crypto_generichash_blake2b_PERSONALBYTES as usize;
+
 /// `State` contains the state for multi-part (streaming) hash computations. This allows the caller
 /// to process a message as a sequence of multiple chunks.
 pub struct State {
     out_len: usize,
     state: crypto_generichash_state,
+    // set by `zeroize()`, after which the state must not be used
+    zeroized: bool,
+}
+
+impl ::std::fmt::Debug for State {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        // A keyed state is derived from the key, hide it from debug output.
+        write!(formatter, "State {{ out_len: {}, .. }}", self.out_len)
+    }
 }
 
 impl State {
@@ -44,11 +83,15 @@ impl State {
     /// `key` is an optional parameter, which when given,
     /// a custom key can be used for the computation of the hash.
     /// The size of the key must be in the interval [`KEY_MIN`, `KEY_MAX`].
-    pub fn new(out_len: Option<usize>, key: Option<&[u8]>) -> Result<State, ()> {
+    ///
+    /// It returns `Err(Error::InvalidLength)` if `out_len` or the key length
+    /// are out of range.
+    pub fn new(out_len: Option<usize>, key: Option<&[u8]>) -> Result<State, Error> {
         let out_len = unwrap_out_len(out_len)?;
         let (key_ptr, key_len) = unwrap_key(key)?;
 
-        let mut state = mem::MaybeUninit::uninit();
+        // the state is opaque and may contain bytes the init function does not write
+        let mut state = mem::MaybeUninit::zeroed();
 
         let result =
             unsafe { crypto_generichash_init(state.as_mut_ptr(), key_ptr, key_len, out_len) };
@@ -56,28 +99,88 @@ impl State {
         if result == 0 {
             // result == 0 and state is initialized
             let state = unsafe { state.assume_init() };
-            Ok(State { out_len, state })
+            Ok(State {
+                out_len,
+                state,
+                zeroized: false,
+            })
         } else {
-            Err(())
+            Err(Error::InitFailed)
+        }
+    }
+
+    /// `init_with_params` constructs and initializes a new `State` like `new`, additionally
+    /// setting the `BLAKE2b` salt and personalization parameters.
+    ///
+    /// `salt` and `personal` are optional parameters that can be used for domain separation:
+    /// hashes computed with different salts or personalization strings are unrelated, even
+    /// for the same key and data. They are at most [`SALT_MAX`] and [`PERSONAL_MAX`] bytes
+    /// long and shorter values are padded with zeros, so that omitting them is equivalent to
+    /// passing all zeros.
+    pub fn init_with_params(
+        out_len: Option<usize>,
+        key: Option<&[u8]>,
+        salt: Option<&[u8]>,
+        personal: Option<&[u8]>,
+    ) -> Result<State, Error> {
+        let out_len = unwrap_out_len(out_len)?;
+        let (key_ptr, key_len) = unwrap_key(key)?;
+        let salt = unwrap_param::<[u8; SALT_MAX]>(salt)?;
+        let personal = unwrap_param::<[u8; PERSONAL_MAX]>(personal)?;
+
+        // the state is opaque and may contain bytes the init function does not write
+        let mut state = mem::MaybeUninit::zeroed();
+
+        let result = unsafe {
+            crypto_generichash_blake2b_init_salt_personal(
+                state.as_mut_ptr(),
+                key_ptr,
+                key_len,
+                out_len,
+                salt.as_ptr(),
+                personal.as_ptr(),
+            )
+        };
+
+        if result == 0 {
+            // result == 0 and state is initialized
+            let state = unsafe { state.assume_init() };
+            Ok(State {
+                out_len,
+                state,
+                zeroized: false,
+            })
+        } else {
+            Err(Error::InitFailed)
         }
     }
 
     /// `update` updates the `State` with `data`. `update` can be called multiple times in order
     /// to compute the hash from sequential chunks of the message.
-    pub fn update(&mut self, data: &[u8]) -> Result<(), ()> {
+    ///
+    /// It returns `Err(Error::Finalized)` if the state has been zeroized.
+    pub fn update(&mut self, data: &[u8]) -> Result<(), Error> {
+        if self.zeroized {
+            return Err(Error::Finalized);
+        }
         let rc = unsafe {
             crypto_generichash_update(&mut self.state, data.as_ptr(), data.len() as c_ulonglong)
         };
         if rc == 0 {
             Ok(())
         } else {
-            Err(())
+            Err(Error::InvalidParameter)
         }
     }
 
     /// `finalize` finalizes the state and returns the digest value. `finalize` consumes the
     /// `State` so that it cannot be accidentally reused.
-    pub fn finalize(mut self) -> Result<Digest, ()> {
+    ///
+    /// It returns `Err(Error::Finalized)` if the state has been zeroized.
+    pub fn finalize(mut self) -> Result<Digest, Error> {
+        if self.zeroized {
+            return Err(Error::Finalized);
+        }
         let mut result = Digest::new(self.out_len);
         let rc = unsafe {
             crypto_generichash_final(&mut self.state, result.data.as_mut_ptr(), result.len)
@@ -85,8 +188,125 @@ impl State {
         if rc == 0 {
             Ok(result)
         } else {
-            Err(())
+            Err(Error::InvalidParameter)
+        }
+    }
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        // the state of a keyed hash is derived from the key
+        unsafe {
+            ffi::sodium_memzero(
+                &mut self.state as *mut crypto_generichash_state as *mut _,
+                ffi::crypto_generichash_statebytes(),
+            );
+        }
+    }
+}
+
+/// Feeds everything written into `update()`, e.g. to hash a stream with
+/// `io::copy()`.
+#[cfg(feature = "std")]
+impl ::std::io::Write for State {
+    fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+        self.update(buf).map_err(|_| {
+            ::std::io::Error::new(
+                ::std::io::ErrorKind::InvalidInput,
+                "generichash update failed",
+            )
+        })?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> ::std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Lets a `State` be used with `Hash` implementations. `finish()` returns the
+/// low 64 bits, in little-endian order, of the digest of the data written
+/// so far, without consuming the state; `finalize()` still returns the full
+/// digest.
+///
+/// Both panic if the state has been zeroized.
+impl ::std::hash::Hasher for State {
+    fn write(&mut self, bytes: &[u8]) {
+        // BLAKE2b updates only fail on a zeroized state
+        State::update(self, bytes).expect("generichash update failed")
+    }
+
+    fn finish(&self) -> u64 {
+        assert!(!self.zeroized, "generichash state was zeroized");
+        let mut state = self.state;
+        let mut digest = [0u8; DIGEST_MAX];
+        let rc = unsafe { crypto_generichash_final(&mut state, digest.as_mut_ptr(), self.out_len) };
+        assert_eq!(rc, 0);
+        let mut low = [0u8; 8];
+        low.copy_from_slice(&digest[..8]);
+        unsafe {
+            ffi::sodium_memzero(
+                &mut state as *mut crypto_generichash_state as *mut _,
+                ffi::crypto_generichash_statebytes(),
+            );
         }
+        ::utils::memzero(&mut digest);
+        u64::from_le_bytes(low)
+    }
+}
+
+/// Zeroes out the state. Afterwards `update()` and `finalize()` return
+/// `Err(Error::Finalized)`, and the `Hasher` and `digest` implementations
+/// panic.
+#[cfg(feature = "zeroize")]
+impl ::zeroize::Zeroize for State {
+    fn zeroize(&mut self) {
+        unsafe {
+            ffi::sodium_memzero(
+                &mut self.state as *mut crypto_generichash_state as *mut _,
+                ffi::crypto_generichash_statebytes(),
+            );
+        }
+        self.zeroized = true;
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ::zeroize::ZeroizeOnDrop for State {}
+
+#[cfg(feature = "digest")]
+impl ::digest::HashMarker for State {}
+
+#[cfg(feature = "digest")]
+impl ::digest::Update for State {
+    fn update(&mut self, data: &[u8]) {
+        // BLAKE2b updates only fail on a zeroized state
+        State::update(self, data).expect("generichash update failed")
+    }
+}
+
+/// Hashes with the output length passed to `new()`, which must be between
+/// `DIGEST_MIN` and `DIGEST_MAX`, and without a key.
+#[cfg(feature = "digest")]
+impl ::digest::VariableOutput for State {
+    const MAX_OUTPUT_SIZE: usize = DIGEST_MAX;
+
+    fn new(output_size: usize) -> Result<State, ::digest::InvalidOutputSize> {
+        State::new(Some(output_size), None).map_err(|_| ::digest::InvalidOutputSize)
+    }
+
+    fn output_size(&self) -> usize {
+        self.out_len
+    }
+
+    fn finalize_variable(mut self, out: &mut [u8]) -> Result<(), ::digest::InvalidBufferSize> {
+        if out.len() != self.out_len {
+            return Err(::digest::InvalidBufferSize);
+        }
+        assert!(!self.zeroized, "generichash state was zeroized");
+        let rc = unsafe { crypto_generichash_final(&mut self.state, out.as_mut_ptr(), out.len()) };
+        assert_eq!(rc, 0);
+        Ok(())
     }
 }
 
@@ -100,7 +320,10 @@ impl State {
 /// `key` is an optional parameter, which when given,
 /// a custom key can be used for the computation of the hash.
 /// The size of the key must be in the interval [`KEY_MIN`, `KEY_MAX`].
-pub fn hash(data: &[u8], out_len: Option<usize>, key: Option<&[u8]>) -> Result<Digest, ()> {
+///
+/// It returns `Err(Error::InvalidLength)` if `out_len` or the key length are
+/// out of range.
+pub fn hash(data: &[u8], out_len: Option<usize>, key: Option<&[u8]>) -> Result<Digest, Error> {
     let out_len = unwrap_out_len(out_len)?;
     let (key_ptr, key_len) = unwrap_key(key)?;
 
@@ -118,14 +341,49 @@ pub fn hash(data: &[u8], out_len: Option<usize>, key: Option<&[u8]>) -> Result<D
     if rc == 0 {
         Ok(result)
     } else {
-        Err(())
+        Err(Error::InvalidParameter)
     }
 }
 
-fn unwrap_out_len(out_len: Option<usize>) -> Result<usize, ()> {
+/// `hash_with_params` computes a fingerprint of `data` like `hash`, additionally setting the
+/// `BLAKE2b` salt and personalization parameters as described for
+/// [`State::init_with_params`].
+pub fn hash_with_params(
+    data: &[u8],
+    out_len: Option<usize>,
+    key: Option<&[u8]>,
+    salt: Option<&[u8]>,
+    personal: Option<&[u8]>,
+) -> Result<Digest, Error> {
+    let out_len = unwrap_out_len(out_len)?;
+    let (key_ptr, key_len) = unwrap_key(key)?;
+    let salt = unwrap_param::<[u8; SALT_MAX]>(salt)?;
+    let personal = unwrap_param::<[u8; PERSONAL_MAX]>(personal)?;
+
+    let mut result = Digest::new(out_len);
+    let rc = unsafe {
+        crypto_generichash_blake2b_salt_personal(
+            result.data.as_mut_ptr(),
+            result.len,
+            data.as_ptr(),
+            data.len() as c_ulonglong,
+            key_ptr,
+            key_len,
+            salt.as_ptr(),
+            personal.as_ptr(),
+        )
+    };
+    if rc == 0 {
+        Ok(result)
+    } else {
+        Err(Error::InvalidParameter)
+    }
+}
+
+fn unwrap_out_len(out_len: Option<usize>) -> Result<usize, Error> {
     if let Some(out_len) = out_len {
         if !(DIGEST_MIN..=DIGEST_MAX).contains(&out_len) {
-            return Err(());
+            return Err(Error::InvalidLength);
         }
         Ok(out_len)
     } else {
@@ -133,11 +391,11 @@ fn unwrap_out_len(out_len: Option<usize>) -> Result<usize, ()> {
     }
 }
 
-fn unwrap_key(key: Option<&[u8]>) -> Result<(*const u8, usize), ()> {
+fn unwrap_key(key: Option<&[u8]>) -> Result<(*const u8, usize), Error> {
     if let Some(key) = key {
         let len = key.len();
         if !(KEY_MIN..=KEY_MAX).contains(&len) {
-            return Err(());
+            return Err(Error::InvalidLength);
         }
         Ok((key.as_ptr(), len))
     } else {
@@ -145,6 +403,18 @@ fn unwrap_key(key: Option<&[u8]>) -> Result<(*const u8, usize), ()> {
     }
 }
 
+fn unwrap_param<T: Default + AsMut<[u8]>>(param: Option<&[u8]>) -> Result<T, Error> {
+    let mut padded = T::default();
+    if let Some(param) = param {
+        let buf = padded.as_mut();
+        if param.len() > buf.len() {
+            return Err(Error::InvalidLength);
+        }
+        buf[..param.len()].copy_from_slice(param);
+    }
+    Ok(padded)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -152,6 +422,47 @@ mod test {
     #[cfg(not(feature = "std"))]
     use prelude::*;
 
+    #[test]
+    fn test_hasher() {
+        use randombytes::randombytes;
+        use std::hash::{Hash, Hasher};
+        let key = [0x42; KEY_MIN];
+        for i in 0..64usize {
+            let m = randombytes(i);
+            let mut state = State::new(Some(32), Some(&key)).unwrap();
+            Hasher::write(&mut state, &m);
+            let low = state.finish();
+            // finish() does not consume the state
+            assert_eq!(state.finish(), low);
+            let digest = state.finalize().unwrap();
+            assert_eq!(&low.to_le_bytes()[..], &digest[..8]);
+            assert_eq!(digest, hash(&m, Some(32), Some(&key)).unwrap());
+        }
+
+        let hash_value = |value: &(u32, &str)| {
+            let mut state = State::new(None, Some(&key)).unwrap();
+            value.hash(&mut state);
+            state.finish()
+        };
+        assert_eq!(hash_value(&(1, "a")), hash_value(&(1, "a")));
+        assert_ne!(hash_value(&(1, "a")), hash_value(&(2, "a")));
+    }
+
+    #[cfg(feature = "subtle")]
+    #[test]
+    fn test_digest_constant_time_eq() {
+        use subtle::ConstantTimeEq;
+        let d = hash(b"data", Some(32), None).unwrap();
+        assert!(bool::from(d.ct_eq(&hash(b"data", Some(32), None).unwrap())));
+        assert!(!bool::from(
+            d.ct_eq(&hash(b"datb", Some(32), None).unwrap())
+        ));
+        // different lengths, even though one digest is a prefix of the other
+        let mut prefix = d.clone();
+        prefix.len = 16;
+        assert!(!bool::from(d.ct_eq(&prefix)));
+    }
+
     #[test]
     fn test_vector_1() {
         // hash of empty string
@@ -290,4 +601,224 @@ mod test {
         assert_ne!(h1, h2);
         assert_eq!(h1, h3);
     }
+
+    #[test]
+    fn test_state_debug_redacted() {
+        let state = State::new(Some(DIGEST_MIN), Some(&[0x42; KEY_MIN])).unwrap();
+        assert_eq!(
+            format!("{:?}", state),
+            format!("State {{ out_len: {}, .. }}", DIGEST_MIN)
+        );
+    }
+
+    #[test]
+    fn test_digest_hex_string() {
+        let h = hash(b"", None, None).unwrap();
+        let s = "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8";
+        assert_eq!(h.to_string(), s);
+        assert_eq!(format!("{:x}", h), s);
+        assert_eq!(s.parse::<Digest>(), Ok(h.clone()));
+        assert_eq!(s.to_uppercase().parse::<Digest>(), Ok(h));
+        assert_eq!("0e5".parse::<Digest>(), Err(::Error::InvalidLength));
+        assert_eq!(
+            "0e".repeat(DIGEST_MAX + 1).parse::<Digest>(),
+            Err(::Error::InvalidLength)
+        );
+        assert_eq!(
+            "0g".repeat(DIGEST_MIN).parse::<Digest>(),
+            Err(::Error::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn test_digest_try_from() {
+        use std::convert::TryFrom;
+        let h = hash(b"try_from", Some(DIGEST_MIN), None).unwrap();
+        assert_eq!(Digest::try_from(h.as_ref()), Ok(h));
+        assert!(Digest::try_from(&[0; DIGEST_MAX][..]).is_ok());
+        assert_eq!(
+            Digest::try_from(&[0; DIGEST_MIN - 1][..]),
+            Err(::Error::InvalidLength)
+        );
+        assert_eq!(
+            Digest::try_from(&[0; DIGEST_MAX + 1][..]),
+            Err(::Error::InvalidLength)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_state_zeroize() {
+        use zeroize::Zeroize;
+        let mut state = State::new(None, Some(&[7; KEY_MIN])).unwrap();
+        state.update(b"some data").unwrap();
+        state.zeroize();
+        assert_eq!(state.update(b"more data"), Err(Error::Finalized));
+        assert_eq!(state.finalize(), Err(Error::Finalized));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_state_io_write() {
+        use randombytes::randombytes;
+        use std::io::{self, Write};
+        let m = randombytes(1000);
+        let key = [0x42; KEY_MIN];
+        let mut state = State::new(None, Some(&key)).unwrap();
+        io::copy(&mut &m[..], &mut state).unwrap();
+        write!(state, "{}", 42).unwrap();
+        let mut expected = m.clone();
+        expected.extend_from_slice(b"42");
+        assert_eq!(
+            state.finalize().unwrap(),
+            hash(&expected, None, Some(&key)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_state_keyed_chunks() {
+        use randombytes::randombytes;
+        for i in 0..64usize {
+            let key = randombytes(KEY_MIN + i % (KEY_MAX - KEY_MIN + 1));
+            let out_len = DIGEST_MIN + i % (DIGEST_MAX - DIGEST_MIN + 1);
+            let m = randombytes(i * 3);
+            let mut hasher = State::new(Some(out_len), Some(&key)).unwrap();
+            for chunk in m.chunks(5) {
+                hasher.update(chunk).unwrap();
+            }
+            let h = hasher.finalize().unwrap();
+            assert_eq!(h.as_ref().len(), out_len);
+            assert_eq!(h, hash(&m, Some(out_len), Some(&key)).unwrap());
+            assert!(h != hash(&m, Some(out_len), None).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(State::new(Some(DIGEST_MIN - 1), None).is_err());
+        assert!(State::new(Some(DIGEST_MAX + 1), None).is_err());
+        assert!(State::new(None, Some(&[0; KEY_MIN - 1])).is_err());
+        assert!(State::new(None, Some(&[0; KEY_MAX + 1])).is_err());
+        assert!(hash(b"", Some(DIGEST_MAX + 1), None).is_err());
+    }
+
+    #[test]
+    fn test_params_vector() {
+        // BLAKE2b-256 of "abc" with salt and personalization, as computed by
+        // Python's `hashlib.blake2b(b"abc", digest_size=32, salt=..., person=...)`
+        let h = hash_with_params(
+            b"abc",
+            Some(32),
+            None,
+            Some(b"0123456789abcdef"),
+            Some(b"sodiumoxide"),
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(h.as_ref()),
+            "bd6b3b913d3bb34677c0dfec77701df38fd0446b1a2dc32a3df403b6e39ee6a3"
+        );
+    }
+
+    #[test]
+    fn test_params_state_eq_hash() {
+        use randombytes::randombytes;
+        for i in 0..64usize {
+            let key = randombytes(KEY_MIN);
+            let salt = randombytes(i % (SALT_MAX + 1));
+            let personal = randombytes(PERSONAL_MAX - i % (PERSONAL_MAX + 1));
+            let m = randombytes(i);
+            let mut hasher =
+                State::init_with_params(None, Some(&key), Some(&salt), Some(&personal)).unwrap();
+            for chunk in m.chunks(3) {
+                hasher.update(chunk).unwrap();
+            }
+            let h = hasher.finalize().unwrap();
+            let h2 = hash_with_params(&m, None, Some(&key), Some(&salt), Some(&personal)).unwrap();
+            assert_eq!(h, h2);
+        }
+    }
+
+    #[test]
+    fn test_params_domain_separation() {
+        let h = hash(b"data", None, None).unwrap();
+        assert_eq!(
+            h,
+            hash_with_params(b"data", None, None, None, None).unwrap()
+        );
+        assert_eq!(
+            h,
+            hash_with_params(b"data", None, None, Some(&[0; SALT_MAX]), Some(&[])).unwrap()
+        );
+
+        let h1 = hash_with_params(b"data", None, None, None, Some(b"app.v1")).unwrap();
+        let h2 = hash_with_params(b"data", None, None, None, Some(b"app.v2")).unwrap();
+        let h3 = hash_with_params(b"data", None, None, Some(b"app.v1"), None).unwrap();
+        assert!(h != h1);
+        assert!(h1 != h2);
+        assert!(h1 != h3);
+    }
+
+    #[test]
+    fn test_params_too_long() {
+        let long = [0; SALT_MAX + 1];
+        assert!(State::init_with_params(None, None, Some(&long), None).is_err());
+        assert!(State::init_with_params(None, None, None, Some(&long)).is_err());
+        assert!(hash_with_params(b"", None, None, Some(&long), None).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialisation() {
+        use test_utils::{rejects_length, round_trip};
+        for &len in &[DIGEST_MIN, 32, DIGEST_MAX] {
+            round_trip(hash(b"data", Some(len), None).unwrap());
+        }
+        rejects_length::<Digest>(DIGEST_MIN - 1);
+        rejects_length::<Digest>(DIGEST_MAX + 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialisation_human_readable() {
+        use test_utils::{from_json, to_json};
+        let h = hash(b"", Some(32), None).unwrap();
+        let json = to_json(&h);
+        assert_eq!(json, format!("\"{}\"", hex::encode(&h)));
+        assert_eq!(from_json::<Digest>(&json), Ok(h));
+        assert!(from_json::<Digest>("\"00\"").is_err());
+        assert!(from_json::<Digest>(&format!("\"{}\"", "00".repeat(DIGEST_MAX + 1))).is_err());
+    }
+}
+
+#[cfg(feature = "digest")]
+#[cfg(test)]
+mod test_digest {
+    use super::*;
+    use digest::{Update, VariableOutput};
+    use randombytes::randombytes;
+
+    #[test]
+    fn test_variable_output() {
+        for &len in [DIGEST_MIN, 32, DIGEST_MAX].iter() {
+            for i in 0..64usize {
+                let m = randombytes(i);
+                let mut state = <State as VariableOutput>::new(len).unwrap();
+                assert_eq!(state.output_size(), len);
+                Update::update(&mut state, &m);
+                let mut out = vec![0; len];
+                state.finalize_variable(&mut out).unwrap();
+                assert_eq!(&out[..], &hash(&m, Some(len), None).unwrap()[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_variable_output_invalid() {
+        assert!(<State as VariableOutput>::new(DIGEST_MIN - 1).is_err());
+        assert!(<State as VariableOutput>::new(DIGEST_MAX + 1).is_err());
+        let state = <State as VariableOutput>::new(32).unwrap();
+        assert!(state.finalize_variable(&mut [0; 31]).is_err());
+    }
 }
@@ -4,7 +4,8 @@ macro_rules! hash_module (($hash_name:ident,
                            $hash_update:ident,
                            $hash_final:ident,
                            $hashbytes:expr,
-                           $blockbytes:expr) => (
+                           $blockbytes:expr,
+                           $output_size:ident) => (
 
 use std::mem;
 use libc::c_ulonglong;
@@ -29,6 +30,19 @@ pub fn hash(m: &[u8]) -> Digest {
     }
 }
 
+/// `hash_into` hashes a message `m` and writes the hash to the start of `out`. It returns the
+/// number of bytes written, i.e. `DIGESTBYTES`, or `Err(Error::InvalidLength)` if `out` is
+/// shorter than that.
+pub fn hash_into(out: &mut [u8], m: &[u8]) -> Result<usize, ::Error> {
+    if out.len() < DIGESTBYTES {
+        return Err(::Error::InvalidLength);
+    }
+    unsafe {
+        $hash_name(out.as_mut_ptr(), m.as_ptr(), m.len() as c_ulonglong);
+    }
+    Ok(DIGESTBYTES)
+}
+
 /// `State` contains the state for multi-part (streaming) hash computations. This allows the caller
 /// to process a message as a sequence of multiple chunks.
 #[derive(Copy, Clone)]
@@ -37,10 +51,12 @@ pub struct State($hash_state);
 impl State {
     /// `new` constructs and initializes a new `State`.
     pub fn new() -> Self {
-        let mut st = mem::MaybeUninit::uninit();
+        // The init functions leave the block buffer untouched, so start from
+        // zeroes to never read uninitialized memory, e.g. when copying the state.
+        let mut st = mem::MaybeUninit::zeroed();
         let state = unsafe {
-            $hash_init(st.as_mut_ptr());
-            st.assume_init() // st is definitely initialized
+            assert_eq!($hash_init(st.as_mut_ptr()), 0);
+            st.assume_init()
         };
         State(state)
     }
@@ -64,16 +80,109 @@ impl State {
     }
 }
 
+/// Feeds everything written into `update()`, e.g. to hash a stream with
+/// `io::copy()`.
+#[cfg(feature = "std")]
+impl ::std::io::Write for State {
+    fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> ::std::io::Result<()> {
+        Ok(())
+    }
+}
+
 impl Default for State {
     fn default() -> State {
         State::new()
     }
 }
 
+#[cfg(feature = "digest")]
+impl ::digest::HashMarker for State {}
+
+#[cfg(feature = "digest")]
+impl ::digest::OutputSizeUser for State {
+    type OutputSize = ::digest::consts::$output_size;
+}
+
+#[cfg(feature = "digest")]
+impl ::digest::Update for State {
+    fn update(&mut self, data: &[u8]) {
+        State::update(self, data)
+    }
+}
+
+#[cfg(feature = "digest")]
+impl ::digest::FixedOutput for State {
+    fn finalize_into(mut self, out: &mut ::digest::Output<Self>) {
+        unsafe {
+            $hash_final(&mut self.0, out.as_mut_ptr());
+        }
+    }
+}
+
+#[cfg(feature = "digest")]
+impl ::digest::Reset for State {
+    fn reset(&mut self) {
+        *self = State::new();
+    }
+}
+
+#[cfg(feature = "digest")]
+impl ::digest::FixedOutputReset for State {
+    fn finalize_into_reset(&mut self, out: &mut ::digest::Output<Self>) {
+        unsafe {
+            $hash_final(&mut self.0, out.as_mut_ptr());
+        }
+        *self = State::new();
+    }
+}
+
 #[cfg(test)]
 mod test_m {
     use super::*;
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_state_io_write() {
+        use randombytes::randombytes;
+        use std::io::{self, Write};
+        let m = randombytes(1000);
+        let mut state = State::new();
+        io::copy(&mut &m[..], &mut state).unwrap();
+        write!(state, "{}", 42).unwrap();
+        let mut expected = m.clone();
+        expected.extend_from_slice(b"42");
+        assert_eq!(state.finalize(), hash(&expected));
+    }
+
+    #[test]
+    fn test_state_fully_initialized() {
+        use std::slice;
+        let a = State::new();
+        let b = State::default();
+        let bytes = |s: &State| unsafe {
+            slice::from_raw_parts(&s.0 as *const $hash_state as *const u8, mem::size_of::<$hash_state>()).to_vec()
+        };
+        assert_eq!(bytes(&a), bytes(&b));
+        assert_eq!(a.finalize(), hash(b""));
+    }
+
+    #[test]
+    fn test_hash_into() {
+        let mut out = [0; DIGESTBYTES + 1];
+        assert_eq!(hash_into(&mut out, b"into"), Ok(DIGESTBYTES));
+        assert_eq!(&out[..DIGESTBYTES], hash(b"into").as_ref());
+        assert_eq!(out[DIGESTBYTES], 0);
+        assert_eq!(
+            hash_into(&mut out[..DIGESTBYTES - 1], b"into"),
+            Err(::Error::InvalidLength)
+        );
+    }
+
     #[test]
     fn test_hash_multipart() {
         use randombytes::randombytes;
@@ -90,6 +199,39 @@ mod test_m {
     }
 }
 
+#[cfg(feature = "digest")]
+#[cfg(test)]
+mod test_digest {
+    use super::*;
+    use digest::{Digest as _, FixedOutputReset};
+    use randombytes::randombytes;
+
+    fn generic_hash<D: ::digest::Digest>(m: &[u8]) -> Vec<u8> {
+        let mut d = D::new();
+        d.update(m);
+        d.finalize().to_vec()
+    }
+
+    #[test]
+    fn test_digest() {
+        for i in 0..256usize {
+            let m = randombytes(i);
+            let h = hash(&m);
+            assert_eq!(generic_hash::<State>(&m), h.as_ref());
+            assert_eq!(&State::digest(&m)[..], h.as_ref());
+        }
+    }
+
+    #[test]
+    fn test_finalize_reset() {
+        let mut state = State::new();
+        ::digest::Update::update(&mut state, b"first");
+        assert_eq!(&state.finalize_fixed_reset()[..], hash(b"first").as_ref());
+        ::digest::Update::update(&mut state, b"second");
+        assert_eq!(&state.finalize_fixed_reset()[..], hash(b"second").as_ref());
+    }
+}
+
 #[cfg(feature = "serde")]
 #[cfg(test)]
 mod test_encode {
@@ -40,9 +40,19 @@
 //! hash_state.update(b"data!");
 //! let digest = hash_state.finalize();
 //! ```
+//!
+//! # Parallel hashing
+//! With the `rayon` feature, [`par_hash()`](fn.par_hash.html) hashes large
+//! inputs with `BLAKE2b` on all cores, using a stable tree construction that
+//! is described in its documentation.
 
 pub use self::sha512::*;
 #[macro_use]
 mod hash_macros;
 pub mod sha256;
 pub mod sha512;
+
+#[cfg(all(feature = "rayon", feature = "std", feature = "generichash"))]
+pub use self::par::{par_hash, PAR_BYTES, PAR_CHUNKBYTES};
+#[cfg(all(feature = "rayon", feature = "std", feature = "generichash"))]
+mod par;
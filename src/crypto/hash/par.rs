@@ -0,0 +1,148 @@
+//! Parallel hashing of large inputs.
+//!
+//! `par_hash()` splits its input into chunks of `PAR_CHUNKBYTES` bytes, which
+//! are hashed in parallel on the rayon thread pool, and combines the chunk
+//! digests in a two level tree. All hashes are unkeyed `BLAKE2b` with
+//! `PAR_BYTES` bytes of output, and the digest of a message `m` is
+//!
+//! ```text
+//! leaf(i) = BLAKE2b(0x00 || m[i * PAR_CHUNKBYTES..(i + 1) * PAR_CHUNKBYTES])
+//! root    = BLAKE2b(0x01 || LE64(m.len()) || leaf(0) || ... || leaf(n - 1))
+//! ```
+//!
+//! where `n` is the number of chunks, the last of which may be shorter, and
+//! is 1 for an empty message. The prefix bytes keep chunk digests and the root
+//! apart, and the length makes sure that messages of a different length never
+//! share a root. The construction does not depend on the number of threads,
+//! and will not change in future versions.
+//!
+//! The result is not the same as the `generichash::hash()` of `m`.
+
+use crypto::generichash::{Digest, State, DIGEST_MAX};
+use rayon::prelude::*;
+
+/// Number of bytes of the message hashed by each leaf of the tree.
+pub const PAR_CHUNKBYTES: usize = 1 << 20;
+
+/// Number of bytes in a digest returned by `par_hash()`.
+pub const PAR_BYTES: usize = DIGEST_MAX;
+
+const LEAF_PREFIX: u8 = 0x00;
+const ROOT_PREFIX: u8 = 0x01;
+
+fn blake2b(prefix: u8, parts: &[&[u8]]) -> Digest {
+    // the output length is valid and there is no key, so these cannot fail
+    let mut state = State::new(Some(PAR_BYTES), None).unwrap();
+    state.update(&[prefix]).unwrap();
+    for part in parts {
+        state.update(part).unwrap();
+    }
+    state.finalize().unwrap()
+}
+
+fn leaf(chunk: &[u8]) -> Digest {
+    blake2b(LEAF_PREFIX, &[chunk])
+}
+
+/// `par_hash()` hashes a message `m` with the tree construction described in
+/// the [module documentation](index.html), using all threads of the global
+/// rayon thread pool for large messages.
+///
+/// # Example
+/// ```
+/// use sodiumoxide::crypto::hash;
+///
+/// let data = vec![0x42; 3 * hash::PAR_CHUNKBYTES + 1];
+/// let digest = hash::par_hash(&data);
+/// assert_eq!(digest.as_ref().len(), hash::PAR_BYTES);
+/// ```
+pub fn par_hash(m: &[u8]) -> Digest {
+    let leaves: Vec<Digest> = if m.is_empty() {
+        vec![leaf(m)]
+    } else {
+        m.par_chunks(PAR_CHUNKBYTES).map(leaf).collect()
+    };
+    let len = (m.len() as u64).to_le_bytes();
+    let mut parts: Vec<&[u8]> = Vec::with_capacity(leaves.len() + 1);
+    parts.push(&len);
+    parts.extend(leaves.iter().map(|d| d.as_ref()));
+    blake2b(ROOT_PREFIX, &parts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crypto::generichash;
+    use randombytes::randombytes;
+
+    fn sequential(m: &[u8]) -> Digest {
+        let mut root = vec![ROOT_PREFIX];
+        root.extend_from_slice(&(m.len() as u64).to_le_bytes());
+        let mut chunks: Vec<&[u8]> = m.chunks(PAR_CHUNKBYTES).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+        for chunk in chunks {
+            let mut prefixed = vec![LEAF_PREFIX];
+            prefixed.extend_from_slice(chunk);
+            let digest = generichash::hash(&prefixed, Some(PAR_BYTES), None).unwrap();
+            root.extend_from_slice(digest.as_ref());
+        }
+        generichash::hash(&root, Some(PAR_BYTES), None).unwrap()
+    }
+
+    #[test]
+    fn test_par_hash() {
+        let m = randombytes(3 * PAR_CHUNKBYTES + 1);
+        for &len in [
+            0,
+            1,
+            PAR_CHUNKBYTES - 1,
+            PAR_CHUNKBYTES,
+            PAR_CHUNKBYTES + 1,
+            m.len(),
+        ]
+        .iter()
+        {
+            assert_eq!(par_hash(&m[..len]), sequential(&m[..len]), "{}", len);
+        }
+    }
+
+    #[test]
+    fn test_par_hash_vectors() {
+        // computed independently, the construction must never change
+        let vectors: [(Vec<u8>, &str); 3] = [
+            (
+                vec![],
+                "e0a45e429cb05d11c33e17f2b3133ede8f778ee423cd6835f4a025ee16aecb3d\
+                 d5ffc69b47542e86aef241a35fd202e67a6589c63473d03dea31cb864ef5a1ff",
+            ),
+            (
+                b"abc".to_vec(),
+                "e72f1450527dff6a4cf6f9886a06298419ca8bd85df89e6eb6723a9a45d488bb\
+                 fc4e5d9e5bb67176cae039e93c2f6178701ea788b723e96e3e44ae420095d254",
+            ),
+            (
+                vec![0x42; PAR_CHUNKBYTES + 1],
+                "f0814737ad4531b06988b823948fc04909784b6c9eabb1d390590e63e9bfd673\
+                 3830851f2b14ff2088272caeb4120021f06fa35e0429aa4a0ca5fbc098e253d9",
+            ),
+        ];
+        for &(ref m, expected) in vectors.iter() {
+            assert_eq!(format!("{:x}", par_hash(m)), expected);
+        }
+        assert!(par_hash(b"abc") != generichash::hash(b"abc", Some(PAR_BYTES), None).unwrap());
+    }
+
+    #[test]
+    fn test_par_hash_distinct() {
+        let m = vec![0u8; 2 * PAR_CHUNKBYTES];
+        let a = par_hash(&m[..PAR_CHUNKBYTES]);
+        let b = par_hash(&m);
+        let c = par_hash(&m[..PAR_CHUNKBYTES + 1]);
+        assert!(a != b && b != c && a != c);
+        let mut m2 = m.clone();
+        m2[PAR_CHUNKBYTES + 7] ^= 1;
+        assert!(par_hash(&m2) != b);
+    }
+}
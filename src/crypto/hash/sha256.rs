@@ -18,7 +18,8 @@ hash_module!(
     crypto_hash_sha256_update,
     crypto_hash_sha256_final,
     crypto_hash_sha256_BYTES as usize,
-    64
+    64,
+    U32
 );
 
 #[cfg(test)]
@@ -18,7 +18,8 @@ hash_module!(
     crypto_hash_sha512_update,
     crypto_hash_sha512_final,
     crypto_hash_sha512_BYTES,
-    128
+    128,
+    U64
 );
 
 #[cfg(test)]
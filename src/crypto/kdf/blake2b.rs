@@ -1,6 +1,7 @@
 //! `blake2b` is the current default key derivation scheme of `libsodium`.
 
 use ffi;
+use Error;
 
 /// Number of bytes in a `Key`.
 pub const KEYBYTES: usize = ffi::crypto_kdf_blake2b_KEYBYTES as usize;
@@ -15,8 +16,25 @@ pub const BYTES_MIN: usize = ffi::crypto_kdf_blake2b_BYTES_MIN as usize;
 pub const BYTES_MAX: usize = ffi::crypto_kdf_blake2b_BYTES_MAX as usize;
 
 new_type! {
-    /// `Key` for key derivation.
-    public Key(KEYBYTES);
+    /// Master `Key` for key derivation.
+    ///
+    /// When a `Key` goes out of scope its contents will be zeroed out
+    secret Key(KEYBYTES);
+}
+
+new_type! {
+    /// `Context` for key derivation.
+    ///
+    /// The context separates subkeys derived for different purposes from the
+    /// same master key. It is always exactly `CONTEXTBYTES` bytes long and
+    /// does not need to be secret.
+    public Context(CONTEXTBYTES);
+}
+
+impl From<[u8; CONTEXTBYTES]> for Context {
+    fn from(ctx: [u8; CONTEXTBYTES]) -> Context {
+        Context(ctx)
+    }
 }
 
 /// `gen_key()` randomly generates a key for key derivation.
@@ -34,23 +52,27 @@ pub fn gen_key() -> Key {
 
 /// `derive_from_key` derives the subkey_id-th subkey from the master key `key` and the context `ctx`
 ///
-/// Fails if the length of subkey is not within the bounds given by `BYTES_MIN` and `BYTES_MAX`.
-pub fn derive_from_key(
+/// `ctx` can be given either as a `Context` or as a `[u8; CONTEXTBYTES]` array.
+///
+/// Fails with `Error::InvalidLength` if the length of subkey is not within the bounds given by
+/// `BYTES_MIN` and `BYTES_MAX`.
+pub fn derive_from_key<C: Into<Context>>(
     subkey: &mut [u8],
     subkey_id: u64,
-    ctx: [u8; CONTEXTBYTES],
+    ctx: C,
     key: &Key,
-) -> Result<(), ()> {
+) -> Result<(), Error> {
+    let ctx = ctx.into();
     unsafe {
         let r = ffi::crypto_kdf_blake2b_derive_from_key(
             subkey.as_mut_ptr() as _,
             subkey.len(),
             subkey_id,
-            ctx.as_ptr() as _,
+            ctx.0.as_ptr() as _,
             key.0.as_ptr(),
         );
         if r != 0 {
-            Err(())
+            Err(Error::InvalidLength)
         } else {
             Ok(())
         }
@@ -69,6 +91,24 @@ mod test {
         assert_ne!(key1, key2);
     }
 
+    #[test]
+    fn test_context() {
+        let key = gen_key();
+        assert!(Context::from_slice(b"too short").is_none());
+        assert!(Context::from_slice(b"short").is_none());
+        let ctx = Context::from_slice(b"Examples").unwrap();
+        assert_eq!(ctx, Context::from(*b"Examples"));
+
+        let mut subkey1 = [0u8; 32];
+        let mut subkey2 = [0u8; 32];
+        derive_from_key(&mut subkey1, 1, ctx, &key).unwrap();
+        derive_from_key(&mut subkey2, 1, *b"Examples", &key).unwrap();
+        assert_eq!(subkey1, subkey2);
+
+        derive_from_key(&mut subkey2, 1, *b"Example2", &key).unwrap();
+        assert_ne!(subkey1, subkey2);
+    }
+
     #[test]
     fn test_keysize_out_of_bounds() {
         // Length of the subkey must be within bounds given by `BYTES_MIN`
@@ -0,0 +1,153 @@
+macro_rules! hkdf_module (($hmac:ident) => (
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+
+use crypto::auth::$hmac;
+use randombytes::randombytes_into;
+use utils::memzero;
+use Error;
+
+/// Number of bytes in a `Key`, the output of the hash function.
+pub const KEYBYTES: usize = $hmac::TAGBYTES;
+
+/// Minimum number of bytes `expand_into()` can derive.
+pub const BYTES_MIN: usize = 0;
+
+/// Maximum number of bytes `expand_into()` can derive.
+pub const BYTES_MAX: usize = 255 * KEYBYTES;
+
+new_type! {
+    /// Pseudorandom `Key` extracted from the input keying material, used to
+    /// expand output keying material.
+    ///
+    /// When a `Key` goes out of scope its contents will be zeroed out
+    secret Key(KEYBYTES);
+}
+
+/// `gen_key()` randomly generates a `Key`, for protocols that expand from a
+/// random key directly.
+///
+/// THREAD SAFETY: `gen_key()` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+pub fn gen_key() -> Key {
+    let mut k = Key([0; KEYBYTES]);
+    randombytes_into(&mut k.0);
+    k
+}
+
+/// `extract()` computes the pseudorandom `Key` from the salt `salt` and the
+/// input keying material `ikm`. An empty salt is the same as a salt of
+/// `KEYBYTES` zeros.
+pub fn extract(salt: &[u8], ikm: &[u8]) -> Key {
+    let mut state = ExtractState::init(salt);
+    state.update(ikm);
+    state.finalize()
+}
+
+/// `ExtractState` computes the pseudorandom `Key` from input keying material
+/// given in several pieces.
+pub struct ExtractState($hmac::State);
+
+impl ExtractState {
+    /// `init()` starts an extraction with the salt `salt`.
+    pub fn init(salt: &[u8]) -> ExtractState {
+        ExtractState($hmac::State::init(salt))
+    }
+
+    /// `update()` adds `ikm` to the input keying material.
+    pub fn update(&mut self, ikm: &[u8]) {
+        self.0.update(ikm)
+    }
+
+    /// `finalize()` returns the pseudorandom `Key`.
+    pub fn finalize(self) -> Key {
+        let tag = self.0.finalize();
+        let mut k = Key([0; KEYBYTES]);
+        k.0.copy_from_slice(tag.as_ref());
+        k
+    }
+}
+
+/// `expand_into()` fills `out` with output keying material derived from
+/// the pseudorandom key `prk` and the context `info`.
+///
+/// It returns `Err(Error::InvalidLength)` if `out` is longer than
+/// `BYTES_MAX`.
+pub fn expand_into(out: &mut [u8], info: &[u8], prk: &Key) -> Result<(), Error> {
+    if out.len() > BYTES_MAX {
+        return Err(Error::InvalidLength);
+    }
+    let mut t = [0u8; KEYBYTES];
+    for (i, chunk) in out.chunks_mut(KEYBYTES).enumerate() {
+        let mut state = $hmac::State::init(&prk.0);
+        if i > 0 {
+            state.update(&t);
+        }
+        state.update(info);
+        state.update(&[i as u8 + 1]);
+        t.copy_from_slice(state.finalize().as_ref());
+        chunk.copy_from_slice(&t[..chunk.len()]);
+    }
+    memzero(&mut t);
+    Ok(())
+}
+
+/// `expand()` returns `len` bytes of output keying material derived from the
+/// pseudorandom key `prk` and the context `info`.
+///
+/// It returns `Err(Error::InvalidLength)` if `len` is larger than
+/// `BYTES_MAX`.
+pub fn expand(len: usize, info: &[u8], prk: &Key) -> Result<Vec<u8>, Error> {
+    if len > BYTES_MAX {
+        return Err(Error::InvalidLength);
+    }
+    let mut out = vec![0u8; len];
+    expand_into(&mut out, info, prk)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test_m {
+    use super::*;
+
+    #[test]
+    fn test_extract_state() {
+        let ikm = [0x42; 100];
+        let mut state = ExtractState::init(b"salt");
+        for chunk in ikm.chunks(7) {
+            state.update(chunk);
+        }
+        assert_eq!(state.finalize(), extract(b"salt", &ikm));
+    }
+
+    #[test]
+    fn test_expand_prefix() {
+        let prk = gen_key();
+        let long = expand(BYTES_MAX, b"info", &prk).unwrap();
+        for &len in [0, 1, KEYBYTES - 1, KEYBYTES, KEYBYTES + 1, 3 * KEYBYTES].iter() {
+            assert_eq!(expand(len, b"info", &prk).unwrap(), &long[..len]);
+        }
+        assert!(expand(len_over(), b"info", &prk).is_err());
+        let mut out = vec![0u8; len_over()];
+        assert_eq!(
+            expand_into(&mut out, b"info", &prk),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_expand_info() {
+        let prk = gen_key();
+        let a = expand(32, b"info a", &prk).unwrap();
+        let b = expand(32, b"info b", &prk).unwrap();
+        assert!(a != b);
+    }
+
+    fn len_over() -> usize {
+        BYTES_MAX + 1
+    }
+}
+
+));
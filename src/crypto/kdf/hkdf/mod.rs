@@ -0,0 +1,31 @@
+//! HKDF key derivation
+//!
+//! HKDF, specified in [RFC 5869](https://tools.ietf.org/html/rfc5869),
+//! first extracts a pseudorandom key (`Key`) from input keying material of
+//! any quality and length, then expands it into as many output bytes as
+//! needed, bound to a context string `info`. Protocols such as Noise or the
+//! TLS exporters are specified in terms of HKDF.
+//!
+//! Unlike [`derive_from_key()`](../fn.derive_from_key.html), HKDF does not
+//! require the input to be a uniformly random key, and it can derive long
+//! outputs.
+//!
+//! libsodium 1.0.19 added `crypto_kdf_hkdf_sha256` and
+//! `crypto_kdf_hkdf_sha512`. This module implements the same construction on
+//! top of [`auth::hmacsha256`](../../auth/hmacsha256/index.html) and
+//! [`auth::hmacsha512`](../../auth/hmacsha512/index.html), so it also works
+//! with older versions of the library. It requires the `auth` feature.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::kdf::hkdf::sha256;
+//!
+//! let prk = sha256::extract(b"salt", b"input keying material");
+//! let mut okm = [0u8; 42];
+//! sha256::expand_into(&mut okm, b"context", &prk).unwrap();
+//! ```
+
+#[macro_use]
+mod hkdf_macros;
+pub mod sha256;
+pub mod sha512;
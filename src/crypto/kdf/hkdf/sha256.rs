@@ -0,0 +1,43 @@
+//! `HKDF-SHA-256`
+
+hkdf_module!(hmacsha256);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex;
+
+    #[test]
+    fn test_rfc5869_case_1() {
+        let ikm = [0x0b; 22];
+        let salt = hex::decode("000102030405060708090a0b0c").unwrap();
+        let info = hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap();
+        let prk = extract(&salt, &ikm);
+        assert_eq!(
+            hex::encode(&prk.0),
+            "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5"
+        );
+        let okm = expand(42, &info, &prk).unwrap();
+        assert_eq!(
+            hex::encode(&okm),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c\
+             5db02d56ecc4c5bf34007208d5b887185865"
+        );
+    }
+
+    #[test]
+    fn test_rfc5869_case_3() {
+        // zero-length salt and info
+        let prk = extract(&[], &[0x0b; 22]);
+        assert_eq!(
+            hex::encode(&prk.0),
+            "19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04"
+        );
+        let okm = expand(42, &[], &prk).unwrap();
+        assert_eq!(
+            hex::encode(&okm),
+            "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879e\
+             c3454e5f3c738d2d9d201395faa4b61a96c8"
+        );
+    }
+}
@@ -0,0 +1,46 @@
+//! `HKDF-SHA-512`
+
+hkdf_module!(hmacsha512);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex;
+
+    #[test]
+    fn test_vector_1() {
+        // RFC 5869 test case 1 inputs, with SHA-512
+        let ikm = [0x0b; 22];
+        let salt = hex::decode("000102030405060708090a0b0c").unwrap();
+        let info = hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap();
+        let prk = extract(&salt, &ikm);
+        assert_eq!(
+            hex::encode(&prk.0[..]),
+            "665799823737ded04a88e47e54a5890bb2c3d247c7a4254a8e61350723590a26\
+             c36238127d8661b88cf80ef802d57e2f7cebcf1e00e083848be19929c61b4237"
+        );
+        let okm = expand(42, &info, &prk).unwrap();
+        assert_eq!(
+            hex::encode(&okm),
+            "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9\
+             fc7fe92c1481579338da362cb8d9f925d7cb"
+        );
+    }
+
+    #[test]
+    fn test_vector_2() {
+        // zero-length salt and info
+        let prk = extract(&[], &[0x0b; 22]);
+        assert_eq!(
+            hex::encode(&prk.0[..]),
+            "fd200c4987ac491313bd4a2a13287121247239e11c9ef82802044b66ef357e5b\
+             194498d0682611382348572a7b1611de54764094286320578a863f36562b0df6"
+        );
+        let okm = expand(42, &[], &prk).unwrap();
+        assert_eq!(
+            hex::encode(&okm),
+            "f5fa02b18298a72a8c23898a8703472c6eb179dc204c0342\
+             5c970e3b164bf90fff22d04836d0e2343bac"
+        );
+    }
+}
@@ -6,6 +6,9 @@
 //!
 //! This API was introduced in libsodium 1.0.12
 //!
+//! For protocols specified in terms of HKDF, see the [`hkdf`](hkdf/index.html)
+//! module, which is available with the `auth` feature.
+//!
 //! # Example
 //!
 //! ```
@@ -28,3 +31,5 @@
 
 pub mod blake2b;
 pub use self::blake2b::*;
+#[cfg(feature = "auth")]
+pub mod hkdf;
@@ -0,0 +1,257 @@
+//! Key exchange handshake over tokio's `AsyncRead` and `AsyncWrite`.
+//!
+//! [`client_handshake()`] and [`server_handshake()`] send the local public key
+//! to the peer, read the peer's public key and compute the session keys. The
+//! returned keys can be used with
+//! [`secretstream::async_io`](../../secretstream/async_io/index.html) to
+//! encrypt the rest of the connection.
+//!
+//! The handshake does not authenticate the peer: it is up to the caller to
+//! check the returned public key, e.g. against a list of known servers.
+//!
+//! This module is only available with the `tokio` feature.
+
+use super::x25519blake2b::{
+    client_session_keys, server_session_keys, PublicKey, SecretKey, SessionKey, PUBLICKEYBYTES,
+};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Client,
+    Server,
+}
+
+/// `Handshake` is the future returned by `client_handshake()` and
+/// `server_handshake()`.
+///
+/// It resolves to the peer's public key and the two session keys, the first
+/// for `rx` and the second for `tx`. It fails with an error of kind
+/// `io::ErrorKind::UnexpectedEof` if the connection is closed early, and of
+/// kind `io::ErrorKind::InvalidData` if the peer's public key is not
+/// acceptable.
+#[must_use = "futures do nothing unless polled"]
+pub struct Handshake<'a, S: 'a> {
+    stream: &'a mut S,
+    role: Role,
+    pk: PublicKey,
+    sk: SecretKey,
+    sent: usize,
+    flushed: bool,
+    peer_pk: [u8; PUBLICKEYBYTES],
+    received: usize,
+}
+
+/// `client_handshake()` performs the client side of a key exchange over
+/// `stream`, using the client's public key `pk` and secret key `sk`.
+pub fn client_handshake<'a, S>(
+    stream: &'a mut S,
+    pk: &PublicKey,
+    sk: &SecretKey,
+) -> Handshake<'a, S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    Handshake::new(stream, Role::Client, pk, sk)
+}
+
+/// `server_handshake()` performs the server side of a key exchange over
+/// `stream`, using the server's public key `pk` and secret key `sk`.
+pub fn server_handshake<'a, S>(
+    stream: &'a mut S,
+    pk: &PublicKey,
+    sk: &SecretKey,
+) -> Handshake<'a, S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    Handshake::new(stream, Role::Server, pk, sk)
+}
+
+impl<'a, S: AsyncRead + AsyncWrite + Unpin> Handshake<'a, S> {
+    fn new(stream: &'a mut S, role: Role, pk: &PublicKey, sk: &SecretKey) -> Handshake<'a, S> {
+        Handshake {
+            stream,
+            role,
+            pk: *pk,
+            sk: sk.clone(),
+            sent: 0,
+            flushed: false,
+            peer_pk: [0; PUBLICKEYBYTES],
+            received: 0,
+        }
+    }
+
+    // Both sides send their public key before reading the peer's, so the
+    // handshake cannot deadlock.
+    fn poll_exchange(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        while self.sent < PUBLICKEYBYTES {
+            match Pin::new(&mut *self.stream).poll_write(cx, &self.pk.0[self.sent..]) {
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "failed to write the public key",
+                    )));
+                }
+                Poll::Ready(Ok(n)) => self.sent += n,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        if !self.flushed {
+            match Pin::new(&mut *self.stream).poll_flush(cx) {
+                Poll::Ready(Ok(())) => self.flushed = true,
+                other => return other,
+            }
+        }
+        while self.received < PUBLICKEYBYTES {
+            let mut buf = ReadBuf::new(&mut self.peer_pk[self.received..]);
+            match Pin::new(&mut *self.stream).poll_read(cx, &mut buf) {
+                Poll::Ready(Ok(())) if buf.filled().is_empty() => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "connection closed during the handshake",
+                    )));
+                }
+                Poll::Ready(Ok(())) => self.received += buf.filled().len(),
+                other => return other,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<'a, S: AsyncRead + AsyncWrite + Unpin> Future for Handshake<'a, S> {
+    type Output = io::Result<(PublicKey, SessionKey, SessionKey)>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        match this.poll_exchange(cx) {
+            Poll::Ready(Ok(())) => {}
+            Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            Poll::Pending => return Poll::Pending,
+        }
+        let peer_pk = PublicKey(this.peer_pk);
+        let keys = match this.role {
+            Role::Client => client_session_keys(&this.pk, &this.sk, &peer_pk),
+            Role::Server => server_session_keys(&this.pk, &this.sk, &peer_pk),
+        };
+        Poll::Ready(match keys {
+            Ok((rx, tx)) => Ok((peer_pk, rx, tx)),
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unacceptable peer public key",
+            )),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::x25519blake2b::gen_keypair;
+    use super::*;
+    use async_test_utils::block_on;
+    use std::cmp;
+
+    // A connection whose peer has already sent `input`. Reads and writes
+    // return `Pending` every other time and move at most one byte.
+    struct Connection {
+        input: Vec<u8>,
+        output: Vec<u8>,
+        ready: bool,
+    }
+
+    impl Connection {
+        fn new(input: &[u8]) -> Connection {
+            Connection {
+                input: input.to_vec(),
+                output: Vec::new(),
+                ready: false,
+            }
+        }
+    }
+
+    impl AsyncRead for Connection {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _: &mut Context,
+            buf: &mut ReadBuf,
+        ) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            this.ready = !this.ready;
+            if !this.ready {
+                return Poll::Pending;
+            }
+            let n = cmp::min(cmp::min(buf.remaining(), 1), this.input.len());
+            buf.put_slice(&this.input[..n]);
+            this.input.drain(..n);
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl AsyncWrite for Connection {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let this = self.get_mut();
+            this.ready = !this.ready;
+            if !this.ready {
+                return Poll::Pending;
+            }
+            let n = cmp::min(buf.len(), 1);
+            this.output.extend_from_slice(&buf[..n]);
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn test_handshake() {
+        let (client_pk, client_sk) = gen_keypair();
+        let (server_pk, server_sk) = gen_keypair();
+
+        let mut client_conn = Connection::new(server_pk.as_ref());
+        let (peer_pk, client_rx, client_tx) =
+            block_on(client_handshake(&mut client_conn, &client_pk, &client_sk)).unwrap();
+        assert_eq!(peer_pk, server_pk);
+        assert_eq!(client_conn.output, client_pk.as_ref());
+
+        let mut server_conn = Connection::new(client_pk.as_ref());
+        let (peer_pk, server_rx, server_tx) =
+            block_on(server_handshake(&mut server_conn, &server_pk, &server_sk)).unwrap();
+        assert_eq!(peer_pk, client_pk);
+        assert_eq!(server_conn.output, server_pk.as_ref());
+
+        assert_eq!(client_rx, server_tx);
+        assert_eq!(client_tx, server_rx);
+    }
+
+    #[test]
+    fn test_handshake_eof() {
+        let (pk, sk) = gen_keypair();
+        let mut conn = Connection::new(&pk.as_ref()[..PUBLICKEYBYTES - 1]);
+        let err = block_on(client_handshake(&mut conn, &pk, &sk)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_handshake_bad_public_key() {
+        let (pk, sk) = gen_keypair();
+        let mut conn = Connection::new(&[0; PUBLICKEYBYTES]);
+        let err = block_on(server_handshake(&mut conn, &pk, &sk)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -5,6 +5,9 @@
 //!
 //! This API was introduced in libsodium 1.0.12.
 //!
+//! With the `tokio` feature, the [`async_io`](async_io/index.html) module
+//! exchanges the public keys over an `AsyncRead + AsyncWrite` connection.
+//!
 //! # Example
 //!
 //! ```
@@ -21,13 +24,13 @@
 //! // client deduces the two session keys rx1 and tx1
 //! let (rx1, tx1) = match kx::client_session_keys(&client_pk, &client_sk, &server_pk) {
 //!     Ok((rx, tx)) => (rx, tx),
-//!     Err(()) => panic!("bad server signature"),
+//!     Err(_) => panic!("bad server public key"),
 //! };
 //!
 //! // server performs the same operation
 //! let (rx2, tx2) = match kx::server_session_keys(&server_pk, &server_sk, &client_pk) {
 //!     Ok((rx, tx)) => (rx, tx),
-//!     Err(()) => panic!("bad client signature"),
+//!     Err(_) => panic!("bad client public key"),
 //! };
 //!
 //! assert!(rx1==tx2);
@@ -37,3 +40,8 @@
 
 pub use self::x25519blake2b::*;
 pub mod x25519blake2b;
+
+#[cfg(all(feature = "std", feature = "tokio"))]
+pub use self::async_io::{client_handshake, server_handshake, Handshake};
+#[cfg(all(feature = "std", feature = "tokio"))]
+pub mod async_io;
@@ -1,6 +1,7 @@
 //! `x25519blake2b` is the current default key exchange scheme of `libsodium`.
 
 use ffi;
+use Error;
 
 /// Number of bytes in a `PublicKey`.
 pub const PUBLICKEYBYTES: usize = ffi::crypto_kx_PUBLICKEYBYTES as usize;
@@ -26,6 +27,17 @@ new_type! {
     secret SecretKey(SECRETKEYBYTES);
 }
 
+impl SecretKey {
+    /// `public_key()` computes the corresponding public key for a given secret key
+    pub fn public_key(&self) -> PublicKey {
+        unsafe {
+            let mut pk = PublicKey([0u8; PUBLICKEYBYTES]);
+            ffi::crypto_scalarmult_curve25519_base(pk.0.as_mut_ptr(), self.0.as_ptr());
+            pk
+        }
+    }
+}
+
 new_type! {
     /// `Seed` that can be used for keypair generation
     ///
@@ -49,6 +61,7 @@ new_type! {
 /// called `sodiumoxide::init()` once before using any other function
 /// from sodiumoxide.
 pub fn gen_keypair() -> (PublicKey, SecretKey) {
+    ::ensure_init();
     unsafe {
         let mut pk = PublicKey([0u8; PUBLICKEYBYTES]);
         let mut sk = SecretKey([0u8; SECRETKEYBYTES]);
@@ -71,12 +84,13 @@ pub fn keypair_from_seed(seed: &Seed) -> (PublicKey, SecretKey) {
 /// `server_session_keys()` computes a pair of shared keys (rx and tx) using the server's public
 /// key `server_pk`, the server's secret key `server_sk` and the client's public key `client_pk`.
 /// If the client's public key is acceptable, it returns the two shared keys, the first for `rx`
-/// and the second for `tx`. Otherwise, it returns `None`.
+/// and the second for `tx`. Otherwise, e.g. if it is of small order, it returns
+/// `Err(Error::InvalidParameter)`.
 pub fn server_session_keys(
     server_pk: &PublicKey,
     server_sk: &SecretKey,
     client_pk: &PublicKey,
-) -> Result<(SessionKey, SessionKey), ()> {
+) -> Result<(SessionKey, SessionKey), Error> {
     unsafe {
         let mut rx = SessionKey([0u8; SESSIONKEYBYTES]);
         let mut tx = SessionKey([0u8; SESSIONKEYBYTES]);
@@ -88,7 +102,7 @@ pub fn server_session_keys(
             client_pk.0.as_ptr(),
         );
         if r != 0 {
-            Err(())
+            Err(Error::InvalidParameter)
         } else {
             Ok((rx, tx))
         }
@@ -98,12 +112,13 @@ pub fn server_session_keys(
 /// `client_session_keys()` computes a pair of shared keys (rx and tx) using the client's public
 /// key `client_pk`, the client's secret key `client_sk` and the server's public key `server_pk`.
 /// If the server's public key is acceptable, it returns the two shared keys, the first for `rx`
-/// and the second for `tx`. Otherwise, it returns `None`.
+/// and the second for `tx`. Otherwise, e.g. if it is of small order, it returns
+/// `Err(Error::InvalidParameter)`.
 pub fn client_session_keys(
     client_pk: &PublicKey,
     client_sk: &SecretKey,
     server_pk: &PublicKey,
-) -> Result<(SessionKey, SessionKey), ()> {
+) -> Result<(SessionKey, SessionKey), Error> {
     unsafe {
         let mut rx = SessionKey([0u8; SESSIONKEYBYTES]);
         let mut tx = SessionKey([0u8; SESSIONKEYBYTES]);
@@ -116,7 +131,7 @@ pub fn client_session_keys(
         );
 
         if r != 0 {
-            Err(())
+            Err(Error::InvalidParameter)
         } else {
             Ok((rx, tx))
         }
@@ -144,6 +159,15 @@ mod test {
         assert!(client_tx == server_rx);
     }
 
+    #[test]
+    fn test_sk_to_pk() {
+        let (pk, sk) = gen_keypair();
+        assert_eq!(sk.public_key(), pk);
+
+        let (pk, sk) = keypair_from_seed(&Seed([0x42; SEEDBYTES]));
+        assert_eq!(sk.public_key(), pk);
+    }
+
     #[test]
     fn test_kx_non_acceptable_keys() {
         let (client_pk, client_sk) = gen_keypair();
@@ -153,8 +177,14 @@ mod test {
         let fake_client_pk = PublicKey([0u8; PUBLICKEYBYTES]);
         let fake_server_pk = PublicKey([0u8; PUBLICKEYBYTES]);
 
-        assert!(client_session_keys(&client_pk, &client_sk, &fake_server_pk) == Err(()));
-        assert!(server_session_keys(&server_pk, &server_sk, &fake_client_pk) == Err(()));
+        assert!(
+            client_session_keys(&client_pk, &client_sk, &fake_server_pk)
+                == Err(Error::InvalidParameter)
+        );
+        assert!(
+            server_session_keys(&server_pk, &server_sk, &fake_client_pk)
+                == Err(Error::InvalidParameter)
+        );
     }
 
     #[test]
@@ -188,14 +218,14 @@ mod test {
 
         assert_eq!(
             client_session_keys(&client_pk, &client_sk, &small_order_p),
-            Err(())
+            Err(Error::InvalidParameter)
         );
         let (client_rx, client_tx) =
             client_session_keys(&client_pk, &client_sk, &server_pk).unwrap();
 
         assert_eq!(
             server_session_keys(&server_pk, &server_sk, &small_order_p),
-            Err(())
+            Err(Error::InvalidParameter)
         );
         let _ = server_session_keys(&server_pk, &server_sk, &client_pk).unwrap();
 
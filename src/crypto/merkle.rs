@@ -0,0 +1,354 @@
+//! Merkle trees over `BLAKE2b`
+//!
+//! # Purpose
+//! A Merkle tree commits to a sequence of leaves with a single `Root` digest.
+//! An inclusion `Proof` then shows that a given leaf is at a given position of
+//! the tree, using only a logarithmic number of hashes, e.g. to check a block
+//! of content-addressed storage or an entry of an append-only audit log
+//! against a known root.
+//!
+//! # Algorithm Details
+//! The tree is the one of [RFC 6962](https://tools.ietf.org/html/rfc6962#section-2.1),
+//! with unkeyed `BLAKE2b` of `BYTES` bytes as the hash function. The root of
+//! a list of `n` leaves `D[0..n]` is
+//!
+//! ```text
+//! MTH(D[0..0]) = BLAKE2b()
+//! MTH(D[0..1]) = BLAKE2b(0x00 || D[0])
+//! MTH(D[0..n]) = BLAKE2b(0x01 || MTH(D[0..k]) || MTH(D[k..n]))
+//! ```
+//!
+//! where `k` is the largest power of two smaller than `n`. The prefix bytes
+//! keep leaves and inner nodes apart, so that a leaf cannot be passed off as
+//! an inner node. A `Proof` holds the position of the leaf, the number of
+//! leaves and the audit path of RFC 6962, from the bottom of the tree up.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::merkle;
+//!
+//! let leaves: &[&[u8]] = &[b"first", b"second", b"third"];
+//! let tree = merkle::Tree::from_leaves(leaves);
+//! let root = tree.root();
+//!
+//! let proof = tree.proof(1).unwrap();
+//! assert!(merkle::verify(&root, b"second", &proof));
+//! assert!(!merkle::verify(&root, b"first", &proof));
+//! ```
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+
+use crypto::generichash::State;
+use std::iter::FromIterator;
+
+/// Number of bytes in a `Root` or a `Node`.
+pub const BYTES: usize = 32;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+new_type! {
+    /// `Root` digest of a Merkle tree.
+    public Root(BYTES);
+}
+
+new_type! {
+    /// Hash of a subtree, as found in the audit path of a `Proof`.
+    public Node(BYTES);
+}
+
+fn blake2b(parts: &[&[u8]]) -> Node {
+    // the output length is valid and there is no key, so these cannot fail
+    let mut state = State::new(Some(BYTES), None).unwrap();
+    for part in parts {
+        state.update(part).unwrap();
+    }
+    let mut node = Node([0; BYTES]);
+    node.0.copy_from_slice(state.finalize().unwrap().as_ref());
+    node
+}
+
+fn hash_leaf(leaf: &[u8]) -> Node {
+    blake2b(&[&[LEAF_PREFIX], leaf])
+}
+
+fn hash_node(left: &Node, right: &Node) -> Node {
+    blake2b(&[&[NODE_PREFIX], &left.0, &right.0])
+}
+
+/// A Merkle tree, holding the hashes of all its nodes so that proofs can be
+/// produced for any leaf.
+#[derive(Debug, Clone)]
+pub struct Tree {
+    // levels[0] holds the leaf hashes, the last level the root. A node
+    // without a sibling is moved up to the next level unchanged, which
+    // gives the same tree as the recursive definition of RFC 6962.
+    levels: Vec<Vec<Node>>,
+}
+
+impl Tree {
+    /// `from_leaves()` builds the tree of the sequence of leaves `leaves`.
+    pub fn from_leaves<I>(leaves: I) -> Tree
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let leaves: Vec<Node> = leaves
+            .into_iter()
+            .map(|leaf| hash_leaf(leaf.as_ref()))
+            .collect();
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let next = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| {
+                    if pair.len() == 2 {
+                        hash_node(&pair[0], &pair[1])
+                    } else {
+                        pair[0]
+                    }
+                })
+                .collect();
+            levels.push(next);
+        }
+        Tree { levels }
+    }
+
+    /// `len()` returns the number of leaves in the tree.
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// `is_empty()` returns true if the tree has no leaves.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// `root()` returns the root digest of the tree. The root of an empty
+    /// tree is the hash of the empty string.
+    pub fn root(&self) -> Root {
+        let node = match self.levels[self.levels.len() - 1].first() {
+            Some(node) => *node,
+            None => blake2b(&[]),
+        };
+        Root(node.0)
+    }
+
+    /// `proof()` returns a proof that the leaf at position `index` is part of
+    /// the tree, or `None` if there is no such leaf.
+    pub fn proof(&self, index: usize) -> Option<Proof> {
+        if index >= self.len() {
+            return None;
+        }
+        let mut path = Vec::new();
+        let mut i = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(i ^ 1) {
+                path.push(*sibling);
+            }
+            i >>= 1;
+        }
+        Some(Proof {
+            index,
+            tree_size: self.len(),
+            path,
+        })
+    }
+}
+
+impl<T: AsRef<[u8]>> FromIterator<T> for Tree {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Tree {
+        Tree::from_leaves(iter)
+    }
+}
+
+/// Inclusion proof for a leaf of a `Tree`, produced by `Tree::proof()` and
+/// checked by `verify()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    index: usize,
+    tree_size: usize,
+    path: Vec<Node>,
+}
+
+impl Proof {
+    /// `new()` reassembles a proof from its parts, e.g. after it was sent
+    /// over the network.
+    pub fn new(index: usize, tree_size: usize, path: Vec<Node>) -> Proof {
+        Proof {
+            index,
+            tree_size,
+            path,
+        }
+    }
+
+    /// `index()` returns the position of the leaf in the tree.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// `tree_size()` returns the number of leaves in the tree.
+    pub fn tree_size(&self) -> usize {
+        self.tree_size
+    }
+
+    /// `path()` returns the audit path, from the sibling of the leaf up to
+    /// the sibling of the topmost subtree holding it.
+    pub fn path(&self) -> &[Node] {
+        &self.path
+    }
+}
+
+/// `verify()` checks that `proof` shows `leaf` to be the leaf at position
+/// `proof.index()` of the tree with the root `root`. The tree size of the
+/// proof only determines which hashes the audit path holds.
+pub fn verify(root: &Root, leaf: &[u8], proof: &Proof) -> bool {
+    if proof.index >= proof.tree_size {
+        return false;
+    }
+    let mut node = hash_leaf(leaf);
+    let mut path = proof.path.iter();
+    let mut i = proof.index;
+    let mut size = proof.tree_size;
+    while size > 1 {
+        if i % 2 == 1 {
+            match path.next() {
+                Some(left) => node = hash_node(left, &node),
+                None => return false,
+            }
+        } else if i + 1 < size {
+            match path.next() {
+                Some(right) => node = hash_node(&node, right),
+                None => return false,
+            }
+        }
+        i >>= 1;
+        size = size / 2 + size % 2;
+    }
+    path.next().is_none() && Root(node.0) == *root
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex;
+
+    // the recursive definition of RFC 6962
+    fn mth(leaves: &[Vec<u8>]) -> Node {
+        match leaves.len() {
+            0 => blake2b(&[]),
+            1 => hash_leaf(&leaves[0]),
+            n => {
+                let mut k = 1;
+                while k * 2 < n {
+                    k *= 2;
+                }
+                hash_node(&mth(&leaves[..k]), &mth(&leaves[k..]))
+            }
+        }
+    }
+
+    fn leaves(n: usize) -> Vec<Vec<u8>> {
+        (0..n).map(|i| format!("leaf {}", i).into_bytes()).collect()
+    }
+
+    #[test]
+    fn test_root() {
+        for n in 0..40 {
+            let leaves = leaves(n);
+            let tree = Tree::from_leaves(&leaves);
+            assert_eq!(tree.len(), n);
+            assert_eq!(tree.root().0, mth(&leaves).0, "{}", n);
+            assert_eq!(leaves.iter().collect::<Tree>().root(), tree.root());
+        }
+    }
+
+    #[test]
+    fn test_vectors() {
+        // computed independently
+        let tree = Tree::from_leaves(Vec::<&[u8]>::new());
+        assert_eq!(
+            hex::encode(tree.root().0),
+            "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8"
+        );
+        let leaves: &[&[u8]] = &[b"a", b"b", b"c"];
+        assert_eq!(
+            hex::encode(Tree::from_leaves(leaves).root().0),
+            "17321db51c1ef3ec1f77e271aa300b4e5c6091708bcba37e46025774a26142ee"
+        );
+    }
+
+    #[test]
+    fn test_proofs() {
+        for n in 1..40 {
+            let leaves = leaves(n);
+            let tree = Tree::from_leaves(&leaves);
+            let root = tree.root();
+            for (i, leaf) in leaves.iter().enumerate() {
+                let proof = tree.proof(i).unwrap();
+                assert_eq!(proof.index(), i);
+                assert_eq!(proof.tree_size(), n);
+                assert!(verify(&root, leaf, &proof), "{} {}", n, i);
+                assert!(!verify(&root, b"other leaf", &proof));
+                let other = (i + 1) % n;
+                if other != i {
+                    assert!(!verify(&root, &leaves[other], &proof));
+                }
+            }
+            assert_eq!(tree.proof(n), None);
+        }
+    }
+
+    #[test]
+    fn test_invalid_proofs() {
+        let leaves = leaves(7);
+        let tree = Tree::from_leaves(&leaves);
+        let root = tree.root();
+        let proof = tree.proof(4).unwrap();
+        let Proof {
+            index,
+            tree_size,
+            path,
+        } = proof.clone();
+
+        let wrong_index = Proof::new(5, tree_size, path.clone());
+        assert!(!verify(&root, &leaves[4], &wrong_index));
+        let wrong_size = Proof::new(index, 6, path.clone());
+        assert!(!verify(&root, &leaves[4], &wrong_size));
+        let out_of_range = Proof::new(7, 7, path.clone());
+        assert!(!verify(&root, &leaves[4], &out_of_range));
+
+        let mut short = path.clone();
+        short.pop();
+        assert!(!verify(
+            &root,
+            &leaves[4],
+            &Proof::new(index, tree_size, short)
+        ));
+        let mut long = path.clone();
+        long.push(Node([0; BYTES]));
+        assert!(!verify(
+            &root,
+            &leaves[4],
+            &Proof::new(index, tree_size, long)
+        ));
+        for i in 0..path.len() {
+            let mut tampered = path.clone();
+            tampered[i].0[0] ^= 1;
+            assert!(!verify(
+                &root,
+                &leaves[4],
+                &Proof::new(index, tree_size, tampered)
+            ));
+        }
+
+        // a leaf cannot be passed off as an inner node
+        let mut two = hash_leaf(&leaves[0]).0.to_vec();
+        two.extend_from_slice(&hash_leaf(&leaves[1]).0);
+        let single = Tree::from_leaves(&[two]);
+        assert!(single.root() != Tree::from_leaves(&leaves[..2]).root());
+
+        assert!(verify(&root, &leaves[4], &proof));
+    }
+}
@@ -20,3 +20,45 @@ pub fn gen_random_nonce<N: Nonce>() -> N {
     randombytes_into(n.as_mut());
     N::from_bytes(n)
 }
+
+#[cfg(test)]
+mod test {
+    // `add_le()` is generated by the `nonce` arm of `new_type!`, so it is
+    // tested once here rather than for every primitive.
+    new_type! {
+        nonce TestNonce(24);
+    }
+
+    new_type! {
+        nonce ShortNonce(4);
+    }
+
+    #[test]
+    fn test_nonce_add_le() {
+        let n = TestNonce([0x42; 24]);
+        let mut m = n;
+        for _ in 0..300 {
+            m.increment_le_inplace();
+        }
+        assert_eq!(n.add_le(300), m);
+        assert_eq!(n.add_le(0), n);
+        assert_eq!(n.add_le(1), n.increment_le());
+
+        // the carry propagates through all bytes and wraps around
+        let mut bytes = [0xff; 24];
+        bytes[0] = 0xfe;
+        let mut m = TestNonce(bytes);
+        m.add_le_inplace(3);
+        let mut expected = [0x00; 24];
+        expected[0] = 0x01;
+        assert_eq!(m, TestNonce(expected));
+
+        let m = TestNonce([0; 24]).add_le(0x0102_0304_0506_0708);
+        assert_eq!(&m.0[..8], &[8, 7, 6, 5, 4, 3, 2, 1]);
+        assert!(m.0[8..].iter().all(|b| *b == 0));
+
+        // only the low bytes of `n` are added to a nonce shorter than a u64
+        let m = ShortNonce([0; 4]).add_le(0x0102_0304_0506_0708);
+        assert_eq!(m, ShortNonce([8, 7, 6, 5]));
+    }
+}
@@ -5,12 +5,14 @@
 
 use ffi::{
     crypto_onetimeauth_poly1305, crypto_onetimeauth_poly1305_BYTES,
-    crypto_onetimeauth_poly1305_KEYBYTES, crypto_onetimeauth_poly1305_verify,
+    crypto_onetimeauth_poly1305_KEYBYTES, crypto_onetimeauth_poly1305_keygen,
+    crypto_onetimeauth_poly1305_verify,
 };
 
 auth_module!(
     crypto_onetimeauth_poly1305,
     crypto_onetimeauth_poly1305_verify,
+    crypto_onetimeauth_poly1305_keygen,
     crypto_onetimeauth_poly1305_KEYBYTES as usize,
     crypto_onetimeauth_poly1305_BYTES as usize
 );
@@ -0,0 +1,429 @@
+//! One-time passwords
+//!
+//! # Purpose
+//! HOTP ([RFC 4226](https://tools.ietf.org/html/rfc4226)) and TOTP
+//! ([RFC 6238](https://tools.ietf.org/html/rfc6238)) compute short numeric
+//! codes from a shared `Secret` and a counter, or the current time, as used
+//! by authenticator apps for two-factor authentication.
+//!
+//! # Algorithm Details
+//! A code is `HMAC(secret, counter)`, with the counter as a 64 bit big-endian
+//! integer, dynamically truncated to 31 bits and reduced modulo
+//! `10^digits`. For TOTP the counter is `unix_time / period`.
+//!
+//! Only `HMAC-SHA-256` and `HMAC-SHA-512` are supported, as libsodium does
+//! not implement SHA-1. Authenticator apps must be configured accordingly,
+//! e.g. with `algorithm=SHA256` in an `otpauth://` URI.
+//!
+//! Codes are compared in constant time, and `verify()` checks every counter
+//! of the window, so that the time taken does not reveal which one matched.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::otp::{self, Algorithm, Totp};
+//!
+//! let secret = otp::gen_secret(Algorithm::Sha256);
+//! let totp = Totp::default();
+//! let code = totp.generate_at(&secret, 1_234_567_890);
+//! assert_eq!(code.len(), 6);
+//!
+//! // accept the codes for one time step before and after the current one
+//! let step = totp.verify_at(&secret, &code, 1_234_567_890 + 30, 1);
+//! assert_eq!(step, Some(1_234_567_890 / 30));
+//! ```
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+
+use crypto::auth::{hmacsha256, hmacsha512};
+use randombytes::randombytes;
+use utils::{memcmp, memzero};
+use Error;
+
+/// Minimum number of digits in a code.
+pub const DIGITS_MIN: u32 = 6;
+
+/// Maximum number of digits in a code.
+pub const DIGITS_MAX: u32 = 8;
+
+/// Maximum `window` of `Hotp::verify()`. Larger windows are clamped to it,
+/// and the `Totp` functions to half of it on either side of the time step.
+pub const WINDOW_MAX: u64 = 100;
+
+/// Hash function used to compute the codes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    /// `HMAC-SHA-256`
+    Sha256,
+    /// `HMAC-SHA-512`
+    Sha512,
+}
+
+impl Algorithm {
+    /// Number of bytes in a `Secret` generated by `gen_secret()` for this
+    /// algorithm, i.e. the output size of the hash function, as recommended
+    /// by RFC 6238.
+    pub fn secret_bytes(self) -> usize {
+        match self {
+            Algorithm::Sha256 => hmacsha256::TAGBYTES,
+            Algorithm::Sha512 => hmacsha512::TAGBYTES,
+        }
+    }
+
+    fn mac(self, secret: &Secret, counter: u64) -> Vec<u8> {
+        let counter = counter.to_be_bytes();
+        match self {
+            Algorithm::Sha256 => {
+                let mut state = hmacsha256::State::init(&secret.0);
+                state.update(&counter);
+                state.finalize().0.to_vec()
+            }
+            Algorithm::Sha512 => {
+                let mut state = hmacsha512::State::init(&secret.0);
+                state.update(&counter);
+                state.finalize().0.to_vec()
+            }
+        }
+    }
+}
+
+/// `Secret` shared between the server and the authenticator.
+///
+/// It can have any length, usually it is decoded from the base32 string
+/// shown to the user when enrolling.
+///
+/// When a `Secret` goes out of scope its contents will be zeroed out
+pub struct Secret(Vec<u8>);
+
+impl Secret {
+    /// `new()` copies the bytes of a secret.
+    pub fn new(secret: &[u8]) -> Secret {
+        Secret(secret.to_vec())
+    }
+
+    /// `expose_bytes()` returns the bytes of the secret, e.g. to encode them
+    /// for the user.
+    pub fn expose_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Clone for Secret {
+    fn clone(&self) -> Secret {
+        Secret::new(&self.0)
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        memzero(&mut self.0);
+    }
+}
+
+impl ::std::fmt::Debug for Secret {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(formatter, "Secret(****)")
+    }
+}
+
+/// `gen_secret()` randomly generates a `Secret` of
+/// `algorithm.secret_bytes()` bytes.
+///
+/// THREAD SAFETY: `gen_secret()` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+pub fn gen_secret(algorithm: Algorithm) -> Secret {
+    let mut bytes = randombytes(algorithm.secret_bytes());
+    let secret = Secret::new(&bytes);
+    memzero(&mut bytes);
+    secret
+}
+
+/// Counter-based one-time passwords, as specified in RFC 4226.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hotp {
+    algorithm: Algorithm,
+    digits: u32,
+}
+
+impl Default for Hotp {
+    /// `HMAC-SHA-256` with 6 digit codes.
+    fn default() -> Hotp {
+        Hotp {
+            algorithm: Algorithm::Sha256,
+            digits: DIGITS_MIN,
+        }
+    }
+}
+
+impl Hotp {
+    /// `new()` returns the parameters for codes of `digits` digits computed
+    /// with `algorithm`.
+    ///
+    /// It returns `Err(Error::InvalidParameter)` unless `digits` lies between
+    /// `DIGITS_MIN` and `DIGITS_MAX`.
+    pub fn new(algorithm: Algorithm, digits: u32) -> Result<Hotp, Error> {
+        if !(DIGITS_MIN..=DIGITS_MAX).contains(&digits) {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Hotp { algorithm, digits })
+    }
+
+    /// The hash function used to compute the codes.
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// The number of digits in a code.
+    pub fn digits(&self) -> u32 {
+        self.digits
+    }
+
+    /// `generate()` returns the code for `counter`, padded with leading
+    /// zeros to `digits()` digits.
+    pub fn generate(&self, secret: &Secret, counter: u64) -> String {
+        let mut mac = self.algorithm.mac(secret, counter);
+        let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+        let mut bin = [0u8; 4];
+        bin.copy_from_slice(&mac[offset..offset + 4]);
+        memzero(&mut mac);
+        let mut value = u32::from_be_bytes(bin) & 0x7fff_ffff;
+
+        let mut digits = vec![b'0'; self.digits as usize];
+        for d in digits.iter_mut().rev() {
+            *d = b'0' + (value % 10) as u8;
+            value /= 10;
+        }
+        String::from_utf8(digits).expect("digits are ASCII")
+    }
+
+    /// `verify()` checks `code` against the codes for the counters from
+    /// `counter` to `counter + window`. It returns the counter whose code
+    /// matched, so that the caller can store its successor, or `None` if
+    /// no code matched.
+    ///
+    /// `window` is clamped to `WINDOW_MAX`, and the counters stop at the
+    /// largest `u64`.
+    pub fn verify(&self, secret: &Secret, code: &str, counter: u64, window: u64) -> Option<u64> {
+        let mut found = None;
+        for c in counter..=counter.saturating_add(window.min(WINDOW_MAX)) {
+            let expected = self.generate(secret, c);
+            if memcmp(expected.as_bytes(), code.as_bytes()) && found.is_none() {
+                found = Some(c);
+            }
+        }
+        found
+    }
+}
+
+/// Time-based one-time passwords, as specified in RFC 6238.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Totp {
+    hotp: Hotp,
+    period: u64,
+}
+
+impl Default for Totp {
+    /// `HMAC-SHA-256` with 6 digit codes and a period of 30 seconds.
+    fn default() -> Totp {
+        Totp {
+            hotp: Hotp::default(),
+            period: 30,
+        }
+    }
+}
+
+impl Totp {
+    /// `new()` returns the parameters for codes of `digits` digits computed
+    /// with `algorithm`, changing every `period` seconds.
+    ///
+    /// It returns `Err(Error::InvalidParameter)` unless `digits` lies between
+    /// `DIGITS_MIN` and `DIGITS_MAX`, or if `period` is zero.
+    pub fn new(algorithm: Algorithm, digits: u32, period: u64) -> Result<Totp, Error> {
+        if period == 0 {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Totp {
+            hotp: Hotp::new(algorithm, digits)?,
+            period,
+        })
+    }
+
+    /// The underlying counter-based parameters.
+    pub fn hotp(&self) -> Hotp {
+        self.hotp
+    }
+
+    /// The number of seconds a code is valid for.
+    pub fn period(&self) -> u64 {
+        self.period
+    }
+
+    /// `time_step()` returns the counter for the Unix time `unix_time`, in
+    /// seconds.
+    pub fn time_step(&self, unix_time: u64) -> u64 {
+        unix_time / self.period
+    }
+
+    /// `generate_at()` returns the code for the Unix time `unix_time`, in
+    /// seconds.
+    pub fn generate_at(&self, secret: &Secret, unix_time: u64) -> String {
+        self.hotp.generate(secret, self.time_step(unix_time))
+    }
+
+    /// `verify_at()` checks `code` against the codes for the Unix time
+    /// `unix_time` and the `window` time steps before and after it, to allow
+    /// for clock drift. It returns the time step whose code matched, or
+    /// `None` if no code matched. `window` is clamped to `WINDOW_MAX / 2`.
+    ///
+    /// To prevent a code from being used twice, the caller should reject
+    /// time steps that are not greater than the last accepted one.
+    pub fn verify_at(
+        &self,
+        secret: &Secret,
+        code: &str,
+        unix_time: u64,
+        window: u64,
+    ) -> Option<u64> {
+        let window = window.min(WINDOW_MAX / 2);
+        let step = self.time_step(unix_time);
+        let first = step.saturating_sub(window);
+        let window = (step - first).saturating_add(window);
+        self.hotp.verify(secret, code, first, window)
+    }
+
+    /// `generate_now()` returns the code for the current time.
+    #[cfg(feature = "std")]
+    pub fn generate_now(&self, secret: &Secret) -> String {
+        self.generate_at(secret, unix_time_now())
+    }
+
+    /// `verify_now()` checks `code` against the codes for the current time
+    /// and the `window` time steps before and after it, see `verify_at()`.
+    #[cfg(feature = "std")]
+    pub fn verify_now(&self, secret: &Secret, code: &str, window: u64) -> Option<u64> {
+        self.verify_at(secret, code, unix_time_now(), window)
+    }
+}
+
+#[cfg(feature = "std")]
+fn unix_time_now() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // RFC 6238, Appendix B
+    const TIMES: [u64; 6] = [
+        59,
+        1_111_111_109,
+        1_111_111_111,
+        1_234_567_890,
+        2_000_000_000,
+        20_000_000_000,
+    ];
+
+    #[test]
+    fn test_rfc6238_sha256() {
+        let secret = Secret::new(b"12345678901234567890123456789012");
+        let totp = Totp::new(Algorithm::Sha256, 8, 30).unwrap();
+        let codes = [
+            "46119246", "68084774", "67062674", "91819424", "90698825", "77737706",
+        ];
+        for (&t, &code) in TIMES.iter().zip(codes.iter()) {
+            assert_eq!(totp.generate_at(&secret, t), code);
+            assert_eq!(totp.verify_at(&secret, code, t, 0), Some(t / 30));
+        }
+    }
+
+    #[test]
+    fn test_rfc6238_sha512() {
+        let secret =
+            Secret::new(b"1234567890123456789012345678901234567890123456789012345678901234");
+        let totp = Totp::new(Algorithm::Sha512, 8, 30).unwrap();
+        let codes = [
+            "90693936", "25091201", "99943326", "93441116", "38618901", "47863826",
+        ];
+        for (&t, &code) in TIMES.iter().zip(codes.iter()) {
+            assert_eq!(totp.generate_at(&secret, t), code);
+        }
+    }
+
+    #[test]
+    fn test_hotp_leading_zeros() {
+        let secret = Secret::new(b"12345678901234567890123456789012");
+        let hotp = Hotp::default();
+        assert_eq!(hotp.generate(&secret, 1), "119246");
+        assert_eq!(hotp.generate(&secret, 0), "920136");
+    }
+
+    #[test]
+    fn test_hotp_verify_window() {
+        let secret = gen_secret(Algorithm::Sha512);
+        let hotp = Hotp::new(Algorithm::Sha512, 7).unwrap();
+        let code = hotp.generate(&secret, 12);
+        assert_eq!(hotp.verify(&secret, &code, 10, 2), Some(12));
+        assert_eq!(hotp.verify(&secret, &code, 10, 1), None);
+        assert_eq!(hotp.verify(&secret, &code, 13, 5), None);
+        assert_eq!(hotp.verify(&secret, &code[1..], 12, 0), None);
+        assert_eq!(
+            hotp.verify(&gen_secret(Algorithm::Sha512), &code, 12, 0),
+            None
+        );
+
+        // huge windows are clamped, and the last counter does not overflow
+        assert_eq!(hotp.verify(&secret, &code, 10, !0), Some(12));
+        let code = hotp.generate(&secret, 20 + WINDOW_MAX + 1);
+        assert_eq!(hotp.verify(&secret, &code, 20, !0), None);
+        let code = hotp.generate(&secret, !0);
+        assert_eq!(hotp.verify(&secret, &code, !0 - 1, !0), Some(!0));
+    }
+
+    #[test]
+    fn test_totp_verify_window() {
+        let secret = gen_secret(Algorithm::Sha256);
+        let totp = Totp::default();
+        let code = totp.generate_at(&secret, 1000 * 30);
+        assert_eq!(totp.verify_at(&secret, &code, 1001 * 30, 1), Some(1000));
+        assert_eq!(totp.verify_at(&secret, &code, 999 * 30 + 29, 1), Some(1000));
+        assert_eq!(totp.verify_at(&secret, &code, 1002 * 30, 1), None);
+        // no underflow near the epoch
+        let code = totp.generate_at(&secret, 0);
+        assert_eq!(totp.verify_at(&secret, &code, 0, 3), Some(0));
+        // a huge window is clamped on both sides
+        let code = totp.generate_at(&secret, 1000 * 30);
+        let late = (1000 + WINDOW_MAX / 2) * 30;
+        assert_eq!(totp.verify_at(&secret, &code, late, !0), Some(1000));
+        assert_eq!(totp.verify_at(&secret, &code, late + 30, !0), None);
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        assert_eq!(
+            Hotp::new(Algorithm::Sha256, DIGITS_MIN - 1),
+            Err(Error::InvalidParameter)
+        );
+        assert_eq!(
+            Hotp::new(Algorithm::Sha256, DIGITS_MAX + 1),
+            Err(Error::InvalidParameter)
+        );
+        assert_eq!(
+            Totp::new(Algorithm::Sha256, 6, 0),
+            Err(Error::InvalidParameter)
+        );
+    }
+
+    #[test]
+    fn test_secret() {
+        let secret = gen_secret(Algorithm::Sha512);
+        assert_eq!(secret.expose_bytes().len(), 64);
+        assert_eq!(secret.clone().expose_bytes(), secret.expose_bytes());
+        assert_eq!(format!("{:?}", secret), "Secret(****)");
+    }
+}
@@ -0,0 +1,255 @@
+//! Password-based encryption
+//!
+//! # Purpose
+//! A pwbox encrypts a message with a key derived from a password. The
+//! ciphertext carries everything needed to decrypt it again except the
+//! password, so it can be stored as a single blob, e.g. to protect a secret
+//! key on disk.
+//!
+//! # Algorithm Details
+//! The key is derived with `pwhash::argon2id13` from the password and a
+//! random salt, and the message is encrypted with
+//! `aead::xchacha20poly1305_ietf` under a random nonce. A blob is
+//!
+//! ```text
+//! header || aead(m, ad=header, nonce, key)
+//! ```
+//!
+//! where the `HEADERBYTES` byte header is
+//!
+//! ```text
+//! MAGIC || VERSION || opslimit || memlimit || salt || nonce
+//! ```
+//!
+//! with `opslimit` and `memlimit` as 64 bit little-endian integers. The
+//! header is authenticated as additional data, so changing the parameters
+//! makes decryption fail.
+//!
+//! Since the parameters are read from the blob, `open()` rejects limits above
+//! `OPSLIMIT_SENSITIVE` and `MEMLIMIT_SENSITIVE` of `argon2id13`, so that a
+//! forged blob cannot make it allocate unbounded amounts of memory.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::pwbox;
+//! use sodiumoxide::crypto::pwhash::argon2id13;
+//!
+//! let passwd = b"Correct Horse Battery Staple";
+//! let c = pwbox::seal(
+//!     b"some data",
+//!     passwd,
+//!     argon2id13::OPSLIMIT_INTERACTIVE,
+//!     argon2id13::MEMLIMIT_INTERACTIVE,
+//! )
+//! .unwrap();
+//! assert_eq!(c.len(), b"some data".len() + pwbox::SEALBYTES);
+//!
+//! let m = pwbox::open(&c, passwd).unwrap();
+//! assert_eq!(m, b"some data");
+//! ```
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+
+use crypto::aead::xchacha20poly1305_ietf as aead;
+use crypto::pwhash::argon2id13::{
+    self, MemLimit, OpsLimit, Salt, MEMLIMIT_MIN, MEMLIMIT_SENSITIVE, OPSLIMIT_MIN,
+    OPSLIMIT_SENSITIVE, SALTBYTES,
+};
+use std::convert::TryInto;
+use Error;
+
+/// Bytes at the start of every pwbox.
+pub const MAGIC: &[u8] = b"pwbox";
+
+/// Version of the format written by `seal()`.
+pub const VERSION: u8 = 1;
+
+/// Number of bytes in the header of a pwbox.
+pub const HEADERBYTES: usize = MAGIC.len() + 1 + 8 + 8 + SALTBYTES + aead::NONCEBYTES;
+
+/// Number of additional bytes in a ciphertext compared to the corresponding
+/// plaintext.
+pub const SEALBYTES: usize = HEADERBYTES + aead::TAGBYTES;
+
+fn derive_key(
+    passwd: &[u8],
+    salt: &Salt,
+    opslimit: OpsLimit,
+    memlimit: MemLimit,
+) -> Result<aead::Key, Error> {
+    let mut k = aead::Key([0; aead::KEYBYTES]);
+    argon2id13::derive_key(&mut k.0, passwd, salt, opslimit, memlimit)?;
+    Ok(k)
+}
+
+/// `seal()` encrypts the message `m` with a key derived from the password
+/// `passwd`, using `opslimit` and `memlimit` as the cost of the key
+/// derivation. It returns a ciphertext whose length is `SEALBYTES + m.len()`.
+///
+/// It returns `Err(Error::InvalidParameter)` if `opslimit` or `memlimit` is
+/// out of the range accepted by `argon2id13`, and `Err(Error::OutOfMemory)`
+/// if the memory for the key derivation could not be allocated.
+pub fn seal(
+    m: &[u8],
+    passwd: &[u8],
+    opslimit: OpsLimit,
+    memlimit: MemLimit,
+) -> Result<Vec<u8>, Error> {
+    let salt = argon2id13::gen_salt();
+    let nonce = aead::gen_nonce();
+    let k = derive_key(passwd, &salt, opslimit, memlimit)?;
+
+    let mut header = Vec::with_capacity(HEADERBYTES);
+    header.extend_from_slice(MAGIC);
+    header.push(VERSION);
+    header.extend_from_slice(&(opslimit.0 as u64).to_le_bytes());
+    header.extend_from_slice(&(memlimit.0 as u64).to_le_bytes());
+    header.extend_from_slice(salt.as_ref());
+    header.extend_from_slice(nonce.as_ref());
+
+    let c = aead::seal(m, Some(&header), &nonce, &k);
+    let mut out = header;
+    out.extend_from_slice(&c);
+    Ok(out)
+}
+
+fn read_limit(b: &[u8], min: usize, max: usize) -> Result<usize, Error> {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(b);
+    let limit = u64::from_le_bytes(buf);
+    if limit < min as u64 || limit > max as u64 {
+        return Err(Error::InvalidEncoding);
+    }
+    Ok(limit as usize)
+}
+
+/// `open()` decrypts the ciphertext `c` produced by `seal()` with the
+/// password `passwd`.
+///
+/// It returns the message on success and
+/// - `Err(Error::InvalidLength)` if `c` is too short to be a pwbox,
+/// - `Err(Error::InvalidEncoding)` if `c` is not a pwbox of a supported
+///   version, or its limits are below the minimum of `argon2id13` or above
+///   the `SENSITIVE` presets,
+/// - `Err(Error::OutOfMemory)` if the key could not be derived,
+/// - `Err(Error::VerificationFailed)` if the password is wrong or `c` has
+///   been modified.
+pub fn open(c: &[u8], passwd: &[u8]) -> Result<Vec<u8>, Error> {
+    if c.len() < SEALBYTES {
+        return Err(Error::InvalidLength);
+    }
+    let (header, body) = c.split_at(HEADERBYTES);
+    let (magic, rest) = header.split_at(MAGIC.len());
+    if magic != MAGIC || rest[0] != VERSION {
+        return Err(Error::InvalidEncoding);
+    }
+    let rest = &rest[1..];
+    let opslimit = read_limit(&rest[..8], OPSLIMIT_MIN.0, OPSLIMIT_SENSITIVE.0)?;
+    let memlimit = read_limit(&rest[8..16], MEMLIMIT_MIN.0, MEMLIMIT_SENSITIVE.0)?;
+    let (salt, nonce) = rest[16..].split_at(SALTBYTES);
+    let salt: Salt = salt.try_into()?;
+    let nonce: aead::Nonce = nonce.try_into()?;
+
+    let k = derive_key(passwd, &salt, OpsLimit(opslimit), MemLimit(memlimit))?;
+    aead::open(body, Some(header), &nonce, &k)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crypto::pwhash::argon2id13::{MEMLIMIT_INTERACTIVE, OPSLIMIT_INTERACTIVE};
+    use randombytes::randombytes;
+
+    // cheap limits, the security of the tests does not matter
+    const OPSLIMIT: OpsLimit = OpsLimit(1);
+    const MEMLIMIT: MemLimit = MemLimit(8192);
+
+    #[test]
+    fn test_seal_open() {
+        for i in 0..8usize {
+            let m = randombytes(i * 10);
+            let c = seal(&m, b"password", OPSLIMIT, MEMLIMIT).unwrap();
+            assert_eq!(c.len(), m.len() + SEALBYTES);
+            assert_eq!(open(&c, b"password"), Ok(m));
+        }
+    }
+
+    #[test]
+    fn test_header() {
+        let c = seal(b"", b"password", OPSLIMIT_INTERACTIVE, MEMLIMIT_INTERACTIVE).unwrap();
+        assert!(c.starts_with(b"pwbox\x01"));
+        assert_eq!(
+            &c[6..14],
+            &(OPSLIMIT_INTERACTIVE.0 as u64).to_le_bytes()[..]
+        );
+        assert_eq!(
+            &c[14..22],
+            &(MEMLIMIT_INTERACTIVE.0 as u64).to_le_bytes()[..]
+        );
+        assert_eq!(open(&c, b"password"), Ok(Vec::new()));
+
+        let c2 = seal(b"", b"password", OPSLIMIT_INTERACTIVE, MEMLIMIT_INTERACTIVE).unwrap();
+        // fresh salt and nonce
+        assert!(c[22..HEADERBYTES] != c2[22..HEADERBYTES]);
+    }
+
+    #[test]
+    fn test_wrong_password() {
+        let c = seal(b"some data", b"password", OPSLIMIT, MEMLIMIT).unwrap();
+        assert_eq!(open(&c, b"passwore"), Err(Error::VerificationFailed));
+    }
+
+    #[test]
+    fn test_tamper() {
+        let mut c = seal(b"some data", b"password", OPSLIMIT, MEMLIMIT).unwrap();
+        for i in MAGIC.len() + 1..c.len() {
+            c[i] ^= 0x01;
+            assert!(open(&c, b"password").is_err());
+            c[i] ^= 0x01;
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        let c = seal(b"some data", b"password", OPSLIMIT, MEMLIMIT).unwrap();
+        assert_eq!(
+            open(&c[..SEALBYTES - 1], b"password"),
+            Err(Error::InvalidLength)
+        );
+
+        let mut bad_magic = c.clone();
+        bad_magic[0] = b'P';
+        assert_eq!(open(&bad_magic, b"password"), Err(Error::InvalidEncoding));
+
+        let mut bad_version = c.clone();
+        bad_version[MAGIC.len()] = VERSION + 1;
+        assert_eq!(open(&bad_version, b"password"), Err(Error::InvalidEncoding));
+
+        let mut huge_memlimit = c.clone();
+        huge_memlimit[14..22].copy_from_slice(&(!0u64).to_le_bytes());
+        assert_eq!(
+            open(&huge_memlimit, b"password"),
+            Err(Error::InvalidEncoding)
+        );
+
+        let mut zero_opslimit = c;
+        zero_opslimit[6..14].copy_from_slice(&0u64.to_le_bytes());
+        assert_eq!(
+            open(&zero_opslimit, b"password"),
+            Err(Error::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn test_seal_limits() {
+        assert_eq!(
+            seal(b"some data", b"password", OpsLimit(0), MEMLIMIT),
+            Err(Error::InvalidParameter)
+        );
+        assert_eq!(
+            seal(b"some data", b"password", OPSLIMIT, MemLimit(0)),
+            Err(Error::InvalidParameter)
+        );
+    }
+}
@@ -1,6 +1,7 @@
 macro_rules! argon2_module (($pwhash_name:ident,
                            $pwhash_str_name:ident,
                            $pwhash_str_verify_name:ident,
+                           $pwhash_str_needs_rehash_name:ident,
                            $saltbytes:expr,
                            $hashedpasswordbytes:expr,
                            $strprefix:expr,
@@ -10,9 +11,15 @@ macro_rules! argon2_module (($pwhash_name:ident,
                            $memlimit_interative:expr,
                            $memlimit_moderate:expr,
                            $memlimit_sensitive:expr,
+                           $opslimit_min:expr,
+                           $opslimit_max:expr,
+                           $memlimit_min:expr,
+                           $memlimit_max:ident,
                            $variant:expr) => (
 
 use libc::{c_int, c_ulonglong};
+use super::DerivableKey;
+use Error;
 use randombytes::randombytes_into;
 
 /// Number of bytes in a `Salt`.
@@ -21,7 +28,8 @@ pub const SALTBYTES: usize = $saltbytes;
 /// Number of bytes in a `HashedPassword`.
 pub const HASHEDPASSWORDBYTES: usize = $hashedpasswordbytes;
 
-/// All `HashedPasswords` start with this string.
+/// All `HashedPasswords` start with this string. Unlike the libsodium
+/// constant it does not include a trailing NUL byte.
 pub const STRPREFIX: &'static [u8] = $strprefix;
 
 /// Safe base line for `OpsLimit` for interactive password hashing.
@@ -42,6 +50,15 @@ pub const OPSLIMIT_SENSITIVE: OpsLimit = OpsLimit($opslimit_sensitive);
 /// `MemLimit` for highly sensitive data.
 pub const MEMLIMIT_SENSITIVE: MemLimit = MemLimit($memlimit_sensitive);
 
+/// Smallest `OpsLimit` accepted by the functions in this module.
+pub const OPSLIMIT_MIN: OpsLimit = OpsLimit($opslimit_min);
+
+/// Largest `OpsLimit` accepted by the functions in this module.
+pub const OPSLIMIT_MAX: OpsLimit = OpsLimit($opslimit_max);
+
+/// Smallest `MemLimit` accepted by the functions in this module.
+pub const MEMLIMIT_MIN: MemLimit = MemLimit($memlimit_min);
+
 /// Variant id for the Argon2i13 algorithm
 pub const VARIANT: u32 = $variant;
 
@@ -50,7 +67,7 @@ pub const VARIANT: u32 = $variant;
 ///
 /// A high `OpsLimit` will make the functions
 /// require more CPU cycles
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct OpsLimit(pub usize);
 
 /// `MemLimit` represents the maximum amount of RAM that the functions in this
@@ -58,9 +75,43 @@ pub struct OpsLimit(pub usize);
 ///
 /// It is highly recommended to allow the functions to use
 /// at least 16 megabytes.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct MemLimit(pub usize);
 
+impl OpsLimit {
+    /// `new()` returns `Ok(OpsLimit(ops))` if `ops` is between `OPSLIMIT_MIN`
+    /// and `OPSLIMIT_MAX`, and `Err(Error::InvalidParameter)` otherwise.
+    pub fn new(ops: usize) -> Result<OpsLimit, Error> {
+        if !(OPSLIMIT_MIN.0..=OPSLIMIT_MAX.0).contains(&ops) {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(OpsLimit(ops))
+    }
+}
+
+impl MemLimit {
+    /// `new()` returns `Ok(MemLimit(mem))` if `mem` is between `MEMLIMIT_MIN`
+    /// and `memlimit_max()`, and `Err(Error::InvalidParameter)` otherwise.
+    pub fn new(mem: usize) -> Result<MemLimit, Error> {
+        if !(MEMLIMIT_MIN.0..=memlimit_max().0).contains(&mem) {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(MemLimit(mem))
+    }
+}
+
+/// `memlimit_max()` returns the largest `MemLimit` accepted on this platform.
+pub fn memlimit_max() -> MemLimit {
+    MemLimit(unsafe { $memlimit_max() })
+}
+
+// Rejects limits that libsodium would only refuse after allocating memory.
+fn check_limits(opslimit: usize, memlimit: usize) -> Result<(), Error> {
+    OpsLimit::new(opslimit)?;
+    MemLimit::new(memlimit)?;
+    Ok(())
+}
+
 new_type! {
     /// `Salt` used for password hashing
     public Salt(SALTBYTES);
@@ -111,16 +162,18 @@ pub fn gen_salt() -> Salt {
 /// the same salt, and the same values for opslimit and memlimit have to be
 /// used.
 ///
-/// The function returns `Ok(key)` on success and `Err(())` if the computation didn't
-/// complete, usually because the operating system refused to allocate the
-/// amount of requested memory.
+/// The function returns `Ok(key)` on success, `Err(Error::InvalidParameter)` if
+/// `opslimit` or `memlimit` is out of range, and `Err(Error::OutOfMemory)` if the
+/// computation didn't complete, usually because the operating system refused to
+/// allocate the amount of requested memory.
 pub fn derive_key<'a>(
     key: &'a mut [u8],
     passwd: &[u8],
     &Salt(ref sb): &Salt,
     OpsLimit(opslimit): OpsLimit,
     MemLimit(memlimit): MemLimit,
-) -> Result<&'a [u8], ()> {
+) -> Result<&'a [u8], Error> {
+    check_limits(opslimit, memlimit)?;
 
     let res = unsafe {
         $pwhash_name(
@@ -136,11 +189,93 @@ pub fn derive_key<'a>(
 
     match res {
         0 => Ok(key),
-        _ => Err(()),
+        _ => Err(Error::OutOfMemory),
     }
 }
 
 
+/// `derive_key_for()` derives a key of type `K`, e.g. `secretbox::Key`, from
+/// a password and a `Salt`, as `derive_key()` does with a buffer of the
+/// right length.
+///
+/// The function fails like `derive_key()`.
+pub fn derive_key_for<K: DerivableKey>(
+    passwd: &[u8],
+    salt: &Salt,
+    opslimit: OpsLimit,
+    memlimit: MemLimit,
+) -> Result<K, Error> {
+    K::derive_with(|k| derive_key(k, passwd, salt, opslimit, memlimit).map(|_| ()))
+}
+
+/// `calibrate()` benchmarks key derivation on the current machine and returns
+/// the highest `OpsLimit` and `MemLimit` for which deriving a key takes at
+/// most `target`, using at most `max_memory` bytes of RAM.
+///
+/// Memory is preferred over computations: `max_memory` is used whenever a
+/// single pass over it fits into `target`, and halved until it does
+/// otherwise. The `OpsLimit` is then raised as far as `target` allows.
+/// Calibration runs the key derivation several times, so it takes a few
+/// multiples of `target`; the results are only as stable as the machine's
+/// load.
+///
+/// It returns `Err(Error::InvalidParameter)` if `max_memory` is less than
+/// `MEMLIMIT_MIN` or if even `OPSLIMIT_MIN` and `MEMLIMIT_MIN` take longer
+/// than `target`, and `Err(Error::OutOfMemory)` if a key derivation fails
+/// because the memory could not be allocated.
+#[cfg(feature = "std")]
+pub fn calibrate(
+    target: ::std::time::Duration,
+    max_memory: usize,
+) -> Result<(OpsLimit, MemLimit), Error> {
+    use std::time::{Duration, Instant};
+
+    // libsodium works in kibibytes
+    let kib = |mem: usize| mem - mem % 1024;
+    let mut memlimit = kib(max_memory);
+    if memlimit < MEMLIMIT_MIN.0 {
+        return Err(Error::InvalidParameter);
+    }
+    let salt = gen_salt();
+    let mut key = [0u8; 32];
+    let mut run = |ops: usize, mem: usize| -> Result<Duration, Error> {
+        let start = Instant::now();
+        derive_key(&mut key, b"calibrate", &salt, OpsLimit(ops), MemLimit(mem))?;
+        Ok(start.elapsed())
+    };
+
+    let mut lo = OPSLIMIT_MIN.0;
+    while run(lo, memlimit)? > target {
+        if kib(memlimit / 2) < MEMLIMIT_MIN.0 {
+            return Err(Error::InvalidParameter);
+        }
+        memlimit = kib(memlimit / 2);
+    }
+
+    // `lo` fits into `target`; find the first `hi` that does not and search
+    // in between
+    let mut hi = lo;
+    loop {
+        if hi > OPSLIMIT_MAX.0 / 2 {
+            return Ok((OpsLimit(hi), MemLimit(memlimit)));
+        }
+        hi *= 2;
+        if run(hi, memlimit)? > target {
+            break;
+        }
+        lo = hi;
+    }
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if run(mid, memlimit)? > target {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    Ok((OpsLimit(lo), MemLimit(memlimit)))
+}
+
 /// The `pwhash()` returns a `HashedPassword` which
 /// includes:
 ///
@@ -153,13 +288,16 @@ pub fn derive_key<'a>(
 /// `OPSLIMIT_INTERACTIVE` and `MEMLIMIT_INTERACTIVE` are safe baseline
 /// values to use for `opslimit` and `memlimit`.
 ///
-/// The function returns `Ok(hashed_password)` on success and `Err(())` if it didn't complete
-/// successfully
+/// The function returns `Ok(hashed_password)` on success, `Err(Error::InvalidParameter)`
+/// if `opslimit` or `memlimit` is out of range, and `Err(Error::OutOfMemory)` if it
+/// didn't complete successfully
 pub fn pwhash(
     passwd: &[u8],
     OpsLimit(opslimit): OpsLimit,
     MemLimit(memlimit): MemLimit,
-) -> Result<HashedPassword, ()> {
+) -> Result<HashedPassword, Error> {
+    check_limits(opslimit, memlimit)?;
+    ::ensure_init();
     let mut out = HashedPassword([0; HASHEDPASSWORDBYTES]);
     let res = unsafe {
         $pwhash_str_name(
@@ -172,7 +310,7 @@ pub fn pwhash(
 
     match res {
         0 => Ok(out),
-        _ => Err(()),
+        _ => Err(Error::OutOfMemory),
     }
 }
 
@@ -191,4 +329,65 @@ pub fn pwhash_verify(hp: &HashedPassword, passwd: &[u8]) -> bool {
     res == 0
 }
 
+/// `pwhash_str_needs_rehash()` checks whether the hashed password `hp` was
+/// created with exactly `opslimit` and `memlimit`, so that hashes made with
+/// older (usually weaker) parameters can be upgraded after a successful
+/// `pwhash_verify()`.
+///
+/// It returns `Ok(true)` if the parameters differ, `Ok(false)` if they match
+/// and `Err(Error::InvalidEncoding)` if `hp` is not a valid hashed password for this algorithm.
+pub fn pwhash_str_needs_rehash(
+    hp: &HashedPassword,
+    OpsLimit(opslimit): OpsLimit,
+    MemLimit(memlimit): MemLimit,
+) -> Result<bool, Error> {
+    let res = unsafe {
+        $pwhash_str_needs_rehash_name(
+            hp.0.as_ptr() as *const _,
+            opslimit as c_ulonglong,
+            memlimit)
+    };
+
+    match res {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(Error::InvalidEncoding),
+    }
+}
+
+#[cfg(test)]
+mod limits_test {
+    use super::*;
+
+    #[test]
+    fn test_limits_range() {
+        for &ops in [OPSLIMIT_INTERACTIVE, OPSLIMIT_SENSITIVE, OPSLIMIT_MIN, OPSLIMIT_MAX].iter() {
+            assert_eq!(OpsLimit::new(ops.0), Ok(ops));
+        }
+        let mems = [MEMLIMIT_INTERACTIVE, MEMLIMIT_SENSITIVE, MEMLIMIT_MIN, memlimit_max()];
+        for &mem in mems.iter() {
+            assert_eq!(MemLimit::new(mem.0), Ok(mem));
+        }
+        assert_eq!(OpsLimit::new(OPSLIMIT_MIN.0 - 1), Err(Error::InvalidParameter));
+        assert_eq!(MemLimit::new(MEMLIMIT_MIN.0 - 1), Err(Error::InvalidParameter));
+        if memlimit_max().0 < !0 {
+            assert_eq!(MemLimit::new(memlimit_max().0 + 1), Err(Error::InvalidParameter));
+        }
+    }
+
+    #[test]
+    fn test_limits_checked() {
+        let mut key = [0u8; 32];
+        let salt = gen_salt();
+        let ops = OpsLimit(OPSLIMIT_MIN.0 - 1);
+        let mem = MemLimit(MEMLIMIT_MIN.0 - 1);
+        assert_eq!(derive_key(&mut key, b"passwd", &salt, ops, MEMLIMIT_MIN),
+                   Err(Error::InvalidParameter));
+        assert_eq!(derive_key(&mut key, b"passwd", &salt, OPSLIMIT_MIN, mem),
+                   Err(Error::InvalidParameter));
+        assert_eq!(pwhash(b"passwd", ops, MEMLIMIT_MIN), Err(Error::InvalidParameter));
+        assert_eq!(pwhash(b"passwd", OPSLIMIT_MIN, mem), Err(Error::InvalidParameter));
+    }
+}
+
 ));
@@ -17,26 +17,33 @@
 
 use ffi::{
     crypto_pwhash_ALG_ARGON2I13, crypto_pwhash_argon2i, crypto_pwhash_argon2i_MEMLIMIT_INTERACTIVE,
-    crypto_pwhash_argon2i_MEMLIMIT_MODERATE, crypto_pwhash_argon2i_MEMLIMIT_SENSITIVE,
-    crypto_pwhash_argon2i_OPSLIMIT_INTERACTIVE, crypto_pwhash_argon2i_OPSLIMIT_MODERATE,
-    crypto_pwhash_argon2i_OPSLIMIT_SENSITIVE, crypto_pwhash_argon2i_SALTBYTES,
-    crypto_pwhash_argon2i_STRBYTES, crypto_pwhash_argon2i_STRPREFIX, crypto_pwhash_argon2i_str,
-    crypto_pwhash_argon2i_str_verify,
+    crypto_pwhash_argon2i_MEMLIMIT_MIN, crypto_pwhash_argon2i_MEMLIMIT_MODERATE,
+    crypto_pwhash_argon2i_MEMLIMIT_SENSITIVE, crypto_pwhash_argon2i_OPSLIMIT_INTERACTIVE,
+    crypto_pwhash_argon2i_OPSLIMIT_MAX, crypto_pwhash_argon2i_OPSLIMIT_MIN,
+    crypto_pwhash_argon2i_OPSLIMIT_MODERATE, crypto_pwhash_argon2i_OPSLIMIT_SENSITIVE,
+    crypto_pwhash_argon2i_SALTBYTES, crypto_pwhash_argon2i_STRBYTES,
+    crypto_pwhash_argon2i_memlimit_max, crypto_pwhash_argon2i_str,
+    crypto_pwhash_argon2i_str_needs_rehash, crypto_pwhash_argon2i_str_verify,
 };
 
 argon2_module!(
     crypto_pwhash_argon2i,
     crypto_pwhash_argon2i_str,
     crypto_pwhash_argon2i_str_verify,
+    crypto_pwhash_argon2i_str_needs_rehash,
     crypto_pwhash_argon2i_SALTBYTES as usize,
     crypto_pwhash_argon2i_STRBYTES as usize,
-    crypto_pwhash_argon2i_STRPREFIX,
+    b"$argon2i$",
     crypto_pwhash_argon2i_OPSLIMIT_INTERACTIVE as usize,
     crypto_pwhash_argon2i_OPSLIMIT_MODERATE as usize,
     crypto_pwhash_argon2i_OPSLIMIT_SENSITIVE as usize,
     crypto_pwhash_argon2i_MEMLIMIT_INTERACTIVE as usize,
     crypto_pwhash_argon2i_MEMLIMIT_MODERATE as usize,
     crypto_pwhash_argon2i_MEMLIMIT_SENSITIVE as usize,
+    crypto_pwhash_argon2i_OPSLIMIT_MIN as usize,
+    crypto_pwhash_argon2i_OPSLIMIT_MAX as usize,
+    crypto_pwhash_argon2i_MEMLIMIT_MIN as usize,
+    crypto_pwhash_argon2i_memlimit_max,
     crypto_pwhash_ALG_ARGON2I13
 );
 
@@ -105,6 +112,26 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_pwhash_str_needs_rehash() {
+        let pwh = pwhash(b"Correct Horse Battery Staple", OpsLimit(3), MemLimit(8192)).unwrap();
+        assert_eq!(
+            pwhash_str_needs_rehash(&pwh, OpsLimit(3), MemLimit(8192)),
+            Ok(false)
+        );
+        assert_eq!(
+            pwhash_str_needs_rehash(&pwh, OpsLimit(4), MemLimit(8192)),
+            Ok(true)
+        );
+        assert_eq!(
+            pwhash_str_needs_rehash(&pwh, OpsLimit(3), MemLimit(16384)),
+            Ok(true)
+        );
+
+        let empty = HashedPassword([0; HASHEDPASSWORDBYTES]);
+        assert!(pwhash_str_needs_rehash(&empty, OpsLimit(3), MemLimit(8192)).is_err());
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn test_serialisation() {
@@ -13,14 +13,28 @@
 //!
 //! Note: libsodium provides a limited version of the Argon2 function. The salt
 //! parameter is fixed at 128 bits and the parallelism parameter is fixed to 1.
+//!
+//! # Example (password hashing and verification)
+//! ```
+//! use sodiumoxide::crypto::pwhash::argon2id13;
+//!
+//! let passwd = b"Correct Horse Battery Staple";
+//! let pwh = argon2id13::pwhash(passwd,
+//!                              argon2id13::OPSLIMIT_INTERACTIVE,
+//!                              argon2id13::MEMLIMIT_INTERACTIVE).unwrap();
+//! assert!(pwh.as_ref().starts_with(argon2id13::STRPREFIX));
+//! assert!(argon2id13::pwhash_verify(&pwh, passwd));
+//! ```
 
 use ffi::{
     crypto_pwhash_ALG_ARGON2ID13, crypto_pwhash_argon2id,
-    crypto_pwhash_argon2id_MEMLIMIT_INTERACTIVE, crypto_pwhash_argon2id_MEMLIMIT_MODERATE,
-    crypto_pwhash_argon2id_MEMLIMIT_SENSITIVE, crypto_pwhash_argon2id_OPSLIMIT_INTERACTIVE,
-    crypto_pwhash_argon2id_OPSLIMIT_MODERATE, crypto_pwhash_argon2id_OPSLIMIT_SENSITIVE,
-    crypto_pwhash_argon2id_SALTBYTES, crypto_pwhash_argon2id_STRBYTES,
-    crypto_pwhash_argon2id_STRPREFIX, crypto_pwhash_argon2id_str,
+    crypto_pwhash_argon2id_MEMLIMIT_INTERACTIVE, crypto_pwhash_argon2id_MEMLIMIT_MIN,
+    crypto_pwhash_argon2id_MEMLIMIT_MODERATE, crypto_pwhash_argon2id_MEMLIMIT_SENSITIVE,
+    crypto_pwhash_argon2id_OPSLIMIT_INTERACTIVE, crypto_pwhash_argon2id_OPSLIMIT_MAX,
+    crypto_pwhash_argon2id_OPSLIMIT_MIN, crypto_pwhash_argon2id_OPSLIMIT_MODERATE,
+    crypto_pwhash_argon2id_OPSLIMIT_SENSITIVE, crypto_pwhash_argon2id_SALTBYTES,
+    crypto_pwhash_argon2id_STRBYTES, crypto_pwhash_argon2id_memlimit_max,
+    crypto_pwhash_argon2id_str, crypto_pwhash_argon2id_str_needs_rehash,
     crypto_pwhash_argon2id_str_verify,
 };
 
@@ -28,15 +42,20 @@ argon2_module!(
     crypto_pwhash_argon2id,
     crypto_pwhash_argon2id_str,
     crypto_pwhash_argon2id_str_verify,
+    crypto_pwhash_argon2id_str_needs_rehash,
     crypto_pwhash_argon2id_SALTBYTES as usize,
     crypto_pwhash_argon2id_STRBYTES as usize,
-    crypto_pwhash_argon2id_STRPREFIX,
+    b"$argon2id$",
     crypto_pwhash_argon2id_OPSLIMIT_INTERACTIVE as usize,
     crypto_pwhash_argon2id_OPSLIMIT_MODERATE as usize,
     crypto_pwhash_argon2id_OPSLIMIT_SENSITIVE as usize,
     crypto_pwhash_argon2id_MEMLIMIT_INTERACTIVE as usize,
     crypto_pwhash_argon2id_MEMLIMIT_MODERATE as usize,
     crypto_pwhash_argon2id_MEMLIMIT_SENSITIVE as usize,
+    crypto_pwhash_argon2id_OPSLIMIT_MIN as usize,
+    crypto_pwhash_argon2id_OPSLIMIT_MAX as usize,
+    crypto_pwhash_argon2id_MEMLIMIT_MIN as usize,
+    crypto_pwhash_argon2id_memlimit_max,
     crypto_pwhash_ALG_ARGON2ID13
 );
 
@@ -104,6 +123,26 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_pwhash_str_needs_rehash() {
+        let pwh = pwhash(b"Correct Horse Battery Staple", OpsLimit(1), MemLimit(8192)).unwrap();
+        assert_eq!(
+            pwhash_str_needs_rehash(&pwh, OpsLimit(1), MemLimit(8192)),
+            Ok(false)
+        );
+        assert_eq!(
+            pwhash_str_needs_rehash(&pwh, OpsLimit(2), MemLimit(8192)),
+            Ok(true)
+        );
+        assert_eq!(
+            pwhash_str_needs_rehash(&pwh, OpsLimit(1), MemLimit(16384)),
+            Ok(true)
+        );
+
+        let empty = HashedPassword([0; HASHEDPASSWORDBYTES]);
+        assert!(pwhash_str_needs_rehash(&empty, OpsLimit(1), MemLimit(8192)).is_err());
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn test_serialisation() {
@@ -598,4 +637,46 @@ mod test {
             "failed to verify password with hash"
         );
     }
+
+    #[test]
+    fn test_verify_argon2i_string() {
+        let password = "";
+        let out = "$argon2i$v=19$m=4096,t=1,p=1$X1NhbHQAAAAAAAAAAAAAAA$bWh++\
+                   MKN1OiFHKgIWTLvIi1iHicmHH7+Fv3K88ifFfI";
+        let hashed = to_hashed_password(out);
+
+        assert!(
+            !pwhash_verify(&hashed, password.as_bytes()),
+            "verified an argon2i hash as argon2id"
+        );
+    }
+
+    #[test]
+    fn test_pwhash_strprefix() {
+        let pwh = pwhash(b"Correct Horse Battery Staple", OpsLimit(1), MemLimit(8192)).unwrap();
+        assert!(pwh.as_ref().starts_with(STRPREFIX));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_calibrate() {
+        use std::time::Duration;
+
+        let (ops, mem) = calibrate(Duration::from_millis(20), 1 << 20).unwrap();
+        assert!(ops.0 >= OPSLIMIT_MIN.0);
+        assert!(mem.0 >= MEMLIMIT_MIN.0 && mem.0 <= 1 << 20);
+        assert_eq!(mem.0 % 1024, 0);
+        let mut key = [0u8; 32];
+        assert!(derive_key(&mut key, b"passwd", &gen_salt(), ops, mem).is_ok());
+
+        // nothing fits
+        assert_eq!(
+            calibrate(Duration::from_millis(0), 1 << 20),
+            Err(Error::InvalidParameter)
+        );
+        assert_eq!(
+            calibrate(Duration::from_millis(20), MEMLIMIT_MIN.0 - 1),
+            Err(Error::InvalidParameter)
+        );
+    }
 }
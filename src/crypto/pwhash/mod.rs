@@ -18,6 +18,11 @@
 //!   memory. Therefore, it mitigates brute-force attacks by requiring a
 //!   significant effort to verify each password.
 //!
+//! The cost of the computation is given as an `OpsLimit` and a `MemLimit`.
+//! Each primitive has its own pair of types, with `INTERACTIVE` and
+//! `SENSITIVE` presets, and `OpsLimit::new()` and `MemLimit::new()` check a
+//! custom value against the range libsodium accepts.
+//!
 //! Common use cases:
 //!
 //! - Protecting an on-disk secret key with a password,
@@ -40,6 +45,22 @@
 //! }
 //! ```
 //!
+//! `derive_key_for()` does the same, but directly returns a key of the type
+//! the caller asks for:
+//!
+//! ```
+//! use sodiumoxide::crypto::secretbox;
+//! use sodiumoxide::crypto::pwhash;
+//!
+//! let passwd = b"Correct Horse Battery Staple";
+//! let salt = pwhash::gen_salt();
+//! let k = pwhash::derive_key_for::<secretbox::Key>(passwd, &salt,
+//!                                                  pwhash::OPSLIMIT_INTERACTIVE,
+//!                                                  pwhash::MEMLIMIT_INTERACTIVE).unwrap();
+//! let n = secretbox::gen_nonce();
+//! let c = secretbox::seal(b"some data", &n, &k);
+//! ```
+//!
 //! # Example (password hashing)
 //! ```
 //! use sodiumoxide::crypto::pwhash;
@@ -64,6 +85,21 @@
 //!                          pwhash::MEMLIMIT_INTERACTIVE).unwrap();
 //! assert!(pwhash::pwhash_verify(&pwh, passwd));
 //! ```
+//!
+//! # Example (runtime selected algorithm)
+//! ```
+//! use sodiumoxide::crypto::pwhash::{self, argon2id13, Algorithm};
+//!
+//! let passwd = b"Correct Horse Battery Staple";
+//! let alg = Algorithm::Argon2id13;
+//! let pwh = pwhash::pwhash_with_alg(alg, passwd,
+//!                                   argon2id13::OPSLIMIT_INTERACTIVE.0,
+//!                                   argon2id13::MEMLIMIT_INTERACTIVE.0).unwrap();
+//! assert!(pwhash::pwhash_verify_with_alg(alg, &pwh, passwd));
+//! ```
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
 
 pub use self::scryptsalsa208sha256::*;
 #[macro_use]
@@ -71,3 +107,383 @@ mod argon2_macros;
 pub mod argon2i13;
 pub mod argon2id13;
 pub mod scryptsalsa208sha256;
+
+/// `DerivableKey` is implemented by the symmetric key types of the other
+/// modules, so that `derive_key_for()` can derive them from a password
+/// directly, with the right length.
+pub trait DerivableKey: Sized {
+    /// `derive_with()` creates a zeroed key and lets `f` fill in its bytes.
+    #[doc(hidden)]
+    fn derive_with<F: FnOnce(&mut [u8]) -> Result<(), ::Error>>(f: F) -> Result<Self, ::Error>;
+}
+
+// Implements `DerivableKey` for the `Key` of each of the given modules.
+#[allow(unused_macros)] // unused when only pwhash is enabled
+macro_rules! derivable_key (($($($m:ident)::+),+) => ($(
+    impl DerivableKey for ::crypto::$($m)::+::Key {
+        fn derive_with<F: FnOnce(&mut [u8]) -> Result<(), ::Error>>(f: F) -> Result<Self, ::Error> {
+            let mut k = ::crypto::$($m)::+::Key([0; ::crypto::$($m)::+::KEYBYTES]);
+            f(&mut k.0)?;
+            Ok(k)
+        }
+    }
+)+));
+
+#[cfg(feature = "aead")]
+derivable_key!(
+    aead::aes256gcm,
+    aead::chacha20poly1305,
+    aead::chacha20poly1305_ietf,
+    aead::xchacha20poly1305_ietf
+);
+#[cfg(feature = "auth")]
+derivable_key!(
+    auth::blake2b,
+    auth::hmacsha256,
+    auth::hmacsha512,
+    auth::hmacsha512256
+);
+#[cfg(feature = "kdf")]
+derivable_key!(kdf::blake2b);
+#[cfg(feature = "secretbox")]
+derivable_key!(secretbox::xchacha20poly1305, secretbox::xsalsa20poly1305);
+#[cfg(feature = "secretstream")]
+derivable_key!(secretstream::xchacha20poly1305);
+#[cfg(feature = "shorthash")]
+derivable_key!(shorthash::siphash24, shorthash::siphashx24);
+#[cfg(feature = "stream")]
+derivable_key!(
+    stream::chacha20,
+    stream::chacha20_ietf,
+    stream::salsa20,
+    stream::xchacha20,
+    stream::xsalsa20
+);
+
+/// `Algorithm` selects one of the password hashing primitives at runtime, e.g.
+/// to keep verifying hashes produced by another system. Salts, limits and
+/// hashed passwords are passed as plain bytes and integers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    /// `Argon2i` version 1.3, see [`argon2i13`].
+    Argon2i13,
+    /// `Argon2id` version 1.3, see [`argon2id13`].
+    Argon2id13,
+    /// The legacy scrypt primitive, see [`scryptsalsa208sha256`].
+    ScryptSalsa208Sha256,
+}
+
+macro_rules! dispatch (($alg:expr, $m:ident => $e:expr) => (
+    match $alg {
+        Algorithm::Argon2i13 => { use self::argon2i13 as $m; $e }
+        Algorithm::Argon2id13 => { use self::argon2id13 as $m; $e }
+        Algorithm::ScryptSalsa208Sha256 => { use self::scryptsalsa208sha256 as $m; $e }
+    }
+));
+
+// `dispatch!` qualifies paths for every primitive, including the glob-imported default.
+#[allow(unused_qualifications)]
+impl Algorithm {
+    /// `salt_len()` returns the number of bytes in a salt for this algorithm.
+    pub fn salt_len(self) -> usize {
+        dispatch!(self, m => m::SALTBYTES)
+    }
+
+    /// `hashed_password_len()` returns the number of bytes in a hashed
+    /// password for this algorithm, including the zero padding.
+    pub fn hashed_password_len(self) -> usize {
+        dispatch!(self, m => m::HASHEDPASSWORDBYTES)
+    }
+
+    /// `gen_salt()` randomly generates a new salt for this algorithm.
+    ///
+    /// THREAD SAFETY: `gen_salt()` is thread-safe provided that you have called
+    /// `sodiumoxide::init()` once before using any other function from sodiumoxide.
+    pub fn gen_salt(self) -> Vec<u8> {
+        dispatch!(self, m => m::gen_salt().as_ref().to_vec())
+    }
+}
+
+/// `derive_key_with_alg()` derives a key from a password and a salt using the
+/// algorithm `alg`, as `derive_key()` of the corresponding module does.
+///
+/// It returns `Err(Error::InvalidLength)` if `salt` is not `alg.salt_len()`
+/// bytes long, and fails like `derive_key()` if the computation didn't
+/// complete.
+#[allow(unused_qualifications)]
+pub fn derive_key_with_alg<'a>(
+    alg: Algorithm,
+    key: &'a mut [u8],
+    passwd: &[u8],
+    salt: &[u8],
+    opslimit: usize,
+    memlimit: usize,
+) -> Result<&'a [u8], ::Error> {
+    dispatch!(alg, m => {
+        let salt = m::Salt::from_slice(salt).ok_or(::Error::InvalidLength)?;
+        m::derive_key(key, passwd, &salt, m::OpsLimit(opslimit), m::MemLimit(memlimit))
+    })
+}
+
+/// `pwhash_with_alg()` hashes a password using the algorithm `alg`, as
+/// `pwhash()` of the corresponding module does.
+///
+/// It returns the `alg.hashed_password_len()` bytes of the hashed password on
+/// success, and fails like `pwhash()` if it didn't complete successfully.
+#[allow(unused_qualifications)]
+pub fn pwhash_with_alg(
+    alg: Algorithm,
+    passwd: &[u8],
+    opslimit: usize,
+    memlimit: usize,
+) -> Result<Vec<u8>, ::Error> {
+    dispatch!(alg, m => {
+        let hp = m::pwhash(passwd, m::OpsLimit(opslimit), m::MemLimit(memlimit))?;
+        Ok(hp.as_ref().to_vec())
+    })
+}
+
+// Copies `hp` into the zeroed buffer `buf`, making sure that it stays a
+// zero-terminated string as libsodium expects.
+fn pad_hashed_password(hp: &[u8], buf: &mut [u8]) -> bool {
+    if hp.len() > buf.len() || (hp.len() == buf.len() && !hp.contains(&0)) {
+        return false;
+    }
+    buf[..hp.len()].copy_from_slice(hp);
+    true
+}
+
+/// `pwhash_verify_with_alg()` verifies that `hp` is a valid password
+/// verification string for `passwd` under the algorithm `alg`.
+///
+/// `hp` may have its zero padding stripped. It returns `false` if `hp` is
+/// longer than `alg.hashed_password_len()` bytes or if the verification fails.
+#[allow(unused_qualifications)]
+pub fn pwhash_verify_with_alg(alg: Algorithm, hp: &[u8], passwd: &[u8]) -> bool {
+    dispatch!(alg, m => {
+        let mut padded = m::HashedPassword([0; m::HASHEDPASSWORDBYTES]);
+        if !pad_hashed_password(hp, &mut padded.0) {
+            return false;
+        }
+        m::pwhash_verify(&padded, passwd)
+    })
+}
+
+/// `pwhash_str_needs_rehash_with_alg()` returns `true` if the hashed password
+/// `hp` should be replaced by a new hash computed with the algorithm `alg` and
+/// the limits `opslimit` and `memlimit`.
+///
+/// This is the case if `hp` was created with different limits, or if it is not
+/// an `alg` hash at all, e.g. because it was created with an older algorithm.
+/// `hp` may have its zero padding stripped.
+#[allow(unused_qualifications)]
+pub fn pwhash_str_needs_rehash_with_alg(
+    alg: Algorithm,
+    hp: &[u8],
+    opslimit: usize,
+    memlimit: usize,
+) -> bool {
+    dispatch!(alg, m => {
+        let mut padded = m::HashedPassword([0; m::HASHEDPASSWORDBYTES]);
+        if !pad_hashed_password(hp, &mut padded.0) {
+            return true;
+        }
+        m::pwhash_str_needs_rehash(&padded, m::OpsLimit(opslimit), m::MemLimit(memlimit))
+            .unwrap_or(true)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ALGS: [Algorithm; 3] = [
+        Algorithm::Argon2i13,
+        Algorithm::Argon2id13,
+        Algorithm::ScryptSalsa208Sha256,
+    ];
+
+    // cheap limits accepted by every algorithm
+    fn limits(alg: Algorithm) -> (usize, usize) {
+        match alg {
+            Algorithm::Argon2i13 => (3, 8192),
+            Algorithm::Argon2id13 => (1, 8192),
+            Algorithm::ScryptSalsa208Sha256 => (OPSLIMIT_INTERACTIVE.0, MEMLIMIT_INTERACTIVE.0),
+        }
+    }
+
+    #[test]
+    fn test_derive_key_with_alg() {
+        for &alg in ALGS.iter() {
+            let (ops, mem) = limits(alg);
+            let salt = alg.gen_salt();
+            assert_eq!(salt.len(), alg.salt_len());
+            let mut k1 = [0u8; 32];
+            let mut k2 = [0u8; 32];
+            derive_key_with_alg(alg, &mut k1, b"passwd", &salt, ops, mem).unwrap();
+            derive_key_with_alg(alg, &mut k2, b"passwd", &salt, ops, mem).unwrap();
+            assert_eq!(k1, k2);
+
+            let mut k3 = [0u8; 32];
+            derive_key_with_alg(alg, &mut k3, b"passwe", &salt, ops, mem).unwrap();
+            assert!(k1 != k3);
+
+            assert!(derive_key_with_alg(alg, &mut k3, b"passwd", &salt[1..], ops, mem).is_err());
+        }
+    }
+
+    #[test]
+    fn test_derive_key_with_alg_eq_module() {
+        let salt = argon2id13::gen_salt();
+        let mut k1 = [0u8; 32];
+        let mut k2 = [0u8; 32];
+        derive_key_with_alg(
+            Algorithm::Argon2id13,
+            &mut k1,
+            b"passwd",
+            salt.as_ref(),
+            1,
+            8192,
+        )
+        .unwrap();
+        argon2id13::derive_key(
+            &mut k2,
+            b"passwd",
+            &salt,
+            argon2id13::OpsLimit(1),
+            argon2id13::MemLimit(8192),
+        )
+        .unwrap();
+        assert_eq!(k1, k2);
+    }
+
+    #[test]
+    fn test_pwhash_with_alg() {
+        for &alg in ALGS.iter() {
+            let (ops, mem) = limits(alg);
+            let hp = pwhash_with_alg(alg, b"passwd", ops, mem).unwrap();
+            assert_eq!(hp.len(), alg.hashed_password_len());
+            assert!(pwhash_verify_with_alg(alg, &hp, b"passwd"));
+            assert!(!pwhash_verify_with_alg(alg, &hp, b"passwe"));
+
+            // stored without the zero padding
+            let end = hp.iter().position(|&b| b == 0).unwrap();
+            assert!(pwhash_verify_with_alg(alg, &hp[..end], b"passwd"));
+
+            for &other in ALGS.iter().filter(|&&other| other != alg) {
+                assert!(!pwhash_verify_with_alg(other, &hp[..end], b"passwd"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_pwhash_verify_with_alg_too_long() {
+        let hp = [b'$'; argon2id13::HASHEDPASSWORDBYTES + 1];
+        assert!(!pwhash_verify_with_alg(
+            Algorithm::Argon2id13,
+            &hp,
+            b"passwd"
+        ));
+    }
+
+    #[test]
+    fn test_pwhash_str_needs_rehash_with_alg() {
+        let (ops, mem) = limits(Algorithm::Argon2i13);
+        let old = pwhash_with_alg(Algorithm::Argon2i13, b"passwd", ops, mem).unwrap();
+        let end = old.iter().position(|&b| b == 0).unwrap();
+        assert!(!pwhash_str_needs_rehash_with_alg(
+            Algorithm::Argon2i13,
+            &old,
+            ops,
+            mem
+        ));
+        assert!(!pwhash_str_needs_rehash_with_alg(
+            Algorithm::Argon2i13,
+            &old[..end],
+            ops,
+            mem
+        ));
+        assert!(pwhash_str_needs_rehash_with_alg(
+            Algorithm::Argon2i13,
+            &old,
+            ops + 1,
+            mem
+        ));
+
+        // upgrading to argon2id
+        let (ops, mem) = limits(Algorithm::Argon2id13);
+        assert!(pwhash_str_needs_rehash_with_alg(
+            Algorithm::Argon2id13,
+            &old[..end],
+            ops,
+            mem
+        ));
+        let new = pwhash_with_alg(Algorithm::Argon2id13, b"passwd", ops, mem).unwrap();
+        assert!(!pwhash_str_needs_rehash_with_alg(
+            Algorithm::Argon2id13,
+            &new,
+            ops,
+            mem
+        ));
+    }
+
+    #[cfg(feature = "secretbox")]
+    #[test]
+    fn test_derive_key_for() {
+        use crypto::secretbox;
+
+        let salt = argon2id13::gen_salt();
+        let (ops, mem) = limits(Algorithm::Argon2id13);
+        let k: secretbox::Key = argon2id13::derive_key_for(
+            b"passwd",
+            &salt,
+            argon2id13::OpsLimit(ops),
+            argon2id13::MemLimit(mem),
+        )
+        .unwrap();
+        let mut kb = [0; secretbox::KEYBYTES];
+        argon2id13::derive_key(
+            &mut kb,
+            b"passwd",
+            &salt,
+            argon2id13::OpsLimit(ops),
+            argon2id13::MemLimit(mem),
+        )
+        .unwrap();
+        assert_eq!(k.0, kb);
+
+        // the input is rejected, not the key type
+        let res = argon2id13::derive_key_for::<secretbox::Key>(
+            b"passwd",
+            &salt,
+            argon2id13::OpsLimit(0),
+            argon2id13::MemLimit(mem),
+        );
+        assert!(res.is_err());
+    }
+
+    #[cfg(feature = "aead")]
+    #[test]
+    fn test_derive_key_for_scrypt() {
+        use crypto::aead;
+
+        let salt = gen_salt();
+        let k = derive_key_for::<aead::Key>(
+            b"passwd",
+            &salt,
+            OPSLIMIT_INTERACTIVE,
+            MEMLIMIT_INTERACTIVE,
+        )
+        .unwrap();
+        let mut kb = [0; aead::KEYBYTES];
+        derive_key(
+            &mut kb,
+            b"passwd",
+            &salt,
+            OPSLIMIT_INTERACTIVE,
+            MEMLIMIT_INTERACTIVE,
+        )
+        .unwrap();
+        assert_eq!(k.0, kb);
+    }
+}
@@ -1,9 +1,11 @@
 //! `crypto_pwhash_scryptsalsa208sha256`, a particular combination of Scrypt, Salsa20/8
 //! and SHA-256
 
+use super::DerivableKey;
 use ffi;
 use libc::c_ulonglong;
 use randombytes::randombytes_into;
+use Error;
 
 /// Number of bytes in a `Salt`.
 pub const SALTBYTES: usize = ffi::crypto_pwhash_scryptsalsa208sha256_SALTBYTES as usize;
@@ -30,12 +32,24 @@ pub const OPSLIMIT_SENSITIVE: OpsLimit =
 pub const MEMLIMIT_SENSITIVE: MemLimit =
     MemLimit(ffi::crypto_pwhash_scryptsalsa208sha256_MEMLIMIT_SENSITIVE as usize);
 
+/// Smallest `OpsLimit` accepted by the functions in this module.
+pub const OPSLIMIT_MIN: OpsLimit =
+    OpsLimit(ffi::crypto_pwhash_scryptsalsa208sha256_OPSLIMIT_MIN as usize);
+
+/// Largest `OpsLimit` accepted by the functions in this module.
+pub const OPSLIMIT_MAX: OpsLimit =
+    OpsLimit(ffi::crypto_pwhash_scryptsalsa208sha256_OPSLIMIT_MAX as usize);
+
+/// Smallest `MemLimit` accepted by the functions in this module.
+pub const MEMLIMIT_MIN: MemLimit =
+    MemLimit(ffi::crypto_pwhash_scryptsalsa208sha256_MEMLIMIT_MIN as usize);
+
 /// `OpsLimit` represents the maximum number of computations to perform when
 /// using the functions in this module.
 ///
 /// A high `OpsLimit` will make the functions
 /// require more CPU cycles
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct OpsLimit(pub usize);
 
 /// `MemLimit` represents the maximum amount of RAM that the functions in this
@@ -43,9 +57,45 @@ pub struct OpsLimit(pub usize);
 ///
 /// It is highly recommended to allow the functions to use
 /// at least 16 megabytes.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct MemLimit(pub usize);
 
+impl OpsLimit {
+    /// `new()` checks that `ops` is an `OpsLimit` scrypt can work with, i.e.
+    /// at least `OPSLIMIT_MIN` and at most `OPSLIMIT_MAX`. Anything else is
+    /// refused with `Err(Error::InvalidParameter)`.
+    pub fn new(ops: usize) -> Result<OpsLimit, Error> {
+        if !(OPSLIMIT_MIN.0..=OPSLIMIT_MAX.0).contains(&ops) {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(OpsLimit(ops))
+    }
+}
+
+impl MemLimit {
+    /// `new()` checks that `mem` is a `MemLimit` scrypt can work with: no
+    /// less than `MEMLIMIT_MIN`, and no more than `memlimit_max()` on this
+    /// platform. Anything else is refused with `Err(Error::InvalidParameter)`.
+    pub fn new(mem: usize) -> Result<MemLimit, Error> {
+        if !(MEMLIMIT_MIN.0..=memlimit_max().0).contains(&mem) {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(MemLimit(mem))
+    }
+}
+
+/// `memlimit_max()` returns the largest `MemLimit` accepted on this platform.
+pub fn memlimit_max() -> MemLimit {
+    MemLimit(unsafe { ffi::crypto_pwhash_scryptsalsa208sha256_memlimit_max() })
+}
+
+// scrypt silently adjusts some parameters, so out-of-range limits are
+// refused up front rather than left to libsodium.
+fn check_limits(opslimit: usize, memlimit: usize) -> Result<(), Error> {
+    OpsLimit::new(opslimit)?;
+    MemLimit::new(memlimit).map(|_| ())
+}
+
 new_type! {
     /// `Salt` used for password hashing
     public Salt(SALTBYTES);
@@ -96,16 +146,18 @@ pub fn gen_salt() -> Salt {
 /// the same salt, and the same values for opslimit and memlimit have to be
 /// used.
 ///
-/// The function returns `Ok(key)` on success and `Err(())` if the computation didn't
-/// complete, usually because the operating system refused to allocate the
-/// amount of requested memory.
+/// The function returns `Ok(key)` on success. It returns `Err(Error::InvalidParameter)`
+/// without deriving anything if a limit is outside of the range checked by `OpsLimit::new()`
+/// and `MemLimit::new()`, and `Err(Error::OutOfMemory)` if the computation didn't complete,
+/// usually because the operating system refused to allocate the amount of requested memory.
 pub fn derive_key<'a>(
     key: &'a mut [u8],
     passwd: &[u8],
     salt: &Salt,
     OpsLimit(opslimit): OpsLimit,
     MemLimit(memlimit): MemLimit,
-) -> Result<&'a [u8], ()> {
+) -> Result<&'a [u8], Error> {
+    check_limits(opslimit, memlimit)?;
     if unsafe {
         ffi::crypto_pwhash_scryptsalsa208sha256(
             key.as_mut_ptr(),
@@ -120,10 +172,24 @@ pub fn derive_key<'a>(
     {
         Ok(key)
     } else {
-        Err(())
+        Err(Error::OutOfMemory)
     }
 }
 
+/// `derive_key_for()` derives a key of type `K`, e.g. `secretbox::Key`, from
+/// a password and a `Salt`, as `derive_key()` does with a buffer of the
+/// right length.
+///
+/// Errors are those of `derive_key()`.
+pub fn derive_key_for<K: DerivableKey>(
+    passwd: &[u8],
+    salt: &Salt,
+    opslimit: OpsLimit,
+    memlimit: MemLimit,
+) -> Result<K, Error> {
+    K::derive_with(|k| derive_key(k, passwd, salt, opslimit, memlimit).map(|_| ()))
+}
+
 /// `derive_key_interactive()` is a shortcut function for `derive_key()` with
 /// interactive limits (i.e. using `derive_key()` with `OPSLIMIT_INTERACTIVE`
 /// and `MEMLIMIT_INTERACTIVE`)
@@ -131,7 +197,7 @@ pub fn derive_key_interactive<'a>(
     key: &'a mut [u8],
     passwd: &[u8],
     salt: &Salt,
-) -> Result<&'a [u8], ()> {
+) -> Result<&'a [u8], Error> {
     derive_key(
         key,
         passwd,
@@ -148,7 +214,7 @@ pub fn derive_key_sensitive<'a>(
     key: &'a mut [u8],
     passwd: &[u8],
     salt: &Salt,
-) -> Result<&'a [u8], ()> {
+) -> Result<&'a [u8], Error> {
     derive_key(key, passwd, salt, OPSLIMIT_SENSITIVE, MEMLIMIT_SENSITIVE)
 }
 
@@ -164,13 +230,16 @@ pub fn derive_key_sensitive<'a>(
 /// `OPSLIMIT_INTERACTIVE` and `MEMLIMIT_INTERACTIVE` are safe baseline
 /// values to use for `opslimit` and `memlimit`.
 ///
-/// The function returns `Ok(hashed_password)` on success and `Err(())` if it didn't complete
-/// successfully
+/// The function returns `Ok(hashed_password)` on success. Like `derive_key()`, it returns
+/// `Err(Error::InvalidParameter)` for out-of-range limits and `Err(Error::OutOfMemory)` if it
+/// didn't complete successfully.
 pub fn pwhash(
     passwd: &[u8],
     OpsLimit(opslimit): OpsLimit,
     MemLimit(memlimit): MemLimit,
-) -> Result<HashedPassword, ()> {
+) -> Result<HashedPassword, Error> {
+    check_limits(opslimit, memlimit)?;
+    ::ensure_init();
     let mut hp = HashedPassword([0; HASHEDPASSWORDBYTES]);
     if unsafe {
         ffi::crypto_pwhash_scryptsalsa208sha256_str(
@@ -184,21 +253,21 @@ pub fn pwhash(
     {
         Ok(hp)
     } else {
-        Err(())
+        Err(Error::OutOfMemory)
     }
 }
 
 /// `pwhash_interactive()` is a shortcut function for `pwhash()` with
 /// interactive limits (i.e. using `pwhash()` with `OPSLIMIT_INTERACTIVE`
 /// and `MEMLIMIT_INTERACTIVE`)
-pub fn pwhash_interactive(passwd: &[u8]) -> Result<HashedPassword, ()> {
+pub fn pwhash_interactive(passwd: &[u8]) -> Result<HashedPassword, Error> {
     pwhash(passwd, OPSLIMIT_INTERACTIVE, MEMLIMIT_INTERACTIVE)
 }
 
 /// `pwhash_sensitive()` is a shortcut function for `pwhash()` with
 /// sensitive limits (i.e. using `pwhash()` with `OPSLIMIT_SENSITIVE`
 /// and `MEMLIMIT_SENSITIVE`)
-pub fn pwhash_sensitive(passwd: &[u8]) -> Result<HashedPassword, ()> {
+pub fn pwhash_sensitive(passwd: &[u8]) -> Result<HashedPassword, Error> {
     pwhash(passwd, OPSLIMIT_SENSITIVE, MEMLIMIT_SENSITIVE)
 }
 
@@ -216,6 +285,31 @@ pub fn pwhash_verify(hp: &HashedPassword, passwd: &[u8]) -> bool {
     }
 }
 
+/// `pwhash_str_needs_rehash()` checks whether the hashed password `hp` was
+/// created with exactly `opslimit` and `memlimit`, so that hashes made with
+/// older (usually weaker) parameters can be upgraded after a successful
+/// `pwhash_verify()`.
+///
+/// It returns `Ok(true)` if the parameters differ, `Ok(false)` if they match
+/// and `Err(Error::InvalidEncoding)` if `hp` is not a valid hashed password for this algorithm.
+pub fn pwhash_str_needs_rehash(
+    hp: &HashedPassword,
+    OpsLimit(opslimit): OpsLimit,
+    MemLimit(memlimit): MemLimit,
+) -> Result<bool, Error> {
+    match unsafe {
+        ffi::crypto_pwhash_scryptsalsa208sha256_str_needs_rehash(
+            hp.0.as_ptr() as *const _,
+            opslimit as c_ulonglong,
+            memlimit,
+        )
+    } {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(Error::InvalidEncoding),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -269,6 +363,30 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_pwhash_str_needs_rehash() {
+        let pwh = pwhash(
+            b"Correct Horse Battery Staple",
+            OPSLIMIT_INTERACTIVE,
+            MEMLIMIT_INTERACTIVE,
+        )
+        .unwrap();
+        assert_eq!(
+            pwhash_str_needs_rehash(&pwh, OPSLIMIT_INTERACTIVE, MEMLIMIT_INTERACTIVE),
+            Ok(false)
+        );
+        // the limits are mapped to the scrypt parameters before comparing
+        assert_eq!(
+            pwhash_str_needs_rehash(&pwh, OPSLIMIT_SENSITIVE, MEMLIMIT_SENSITIVE),
+            Ok(true)
+        );
+
+        let empty = HashedPassword([0; HASHEDPASSWORDBYTES]);
+        assert!(
+            pwhash_str_needs_rehash(&empty, OPSLIMIT_INTERACTIVE, MEMLIMIT_INTERACTIVE).is_err()
+        );
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn test_serialisation() {
@@ -282,4 +400,27 @@ mod test {
             round_trip(salt);
         }
     }
+
+    #[test]
+    fn test_out_of_range_limits() {
+        // the presets must pass the checks of `new()`
+        assert_eq!(OpsLimit::new(OPSLIMIT_SENSITIVE.0), Ok(OPSLIMIT_SENSITIVE));
+        assert_eq!(MemLimit::new(MEMLIMIT_SENSITIVE.0), Ok(MEMLIMIT_SENSITIVE));
+        assert!(OpsLimit::new(OPSLIMIT_MAX.0).is_ok());
+        assert!(OpsLimit::new(0).is_err());
+        assert!(MemLimit::new(0).is_err());
+
+        // and the other functions refuse what `new()` refuses
+        let tiny = MemLimit(MEMLIMIT_MIN.0 - 1);
+        let mut key = [0u8; 32];
+        assert_eq!(
+            derive_key(&mut key, b"passwd", &gen_salt(), OPSLIMIT_INTERACTIVE, tiny),
+            Err(Error::InvalidParameter)
+        );
+        assert_eq!(
+            pwhash(b"passwd", OpsLimit(0), MEMLIMIT_INTERACTIVE),
+            Err(Error::InvalidParameter)
+        );
+        assert_eq!(key, [0u8; 32]);
+    }
 }
@@ -4,6 +4,7 @@
 //! "Curve25519: new Diffie-Hellman speed records," Lecture Notes in Computer
 //! Science 3958 (2006), 207–228, <http://cr.yp.to/papers.html#curve25519>.
 use ffi;
+use Error;
 
 /// Number of bytes in a `GroupElement`.
 pub const GROUPELEMENTBYTES: usize = ffi::crypto_scalarmult_curve25519_BYTES as usize;
@@ -23,13 +24,14 @@ new_type! {
 
 /// `scalarmult()` multiplies a group element `p`
 /// by an integer `n`. It returns the resulting group element `Ok(q)`.
-/// If the the `GroupElement` is all zero, `scalarmult()` returns `Err(())` since
-/// the resulting `GroupElement` would be all zero, no matter the `Scalar`.
-pub fn scalarmult(n: &Scalar, p: &GroupElement) -> Result<GroupElement, ()> {
+/// If the the `GroupElement` is all zero, `scalarmult()` returns
+/// `Err(Error::InvalidParameter)` since the resulting `GroupElement` would be
+/// all zero, no matter the `Scalar`.
+pub fn scalarmult(n: &Scalar, p: &GroupElement) -> Result<GroupElement, Error> {
     let mut q = [0; GROUPELEMENTBYTES];
     unsafe {
         if ffi::crypto_scalarmult_curve25519(q.as_mut_ptr(), n.0.as_ptr(), p.0.as_ptr()) != 0 {
-            Err(())
+            Err(Error::InvalidParameter)
         } else {
             Ok(GroupElement(q))
         }
@@ -34,5 +34,11 @@
 //! This function is conjectured to be strong. For background see Bernstein,
 //! "Curve25519: new Diffie-Hellman speed records," Lecture Notes in Computer
 //! Science 3958 (2006), 207–228, <http://cr.yp.to/papers.html#curve25519>.
+//!
+//! # Alternate primitives
+//! [`ristretto255`] provides scalar multiplication together with point
+//! validation, addition and subtraction in the prime-order ristretto255
+//! group.
 pub use self::curve25519::*;
 pub mod curve25519;
+pub mod ristretto255;
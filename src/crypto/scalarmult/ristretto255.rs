@@ -0,0 +1,283 @@
+//! `crypto_scalarmult_ristretto255` and the `crypto_core_ristretto255` group
+//! operations.
+//!
+//! Ristretto255 is a prime-order group built on top of Curve25519. Unlike
+//! Curve25519 and Ed25519 points, every valid encoding represents a distinct
+//! group element and there is no cofactor to clear, which makes it suitable
+//! for protocols such as OPRFs, Privacy Pass style tokens and zero-knowledge
+//! proofs that need a proper prime-order group.
+//!
+//! Group elements received from other parties must be checked with
+//! [`is_valid_point()`](fn.is_valid_point.html) before use; the other
+//! functions return `Err(Error::InvalidEncoding)` when given an invalid
+//! encoding.
+//!
+//! This API was introduced in libsodium 1.0.18.
+//!
+//! # Example (blinding)
+//! ```
+//! use sodiumoxide::crypto::scalarmult::ristretto255;
+//!
+//! // the element to blind, e.g. the hash of some input
+//! let p = ristretto255::from_hash(&[0x42; ristretto255::HASHBYTES]);
+//!
+//! let r = ristretto255::gen_scalar();
+//! let blinded = ristretto255::scalarmult(&r, &p).unwrap();
+//!
+//! let r_inv = ristretto255::scalar_invert(&r).unwrap();
+//! let unblinded = ristretto255::scalarmult(&r_inv, &blinded).unwrap();
+//! assert_eq!(unblinded, p);
+//! ```
+use ffi;
+use Error;
+
+/// Number of bytes in a `GroupElement`.
+pub const GROUPELEMENTBYTES: usize = ffi::crypto_scalarmult_ristretto255_BYTES as usize;
+
+/// Number of bytes in a `Scalar`.
+pub const SCALARBYTES: usize = ffi::crypto_scalarmult_ristretto255_SCALARBYTES as usize;
+
+/// Number of bytes in the input of `from_hash()`.
+pub const HASHBYTES: usize = ffi::crypto_core_ristretto255_HASHBYTES as usize;
+
+/// Number of bytes in the input of `scalar_reduce()`.
+pub const NONREDUCEDSCALARBYTES: usize =
+    ffi::crypto_core_ristretto255_NONREDUCEDSCALARBYTES as usize;
+
+new_type! {
+    /// `Scalar` value (integer in little-endian byte representation)
+    secret Scalar(SCALARBYTES);
+}
+
+new_type! {
+    /// `GroupElement`, the canonical encoding of a ristretto255 point
+    public GroupElement(GROUPELEMENTBYTES);
+}
+
+/// `is_valid_point()` returns `true` if `p` is the canonical encoding of a
+/// ristretto255 group element, and `false` otherwise.
+pub fn is_valid_point(p: &GroupElement) -> bool {
+    unsafe { ffi::crypto_core_ristretto255_is_valid_point(p.0.as_ptr()) == 1 }
+}
+
+/// `scalarmult()` multiplies a group element `p` by an integer `n`. It
+/// returns the resulting group element `Ok(q)`.
+///
+/// `scalarmult()` returns `Err(Error::InvalidEncoding)` if `p` is not a valid
+/// encoding, or `Err(Error::InvalidParameter)` if the result is the identity
+/// element.
+pub fn scalarmult(n: &Scalar, p: &GroupElement) -> Result<GroupElement, Error> {
+    if !is_valid_point(p) {
+        return Err(Error::InvalidEncoding);
+    }
+    let mut q = [0; GROUPELEMENTBYTES];
+    unsafe {
+        if ffi::crypto_scalarmult_ristretto255(q.as_mut_ptr(), n.0.as_ptr(), p.0.as_ptr()) != 0 {
+            Err(Error::InvalidParameter)
+        } else {
+            Ok(GroupElement(q))
+        }
+    }
+}
+
+/// `scalarmult_base()` computes the scalar product of the standard generator
+/// and an integer `n`. It returns the resulting group element `Ok(q)`, or
+/// `Err(Error::InvalidParameter)` if `n` is zero modulo the group order.
+pub fn scalarmult_base(n: &Scalar) -> Result<GroupElement, Error> {
+    let mut q = [0; GROUPELEMENTBYTES];
+    unsafe {
+        if ffi::crypto_scalarmult_ristretto255_base(q.as_mut_ptr(), n.0.as_ptr()) != 0 {
+            Err(Error::InvalidParameter)
+        } else {
+            Ok(GroupElement(q))
+        }
+    }
+}
+
+/// `add()` computes the sum of the group elements `p` and `q`. It returns
+/// `Err(Error::InvalidEncoding)` if either of them is not a valid encoding.
+pub fn add(p: &GroupElement, q: &GroupElement) -> Result<GroupElement, Error> {
+    let mut r = [0; GROUPELEMENTBYTES];
+    unsafe {
+        if ffi::crypto_core_ristretto255_add(r.as_mut_ptr(), p.0.as_ptr(), q.0.as_ptr()) != 0 {
+            Err(Error::InvalidEncoding)
+        } else {
+            Ok(GroupElement(r))
+        }
+    }
+}
+
+/// `sub()` computes the difference `p - q` of the group elements `p` and
+/// `q`. It returns `Err(Error::InvalidEncoding)` if either of them is not a valid encoding.
+pub fn sub(p: &GroupElement, q: &GroupElement) -> Result<GroupElement, Error> {
+    let mut r = [0; GROUPELEMENTBYTES];
+    unsafe {
+        if ffi::crypto_core_ristretto255_sub(r.as_mut_ptr(), p.0.as_ptr(), q.0.as_ptr()) != 0 {
+            Err(Error::InvalidEncoding)
+        } else {
+            Ok(GroupElement(r))
+        }
+    }
+}
+
+/// `from_hash()` maps a `HASHBYTES` byte hash output `h` to a group element,
+/// as specified in RFC 9496. The discrete logarithm of the result is unknown.
+pub fn from_hash(h: &[u8; HASHBYTES]) -> GroupElement {
+    let mut p = [0; GROUPELEMENTBYTES];
+    unsafe {
+        ffi::crypto_core_ristretto255_from_hash(p.as_mut_ptr(), h.as_ptr());
+    }
+    GroupElement(p)
+}
+
+/// `gen_scalar()` randomly generates a non-zero scalar reduced modulo the
+/// group order.
+///
+/// THREAD SAFETY: `gen_scalar()` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+pub fn gen_scalar() -> Scalar {
+    ::ensure_init();
+    let mut n = [0; SCALARBYTES];
+    unsafe {
+        ffi::crypto_core_ristretto255_scalar_random(n.as_mut_ptr());
+    }
+    Scalar(n)
+}
+
+/// `scalar_invert()` computes the multiplicative inverse of `n` modulo the
+/// group order. It returns `Err(Error::InvalidParameter)` if `n` is zero.
+pub fn scalar_invert(n: &Scalar) -> Result<Scalar, Error> {
+    let mut r = [0; SCALARBYTES];
+    unsafe {
+        if ffi::crypto_core_ristretto255_scalar_invert(r.as_mut_ptr(), n.0.as_ptr()) != 0 {
+            Err(Error::InvalidParameter)
+        } else {
+            Ok(Scalar(r))
+        }
+    }
+}
+
+/// `scalar_reduce()` reduces the `NONREDUCEDSCALARBYTES` byte integer `s`,
+/// e.g. the output of a hash function, modulo the group order.
+pub fn scalar_reduce(s: &[u8; NONREDUCEDSCALARBYTES]) -> Scalar {
+    let mut r = [0; SCALARBYTES];
+    unsafe {
+        ffi::crypto_core_ristretto255_scalar_reduce(r.as_mut_ptr(), s.as_ptr());
+    }
+    Scalar(r)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex;
+
+    fn small_scalar(i: u8) -> Scalar {
+        let mut n = [0; SCALARBYTES];
+        n[0] = i;
+        Scalar(n)
+    }
+
+    #[test]
+    fn test_vector_base_multiples() {
+        // https://www.rfc-editor.org/rfc/rfc9496#appendix-A.1
+        let expected = [
+            "e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d76",
+            "6a493210f7499cd17fecb510ae0cea23a110e8d5b901f8acadd3095c73a3b919",
+            "94741f5d5d52755ece4f23f044ee27d5d1ea1e2bd196b462166b16152a9d0259",
+        ];
+        for (i, e) in expected.iter().enumerate() {
+            let p = scalarmult_base(&small_scalar(i as u8 + 1)).unwrap();
+            assert_eq!(hex::encode(p), *e);
+            assert!(is_valid_point(&p));
+        }
+        assert!(scalarmult_base(&small_scalar(0)).is_err());
+    }
+
+    #[test]
+    fn test_vector_from_hash() {
+        // https://www.rfc-editor.org/rfc/rfc9496#appendix-A.3
+        let mut h = [0; HASHBYTES];
+        h.copy_from_slice(
+            &hex::decode(
+                "5d1be09e3d0c82fc538112490e35701979d99e06ca3e2b5b54bffe8b4dc772c1\
+                 4d98b696a1bbfb5ca32c436cc61c16563790306c79eaca7705668b47dffe5bb6",
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            hex::encode(from_hash(&h)),
+            "3066f82a1a747d45120d1740f14358531a8f04bbffe6a819f86dfe50f44a0a46"
+        );
+    }
+
+    #[test]
+    fn test_add_sub() {
+        let b = scalarmult_base(&small_scalar(1)).unwrap();
+        let b2 = scalarmult_base(&small_scalar(2)).unwrap();
+        let b3 = scalarmult_base(&small_scalar(3)).unwrap();
+        assert_eq!(add(&b, &b2), Ok(b3));
+        assert_eq!(sub(&b3, &b2), Ok(b));
+        assert_eq!(scalarmult(&small_scalar(3), &b), Ok(b3));
+    }
+
+    #[test]
+    fn test_invalid_point() {
+        // non-canonical encoding of an element: the field element p
+        let mut bad = [0xff; GROUPELEMENTBYTES];
+        bad[0] = 0xed;
+        bad[31] = 0x7f;
+        let bad = GroupElement(bad);
+        let b = scalarmult_base(&small_scalar(1)).unwrap();
+        assert!(!is_valid_point(&bad));
+        assert!(scalarmult(&small_scalar(1), &bad).is_err());
+        assert!(add(&b, &bad).is_err());
+        assert!(sub(&bad, &b).is_err());
+    }
+
+    #[test]
+    fn test_scalar_invert() {
+        for _ in 0..64 {
+            let p = scalarmult_base(&gen_scalar()).unwrap();
+            let r = gen_scalar();
+            let q = scalarmult(&r, &p).unwrap();
+            let r_inv = scalar_invert(&r).unwrap();
+            assert_eq!(scalarmult(&r_inv, &q), Ok(p));
+        }
+        assert!(scalar_invert(&small_scalar(0)).is_err());
+    }
+
+    #[test]
+    fn test_scalar_reduce() {
+        // the group order l reduces to zero, l + 1 to one
+        let mut s = [0; NONREDUCEDSCALARBYTES];
+        s[..SCALARBYTES].copy_from_slice(
+            &hex::decode("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010")
+                .unwrap(),
+        );
+        assert_eq!(scalar_reduce(&s), small_scalar(0));
+        s[0] += 1;
+        assert_eq!(scalar_reduce(&s), small_scalar(1));
+    }
+}
+
+#[cfg(feature = "benchmarks")]
+#[cfg(test)]
+mod bench {
+    extern crate test;
+    use super::*;
+
+    #[bench]
+    fn bench_scalarmult(b: &mut test::Bencher) {
+        let p = scalarmult_base(&gen_scalar()).unwrap();
+        let n = gen_scalar();
+        b.iter(|| scalarmult(&n, &p));
+    }
+
+    #[bench]
+    fn bench_scalarmult_base(b: &mut test::Bencher) {
+        let n = gen_scalar();
+        b.iter(|| scalarmult_base(&n));
+    }
+}
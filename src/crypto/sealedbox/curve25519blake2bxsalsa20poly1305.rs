@@ -5,6 +5,7 @@ use ffi;
 use prelude::*;
 
 use libc::c_ulonglong;
+use Error;
 
 use super::super::box_::curve25519xsalsa20poly1305 as box_;
 
@@ -19,6 +20,7 @@ pub const SEALBYTES: usize = ffi::crypto_box_SEALBYTES as usize;
 /// key to the ciphertext. The secret key is overwritten and is not accessible
 /// after this function returns.
 pub fn seal(m: &[u8], pk: &box_::PublicKey) -> Vec<u8> {
+    ::ensure_init();
     let mut c = vec![0u8; m.len() + SEALBYTES];
     unsafe {
         ffi::crypto_box_seal(
@@ -41,10 +43,11 @@ pub fn seal(m: &[u8], pk: &box_::PublicKey) -> Vec<u8> {
 /// This function doesn't require passing the public key of the sender, as the
 /// ciphertext already includes this information.
 ///
-/// If decryption fails it returns `Err(())`.
-pub fn open(c: &[u8], pk: &box_::PublicKey, sk: &box_::SecretKey) -> Result<Vec<u8>, ()> {
+/// If decryption fails it returns `Err(Error::VerificationFailed)`, or
+/// `Err(Error::InvalidLength)` if `c` is shorter than `SEALBYTES`.
+pub fn open(c: &[u8], pk: &box_::PublicKey, sk: &box_::SecretKey) -> Result<Vec<u8>, Error> {
     if c.len() < SEALBYTES {
-        return Err(());
+        return Err(Error::InvalidLength);
     }
     let mut m = vec![0u8; c.len() - SEALBYTES];
     let ret = unsafe {
@@ -59,7 +62,7 @@ pub fn open(c: &[u8], pk: &box_::PublicKey, sk: &box_::SecretKey) -> Result<Vec<
     if ret == 0 {
         Ok(m)
     } else {
-        Err(())
+        Err(Error::VerificationFailed)
     }
 }
 
@@ -89,9 +92,36 @@ mod test {
             let mut c = seal(&m, &pk);
             for j in 0..c.len() {
                 c[j] ^= 0x20;
-                assert!(Err(()) == open(&c, &pk, &sk));
+                assert!(Err(Error::VerificationFailed) == open(&c, &pk, &sk));
                 c[j] ^= 0x20;
             }
         }
     }
+
+    #[test]
+    fn test_seal_ephemeral() {
+        let (pk, sk) = box_::gen_keypair();
+        let c1 = seal(b"some data", &pk);
+        let c2 = seal(b"some data", &pk);
+        // a fresh ephemeral key pair is used for every message
+        assert!(c1[..box_::PUBLICKEYBYTES] != c2[..box_::PUBLICKEYBYTES]);
+        assert!(c1 != c2);
+        assert_eq!(open(&c1, &pk, &sk), open(&c2, &pk, &sk));
+    }
+
+    #[test]
+    fn test_open_wrong_key() {
+        let (pk, _) = box_::gen_keypair();
+        let (other_pk, other_sk) = box_::gen_keypair();
+        let c = seal(b"some data", &pk);
+        assert!(open(&c, &other_pk, &other_sk).is_err());
+    }
+
+    #[test]
+    fn test_open_short() {
+        let (pk, sk) = box_::gen_keypair();
+        for i in 0..SEALBYTES {
+            assert!(open(&vec![0u8; i], &pk, &sk).is_err());
+        }
+    }
 }
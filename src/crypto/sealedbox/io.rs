@@ -0,0 +1,152 @@
+//! Sealed boxes for payloads of any size on top of `std::io`.
+//!
+//! [`seal()`](super::seal) needs the whole message in memory. [`seal_stream()`]
+//! instead generates a random `secretstream` key, seals it to the recipient
+//! and encrypts the body with a
+//! [`secretstream::EncryptingWriter`](../../secretstream/io/struct.EncryptingWriter.html),
+//! so that files bigger than RAM can be encrypted anonymously. The format is
+//!
+//! ```text
+//! sealedbox::seal(key, recipient_pk) || secretstream(body, key)
+//! ```
+//!
+//! where `key` is a fresh `secretstream` key and the second part is a
+//! secretstream header followed by chunks of
+//! [`secretstream::io::CHUNKBYTES`](../../secretstream/io/constant.CHUNKBYTES.html)
+//! bytes. The output is [`STREAMBYTES`] bytes longer than the body, plus
+//! `secretstream::ABYTES` bytes per chunk.
+//!
+//! This module is only available with the `std` and `secretstream` features.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::box_;
+//! use sodiumoxide::crypto::sealedbox;
+//!
+//! let (pk, sk) = box_::gen_keypair();
+//!
+//! let mut c = Vec::new();
+//! sealedbox::seal_stream(&pk, &b"some data"[..], &mut c).unwrap();
+//!
+//! let mut m = Vec::new();
+//! sealedbox::open_stream(&pk, &sk, &c[..], &mut m).unwrap();
+//! assert_eq!(m, b"some data");
+//! ```
+
+use super::curve25519blake2bxsalsa20poly1305::{open, seal, SEALBYTES};
+use crypto::box_::curve25519xsalsa20poly1305::{PublicKey, SecretKey};
+use crypto::secretstream::io::{DecryptingReader, EncryptingWriter};
+use crypto::secretstream::{gen_key, Key, HEADERBYTES, KEYBYTES};
+use std::io::{self, Read, Write};
+use utils::memzero;
+
+/// Number of bytes in the sealed key at the start of a stream.
+pub const SEALEDKEYBYTES: usize = SEALBYTES + KEYBYTES;
+
+/// Number of bytes a sealed stream adds to the body, not counting the
+/// per-chunk overhead.
+pub const STREAMBYTES: usize = SEALEDKEYBYTES + HEADERBYTES;
+
+/// `seal_stream()` reads `reader` until EOF and writes it to `writer`,
+/// encrypted so that only the owner of the secret key matching `recipient_pk`
+/// can decrypt it. It returns the number of bytes read from `reader`.
+pub fn seal_stream<R: Read, W: Write>(
+    recipient_pk: &PublicKey,
+    mut reader: R,
+    mut writer: W,
+) -> io::Result<u64> {
+    let key = gen_key();
+    writer.write_all(&seal(key.as_ref(), recipient_pk))?;
+    let mut encrypted = EncryptingWriter::new(writer, &key)?;
+    let n = io::copy(&mut reader, &mut encrypted)?;
+    encrypted.finish()?;
+    Ok(n)
+}
+
+/// `open_stream()` decrypts a stream produced by `seal_stream()` from
+/// `reader`, using the recipient's key pair `(pk, sk)`, and writes the body
+/// to `writer`. It returns the number of bytes written to `writer`.
+///
+/// It fails with an error of kind `io::ErrorKind::InvalidData` if the stream
+/// was not sealed for `pk`, was modified, or was truncated.
+///
+/// WARNING: the body is written out chunk by chunk, each one only after it
+/// has been authenticated. If `open_stream()` fails, `writer` may have
+/// received a prefix of the body, which should be discarded.
+pub fn open_stream<R: Read, W: Write>(
+    pk: &PublicKey,
+    sk: &SecretKey,
+    mut reader: R,
+    mut writer: W,
+) -> io::Result<u64> {
+    let mut sealed_key = [0u8; SEALEDKEYBYTES];
+    reader.read_exact(&mut sealed_key)?;
+    let mut key_bytes = open(&sealed_key, pk, sk)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid sealed key"))?;
+    let key = Key::from_slice(&key_bytes);
+    memzero(&mut key_bytes);
+    let key = key.expect("sealed key has KEYBYTES bytes");
+    let mut decrypted = DecryptingReader::new(reader, &key)?;
+    io::copy(&mut decrypted, &mut writer)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crypto::box_::curve25519xsalsa20poly1305::gen_keypair;
+    use crypto::secretstream::io::CHUNKBYTES;
+    use crypto::secretstream::ABYTES;
+    use randombytes::randombytes;
+
+    #[test]
+    fn test_seal_open_stream() {
+        let (pk, sk) = gen_keypair();
+        for &size in [0, 1, CHUNKBYTES, CHUNKBYTES + 1, 3 * CHUNKBYTES + 7].iter() {
+            let m = randombytes(size);
+            let mut c = Vec::new();
+            assert_eq!(seal_stream(&pk, &m[..], &mut c).unwrap(), size as u64);
+            let chunks = if size == 0 {
+                1
+            } else {
+                (size - 1) / CHUNKBYTES + 1
+            };
+            assert_eq!(c.len(), STREAMBYTES + size + chunks * ABYTES);
+
+            let mut m2 = Vec::new();
+            assert_eq!(open_stream(&pk, &sk, &c[..], &mut m2).unwrap(), size as u64);
+            assert_eq!(m2, m);
+        }
+    }
+
+    #[test]
+    fn test_open_stream_wrong_key() {
+        let (pk, _) = gen_keypair();
+        let (pk2, sk2) = gen_keypair();
+        let mut c = Vec::new();
+        seal_stream(&pk, &b"some data"[..], &mut c).unwrap();
+        let err = open_stream(&pk2, &sk2, &c[..], io::sink()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_open_stream_tamper() {
+        let (pk, sk) = gen_keypair();
+        let mut c = Vec::new();
+        seal_stream(&pk, &randombytes(100)[..], &mut c).unwrap();
+        for i in 0..c.len() {
+            c[i] ^= 0x20;
+            assert!(open_stream(&pk, &sk, &c[..], io::sink()).is_err());
+            c[i] ^= 0x20;
+        }
+    }
+
+    #[test]
+    fn test_open_stream_truncated() {
+        let (pk, sk) = gen_keypair();
+        let mut c = Vec::new();
+        seal_stream(&pk, &randombytes(100)[..], &mut c).unwrap();
+        for len in 0..c.len() {
+            assert!(open_stream(&pk, &sk, &c[..len], io::sink()).is_err());
+        }
+    }
+}
@@ -25,6 +25,37 @@
 //!                     nonce=blake2b(ephemeral_pk, recipient_pk))
 //! ```
 //!
+//! # Alternate primitives
+//! [`curve25519blake2bxchacha20poly1305`] is the same construction on top of
+//! [`box_::curve25519xchacha20poly1305`](crate::crypto::box_::curve25519xchacha20poly1305).
+//!
+//! # Large payloads
+//! With the `secretstream` feature, [`seal_stream()`](io/fn.seal_stream.html)
+//! and [`open_stream()`](io/fn.open_stream.html) encrypt readers and writers
+//! of any size, by sealing a `secretstream` key and streaming the body with
+//! it.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::box_;
+//! use sodiumoxide::crypto::sealedbox;
+//!
+//! // the recipient publishes pk
+//! let (pk, sk) = box_::gen_keypair();
+//!
+//! // anybody can encrypt to pk, no sender key pair or nonce is needed
+//! let c = sealedbox::seal(b"some data", &pk);
+//! assert_eq!(c.len(), b"some data".len() + sealedbox::SEALBYTES);
+//!
+//! let m = sealedbox::open(&c, &pk, &sk).unwrap();
+//! assert_eq!(m, b"some data");
+//! ```
 
 pub use self::curve25519blake2bxsalsa20poly1305::*;
+pub mod curve25519blake2bxchacha20poly1305;
 pub mod curve25519blake2bxsalsa20poly1305;
+
+#[cfg(all(feature = "std", feature = "secretstream"))]
+pub use self::io::{open_stream, seal_stream};
+#[cfg(all(feature = "std", feature = "secretstream"))]
+pub mod io;
@@ -22,6 +22,10 @@
 //! This function is conjectured to meet the standard notions of privacy and
 //! authenticity.
 //!
+//! # Alternate primitives
+//! [`xchacha20poly1305`] provides the same API on top of `XChaCha20`
+//! instead of `XSalsa20`.
+//!
 //! # Example
 //! ```
 //! use sodiumoxide::crypto::secretbox;
@@ -32,6 +36,38 @@
 //! let their_plaintext = secretbox::open(&ciphertext, &nonce, &key).unwrap();
 //! assert!(plaintext == &their_plaintext[..]);
 //! ```
+//!
+//! # Example (detached interface)
+//! `seal_detached()` encrypts in place and returns the authentication tag
+//! separately, so that no second buffer is allocated and the tag can be
+//! stored in a separate header field.
+//! ```
+//! use sodiumoxide::crypto::secretbox;
+//! let key = secretbox::gen_key();
+//! let nonce = secretbox::gen_nonce();
+//! let mut buf = b"some data".to_vec();
+//! let tag = secretbox::seal_detached(&mut buf, &nonce, &key);
+//! assert!(&buf[..] != b"some data");
+//! secretbox::open_detached(&mut buf, &tag, &nonce, &key).unwrap();
+//! assert_eq!(buf, b"some data");
+//! ```
+//!
+//! # Example (nonce prefix)
+//! `seal_with_nonce_prefix()` generates a random nonce and prepends it to the
+//! ciphertext, and `open_with_nonce_prefix()` splits it off again, so the
+//! nonce does not have to be stored or sent separately.
+//! ```
+//! use sodiumoxide::crypto::secretbox;
+//! let key = secretbox::gen_key();
+//! let ciphertext = secretbox::seal_with_nonce_prefix(b"some data", &key);
+//! assert_eq!(ciphertext.len(), secretbox::NONCEBYTES + secretbox::MACBYTES + 9);
+//! let plaintext = secretbox::open_with_nonce_prefix(&ciphertext, &key).unwrap();
+//! assert_eq!(plaintext, b"some data");
+//! ```
+
+#[macro_use]
+mod secretbox_macros;
 
 pub use self::xsalsa20poly1305::*;
+pub mod xchacha20poly1305;
 pub mod xsalsa20poly1305;
@@ -0,0 +1,653 @@
+macro_rules! secretbox_module (($seal_name:ident,
+                                $open_name:ident,
+                                $seal_detached_name:ident,
+                                $open_detached_name:ident,
+                                $keybytes:expr,
+                                $noncebytes:expr,
+                                $macbytes:expr) => (
+
+use crypto::nonce::gen_random_nonce;
+#[cfg(not(feature = "std"))] use prelude::*;
+use randombytes::randombytes_into;
+use Error;
+
+/// Number of bytes in `Key`.
+pub const KEYBYTES: usize = $keybytes;
+
+/// Number of bytes in a `Nonce`.
+pub const NONCEBYTES: usize = $noncebytes;
+
+new_type! {
+    /// `Key` for symmetric authenticated encryption
+    ///
+    /// When a `Key` goes out of scope its contents
+    /// will be zeroed out
+    secret Key(KEYBYTES);
+}
+
+new_type! {
+    /// Authentication `Tag` for the detached encryption mode
+    ///
+    /// In the combined mode, the tag occupies the first MACBYTES bytes of the ciphertext.
+    public Tag(MACBYTES);
+}
+
+new_type! {
+    /// `Nonce` for symmetric authenticated encryption
+    nonce Nonce(NONCEBYTES);
+}
+
+/// Number of bytes in the authenticator tag of an encrypted message
+/// i.e. the number of bytes by which the ciphertext is larger than the
+/// plaintext.
+pub const MACBYTES: usize = $macbytes;
+
+/// `gen_key()` randomly generates a secret key
+///
+/// THREAD SAFETY: `gen_key()` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+pub fn gen_key() -> Key {
+    let mut key = [0; KEYBYTES];
+    randombytes_into(&mut key);
+    Key(key)
+}
+
+/// `gen_nonce()` randomly generates a nonce
+///
+/// THREAD SAFETY: `gen_key()` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+pub fn gen_nonce() -> Nonce {
+    gen_random_nonce()
+}
+
+/// `seal()` encrypts and authenticates a message `m` using a secret key `k` and a
+/// nonce `n`.  It returns a ciphertext `c`.
+pub fn seal(m: &[u8], n: &Nonce, k: &Key) -> Vec<u8> {
+    let clen = m.len() + MACBYTES;
+    let mut c = Vec::with_capacity(clen);
+    unsafe {
+        $seal_name(
+            c.as_mut_ptr(),
+            m.as_ptr(),
+            m.len() as u64,
+            n.0.as_ptr(),
+            k.0.as_ptr(),
+        );
+        c.set_len(clen);
+    }
+    c
+}
+
+/// `seal_into()` encrypts and authenticates a message `m` using a secret key `k` and a nonce
+/// `n`, and writes the same ciphertext as `seal()` would return to the start of `out`. It
+/// returns the number of bytes written, i.e. `m.len() + MACBYTES`, or
+/// `Err(Error::InvalidLength)` if `out` is shorter than that.
+pub fn seal_into(out: &mut [u8], m: &[u8], n: &Nonce, k: &Key) -> Result<usize, Error> {
+    let clen = m.len() + MACBYTES;
+    if out.len() < clen {
+        return Err(Error::InvalidLength);
+    }
+    unsafe {
+        $seal_name(
+            out.as_mut_ptr(),
+            m.as_ptr(),
+            m.len() as u64,
+            n.0.as_ptr(),
+            k.0.as_ptr(),
+        );
+    }
+    Ok(clen)
+}
+
+/// `seal_detached()` encrypts and authenticates a message `m` using a secret key `k` and a nonce
+/// `n`.  `m` is encrypted in place, so after this function returns it will contain the ciphertext.
+/// The detached authentication tag is returned by value.
+pub fn seal_detached(m: &mut [u8], n: &Nonce, k: &Key) -> Tag {
+    let mut tag = [0; MACBYTES];
+    unsafe {
+        $seal_detached_name(
+            m.as_mut_ptr(),
+            tag.as_mut_ptr(),
+            m.as_ptr(),
+            m.len() as u64,
+            n.0.as_ptr(),
+            k.0.as_ptr(),
+        );
+    };
+    Tag(tag)
+}
+
+/// `open()` verifies and decrypts a ciphertext `c` using a secret key `k` and a nonce `n`.
+/// It returns a plaintext `Ok(m)`.
+/// If the ciphertext fails verification, `open()` returns
+/// `Err(Error::VerificationFailed)`, or `Err(Error::InvalidLength)` if it is
+/// too short to hold an authentication tag.
+pub fn open(c: &[u8], n: &Nonce, k: &Key) -> Result<Vec<u8>, Error> {
+    if c.len() < MACBYTES {
+        return Err(Error::InvalidLength);
+    }
+    let mlen = c.len() - MACBYTES;
+    let mut m = Vec::with_capacity(mlen);
+    unsafe {
+        let ret = $open_name(
+            m.as_mut_ptr(),
+            c.as_ptr(),
+            c.len() as u64,
+            n.0.as_ptr(),
+            k.0.as_ptr(),
+        );
+        if ret == 0 {
+            m.set_len(mlen);
+            Ok(m)
+        } else {
+            Err(Error::VerificationFailed)
+        }
+    }
+}
+
+/// `open_with_any()` verifies and decrypts a ciphertext `c` using a nonce `n`
+/// and each of the secret keys in `keys`, e.g. the current and the previous
+/// key during a key rotation. It returns `Ok((i, m))` where `keys[i]` is the
+/// first key under which `c` verifies and `m` is the plaintext.
+///
+/// Like `auth::verify_with_any()`, every key is tried, in order, whichever one
+/// matches, so that the time taken does not reveal which key was used. It
+/// does still reveal whether some key matched, since only then is the
+/// plaintext decrypted. If no key matches, `open_with_any()` returns
+/// `Err(Error::VerificationFailed)`, or `Err(Error::InvalidLength)` if `c` is
+/// too short to hold an authentication tag.
+pub fn open_with_any(c: &[u8], n: &Nonce, keys: &[Key]) -> Result<(usize, Vec<u8>), Error> {
+    if c.len() < MACBYTES {
+        return Err(Error::InvalidLength);
+    }
+    let mut found = None;
+    for (i, k) in keys.iter().enumerate() {
+        if let Ok(m) = open(c, n, k) {
+            if found.is_none() {
+                found = Some((i, m));
+            }
+        }
+    }
+    found.ok_or(Error::VerificationFailed)
+}
+
+/// `open_into()` verifies and decrypts a ciphertext `c` using a secret key `k` and a nonce `n`,
+/// and writes the plaintext to the start of `out`. It returns the number of bytes written,
+/// i.e. `c.len() - MACBYTES`.
+///
+/// If the ciphertext fails verification, `open_into()` returns
+/// `Err(Error::VerificationFailed)`. If `c` is too short to hold an authentication tag or
+/// `out` is too short to hold the plaintext, it returns `Err(Error::InvalidLength)`.
+pub fn open_into(out: &mut [u8], c: &[u8], n: &Nonce, k: &Key) -> Result<usize, Error> {
+    if c.len() < MACBYTES || out.len() < c.len() - MACBYTES {
+        return Err(Error::InvalidLength);
+    }
+    let ret = unsafe {
+        $open_name(
+            out.as_mut_ptr(),
+            c.as_ptr(),
+            c.len() as u64,
+            n.0.as_ptr(),
+            k.0.as_ptr(),
+        )
+    };
+    if ret == 0 {
+        Ok(c.len() - MACBYTES)
+    } else {
+        Err(Error::VerificationFailed)
+    }
+}
+
+/// `open_detached()` verifies and decrypts a ciphertext `c` and an authentication tag `tag`,
+/// using a secret key `k` and a nonce `n`. `c` is decrypted in place, so if this function is
+/// successful it will contain the plaintext. If the ciphertext fails verification,
+/// `open_detached()` returns `Err(Error::VerificationFailed)`, and the ciphertext is not
+/// modified.
+pub fn open_detached(c: &mut [u8], tag: &Tag, n: &Nonce, k: &Key) -> Result<(), Error> {
+    let ret = unsafe {
+        $open_detached_name(
+            c.as_mut_ptr(),
+            c.as_ptr(),
+            tag.0.as_ptr(),
+            c.len() as u64,
+            n.0.as_ptr(),
+            k.0.as_ptr(),
+        )
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(Error::VerificationFailed)
+    }
+}
+
+/// `seal_in_place()` encrypts and authenticates the message in `buf` using a secret key `k`
+/// and a nonce `n`, and replaces it with the same ciphertext as `seal()` would return.
+///
+/// The authentication tag is inserted at the front of `buf`, so no allocation is needed if it
+/// has `MACBYTES` bytes of spare capacity.
+pub fn seal_in_place(buf: &mut Vec<u8>, n: &Nonce, k: &Key) {
+    buf.splice(0..0, [0; MACBYTES].iter().cloned());
+    let (tag, m) = buf.split_at_mut(MACBYTES);
+    tag.copy_from_slice(&seal_detached(m, n, k).0);
+}
+
+/// `open_in_place()` verifies and decrypts a ciphertext `c`, as returned by `seal()`, using a
+/// secret key `k` and a nonce `n`. The plaintext is written over `c`, and the part of `c`
+/// holding it is returned as `Ok(m)`.
+///
+/// If the ciphertext fails verification, `open_in_place()` returns
+/// `Err(Error::VerificationFailed)` and `c` is not modified, or `Err(Error::InvalidLength)`
+/// if it is too short to hold an authentication tag.
+pub fn open_in_place<'a>(c: &'a mut [u8], n: &Nonce, k: &Key) -> Result<&'a mut [u8], Error> {
+    if c.len() < MACBYTES {
+        return Err(Error::InvalidLength);
+    }
+    let (tag, m) = c.split_at_mut(MACBYTES);
+    let tag = Tag::from_slice(tag).unwrap();
+    open_detached(m, &tag, n, k)?;
+    Ok(m)
+}
+
+/// `seal_with_nonce_prefix()` encrypts and authenticates a message `m` using a secret key `k`
+/// and a freshly generated random nonce. The nonce is prepended to the ciphertext, so the
+/// result is `NONCEBYTES + MACBYTES + m.len()` bytes long and can be passed to
+/// `open_with_nonce_prefix()` as is.
+pub fn seal_with_nonce_prefix(m: &[u8], k: &Key) -> Vec<u8> {
+    let n = gen_nonce();
+    let sealed = seal(m, &n, k);
+    let mut c = Vec::with_capacity(NONCEBYTES + sealed.len());
+    c.extend_from_slice(&n.0);
+    c.extend_from_slice(&sealed);
+    c
+}
+
+/// `open_with_nonce_prefix()` verifies and decrypts a ciphertext `c` produced by
+/// `seal_with_nonce_prefix()`, using a secret key `k`. It returns a plaintext `Ok(m)`.
+///
+/// It returns `Err(Error::InvalidLength)` if `c` is too short to hold a nonce and an
+/// authentication tag, and fails like `open()` otherwise.
+pub fn open_with_nonce_prefix(c: &[u8], k: &Key) -> Result<Vec<u8>, Error> {
+    if c.len() < NONCEBYTES + MACBYTES {
+        return Err(Error::InvalidLength);
+    }
+    let (n, c) = c.split_at(NONCEBYTES);
+    let n = Nonce::from_slice(n).ok_or(Error::InvalidLength)?;
+    open(c, &n, k)
+}
+
+#[cfg(test)]
+mod test_m {
+    use super::*;
+
+    #[test]
+    fn test_seal_open_with_nonce_prefix() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let k = gen_key();
+            let m = randombytes(i);
+            let c = seal_with_nonce_prefix(&m, &k);
+            assert_eq!(c.len(), NONCEBYTES + MACBYTES + m.len());
+            assert_eq!(open_with_nonce_prefix(&c, &k), Ok(m.clone()));
+            let n = Nonce::from_slice(&c[..NONCEBYTES]).unwrap();
+            assert_eq!(open(&c[NONCEBYTES..], &n, &k), Ok(m));
+        }
+    }
+
+    #[test]
+    fn test_open_with_nonce_prefix_invalid() {
+        let k = gen_key();
+        let mut c = seal_with_nonce_prefix(b"some data", &k);
+        assert!(c[..NONCEBYTES] != seal_with_nonce_prefix(b"some data", &k)[..NONCEBYTES]);
+        assert_eq!(
+            open_with_nonce_prefix(&c[..NONCEBYTES + MACBYTES - 1], &k),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(
+            open_with_nonce_prefix(&c, &gen_key()),
+            Err(Error::VerificationFailed)
+        );
+        for i in 0..c.len() {
+            c[i] ^= 0x01;
+            assert_eq!(
+                open_with_nonce_prefix(&c, &k),
+                Err(Error::VerificationFailed)
+            );
+            c[i] ^= 0x01;
+        }
+    }
+
+    #[test]
+    fn test_seal_open() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let k = gen_key();
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let c = seal(&m, &n, &k);
+            let opened = open(&c, &n, &k);
+            assert_eq!(Ok(m), opened);
+        }
+    }
+
+    #[test]
+    fn test_open_with_any() {
+        let old = gen_key();
+        let new = gen_key();
+        let n = gen_nonce();
+        let c = seal(b"rotate", &n, &old);
+        let keys = [new.clone(), old.clone()];
+        assert_eq!(open_with_any(&c, &n, &keys), Ok((1, b"rotate".to_vec())));
+        assert_eq!(
+            open_with_any(&c, &n, &keys[..1]),
+            Err(Error::VerificationFailed)
+        );
+        assert_eq!(open_with_any(&c, &n, &[]), Err(Error::VerificationFailed));
+        assert_eq!(
+            open_with_any(&c, &gen_nonce(), &keys),
+            Err(Error::VerificationFailed)
+        );
+        assert_eq!(
+            open_with_any(&c[..MACBYTES - 1], &n, &keys),
+            Err(Error::InvalidLength)
+        );
+        let keys = [old.clone(), new, old];
+        assert_eq!(open_with_any(&c, &n, &keys), Ok((0, b"rotate".to_vec())));
+    }
+
+    #[test]
+    fn test_seal_open_tamper() {
+        use randombytes::randombytes;
+        for i in 0..32usize {
+            let k = gen_key();
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let mut c = seal(&m, &n, &k);
+            for i in 0..c.len() {
+                c[i] ^= 0x20;
+                // Test the combined mode.
+                assert_eq!(Err(Error::VerificationFailed), open(&c, &n, &k));
+                // Test the detached mode.
+                let tag = Tag::from_slice(&c[..MACBYTES]).unwrap();
+                assert_eq!(Err(Error::VerificationFailed), open_detached(&mut c[MACBYTES..], &tag, &n, &k));
+                c[i] ^= 0x20;
+            }
+        }
+    }
+
+    #[test]
+    fn test_seal_open_detached() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let k = gen_key();
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let mut buf = m.clone();
+            let tag = seal_detached(&mut buf, &n, &k);
+            open_detached(&mut buf, &tag, &n, &k).unwrap();
+            assert_eq!(m, buf);
+        }
+    }
+
+    #[test]
+    fn test_seal_combined_then_open_detached() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let k = gen_key();
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let mut c = seal(&m, &n, &k);
+            let tag = Tag::from_slice(&c[..MACBYTES]).unwrap();
+            let buf = &mut c[MACBYTES..];
+            open_detached(buf, &tag, &n, &k).unwrap();
+            assert_eq!(buf, &*m);
+        }
+    }
+
+    #[test]
+    fn test_seal_detached_then_open_combined() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let k = gen_key();
+            let m = randombytes(i);
+            let n = gen_nonce();
+            let mut buf = vec![0; MACBYTES];
+            buf.extend_from_slice(&m);
+            let tag = seal_detached(&mut buf[MACBYTES..], &n, &k);
+            buf[..MACBYTES].copy_from_slice(&tag.0[..]);
+            let opened = open(&buf, &n, &k);
+            assert_eq!(Ok(m), opened);
+        }
+    }
+
+    #[test]
+    fn test_seal_open_detached_tamper() {
+        use randombytes::randombytes;
+        for i in 0..32usize {
+            let k = gen_key();
+            let mut m = randombytes(i);
+            let n = gen_nonce();
+            let mut tag = seal_detached(&mut m, &n, &k);
+            for j in 0..m.len() {
+                m[j] ^= 0x20;
+                assert_eq!(Err(Error::VerificationFailed), open_detached(&mut m, &tag, &n, &k));
+                m[j] ^= 0x20;
+            }
+            for j in 0..tag.0.len() {
+                tag.0[j] ^= 0x20;
+                assert_eq!(Err(Error::VerificationFailed), open_detached(&mut m, &tag, &n, &k));
+                tag.0[j] ^= 0x20;
+            }
+        }
+    }
+
+    #[test]
+    fn test_seal_open_into() {
+        use randombytes::randombytes;
+        let mut c = [0; 256 + MACBYTES];
+        let mut m2 = [0; 256];
+        for i in 0..256usize {
+            let k = gen_key();
+            let n = gen_nonce();
+            let m = randombytes(i);
+            assert_eq!(seal_into(&mut c, &m, &n, &k), Ok(i + MACBYTES));
+            assert_eq!(&c[..i + MACBYTES], &seal(&m, &n, &k)[..]);
+            assert_eq!(open_into(&mut m2, &c[..i + MACBYTES], &n, &k), Ok(i));
+            assert_eq!(&m2[..i], &m[..]);
+        }
+    }
+
+    #[test]
+    fn test_seal_open_into_failure() {
+        let k = gen_key();
+        let n = gen_nonce();
+        let mut c = [0; 4 + MACBYTES];
+        let mut m = [0; 4];
+        assert_eq!(
+            seal_into(&mut c[..3 + MACBYTES], b"into", &n, &k),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(seal_into(&mut c, b"into", &n, &k), Ok(4 + MACBYTES));
+        assert_eq!(
+            open_into(&mut m[..3], &c, &n, &k),
+            Err(Error::InvalidLength)
+        );
+        assert_eq!(
+            open_into(&mut m, &c[..MACBYTES - 1], &n, &k),
+            Err(Error::InvalidLength)
+        );
+        c[0] ^= 0x20;
+        assert_eq!(
+            open_into(&mut m, &c, &n, &k),
+            Err(Error::VerificationFailed)
+        );
+    }
+
+    #[test]
+    fn test_seal_open_in_place() {
+        use randombytes::randombytes;
+        for i in 0..256usize {
+            let k = gen_key();
+            let n = gen_nonce();
+            let m = randombytes(i);
+            let mut buf = Vec::with_capacity(i + MACBYTES);
+            buf.extend_from_slice(&m);
+            seal_in_place(&mut buf, &n, &k);
+            assert_eq!(buf, seal(&m, &n, &k));
+            assert_eq!(open(&buf, &n, &k), Ok(m.clone()));
+            let m2 = open_in_place(&mut buf, &n, &k).unwrap();
+            assert_eq!(m2, &m[..]);
+        }
+    }
+
+    #[test]
+    fn test_open_in_place_failure() {
+        let k = gen_key();
+        let n = gen_nonce();
+        let mut c = seal(b"in place", &n, &k);
+        c[MACBYTES] ^= 0x20;
+        let tampered = c.clone();
+        assert_eq!(
+            open_in_place(&mut c, &n, &k),
+            Err(Error::VerificationFailed)
+        );
+        assert_eq!(c, tampered);
+        assert_eq!(
+            open_in_place(&mut c[..MACBYTES - 1], &n, &k),
+            Err(Error::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn test_open_detached_failure_does_not_modify() {
+        let mut buf = b"hello world".to_vec();
+        let k = gen_key();
+        let n = gen_nonce();
+        let tag = seal_detached(&mut buf, &n, &k);
+        // Flip the last bit in the ciphertext, to break authentication.
+        *buf.last_mut().unwrap() ^= 1;
+        // Make a copy that we can compare against after the failure below.
+        let copy = buf.clone();
+        // Now try to open the message. This will fail.
+        let failure = open_detached(&mut buf, &tag, &n, &k);
+        assert!(failure.is_err());
+        // Make sure the input hasn't been touched.
+        assert_eq!(
+            buf, copy,
+            "input should not be modified if authentication fails"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialisation() {
+        use test_utils::round_trip;
+        for _ in 0..256usize {
+            let k = gen_key();
+            let n = gen_nonce();
+            round_trip(k);
+            round_trip(n);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialisation_rejects_wrong_length() {
+        use test_utils::rejects_length;
+        rejects_length::<Key>(KEYBYTES - 1);
+        rejects_length::<Key>(KEYBYTES + 1);
+        rejects_length::<Nonce>(0);
+        rejects_length::<Nonce>(NONCEBYTES + 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialisation_human_readable() {
+        use hex;
+        use test_utils::{from_json, to_json};
+        let k = gen_key();
+        let json = to_json(&k);
+        assert_eq!(json, format!("\"{}\"", hex::encode(&k.0[..])));
+        assert_eq!(from_json::<Key>(&json), Ok(k.clone()));
+        // arrays of bytes are still accepted
+        let array = format!("{:?}", &k.0[..]).replace(" ", "");
+        assert_eq!(from_json::<Key>(&array), Ok(k.clone()));
+        // upper case digits, but not odd lengths, other characters or
+        // wrong lengths
+        assert_eq!(from_json::<Key>(&json.to_uppercase()), Ok(k));
+        assert!(from_json::<Nonce>("\"0\"").is_err());
+        assert!(from_json::<Nonce>(&format!("\"{}\"", "zz".repeat(NONCEBYTES))).is_err());
+        assert!(from_json::<Nonce>(&format!("\"{}\"", "00".repeat(NONCEBYTES - 1))).is_err());
+        assert!(from_json::<Nonce>(&format!("\"{}\"", "00".repeat(NONCEBYTES + 1))).is_err());
+    }
+
+    #[test]
+    fn test_into_guarded() {
+        let k = gen_key();
+        let n = gen_nonce();
+        let c = seal(b"guarded", &n, &k);
+        let g = k.clone().into_guarded().unwrap();
+        assert_eq!(*g, k.0);
+        assert_eq!(open(&c, &n, &Key(*g)), Ok(b"guarded".to_vec()));
+    }
+
+    #[test]
+    fn test_try_from_slice() {
+        use std::convert::TryFrom;
+        let k = gen_key();
+        assert_eq!(Key::try_from(&k.0[..]), Ok(k.clone()));
+        assert_eq!(Key::try_from(&k.0[1..]), Err(Error::InvalidLength));
+        let n = gen_nonce();
+        assert_eq!(Nonce::try_from(n.as_ref()), Ok(n));
+        assert_eq!(Nonce::try_from(&[0; NONCEBYTES + 1][..]), Err(Error::InvalidLength));
+    }
+
+    #[test]
+    fn test_nonce_hex_string() {
+        use hex;
+        let n = gen_nonce();
+        assert_eq!(n.to_string(), hex::encode(n));
+        assert_eq!(format!("{:x}", n), hex::encode(n));
+        assert_eq!(n.to_string().parse::<Nonce>(), Ok(n));
+        assert_eq!(
+            n.to_string().to_uppercase().parse::<Nonce>(),
+            Ok(n)
+        );
+        assert_eq!("00".parse::<Nonce>(), Err(Error::InvalidLength));
+        assert_eq!(
+            "zz".repeat(NONCEBYTES).parse::<Nonce>(),
+            Err(Error::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn test_nonce_length() {
+        assert_eq!(192 / 8, gen_nonce().as_ref().len());
+    }
+}
+
+#[cfg(feature = "benchmarks")]
+#[cfg(test)]
+mod bench_m {
+    extern crate test;
+    use super::*;
+    use randombytes::randombytes;
+
+    const BENCH_SIZES: [usize; 14] = [0, 1, 2, 4, 8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096];
+
+    #[bench]
+    fn bench_seal_open(b: &mut test::Bencher) {
+        let k = gen_key();
+        let n = gen_nonce();
+        let ms: Vec<Vec<u8>> = BENCH_SIZES.iter().map(|s| randombytes(*s)).collect();
+        b.iter(|| {
+            for m in ms.iter() {
+                open(&seal(&m, &n, &k), &n, &k).unwrap();
+            }
+        });
+    }
+}
+
+));
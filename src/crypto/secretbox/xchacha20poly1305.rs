@@ -0,0 +1,66 @@
+//! `crypto_secretbox_xchacha20poly1305`, a variant of `crypto_secretbox` using
+//! the `XChaCha20` stream cipher instead of `XSalsa20`.
+//!
+//! Keys and nonces have the same sizes as for
+//! [`xsalsa20poly1305`](super::xsalsa20poly1305), but ciphertexts of the two
+//! constructions are not interchangeable.
+//!
+//! This function is conjectured to meet the standard notions of privacy and
+//! authenticity.
+
+use ffi::{
+    crypto_secretbox_xchacha20poly1305_KEYBYTES, crypto_secretbox_xchacha20poly1305_MACBYTES,
+    crypto_secretbox_xchacha20poly1305_NONCEBYTES, crypto_secretbox_xchacha20poly1305_detached,
+    crypto_secretbox_xchacha20poly1305_easy, crypto_secretbox_xchacha20poly1305_open_detached,
+    crypto_secretbox_xchacha20poly1305_open_easy,
+};
+
+secretbox_module!(
+    crypto_secretbox_xchacha20poly1305_easy,
+    crypto_secretbox_xchacha20poly1305_open_easy,
+    crypto_secretbox_xchacha20poly1305_detached,
+    crypto_secretbox_xchacha20poly1305_open_detached,
+    crypto_secretbox_xchacha20poly1305_KEYBYTES as usize,
+    crypto_secretbox_xchacha20poly1305_NONCEBYTES as usize,
+    crypto_secretbox_xchacha20poly1305_MACBYTES as usize
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex;
+
+    #[test]
+    fn test_vector_1() {
+        // regression vector computed with libsodium 1.0.18
+        let key = Key([
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+            0x1c, 0x1d, 0x1e, 0x1f,
+        ]);
+        let nonce = Nonce([
+            0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d,
+            0x4e, 0x4f, 0x50, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57,
+        ]);
+        let m = b"sodiumoxide xchacha20poly1305 secretbox";
+        let c = seal(m, &nonce, &key);
+        assert_eq!(
+            hex::encode(&c),
+            "58b96c8abf573ab28a51813a6cbb69984cd860e73d0cf79d27dc74b54393b417\
+             c40fc8e0a144b584d45816299af7c5bdb74b6004b28f01"
+        );
+        assert_eq!(open(&c, &nonce, &key), Ok(m.to_vec()));
+    }
+
+    #[test]
+    fn test_not_interchangeable() {
+        use super::super::xsalsa20poly1305 as xsalsa;
+        let k = gen_key();
+        let n = gen_nonce();
+        let c = seal(b"some data", &n, &k);
+        let xsalsa_k = xsalsa::Key::from_slice(k.as_ref()).unwrap();
+        let xsalsa_n = xsalsa::Nonce::from_slice(n.as_ref()).unwrap();
+        assert!(xsalsa::open(&c, &xsalsa_n, &xsalsa_k).is_err());
+        assert_eq!(open(&c, &n, &k), Ok(b"some data".to_vec()));
+    }
+}
@@ -5,268 +5,84 @@
 //! This function is conjectured to meet the standard notions of privacy and
 //! authenticity.
 
-use crypto::nonce::gen_random_nonce;
-use ffi;
-#[cfg(not(feature = "std"))]
-use prelude::*;
-use randombytes::randombytes_into;
-
-/// Number of bytes in `Key`.
-pub const KEYBYTES: usize = ffi::crypto_secretbox_xsalsa20poly1305_KEYBYTES as usize;
-
-/// Number of bytes in a `Nonce`.
-pub const NONCEBYTES: usize = ffi::crypto_secretbox_xsalsa20poly1305_NONCEBYTES as usize;
-
-new_type! {
-    /// `Key` for symmetric authenticated encryption
-    ///
-    /// When a `Key` goes out of scope its contents
-    /// will be zeroed out
-    secret Key(KEYBYTES);
-}
-
-new_type! {
-    /// Authentication `Tag` for the detached encryption mode
-    ///
-    /// In the combined mode, the tag occupies the first MACBYTES bytes of the ciphertext.
-    public Tag(MACBYTES);
-}
-
-new_type! {
-    /// `Nonce` for symmetric authenticated encryption
-    nonce Nonce(NONCEBYTES);
-}
-
-/// Number of bytes in the authenticator tag of an encrypted message
-/// i.e. the number of bytes by which the ciphertext is larger than the
-/// plaintext.
-pub const MACBYTES: usize = ffi::crypto_secretbox_xsalsa20poly1305_MACBYTES as usize;
-
-/// `gen_key()` randomly generates a secret key
-///
-/// THREAD SAFETY: `gen_key()` is thread-safe provided that you have
-/// called `sodiumoxide::init()` once before using any other function
-/// from sodiumoxide.
-pub fn gen_key() -> Key {
-    let mut key = [0; KEYBYTES];
-    randombytes_into(&mut key);
-    Key(key)
-}
-
-/// `gen_nonce()` randomly generates a nonce
-///
-/// THREAD SAFETY: `gen_key()` is thread-safe provided that you have
-/// called `sodiumoxide::init()` once before using any other function
-/// from sodiumoxide.
-pub fn gen_nonce() -> Nonce {
-    gen_random_nonce()
-}
-
-/// `seal()` encrypts and authenticates a message `m` using a secret key `k` and a
-/// nonce `n`.  It returns a ciphertext `c`.
-pub fn seal(m: &[u8], n: &Nonce, k: &Key) -> Vec<u8> {
-    let clen = m.len() + MACBYTES;
-    let mut c = Vec::with_capacity(clen);
-    unsafe {
-        ffi::crypto_secretbox_easy(
-            c.as_mut_ptr(),
-            m.as_ptr(),
-            m.len() as u64,
-            n.0.as_ptr(),
-            k.0.as_ptr(),
-        );
-        c.set_len(clen);
-    }
-    c
-}
-
-/// `seal_detached()` encrypts and authenticates a message `m` using a secret key `k` and a nonce
-/// `n`.  `m` is encrypted in place, so after this function returns it will contain the ciphertext.
-/// The detached authentication tag is returned by value.
-pub fn seal_detached(m: &mut [u8], n: &Nonce, k: &Key) -> Tag {
-    let mut tag = [0; MACBYTES];
-    unsafe {
-        ffi::crypto_secretbox_detached(
-            m.as_mut_ptr(),
-            tag.as_mut_ptr(),
-            m.as_ptr(),
-            m.len() as u64,
-            n.0.as_ptr(),
-            k.0.as_ptr(),
-        );
-    };
-    Tag(tag)
-}
-
-/// `open()` verifies and decrypts a ciphertext `c` using a secret key `k` and a nonce `n`.
-/// It returns a plaintext `Ok(m)`.
-/// If the ciphertext fails verification, `open()` returns `Err(())`.
-pub fn open(c: &[u8], n: &Nonce, k: &Key) -> Result<Vec<u8>, ()> {
-    if c.len() < MACBYTES {
-        return Err(());
-    }
-    let mlen = c.len() - MACBYTES;
-    let mut m = Vec::with_capacity(mlen);
-    unsafe {
-        let ret = ffi::crypto_secretbox_open_easy(
-            m.as_mut_ptr(),
-            c.as_ptr(),
-            c.len() as u64,
-            n.0.as_ptr(),
-            k.0.as_ptr(),
-        );
-        if ret == 0 {
-            m.set_len(mlen);
-            Ok(m)
-        } else {
-            Err(())
-        }
-    }
-}
-
-/// `open_detached()` verifies and decrypts a ciphertext `c` and and authentication tag `tag`,
-/// using a secret key `k` and a nonce `n`. `c` is decrypted in place, so if this function is
-/// successful it will contain the plaintext. If the ciphertext fails verification,
-/// `open_detached()` returns `Err(())`, and the ciphertext is not modified.
-pub fn open_detached(c: &mut [u8], tag: &Tag, n: &Nonce, k: &Key) -> Result<(), ()> {
-    let ret = unsafe {
-        ffi::crypto_secretbox_open_detached(
-            c.as_mut_ptr(),
-            c.as_ptr(),
-            tag.0.as_ptr(),
-            c.len() as u64,
-            n.0.as_ptr(),
-            k.0.as_ptr(),
-        )
-    };
-    if ret == 0 {
-        Ok(())
-    } else {
-        Err(())
-    }
-}
+use ffi::{
+    crypto_secretbox_detached, crypto_secretbox_easy, crypto_secretbox_open_detached,
+    crypto_secretbox_open_easy, crypto_secretbox_xsalsa20poly1305_KEYBYTES,
+    crypto_secretbox_xsalsa20poly1305_MACBYTES, crypto_secretbox_xsalsa20poly1305_NONCEBYTES,
+};
+
+secretbox_module!(
+    crypto_secretbox_easy,
+    crypto_secretbox_open_easy,
+    crypto_secretbox_detached,
+    crypto_secretbox_open_detached,
+    crypto_secretbox_xsalsa20poly1305_KEYBYTES as usize,
+    crypto_secretbox_xsalsa20poly1305_NONCEBYTES as usize,
+    crypto_secretbox_xsalsa20poly1305_MACBYTES as usize
+);
 
 #[cfg(test)]
 mod test {
     use super::*;
 
     #[test]
-    fn test_seal_open() {
-        use randombytes::randombytes;
-        for i in 0..256usize {
-            let k = gen_key();
-            let m = randombytes(i);
-            let n = gen_nonce();
-            let c = seal(&m, &n, &k);
-            let opened = open(&c, &n, &k);
-            assert_eq!(Ok(m), opened);
-        }
-    }
-
-    #[test]
-    fn test_seal_open_tamper() {
-        use randombytes::randombytes;
-        for i in 0..32usize {
-            let k = gen_key();
-            let m = randombytes(i);
-            let n = gen_nonce();
-            let mut c = seal(&m, &n, &k);
-            for i in 0..c.len() {
-                c[i] ^= 0x20;
-                // Test the combined mode.
-                assert_eq!(Err(()), open(&c, &n, &k));
-                // Test the detached mode.
-                let tag = Tag::from_slice(&c[..MACBYTES]).unwrap();
-                assert_eq!(Err(()), open_detached(&mut c[MACBYTES..], &tag, &n, &k));
-                c[i] ^= 0x20;
-            }
-        }
-    }
-
-    #[test]
-    fn test_seal_open_detached() {
-        use randombytes::randombytes;
-        for i in 0..256usize {
-            let k = gen_key();
-            let m = randombytes(i);
-            let n = gen_nonce();
-            let mut buf = m.clone();
-            let tag = seal_detached(&mut buf, &n, &k);
-            open_detached(&mut buf, &tag, &n, &k).unwrap();
-            assert_eq!(m, buf);
-        }
-    }
-
-    #[test]
-    fn test_seal_combined_then_open_detached() {
-        use randombytes::randombytes;
-        for i in 0..256usize {
-            let k = gen_key();
-            let m = randombytes(i);
-            let n = gen_nonce();
-            let mut c = seal(&m, &n, &k);
-            let tag = Tag::from_slice(&c[..MACBYTES]).unwrap();
-            let buf = &mut c[MACBYTES..];
-            open_detached(buf, &tag, &n, &k).unwrap();
-            assert_eq!(buf, &*m);
-        }
-    }
-
-    #[test]
-    fn test_seal_detached_then_open_combined() {
-        use randombytes::randombytes;
-        for i in 0..256usize {
-            let k = gen_key();
-            let m = randombytes(i);
-            let n = gen_nonce();
-            let mut buf = vec![0; MACBYTES];
-            buf.extend_from_slice(&m);
-            let tag = seal_detached(&mut buf[MACBYTES..], &n, &k);
-            buf[..MACBYTES].copy_from_slice(&tag.0[..]);
-            let opened = open(&buf, &n, &k);
-            assert_eq!(Ok(m), opened);
-        }
+    fn test_key_from_hex() {
+        use hex;
+        let k = gen_key();
+        let encoded = hex::encode(&k);
+        assert_eq!(Ok(k.clone()), Key::from_hex(&encoded));
+        assert_eq!(Ok(k), Key::from_hex(&encoded.to_uppercase()));
+
+        let mixed = "000102030405060708090A0b0C0d0E0f101112131415161718191a1B1c1D1e1F";
+        let expected = Key([
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+            0x1c, 0x1d, 0x1e, 0x1f,
+        ]);
+        assert_eq!(Ok(expected), Key::from_hex(mixed));
     }
 
     #[test]
-    fn test_seal_open_detached_tamper() {
-        use randombytes::randombytes;
-        for i in 0..32usize {
-            let k = gen_key();
-            let mut m = randombytes(i);
-            let n = gen_nonce();
-            let mut tag = seal_detached(&mut m, &n, &k);
-            for j in 0..m.len() {
-                m[j] ^= 0x20;
-                assert_eq!(Err(()), open_detached(&mut m, &tag, &n, &k));
-                m[j] ^= 0x20;
-            }
-            for j in 0..tag.0.len() {
-                tag.0[j] ^= 0x20;
-                assert_eq!(Err(()), open_detached(&mut m, &tag, &n, &k));
-                tag.0[j] ^= 0x20;
-            }
-        }
+    fn test_key_from_hex_invalid() {
+        let valid = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+        assert_eq!(Err(::Error::InvalidLength), Key::from_hex(""));
+        assert_eq!(Err(::Error::InvalidLength), Key::from_hex(&valid[..62]));
+        assert_eq!(Err(::Error::InvalidLength), Key::from_hex(&valid[..63]));
+        assert_eq!(
+            Err(::Error::InvalidLength),
+            Key::from_hex(&format!("{}00", valid))
+        );
+        assert_eq!(
+            Err(::Error::InvalidEncoding),
+            Key::from_hex(&valid.replace("1f", "1g"))
+        );
+        assert_eq!(
+            Err(::Error::InvalidEncoding),
+            Key::from_hex(&valid.replace("00", " 0"))
+        );
+        assert_eq!(
+            Err(::Error::InvalidEncoding),
+            Key::from_hex(&valid.replace("0e", "\u{e9}"))
+        );
     }
 
+    #[cfg(feature = "subtle")]
     #[test]
-    fn test_open_detached_failure_does_not_modify() {
-        let mut buf = b"hello world".to_vec();
+    fn test_constant_time_eq() {
+        use subtle::ConstantTimeEq;
         let k = gen_key();
+        let mut other = k.clone();
+        assert!(bool::from(k.ct_eq(&other)));
+        other.0[KEYBYTES - 1] ^= 1;
+        assert!(!bool::from(k.ct_eq(&other)));
+
         let n = gen_nonce();
-        let tag = seal_detached(&mut buf, &n, &k);
-        // Flip the last bit in the ciphertext, to break authentication.
-        *buf.last_mut().unwrap() ^= 1;
-        // Make a copy that we can compare against after the failure below.
-        let copy = buf.clone();
-        // Now try to open the message. This will fail.
-        let failure = open_detached(&mut buf, &tag, &n, &k);
-        assert!(failure.is_err());
-        // Make sure the input hasn't been touched.
-        assert_eq!(
-            buf, copy,
-            "input should not be modified if authentication fails"
-        );
+        assert!(bool::from(n.ct_eq(&n)));
+        let t = seal_detached(&mut [0; 8], &n, &k);
+        let mut other = t;
+        other.0[0] ^= 1;
+        assert!(bool::from(t.ct_eq(&t)));
+        assert!(!bool::from(t.ct_eq(&other)));
     }
 
     #[test]
@@ -311,43 +127,4 @@ mod test {
         let m2 = open(&c, &nonce, &firstkey);
         assert!(Ok(m) == m2);
     }
-
-    #[cfg(feature = "serde")]
-    #[test]
-    fn test_serialisation() {
-        use test_utils::round_trip;
-        for _ in 0..256usize {
-            let k = gen_key();
-            let n = gen_nonce();
-            round_trip(k);
-            round_trip(n);
-        }
-    }
-
-    #[test]
-    fn test_nonce_length() {
-        assert_eq!(192 / 8, gen_nonce().as_ref().len());
-    }
-}
-
-#[cfg(feature = "benchmarks")]
-#[cfg(test)]
-mod bench {
-    extern crate test;
-    use super::*;
-    use randombytes::randombytes;
-
-    const BENCH_SIZES: [usize; 14] = [0, 1, 2, 4, 8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096];
-
-    #[bench]
-    fn bench_seal_open(b: &mut test::Bencher) {
-        let k = gen_key();
-        let n = gen_nonce();
-        let ms: Vec<Vec<u8>> = BENCH_SIZES.iter().map(|s| randombytes(*s)).collect();
-        b.iter(|| {
-            for m in ms.iter() {
-                open(&seal(&m, &n, &k), &n, &k).unwrap();
-            }
-        });
-    }
 }
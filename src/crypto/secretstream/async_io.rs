@@ -0,0 +1,548 @@
+//! Stream encryption on top of tokio's `AsyncRead` and `AsyncWrite`.
+//!
+//! [`AsyncEncryptingWriter`] and [`AsyncDecryptingReader`] are the
+//! non-blocking counterparts of [`EncryptingWriter`](../io/struct.EncryptingWriter.html)
+//! and [`DecryptingReader`](../io/struct.DecryptingReader.html), and use the
+//! same format: a [`Header`] followed by chunks of [`CHUNKBYTES`] bytes, the
+//! last of which is tagged with [`Tag::Final`]. A stream written with one can
+//! be read with the other, so they can be layered over a `TcpStream` in an
+//! async server while the peer uses blocking I/O.
+//!
+//! The final chunk is written by `poll_shutdown()`, i.e. by
+//! `AsyncWriteExt::shutdown()`. A writer that is dropped without being shut
+//! down leaves a truncated stream, which the reader rejects.
+//!
+//! This module is only available with the `tokio` feature.
+
+use super::io::{invalid_data, stream_error, Plaintext, CHUNKBYTES};
+use super::xchacha20poly1305::{Header, Key, Pull, Push, Stream, Tag, ABYTES, HEADERBYTES};
+use std::cmp;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// `AsyncEncryptingWriter` encrypts all bytes written to it with a
+/// secretstream and forwards the ciphertext to an inner writer.
+pub struct AsyncEncryptingWriter<W> {
+    inner: W,
+    stream: Stream<Push>,
+    chunk_size: usize,
+    // plaintext of the current chunk
+    pending: Plaintext,
+    // ciphertext not yet accepted by `inner`, starting at `out_pos`
+    out: Vec<u8>,
+    out_pos: usize,
+    finished: bool,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncEncryptingWriter<W> {
+    /// `new()` wraps `inner` so that everything written to it is encrypted
+    /// with the key `key`, in chunks of `CHUNKBYTES` bytes. The stream header
+    /// is written to `inner` along with the first chunk.
+    pub fn new(inner: W, key: &Key) -> io::Result<AsyncEncryptingWriter<W>> {
+        AsyncEncryptingWriter::with_chunk_size(inner, key, CHUNKBYTES)
+    }
+
+    /// `with_chunk_size()` is like `new()`, but encrypts chunks of
+    /// `chunk_size` bytes. The reader must use the same size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn with_chunk_size(
+        inner: W,
+        key: &Key,
+        chunk_size: usize,
+    ) -> io::Result<AsyncEncryptingWriter<W>> {
+        assert!(chunk_size > 0, "chunk_size must not be zero");
+        let (stream, header) = Stream::init_push(key).map_err(stream_error)?;
+        let mut out = Vec::with_capacity(HEADERBYTES + chunk_size + ABYTES);
+        out.extend_from_slice(header.as_ref());
+        Ok(AsyncEncryptingWriter {
+            inner,
+            stream,
+            chunk_size,
+            pending: Plaintext(Vec::with_capacity(chunk_size)),
+            out,
+            out_pos: 0,
+            finished: false,
+        })
+    }
+
+    /// `get_ref()` returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// `into_inner()` returns the underlying writer. Unless the writer has
+    /// been shut down, the stream written so far is truncated.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn encrypt_chunk(&mut self, tag: Tag) -> io::Result<()> {
+        let res = self
+            .stream
+            .push_to_vec(&self.pending.0, None, tag, &mut self.out);
+        self.pending.clear();
+        self.out_pos = 0;
+        res.map_err(stream_error)
+    }
+
+    // Writes the buffered ciphertext to `inner`.
+    fn poll_write_out(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        while self.out_pos < self.out.len() {
+            match Pin::new(&mut self.inner).poll_write(cx, &self.out[self.out_pos..]) {
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "failed to write the encrypted chunk",
+                    )));
+                }
+                Poll::Ready(Ok(n)) => self.out_pos += n,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        self.out.clear();
+        self.out_pos = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for AsyncEncryptingWriter<W> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.finished {
+            return Poll::Ready(Err(stream_error(::Error::Finalized)));
+        }
+        if this.pending.0.len() == this.chunk_size {
+            if let Err(e) = this.encrypt_chunk(Tag::Message) {
+                return Poll::Ready(Err(e));
+            }
+        }
+        match this.poll_write_out(cx) {
+            Poll::Ready(Ok(())) => {}
+            other => return other.map(|r| r.map(|()| 0)),
+        }
+        let n = cmp::min(buf.len(), this.chunk_size - this.pending.0.len());
+        this.pending.0.extend_from_slice(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    /// Writes out the encrypted chunks and flushes the inner writer.
+    /// Buffered plaintext is not written out before a full chunk has been
+    /// collected or the writer is shut down.
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        match this.poll_write_out(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_flush(cx),
+            other => other,
+        }
+    }
+
+    /// Encrypts the last chunk, tagging it as final, writes it out and shuts
+    /// down the inner writer.
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if !this.finished {
+            match this.poll_write_out(cx) {
+                Poll::Ready(Ok(())) => {}
+                other => return other,
+            }
+            this.finished = true;
+            if let Err(e) = this.encrypt_chunk(Tag::Final) {
+                return Poll::Ready(Err(e));
+            }
+        }
+        match this.poll_write_out(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_shutdown(cx),
+            other => other,
+        }
+    }
+}
+
+/// `AsyncDecryptingReader` reads a stream produced by an
+/// [`AsyncEncryptingWriter`] or an `EncryptingWriter` and returns the
+/// plaintext.
+///
+/// The stream header is read on the first call to `poll_read()`. As with
+/// `DecryptingReader`, each chunk is authenticated before any of it is
+/// returned, and an invalid, truncated or overlong stream makes reading fail
+/// with an error of kind `io::ErrorKind::InvalidData`.
+pub struct AsyncDecryptingReader<R> {
+    inner: R,
+    // the key, until the header has been read
+    key: Option<Key>,
+    stream: Option<Stream<Pull>>,
+    chunk_size: usize,
+    // the header or the ciphertext of the current chunk, possibly partially
+    // read
+    record: Vec<u8>,
+    // plaintext of the current chunk and the number of bytes already returned
+    pending: Plaintext,
+    pos: usize,
+    failed: bool,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncDecryptingReader<R> {
+    /// `new()` prepares to decrypt the stream read from `inner`, in chunks of
+    /// `CHUNKBYTES` bytes, with the key `key`.
+    pub fn new(inner: R, key: &Key) -> AsyncDecryptingReader<R> {
+        AsyncDecryptingReader::with_chunk_size(inner, key, CHUNKBYTES)
+    }
+
+    /// `with_chunk_size()` is like `new()`, for streams written with a chunk
+    /// size of `chunk_size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn with_chunk_size(inner: R, key: &Key, chunk_size: usize) -> AsyncDecryptingReader<R> {
+        assert!(chunk_size > 0, "chunk_size must not be zero");
+        AsyncDecryptingReader {
+            inner,
+            key: Some(key.clone()),
+            stream: None,
+            chunk_size,
+            record: Vec::with_capacity(chunk_size + ABYTES),
+            pending: Plaintext(Vec::with_capacity(chunk_size)),
+            pos: 0,
+            failed: false,
+            done: false,
+        }
+    }
+
+    /// `into_inner()` returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    // Reads into `record` until it holds `len` bytes or `inner` reaches EOF.
+    // Bytes read before `inner` returns `Pending` or fails are kept for the
+    // next call.
+    fn poll_fill(&mut self, cx: &mut Context, len: usize) -> Poll<io::Result<()>> {
+        let mut filled = self.record.len();
+        self.record.resize(len, 0);
+        let res = loop {
+            if filled == len {
+                break Poll::Ready(Ok(()));
+            }
+            let mut buf = ReadBuf::new(&mut self.record[filled..]);
+            match Pin::new(&mut self.inner).poll_read(cx, &mut buf) {
+                Poll::Ready(Ok(())) if buf.filled().is_empty() => break Poll::Ready(Ok(())),
+                Poll::Ready(Ok(())) => filled += buf.filled().len(),
+                other => break other,
+            }
+        };
+        self.record.truncate(filled);
+        res
+    }
+
+    fn poll_next_chunk(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        if self.stream.is_none() {
+            match self.poll_fill(cx, HEADERBYTES) {
+                Poll::Ready(Ok(())) => {}
+                other => return other,
+            }
+            if self.record.len() < HEADERBYTES {
+                return Poll::Ready(Err(invalid_data("truncated stream header")));
+            }
+            let mut header = Header([0; HEADERBYTES]);
+            header.0.copy_from_slice(&self.record);
+            self.record.clear();
+            let key = self
+                .key
+                .take()
+                .expect("key is kept until the header is read");
+            match Stream::init_pull(&header, &key) {
+                Ok(stream) => self.stream = Some(stream),
+                Err(_) => return Poll::Ready(Err(invalid_data("invalid stream header"))),
+            }
+        }
+        let record_len = self.chunk_size + ABYTES;
+        match self.poll_fill(cx, record_len) {
+            Poll::Ready(Ok(())) => {}
+            other => return other,
+        }
+        if self.record.is_empty() {
+            return Poll::Ready(Err(invalid_data("truncated stream")));
+        }
+        self.pending.clear();
+        self.pos = 0;
+        let res = self
+            .stream
+            .as_mut()
+            .expect("stream is initialized")
+            .pull_to_vec(&self.record, None, &mut self.pending.0);
+        let short = self.record.len() < record_len;
+        self.record.clear();
+        match res {
+            Ok(Tag::Final) => {}
+            Ok(_) if short => return Poll::Ready(Err(invalid_data("truncated stream"))),
+            Ok(_) => {}
+            Err(_) => return Poll::Ready(Err(invalid_data("invalid chunk"))),
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    // Checks that nothing follows the final chunk.
+    fn poll_trailing(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        match self.poll_fill(cx, 1) {
+            Poll::Ready(Ok(())) if !self.record.is_empty() => {
+                Poll::Ready(Err(invalid_data("data after the final chunk")))
+            }
+            other => other,
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncDecryptingReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        loop {
+            if this.failed {
+                return Poll::Ready(Err(invalid_data("invalid stream")));
+            }
+            if this.pos < this.pending.0.len() {
+                let n = cmp::min(buf.remaining(), this.pending.0.len() - this.pos);
+                buf.put_slice(&this.pending.0[this.pos..this.pos + n]);
+                this.pos += n;
+                return Poll::Ready(Ok(()));
+            }
+            if this.done {
+                return Poll::Ready(Ok(()));
+            }
+            let finalized = match this.stream {
+                Some(ref stream) => stream.is_finalized(),
+                None => false,
+            };
+            let res = if finalized {
+                match this.poll_trailing(cx) {
+                    Poll::Ready(Ok(())) => {
+                        this.done = true;
+                        continue;
+                    }
+                    other => other,
+                }
+            } else {
+                this.poll_next_chunk(cx)
+            };
+            match res {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(e)) => {
+                    if e.kind() == io::ErrorKind::InvalidData {
+                        this.failed = true;
+                        this.pending.clear();
+                    }
+                    return Poll::Ready(Err(e));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::io::{DecryptingReader, EncryptingWriter};
+    use super::super::xchacha20poly1305::gen_key;
+    use super::*;
+    use async_test_utils::poll_until_ready;
+    use randombytes::randombytes;
+    use std::io::{Read, Write};
+
+    // Returns `Pending` before every read or write and accepts at most one
+    // byte at a time.
+    struct Stutter<T> {
+        inner: T,
+        ready: bool,
+    }
+
+    impl<T> Stutter<T> {
+        fn new(inner: T) -> Stutter<T> {
+            Stutter {
+                inner,
+                ready: false,
+            }
+        }
+
+        fn poll_ready(&mut self) -> bool {
+            self.ready = !self.ready;
+            self.ready
+        }
+    }
+
+    impl AsyncRead for Stutter<&[u8]> {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _: &mut Context,
+            buf: &mut ReadBuf,
+        ) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            if !this.poll_ready() {
+                return Poll::Pending;
+            }
+            let n = cmp::min(cmp::min(buf.remaining(), 1), this.inner.len());
+            buf.put_slice(&this.inner[..n]);
+            this.inner = &this.inner[n..];
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl AsyncWrite for Stutter<Vec<u8>> {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let this = self.get_mut();
+            if !this.poll_ready() {
+                return Poll::Pending;
+            }
+            let n = cmp::min(buf.len(), 1);
+            this.inner.extend_from_slice(&buf[..n]);
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    const CHUNK: usize = 16;
+    const SIZES: [usize; 9] = [0, 1, 15, 16, 17, 31, 32, 33, 1000];
+
+    fn write_all<W: AsyncWrite + Unpin>(w: &mut W, mut m: &[u8]) -> io::Result<()> {
+        while !m.is_empty() {
+            let n = poll_until_ready(|cx| Pin::new(&mut *w).poll_write(cx, m))?;
+            m = &m[n..];
+        }
+        Ok(())
+    }
+
+    fn encrypt<W: AsyncWrite + Unpin>(inner: W, m: &[u8], key: &Key) -> W {
+        let mut writer = AsyncEncryptingWriter::with_chunk_size(inner, key, CHUNK).unwrap();
+        for chunk in m.chunks(7) {
+            write_all(&mut writer, chunk).unwrap();
+        }
+        poll_until_ready(|cx| Pin::new(&mut writer).poll_shutdown(cx)).unwrap();
+        writer.into_inner()
+    }
+
+    fn decrypt<R: AsyncRead + Unpin>(c: R, key: &Key) -> io::Result<Vec<u8>> {
+        let mut reader = AsyncDecryptingReader::with_chunk_size(c, key, CHUNK);
+        let mut m = Vec::new();
+        loop {
+            let mut chunk = [0u8; 5];
+            let mut buf = ReadBuf::new(&mut chunk);
+            poll_until_ready(|cx| Pin::new(&mut reader).poll_read(cx, &mut buf))?;
+            if buf.filled().is_empty() {
+                return Ok(m);
+            }
+            m.extend_from_slice(buf.filled());
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let key = gen_key();
+        for &size in SIZES.iter() {
+            let m = randombytes(size);
+            let c = encrypt(Vec::new(), &m, &key);
+            let chunks = if size == 0 { 1 } else { (size - 1) / CHUNK + 1 };
+            assert_eq!(c.len(), HEADERBYTES + size + chunks * ABYTES);
+            assert_eq!(decrypt(&c[..], &key).unwrap(), m);
+            assert_eq!(decrypt(Stutter::new(&c[..]), &key).unwrap(), m);
+
+            let c2 = encrypt(Stutter::new(Vec::new()), &m, &key).inner;
+            assert_eq!(decrypt(&c2[..], &key).unwrap(), m);
+        }
+    }
+
+    #[test]
+    fn test_compatible_with_blocking_io() {
+        let key = gen_key();
+        let m = randombytes(3 * CHUNKBYTES + 1);
+
+        let mut writer = EncryptingWriter::new(Vec::new(), &key).unwrap();
+        writer.write_all(&m).unwrap();
+        let c = writer.finish().unwrap();
+        let mut reader = AsyncDecryptingReader::new(&c[..], &key);
+        let mut m2 = Vec::new();
+        loop {
+            let mut chunk = [0u8; 1000];
+            let mut buf = ReadBuf::new(&mut chunk);
+            poll_until_ready(|cx| Pin::new(&mut reader).poll_read(cx, &mut buf)).unwrap();
+            if buf.filled().is_empty() {
+                break;
+            }
+            m2.extend_from_slice(buf.filled());
+        }
+        assert_eq!(m2, m);
+
+        let mut writer = AsyncEncryptingWriter::new(Vec::new(), &key).unwrap();
+        write_all(&mut writer, &m).unwrap();
+        poll_until_ready(|cx| Pin::new(&mut writer).poll_shutdown(cx)).unwrap();
+        let c = writer.into_inner();
+        let mut reader = DecryptingReader::new(&c[..], &key).unwrap();
+        let mut m3 = Vec::new();
+        reader.read_to_end(&mut m3).unwrap();
+        assert_eq!(m3, m);
+    }
+
+    #[test]
+    fn test_tamper() {
+        let key = gen_key();
+        let c = encrypt(Vec::new(), &randombytes(40), &key);
+        for i in 0..c.len() {
+            let mut tampered = c.clone();
+            tampered[i] ^= 0x20;
+            let err = decrypt(&tampered[..], &key).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_truncated() {
+        let key = gen_key();
+        let c = encrypt(Vec::new(), &randombytes(40), &key);
+        for len in 0..c.len() {
+            let err = decrypt(&c[..len], &key).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_trailing_data() {
+        let key = gen_key();
+        let mut c = encrypt(Vec::new(), b"some data", &key);
+        c.push(0);
+        let err = decrypt(&c[..], &key).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_write_after_shutdown() {
+        let key = gen_key();
+        let mut writer = AsyncEncryptingWriter::new(Vec::new(), &key).unwrap();
+        poll_until_ready(|cx| Pin::new(&mut writer).poll_shutdown(cx)).unwrap();
+        assert!(write_all(&mut writer, b"too late").is_err());
+        // shutting down twice does not write a second final chunk
+        poll_until_ready(|cx| Pin::new(&mut writer).poll_shutdown(cx)).unwrap();
+        assert_eq!(writer.get_ref().len(), HEADERBYTES + ABYTES);
+    }
+}
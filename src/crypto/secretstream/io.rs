@@ -0,0 +1,635 @@
+//! Stream encryption on top of `std::io`.
+//!
+//! An [`EncryptingWriter`] writes a [`Header`] followed by the data written
+//! through it, encrypted in chunks of [`CHUNKBYTES`] bytes. The last chunk is
+//! tagged with [`Tag::Final`] when the writer is finished. A
+//! [`DecryptingReader`] reads such a stream back. It only returns a chunk
+//! once it has been authenticated, and fails if the stream was truncated.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::secretstream;
+//! use std::io::{Read, Write};
+//!
+//! let key = secretstream::gen_key();
+//!
+//! let mut writer = secretstream::EncryptingWriter::new(Vec::new(), &key).unwrap();
+//! writer.write_all(b"some data").unwrap();
+//! let encrypted = writer.finish().unwrap();
+//!
+//! let mut reader = secretstream::DecryptingReader::new(&encrypted[..], &key).unwrap();
+//! let mut plaintext = Vec::new();
+//! reader.read_to_end(&mut plaintext).unwrap();
+//! assert_eq!(plaintext, b"some data");
+//! ```
+//!
+//! # Length-prefixed messages
+//! For streams of individual messages rather than bytes,
+//! [`Stream::write_message()`] writes each message as its ciphertext length,
+//! a 4 byte little endian integer, followed by the ciphertext, and
+//! [`Stream::messages()`] iterates over the messages read back this way.
+//! ```
+//! use sodiumoxide::crypto::secretstream::{self, Stream, Tag};
+//!
+//! let key = secretstream::gen_key();
+//! let (mut stream, header) = Stream::init_push(&key).unwrap();
+//! let mut encrypted = Vec::new();
+//! stream.write_message(&mut encrypted, b"first", Tag::Message).unwrap();
+//! stream.write_message(&mut encrypted, b"last", Tag::Final).unwrap();
+//!
+//! let mut stream = Stream::init_pull(&header, &key).unwrap();
+//! let mut messages = Vec::new();
+//! for message in stream.messages(&encrypted[..]) {
+//!     let (m, _tag) = message.unwrap();
+//!     messages.push(m);
+//! }
+//! assert_eq!(messages, [&b"first"[..], &b"last"[..]]);
+//! ```
+
+use super::xchacha20poly1305::{Header, Key, Pull, Push, Stream, Tag, ABYTES, HEADERBYTES};
+use std::cmp;
+use std::io::{self, Read, Write};
+use utils::memzero;
+
+/// Default number of plaintext bytes in each encrypted chunk.
+pub const CHUNKBYTES: usize = 4096;
+
+pub(super) fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub(super) fn stream_error(e: ::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, e)
+}
+
+// Buffer holding plaintext, zeroed out when it is cleared or dropped.
+pub(super) struct Plaintext(pub(super) Vec<u8>);
+
+impl Plaintext {
+    pub(super) fn clear(&mut self) {
+        memzero(&mut self.0);
+        self.0.clear();
+    }
+}
+
+impl Drop for Plaintext {
+    fn drop(&mut self) {
+        memzero(&mut self.0);
+    }
+}
+
+/// `EncryptingWriter` encrypts all bytes written to it with a secretstream
+/// and forwards the ciphertext to an inner writer.
+///
+/// The last chunk is only written out by
+/// [`finish()`](EncryptingWriter::finish); dropping an `EncryptingWriter`
+/// leaves a truncated stream that `DecryptingReader` rejects.
+///
+/// A chunk advances the stream state before it is written out, so it cannot
+/// be retried: once writing a chunk to the inner writer fails, every later
+/// call returns an error.
+pub struct EncryptingWriter<W: Write> {
+    inner: W,
+    stream: Stream<Push>,
+    chunk_size: usize,
+    // plaintext of the current chunk
+    pending: Plaintext,
+    // ciphertext of the last chunk
+    out: Vec<u8>,
+    // set while a chunk is being written, and left set if that failed
+    poisoned: bool,
+}
+
+impl<W: Write> EncryptingWriter<W> {
+    /// `new()` wraps `inner` so that everything written to it is encrypted
+    /// with the key `key`, in chunks of `CHUNKBYTES` bytes. It writes the
+    /// stream header to `inner` right away.
+    pub fn new(inner: W, key: &Key) -> io::Result<EncryptingWriter<W>> {
+        EncryptingWriter::with_chunk_size(inner, key, CHUNKBYTES)
+    }
+
+    /// `with_chunk_size()` is like `new()`, but encrypts chunks of
+    /// `chunk_size` bytes. The `DecryptingReader` must use the same size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn with_chunk_size(
+        mut inner: W,
+        key: &Key,
+        chunk_size: usize,
+    ) -> io::Result<EncryptingWriter<W>> {
+        assert!(chunk_size > 0, "chunk_size must not be zero");
+        let (stream, header) = Stream::init_push(key).map_err(stream_error)?;
+        inner.write_all(header.as_ref())?;
+        Ok(EncryptingWriter {
+            inner,
+            stream,
+            chunk_size,
+            pending: Plaintext(Vec::with_capacity(chunk_size)),
+            out: Vec::with_capacity(chunk_size + ABYTES),
+            poisoned: false,
+        })
+    }
+
+    fn check_poisoned(&self) -> io::Result<()> {
+        if self.poisoned {
+            return Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "an earlier chunk of the stream could not be written",
+            ));
+        }
+        Ok(())
+    }
+
+    fn write_chunk(&mut self, tag: Tag) -> io::Result<()> {
+        self.check_poisoned()?;
+        self.poisoned = true;
+        let res = self
+            .stream
+            .push_to_vec(&self.pending.0, None, tag, &mut self.out);
+        self.pending.clear();
+        res.map_err(stream_error)?;
+        self.inner.write_all(&self.out)?;
+        self.poisoned = false;
+        Ok(())
+    }
+
+    /// `finish()` encrypts the last chunk, tagging it as final, flushes the
+    /// inner writer and returns it.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_chunk(Tag::Final)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for EncryptingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check_poisoned()?;
+        if self.pending.0.len() == self.chunk_size {
+            self.write_chunk(Tag::Message)?;
+        }
+        let n = cmp::min(buf.len(), self.chunk_size - self.pending.0.len());
+        self.pending.0.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    /// Flushes the inner writer. Buffered plaintext is not written out before
+    /// a full chunk has been collected or `finish()` is called.
+    fn flush(&mut self) -> io::Result<()> {
+        self.check_poisoned()?;
+        self.inner.flush()
+    }
+}
+
+/// `DecryptingReader` reads a stream produced by an [`EncryptingWriter`] and
+/// returns the plaintext.
+///
+/// Each chunk is authenticated before any of it is returned. A corrupted
+/// chunk, a stream that ends before the final chunk, or data after the final
+/// chunk makes `read()` return an error of kind `io::ErrorKind::InvalidData`.
+pub struct DecryptingReader<R: Read> {
+    inner: R,
+    stream: Stream<Pull>,
+    chunk_size: usize,
+    // ciphertext of the current chunk, possibly partially read
+    record: Vec<u8>,
+    // plaintext of the current chunk and the number of bytes already returned
+    pending: Plaintext,
+    pos: usize,
+    failed: bool,
+}
+
+impl<R: Read> DecryptingReader<R> {
+    /// `new()` reads the stream header from `inner` and prepares to decrypt
+    /// the following chunks, of `CHUNKBYTES` bytes, with the key `key`.
+    pub fn new(inner: R, key: &Key) -> io::Result<DecryptingReader<R>> {
+        DecryptingReader::with_chunk_size(inner, key, CHUNKBYTES)
+    }
+
+    /// `with_chunk_size()` is like `new()`, for streams written with
+    /// `EncryptingWriter::with_chunk_size()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn with_chunk_size(
+        mut inner: R,
+        key: &Key,
+        chunk_size: usize,
+    ) -> io::Result<DecryptingReader<R>> {
+        assert!(chunk_size > 0, "chunk_size must not be zero");
+        let mut header = Header([0; HEADERBYTES]);
+        inner.read_exact(&mut header.0)?;
+        let stream =
+            Stream::init_pull(&header, key).map_err(|_| invalid_data("invalid stream header"))?;
+        Ok(DecryptingReader {
+            inner,
+            stream,
+            chunk_size,
+            record: Vec::with_capacity(chunk_size + ABYTES),
+            pending: Plaintext(Vec::with_capacity(chunk_size)),
+            pos: 0,
+            failed: false,
+        })
+    }
+
+    /// `into_inner()` returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    // Reads up to a full record from `inner`, stopping early only at EOF. If
+    // `inner` fails, the bytes read so far are kept for the next call.
+    fn fill_record(&mut self) -> io::Result<()> {
+        let record_len = self.chunk_size + ABYTES;
+        let mut len = self.record.len();
+        self.record.resize(record_len, 0);
+        while len < record_len {
+            match self.inner.read(&mut self.record[len..]) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.record.truncate(len);
+                    return Err(e);
+                }
+            }
+        }
+        self.record.truncate(len);
+        Ok(())
+    }
+
+    fn next_chunk(&mut self) -> io::Result<()> {
+        self.fill_record()?;
+        if self.record.is_empty() {
+            return Err(invalid_data("truncated stream"));
+        }
+        self.pending.clear();
+        self.pos = 0;
+        let tag = self
+            .stream
+            .pull_to_vec(&self.record, None, &mut self.pending.0)
+            .map_err(|_| invalid_data("invalid chunk"))?;
+        let record_len = self.record.len();
+        self.record.clear();
+        match tag {
+            Tag::Final => {
+                let mut byte = [0u8; 1];
+                if self.inner.read(&mut byte)? != 0 {
+                    return Err(invalid_data("data after the final chunk"));
+                }
+            }
+            _ if record_len < self.chunk_size + ABYTES => {
+                return Err(invalid_data("truncated stream"));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecryptingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if self.failed {
+                return Err(invalid_data("invalid stream"));
+            }
+            if self.pos < self.pending.0.len() {
+                let n = cmp::min(buf.len(), self.pending.0.len() - self.pos);
+                buf[..n].copy_from_slice(&self.pending.0[self.pos..self.pos + n]);
+                self.pos += n;
+                return Ok(n);
+            }
+            if self.stream.is_finalized() {
+                return Ok(0);
+            }
+            if let Err(e) = self.next_chunk() {
+                if e.kind() == io::ErrorKind::InvalidData {
+                    self.failed = true;
+                    self.pending.clear();
+                }
+                return Err(e);
+            }
+        }
+    }
+}
+
+const LENGTHBYTES: usize = 4;
+
+impl Stream<Push> {
+    /// `write_message()` encrypts a message `m` tagged with `tag` and writes
+    /// it to `w` as the length of the ciphertext, a 4 byte little endian
+    /// integer, followed by the ciphertext. [`Stream::messages()`] reads such
+    /// messages back.
+    ///
+    /// It returns an error of kind `io::ErrorKind::InvalidInput` if the
+    /// stream was already finalized or `m` is too long.
+    pub fn write_message<W: Write>(&mut self, w: &mut W, m: &[u8], tag: Tag) -> io::Result<()> {
+        if m.len() > !0u32 as usize - ABYTES {
+            return Err(stream_error(::Error::MessageTooLong));
+        }
+        let c = self.push(m, None, tag).map_err(stream_error)?;
+        w.write_all(&(c.len() as u32).to_le_bytes())?;
+        w.write_all(&c)
+    }
+}
+
+impl Stream<Pull> {
+    /// `messages()` returns an iterator over the messages read from `reader`,
+    /// as written by [`Stream::write_message()`]. Each item is a decrypted
+    /// message and its tag.
+    ///
+    /// The iterator ends after the message tagged with `Tag::Final`, leaving
+    /// any data following it in `reader`. A corrupted message, or a reader
+    /// that ends before the final message, yields an error of kind
+    /// `io::ErrorKind::InvalidData`. The iterator also ends after an error,
+    /// since the position in `reader` is not known any more.
+    pub fn messages<R: Read>(&mut self, reader: R) -> Messages<'_, R> {
+        Messages {
+            stream: self,
+            reader,
+            done: false,
+        }
+    }
+}
+
+/// Iterator over length-prefixed messages, returned by
+/// [`Stream::messages()`].
+pub struct Messages<'a, R: Read> {
+    stream: &'a mut Stream<Pull>,
+    reader: R,
+    done: bool,
+}
+
+impl<'a, R: Read> Messages<'a, R> {
+    fn next_message(&mut self) -> io::Result<(Vec<u8>, Tag)> {
+        let mut len = [0u8; LENGTHBYTES];
+        self.reader.read_exact(&mut len).map_err(truncated)?;
+        let len = u32::from_le_bytes(len) as usize;
+        if len < ABYTES {
+            return Err(invalid_data("invalid message length"));
+        }
+        // read through `take()` so that a bogus length cannot make us
+        // allocate more than the reader actually holds
+        let mut c = Vec::new();
+        (&mut self.reader).take(len as u64).read_to_end(&mut c)?;
+        if c.len() < len {
+            return Err(invalid_data("truncated stream"));
+        }
+        self.stream
+            .pull(&c, None)
+            .map_err(|_| invalid_data("invalid message"))
+    }
+}
+
+fn truncated(e: io::Error) -> io::Error {
+    if e.kind() == io::ErrorKind::UnexpectedEof {
+        invalid_data("truncated stream")
+    } else {
+        e
+    }
+}
+
+impl<'a, R: Read> Iterator for Messages<'a, R> {
+    type Item = io::Result<(Vec<u8>, Tag)>;
+
+    fn next(&mut self) -> Option<io::Result<(Vec<u8>, Tag)>> {
+        if self.done || self.stream.is_finalized() {
+            return None;
+        }
+        let res = self.next_message();
+        self.done = res.is_err();
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::xchacha20poly1305::gen_key;
+    use super::*;
+    use randombytes::randombytes;
+
+    // Reads at most one byte at a time from the wrapped reader.
+    struct OneByteReader<R: Read>(R);
+
+    impl<R: Read> Read for OneByteReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = cmp::min(buf.len(), 1);
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    // Fails every write while `fail` is set.
+    struct FlakyWriter {
+        out: Vec<u8>,
+        fail: bool,
+    }
+
+    impl Write for FlakyWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.fail {
+                return Err(io::Error::new(io::ErrorKind::ConnectionReset, "failed"));
+            }
+            self.out.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    const CHUNK: usize = 16;
+    const SIZES: [usize; 9] = [0, 1, 15, 16, 17, 31, 32, 33, 1000];
+
+    fn encrypt(m: &[u8], key: &Key) -> Vec<u8> {
+        let mut writer = EncryptingWriter::with_chunk_size(Vec::new(), key, CHUNK).unwrap();
+        for chunk in m.chunks(7) {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    fn decrypt<R: Read>(c: R, key: &Key) -> io::Result<Vec<u8>> {
+        let mut reader = DecryptingReader::with_chunk_size(c, key, CHUNK)?;
+        let mut m = Vec::new();
+        reader.read_to_end(&mut m)?;
+        Ok(m)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let key = gen_key();
+        for &size in SIZES.iter() {
+            let m = randombytes(size);
+            let c = encrypt(&m, &key);
+            let chunks = if size == 0 { 1 } else { (size - 1) / CHUNK + 1 };
+            assert_eq!(c.len(), HEADERBYTES + size + chunks * ABYTES);
+            assert_eq!(decrypt(&c[..], &key).unwrap(), m);
+            assert_eq!(decrypt(OneByteReader(&c[..]), &key).unwrap(), m);
+        }
+    }
+
+    #[test]
+    fn test_default_chunk_size() {
+        let key = gen_key();
+        let m = randombytes(3 * CHUNKBYTES + 1);
+        let mut writer = EncryptingWriter::new(Vec::new(), &key).unwrap();
+        io::copy(&mut &m[..], &mut writer).unwrap();
+        let c = writer.finish().unwrap();
+        assert_eq!(c.len(), HEADERBYTES + m.len() + 4 * ABYTES);
+
+        let mut reader = DecryptingReader::new(&c[..], &key).unwrap();
+        let mut m2 = Vec::new();
+        reader.read_to_end(&mut m2).unwrap();
+        assert_eq!(m2, m);
+    }
+
+    #[test]
+    fn test_tamper() {
+        let key = gen_key();
+        let c = encrypt(&randombytes(40), &key);
+        for i in 0..c.len() {
+            let mut tampered = c.clone();
+            tampered[i] ^= 0x20;
+            let err = decrypt(&tampered[..], &key).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_truncated() {
+        let key = gen_key();
+        let c = encrypt(&randombytes(40), &key);
+        for len in 0..c.len() {
+            assert!(decrypt(&c[..len], &key).is_err());
+        }
+    }
+
+    #[test]
+    fn test_unfinished() {
+        let key = gen_key();
+        let mut writer = EncryptingWriter::with_chunk_size(Vec::new(), &key, CHUNK).unwrap();
+        writer.write_all(&randombytes(3 * CHUNK)).unwrap();
+        let c = writer.inner.clone();
+        let err = decrypt(&c[..], &key).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_failed_write() {
+        let key = gen_key();
+        let inner = FlakyWriter {
+            out: Vec::new(),
+            fail: false,
+        };
+        let mut writer = EncryptingWriter::with_chunk_size(inner, &key, CHUNK).unwrap();
+        writer.write_all(&randombytes(2 * CHUNK)).unwrap();
+        writer.inner.fail = true;
+        let err = writer.write_all(b"more data").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+
+        // the failed chunk cannot be written again, and nothing after it
+        writer.inner.fail = false;
+        let len = writer.inner.out.len();
+        let err = writer.write_all(b"more data").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        assert!(writer.flush().is_err());
+        assert_eq!(writer.inner.out.len(), len);
+        assert!(writer.finish().is_err());
+    }
+
+    #[test]
+    fn test_trailing_data() {
+        let key = gen_key();
+        let mut c = encrypt(b"some data", &key);
+        c.push(0);
+        let err = decrypt(&c[..], &key).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_wrong_key() {
+        let c = encrypt(b"some data", &gen_key());
+        assert!(decrypt(&c[..], &gen_key()).is_err());
+    }
+
+    fn write_messages(messages: &[(&[u8], Tag)], key: &Key) -> (Header, Vec<u8>) {
+        let (mut stream, header) = Stream::init_push(key).unwrap();
+        let mut c = Vec::new();
+        for &(m, tag) in messages {
+            stream.write_message(&mut c, m, tag).unwrap();
+        }
+        (header, c)
+    }
+
+    #[test]
+    fn test_messages() {
+        let key = gen_key();
+        let m = randombytes(1000);
+        let messages: [(&[u8], Tag); 4] = [
+            (b"", Tag::Message),
+            (&m, Tag::Push),
+            (b"some data", Tag::Rekey),
+            (b"last", Tag::Final),
+        ];
+        let (header, mut c) = write_messages(&messages, &key);
+        let len = c.len();
+        c.extend_from_slice(b"trailing data");
+
+        let mut stream = Stream::init_pull(&header, &key).unwrap();
+        let mut reader = &c[..];
+        {
+            let mut iter = stream.messages(&mut reader);
+            for &(m, tag) in messages.iter() {
+                assert_eq!(iter.next().unwrap().unwrap(), (m.to_vec(), tag));
+            }
+            assert!(iter.next().is_none());
+        }
+        assert!(stream.is_finalized());
+        assert_eq!(reader, &c[len..]);
+    }
+
+    #[test]
+    fn test_messages_truncated() {
+        let key = gen_key();
+        let (header, c) = write_messages(&[(b"first", Tag::Message), (b"last", Tag::Final)], &key);
+        for len in 0..c.len() {
+            let mut stream = Stream::init_pull(&header, &key).unwrap();
+            let res: io::Result<Vec<_>> = stream.messages(&c[..len]).collect();
+            assert_eq!(res.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_messages_tamper() {
+        let key = gen_key();
+        let (header, c) = write_messages(&[(b"first", Tag::Message), (b"last", Tag::Final)], &key);
+        for i in 0..c.len() {
+            let mut tampered = c.clone();
+            tampered[i] ^= 0x20;
+            let mut stream = Stream::init_pull(&header, &key).unwrap();
+            let mut iter = stream.messages(&tampered[..]);
+            let err = iter.find(|res| res.is_err()).unwrap().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(iter.next().is_none());
+        }
+    }
+
+    #[test]
+    fn test_write_message_finalized() {
+        let (mut stream, _) = Stream::init_push(&gen_key()).unwrap();
+        let mut c = Vec::new();
+        stream.write_message(&mut c, b"last", Tag::Final).unwrap();
+        let len = c.len();
+        let err = stream
+            .write_message(&mut c, b"more", Tag::Message)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(c.len(), len);
+    }
+}
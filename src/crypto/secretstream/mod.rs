@@ -24,6 +24,12 @@
 //!
 //! The `crypto_secretstream_*()` API was introduced in libsodium 1.0.14.
 //!
+//! To encrypt a file or a socket, the [`io`](io/index.html) module provides
+//! `EncryptingWriter` and `DecryptingReader`, which split the data into chunks
+//! and handle the header. With the `tokio` feature, the
+//! [`async_io`](async_io/index.html) module provides the same for tokio's
+//! `AsyncRead` and `AsyncWrite`.
+//!
 //! # Example
 //! ```
 //! use sodiumoxide::crypto::secretstream::{gen_key, Stream, Tag};
@@ -74,3 +80,13 @@ pub use self::xchacha20poly1305::*;
 #[macro_use]
 mod secretstream_macros;
 pub mod xchacha20poly1305;
+
+#[cfg(feature = "std")]
+pub use self::io::{DecryptingReader, EncryptingWriter, Messages};
+#[cfg(feature = "std")]
+pub mod io;
+
+#[cfg(all(feature = "std", feature = "tokio"))]
+pub use self::async_io::{AsyncDecryptingReader, AsyncEncryptingWriter};
+#[cfg(all(feature = "std", feature = "tokio"))]
+pub mod async_io;
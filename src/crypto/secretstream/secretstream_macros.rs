@@ -17,6 +17,7 @@ use libc::c_ulonglong;
 #[cfg(not(feature = "std"))]
 use prelude::Vec;
 use randombytes::randombytes_into;
+use Error;
 use std::mem;
 use std::ops::Drop;
 use std::ptr;
@@ -125,20 +126,52 @@ pub fn gen_key() -> Key {
 
 /// `Stream` contains the state for multi-part (streaming) computations. This
 /// allows the caller to process encryption of a sequence of multiple messages.
+///
+/// When a `Stream` goes out of scope its state, which contains the current
+/// subkey, will be zeroed out.
 pub struct Stream<M: StreamMode> {
     state: $state_name,
     finalized: bool,
     phantom: core::marker::PhantomData<M>,
 }
 
+impl<M: StreamMode> Drop for Stream<M> {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::sodium_memzero(
+                &mut self.state as *mut $state_name as *mut _,
+                mem::size_of_val(&self.state),
+            );
+        }
+    }
+}
+
+/// Zeroes out the state and marks the stream as finalized, so that any
+/// further use fails with `Error::Finalized`.
+#[cfg(feature = "zeroize")]
+impl<M: StreamMode> ::zeroize::Zeroize for Stream<M> {
+    fn zeroize(&mut self) {
+        unsafe {
+            ffi::sodium_memzero(
+                &mut self.state as *mut $state_name as *mut _,
+                mem::size_of_val(&self.state),
+            );
+        }
+        self.finalized = true;
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<M: StreamMode> ::zeroize::ZeroizeOnDrop for Stream<M> {}
+
 impl<M: StreamMode> Stream<M> {
     /// Explicit rekeying. This updates the internal state of the `Stream<Pull>`,
     /// and should only be called in a synchronized manner with how the
     /// corresponding `Stream` called it when encrypting the stream. Returns
-    /// `Err(())` if the stream was already finalized, else `Ok(())`.
-    pub fn rekey(&mut self) -> Result<(), ()> {
+    /// `Err(Error::Finalized)` if the stream was already finalized, else `Ok(())`.
+    pub fn rekey(&mut self) -> Result<(), Error> {
         if self.finalized {
-            return Err(());
+            return Err(Error::Finalized);
         }
         unsafe {
             $rekey_name(&mut self.state);
@@ -168,9 +201,11 @@ impl Stream<Push> {
     /// shared key that can be used to encrypt streams. Similarly, file
     /// encryption applications can use the password hashing API to get a key
     /// that can be used with the functions below.
-    pub fn init_push(key: &Key) -> Result<(Stream<Push>, Header), ()> {
+    pub fn init_push(key: &Key) -> Result<(Stream<Push>, Header), Error> {
+        ::ensure_init();
         let mut header = mem::MaybeUninit::<[u8; HEADERBYTES]>::uninit();
-        let mut state = mem::MaybeUninit::uninit();
+        // start from zeroes in case the init function skips padding bytes
+        let mut state = mem::MaybeUninit::zeroed();
         let rc = unsafe {
             $init_push_name(
                 state.as_mut_ptr(),
@@ -179,7 +214,7 @@ impl Stream<Push> {
             )
         };
         if rc != 0 {
-            return Err(());
+            return Err(Error::InitFailed);
         }
         // rc == 0 and both state and header are initialized
         let state = unsafe { state.assume_init() };
@@ -198,7 +233,11 @@ impl Stream<Push> {
     /// All data (including optional fields) is authenticated. Encrypts a
     /// message `m` and its `tag`. Optionally includes additional data `ad`,
     /// which is not encrypted.
-    pub fn push(&mut self, m: &[u8], ad: Option<&[u8]>, tag: Tag) -> Result<Vec<u8>, ()> {
+    ///
+    /// Returns `Err(Error::Finalized)` if the stream has already been
+    /// finalized, and `Err(Error::MessageTooLong)` if `m` is longer than
+    /// `messagebytes_max()`.
+    pub fn push(&mut self, m: &[u8], ad: Option<&[u8]>, tag: Tag) -> Result<Vec<u8>, Error> {
         let buf_len = self.push_check(m, tag)?;
 
         let mut buf = Vec::with_capacity(buf_len);
@@ -211,20 +250,20 @@ impl Stream<Push> {
     /// which is not encrypted.
     ///
     /// The encrypted message is written to the `out` vector, overwriting any existing data there.
-    pub fn push_to_vec(&mut self, m: &[u8], ad: Option<&[u8]>, tag: Tag, out: &mut Vec<u8>) -> Result<(), ()> {
+    pub fn push_to_vec(&mut self, m: &[u8], ad: Option<&[u8]>, tag: Tag, out: &mut Vec<u8>) -> Result<(), Error> {
         let buf_len = self.push_check(m, tag)?;
         out.clear();
         out.reserve(buf_len);
         self.push_impl(m, ad, tag, out)
     }
 
-    fn push_check(&mut self, m: &[u8], tag: Tag) -> Result<usize, ()> {
+    fn push_check(&mut self, m: &[u8], tag: Tag) -> Result<usize, Error> {
         if self.finalized {
-            return Err(());
+            return Err(Error::Finalized);
         }
         let m_len = m.len();
         if m_len > messagebytes_max() {
-            return Err(());
+            return Err(Error::MessageTooLong);
         }
         if tag == Tag::Final {
             self.finalized = true;
@@ -232,7 +271,7 @@ impl Stream<Push> {
         Ok(m_len + ABYTES)
     }
 
-    fn push_impl(&mut self, m: &[u8], ad: Option<&[u8]>, tag: Tag, buf: &mut Vec<u8>) -> Result<(), ()> {
+    fn push_impl(&mut self, m: &[u8], ad: Option<&[u8]>, tag: Tag, buf: &mut Vec<u8>) -> Result<(), Error> {
         let (ad_p, ad_len) = ad
             .map(|ad| (ad.as_ptr(), ad.len()))
             .unwrap_or((ptr::null(), 0));
@@ -249,7 +288,7 @@ impl Stream<Push> {
                 tag as u8,
                 );
             if rc != 0 {
-                return Err(());
+                return Err(Error::InvalidLength);
             }
             buf.set_len(c_len as usize);
         }
@@ -259,7 +298,7 @@ impl Stream<Push> {
     /// Create a ciphertext for an empty message with the `TAG_FINAL` added
     /// to signal the end of the stream. Since the `Stream` is not usable
     /// after this point, this method consumes the `Stream`.
-    pub fn finalize(mut self, ad: Option<&[u8]>) -> Result<Vec<u8>, ()> {
+    pub fn finalize(mut self, ad: Option<&[u8]>) -> Result<Vec<u8>, Error> {
         self.push(&[], ad, Tag::Final)
     }
 
@@ -267,10 +306,11 @@ impl Stream<Push> {
 
 impl Stream<Pull> {
     /// Initializes a `Stream<Pull>` given a secret `Key` and a `Header`. The key
-    /// will not be required any more for subsequent operations. `Err(())` is
-    /// returned if the header is invalid.
-    pub fn init_pull(header: &Header, key: &Key) -> Result<Stream<Pull>, ()> {
-        let mut state = mem::MaybeUninit::uninit();
+    /// will not be required any more for subsequent operations.
+    /// `Err(Error::InitFailed)` is returned if the header is invalid.
+    pub fn init_pull(header: &Header, key: &Key) -> Result<Stream<Pull>, Error> {
+        // start from zeroes in case the init function skips padding bytes
+        let mut state = mem::MaybeUninit::zeroed();
         let rc = unsafe {
             $init_pull_name(
                 state.as_mut_ptr(),
@@ -283,9 +323,9 @@ impl Stream<Pull> {
             // but when implementing error types we should still consider the
             // possibility of some other non-zero code below with a generic call
             // to external function failed error.
-            return Err(());
+            return Err(Error::InitFailed);
         } else if rc != 0 {
-            return Err(());
+            return Err(Error::InitFailed);
         }
         // rc == 0 and state is initialized
         let state = unsafe { state.assume_init() };
@@ -303,12 +343,15 @@ impl Stream<Pull> {
     /// unencrypted additional data `ad` using the authentication tag attached to
     /// `c`. Finally decrypts the ciphertext and tag, and checks the tag
     /// validity.
-    /// If any authentication fails, the stream has already been finalized, or if
-    /// the tag byte for some reason does not correspond to a valid `Tag`,
-    /// returns `Err(())`. Otherwise returns the plaintext and the tag.
+    /// If any authentication fails, or if the tag byte for some reason does not
+    /// correspond to a valid `Tag`, returns `Err(Error::VerificationFailed)`.
+    /// If the stream has already been finalized, returns `Err(Error::Finalized)`,
+    /// and if the plaintext would be longer than `messagebytes_max()`, returns
+    /// `Err(Error::MessageTooLong)`.
+    /// Otherwise returns the plaintext and the tag.
     /// Applications will typically use a `while stream.is_not_finalized()`
     /// loop to authenticate and decrypt a stream of messages.
-    pub fn pull(&mut self, c: &[u8], ad: Option<&[u8]>) -> Result<(Vec<u8>, Tag), ()> {
+    pub fn pull(&mut self, c: &[u8], ad: Option<&[u8]>) -> Result<(Vec<u8>, Tag), Error> {
         let m_len = self.pull_check(c)?;
         let mut buf = Vec::with_capacity(m_len);
         let tag = self.pull_impl(c, ad, &mut buf)?;
@@ -321,37 +364,40 @@ impl Stream<Pull> {
     /// unencrypted additional data `ad` using the authentication tag attached to
     /// `c`. Finally decrypts the ciphertext and tag, and checks the tag
     /// validity.
-    /// If any authentication fails, the stream has already been finalized, or if
-    /// the tag byte for some reason does not correspond to a valid `Tag`,
-    /// returns `Err(())`. Otherwise returns the plaintext and the tag.
+    /// If any authentication fails, or if the tag byte for some reason does not
+    /// correspond to a valid `Tag`, returns `Err(Error::VerificationFailed)`.
+    /// If the stream has already been finalized, returns `Err(Error::Finalized)`,
+    /// and if the plaintext would be longer than `messagebytes_max()`, returns
+    /// `Err(Error::MessageTooLong)`.
+    /// Otherwise returns the plaintext and the tag.
     /// Applications will typically use a `while stream.is_not_finalized()`
     /// loop to authenticate and decrypt a stream of messages.
     ///
     /// The decrypted message is written to the `out` vector, overwriting any existing data there.
-    pub fn pull_to_vec(&mut self, c: &[u8], ad: Option<&[u8]>, out: &mut Vec<u8>) -> Result<Tag, ()> {
+    pub fn pull_to_vec(&mut self, c: &[u8], ad: Option<&[u8]>, out: &mut Vec<u8>) -> Result<Tag, Error> {
         let m_len = self.pull_check(c)?;
         out.clear();
         out.reserve(m_len);
         self.pull_impl(c, ad, out)
     }
 
-    fn pull_check(&self, c: &[u8]) -> Result<usize, ()> {
+    fn pull_check(&self, c: &[u8]) -> Result<usize, Error> {
         if self.finalized {
-            return Err(());
+            return Err(Error::Finalized);
         }
         let c_len = c.len();
         if c_len < ABYTES {
             // An empty message will still be at least ABYTES.
-            return Err(());
+            return Err(Error::InvalidLength);
         }
         let m_len = c_len - ABYTES;
         if m_len > messagebytes_max() {
-            return Err(());
+            return Err(Error::MessageTooLong);
         }
         Ok(m_len)
     }
 
-    fn pull_impl(&mut self, c: &[u8], ad: Option<&[u8]>, buf: &mut Vec<u8>) -> Result<Tag, ()> {
+    fn pull_impl(&mut self, c: &[u8], ad: Option<&[u8]>, buf: &mut Vec<u8>) -> Result<Tag, Error> {
         let mut tag: u8 = 0;
         let mut m_len: c_ulonglong = 0;
         let (ad_p, ad_len) = ad
@@ -369,13 +415,13 @@ impl Stream<Pull> {
                 ad_len as c_ulonglong,
                 );
             if rc != 0 {
-                return Err(());
+                return Err(Error::VerificationFailed);
             }
             // rc == 0 and tag is initialized
             buf.set_len(m_len as usize);
         }
 
-        let tag = Tag::from_u8(tag)?;
+        let tag = Tag::from_u8(tag).map_err(|()| Error::VerificationFailed)?;
         if tag == Tag::Final {
             self.finalized = true;
         }
@@ -54,6 +54,22 @@ mod test {
         assert!(stream.pull(&ciphertext, None).is_err());
     }
 
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroize() {
+        use zeroize::Zeroize;
+        let mut key = gen_key();
+        let (mut stream, _) = Stream::init_push(&key).unwrap();
+        stream.zeroize();
+        assert!(stream.is_finalized());
+        assert_eq!(
+            stream.push(b"", None, Tag::Message),
+            Err(::Error::Finalized)
+        );
+        key.zeroize();
+        assert_eq!(key.0, [0; KEYBYTES]);
+    }
+
     #[test]
     fn push_pull() {
         let mut msg1 = [0; 128];
@@ -255,8 +271,7 @@ mod test {
         let mut stream = Stream::init_pull(&header, &key).unwrap();
         assert!(stream.is_not_finalized());
         stream.pull(&c, None).unwrap();
-        // TODO: check specific `Err(())` when implemented (#221).
-        assert!(stream.pull(&c, None).is_err());
+        assert_eq!(stream.pull(&c, None), Err(::Error::Finalized));
     }
 
     #[test]
@@ -265,8 +280,7 @@ mod test {
         let key = gen_key();
         let (mut stream, _) = Stream::init_push(&key).unwrap();
         stream.push(&m, None, Tag::Final).unwrap();
-        // TODO: check specific `Err(())` when implemented (#221)
-        assert!(stream.push(&m, None, Tag::Message).is_err());
+        assert_eq!(stream.push(&m, None, Tag::Message), Err(::Error::Finalized));
     }
 
     #[test]
@@ -278,8 +292,7 @@ mod test {
         let mut stream = Stream::init_pull(&header, &key).unwrap();
         assert!(stream.is_not_finalized());
         stream.pull(&c, None).unwrap();
-        // TODO: check specific `Err(())` when implemented (#221).
-        assert!(stream.rekey().is_err());
+        assert_eq!(stream.rekey(), Err(::Error::Finalized));
     }
 
     #[test]
@@ -12,6 +12,10 @@
 //! `shorthash()` is currently an implementation of `SipHash-2-4` as specified in
 //! [`SipHash`: a fast short-input PRF](https://131002.net/siphash/)
 //!
+//! # Alternate primitives
+//! [`siphashx24`] computes the same function with a 128-bit output, for
+//! applications that need a lower collision probability.
+//!
 //! # Example
 //! ```
 //! use sodiumoxide::crypto::shorthash;
@@ -20,6 +24,13 @@
 //! let data_to_hash = b"some data";
 //! let digest = shorthash::shorthash(data_to_hash, &key);
 //! ```
+//!
+//! # Hash tables
+//! `SipHashBuilder` implements `BuildHasher` on top of `shorthash()`, so it
+//! can be used as the hasher of a `HashMap` or `HashSet` with a key managed
+//! by the application. The `SipHasher` of both primitives can also be used on
+//! its own with `Hash` implementations, and gives access to the full digest.
 
 pub use self::siphash24::*;
 pub mod siphash24;
+pub mod siphashx24;
@@ -2,7 +2,10 @@
 
 use ffi;
 use libc::c_ulonglong;
+#[cfg(not(feature = "std"))]
+use prelude::*;
 use randombytes::randombytes_into;
+use std::hash::{BuildHasher, Hasher};
 
 /// Number of bytes in a `Digest`.
 pub const DIGESTBYTES: usize = ffi::crypto_shorthash_siphash24_BYTES as usize;
@@ -48,11 +51,95 @@ pub fn shorthash(m: &[u8], k: &Key) -> Digest {
     }
 }
 
+/// `SipHasher` is a `Hasher` computing `shorthash()` under a fixed key.
+///
+/// libsodium has no streaming interface for SipHash, so the written bytes
+/// are buffered and hashed at once by `finish()`. This is meant for the short
+/// inputs of hash tables.
+#[derive(Clone, Debug)]
+pub struct SipHasher {
+    key: Key,
+    buf: Vec<u8>,
+}
+
+impl SipHasher {
+    /// `new()` creates a `SipHasher` using the key `k`.
+    pub fn new(k: &Key) -> SipHasher {
+        SipHasher {
+            key: k.clone(),
+            buf: Vec::new(),
+        }
+    }
+
+    /// `digest()` returns the `shorthash()` of the bytes written so far, of
+    /// which `finish()` returns the little-endian value.
+    pub fn digest(&self) -> Digest {
+        shorthash(&self.buf, &self.key)
+    }
+}
+
+impl Hasher for SipHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        u64::from_le_bytes(self.digest().0)
+    }
+}
+
+/// `SipHashBuilder` is a `BuildHasher` creating `SipHasher`s with the same
+/// key, for use in `HashMap`s and `HashSet`s whose keys are chosen by an
+/// attacker.
+///
+/// Unlike the `RandomState` of the standard library, the key is managed by
+/// the application, e.g. to share it between processes, or to pick a fresh
+/// one for every table. `default()` uses a random key.
+///
+/// # Example
+/// ```
+/// use sodiumoxide::crypto::shorthash::{self, SipHashBuilder};
+/// use std::collections::HashMap;
+///
+/// let builder = SipHashBuilder::new(shorthash::gen_key());
+/// let mut map = HashMap::with_hasher(builder);
+/// map.insert("attacker-controlled", 1);
+/// assert_eq!(map.get("attacker-controlled"), Some(&1));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SipHashBuilder {
+    key: Key,
+}
+
+impl SipHashBuilder {
+    /// `new()` creates a `SipHashBuilder` for the key `k`.
+    pub fn new(k: Key) -> SipHashBuilder {
+        SipHashBuilder { key: k }
+    }
+
+    /// `key()` returns the key of the builder.
+    pub fn key(&self) -> &Key {
+        &self.key
+    }
+}
+
+impl Default for SipHashBuilder {
+    fn default() -> SipHashBuilder {
+        SipHashBuilder::new(gen_key())
+    }
+}
+
+impl BuildHasher for SipHashBuilder {
+    type Hasher = SipHasher;
+
+    fn build_hasher(&self) -> SipHasher {
+        SipHasher::new(&self.key)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    #[cfg(not(feature = "std"))]
-    use prelude::*;
 
     #[test]
     fn test_vectors() {
@@ -134,6 +221,50 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_hasher() {
+        use randombytes::randombytes;
+        let k = gen_key();
+        for i in 0..64usize {
+            let m = randombytes(i);
+            let mut hasher = SipHasher::new(&k);
+            for chunk in m.chunks(3) {
+                hasher.write(chunk);
+            }
+            assert_eq!(hasher.finish().to_le_bytes(), shorthash(&m, &k).0, "{}", i);
+        }
+    }
+
+    #[test]
+    #[allow(clippy::manual_hash_one)] // hash_one() is newer than the minimum supported Rust
+    fn test_build_hasher() {
+        use std::hash::Hash;
+        let builder = SipHashBuilder::new(gen_key());
+        let hash = |builder: &SipHashBuilder, value: &str| {
+            let mut hasher = builder.build_hasher();
+            value.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&builder, "key"), hash(&builder.clone(), "key"));
+        assert_ne!(hash(&builder, "key"), hash(&builder, "kez"));
+        let other = SipHashBuilder::new(gen_key());
+        assert_ne!(hash(&builder, "key"), hash(&other, "key"));
+        assert_ne!(builder.key(), SipHashBuilder::default().key());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hash_map() {
+        use std::collections::HashMap;
+        let mut map = HashMap::with_hasher(SipHashBuilder::default());
+        for i in 0..100u32 {
+            map.insert(i, i * 2);
+        }
+        for i in 0..100u32 {
+            assert_eq!(map.get(&i), Some(&(i * 2)));
+        }
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn test_serialisation() {
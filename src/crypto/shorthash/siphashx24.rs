@@ -0,0 +1,412 @@
+//! `SipHash-2-4` with a 128-bit output, the variant of
+//! [`SipHash`: a fast short-input PRF](https://131002.net/siphash/) provided by
+//! the reference implementation.
+//!
+//! The longer output makes collisions far less likely than with the 64-bit
+//! `siphash24`, e.g. for deduplication tables keyed on the digest.
+
+use ffi;
+use libc::c_ulonglong;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use randombytes::randombytes_into;
+use std::hash::Hasher;
+
+/// Number of bytes in a `Digest`.
+pub const DIGESTBYTES: usize = ffi::crypto_shorthash_siphashx24_BYTES as usize;
+
+/// Number of bytes in a `Key`.
+pub const KEYBYTES: usize = ffi::crypto_shorthash_siphashx24_KEYBYTES as usize;
+
+new_type! {
+    /// `Digest` structure
+    public Digest(DIGESTBYTES);
+}
+
+new_type! {
+    /// `Key`
+    ///
+    /// When a `Key` goes out of scope its contents will be zeroed out.
+    secret Key(KEYBYTES);
+}
+
+/// `gen_key()` randomly generates a key for shorthash
+///
+/// THREAD SAFETY: `gen_key()` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+pub fn gen_key() -> Key {
+    let mut k = [0; KEYBYTES];
+    randombytes_into(&mut k);
+    Key(k)
+}
+
+/// `shorthash` hashes a message `m` under a key `k`. It
+/// returns a hash `h`.
+pub fn shorthash(m: &[u8], k: &Key) -> Digest {
+    unsafe {
+        let mut h = [0; DIGESTBYTES];
+        ffi::crypto_shorthash_siphashx24(
+            h.as_mut_ptr(),
+            m.as_ptr(),
+            m.len() as c_ulonglong,
+            k.0.as_ptr(),
+        );
+        Digest(h)
+    }
+}
+
+/// `SipHasher` is a `Hasher` computing `shorthash()` under a fixed key.
+///
+/// `finish()` only returns the low 64 bits of the digest, `digest()` returns
+/// all of it. As with `siphash24::SipHasher`, the written bytes are buffered
+/// and hashed at once.
+#[derive(Clone, Debug)]
+pub struct SipHasher {
+    key: Key,
+    buf: Vec<u8>,
+}
+
+impl SipHasher {
+    /// `new()` creates a `SipHasher` using the key `k`.
+    pub fn new(k: &Key) -> SipHasher {
+        SipHasher {
+            key: k.clone(),
+            buf: Vec::new(),
+        }
+    }
+
+    /// `digest()` returns the `shorthash()` of the bytes written so far.
+    pub fn digest(&self) -> Digest {
+        shorthash(&self.buf, &self.key)
+    }
+}
+
+impl Hasher for SipHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let mut low = [0; 8];
+        low.copy_from_slice(&self.digest().0[..8]);
+        u64::from_le_bytes(low)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_vectors() {
+        let maxlen = 64;
+        let mut m = Vec::with_capacity(64);
+        for i in 0usize..64 {
+            m.push(i as u8);
+        }
+        let h_expecteds = [
+            [
+                0xa3, 0x81, 0x7f, 0x04, 0xba, 0x25, 0xa8, 0xe6, 0x6d, 0xf6, 0x72, 0x14, 0xc7, 0x55,
+                0x02, 0x93,
+            ],
+            [
+                0xda, 0x87, 0xc1, 0xd8, 0x6b, 0x99, 0xaf, 0x44, 0x34, 0x76, 0x59, 0x11, 0x9b, 0x22,
+                0xfc, 0x45,
+            ],
+            [
+                0x81, 0x77, 0x22, 0x8d, 0xa4, 0xa4, 0x5d, 0xc7, 0xfc, 0xa3, 0x8b, 0xde, 0xf6, 0x0a,
+                0xff, 0xe4,
+            ],
+            [
+                0x9c, 0x70, 0xb6, 0x0c, 0x52, 0x67, 0xa9, 0x4e, 0x5f, 0x33, 0xb6, 0xb0, 0x29, 0x85,
+                0xed, 0x51,
+            ],
+            [
+                0xf8, 0x81, 0x64, 0xc1, 0x2d, 0x9c, 0x8f, 0xaf, 0x7d, 0x0f, 0x6e, 0x7c, 0x7b, 0xcd,
+                0x55, 0x79,
+            ],
+            [
+                0x13, 0x68, 0x87, 0x59, 0x80, 0x77, 0x6f, 0x88, 0x54, 0x52, 0x7a, 0x07, 0x69, 0x0e,
+                0x96, 0x27,
+            ],
+            [
+                0x14, 0xee, 0xca, 0x33, 0x8b, 0x20, 0x86, 0x13, 0x48, 0x5e, 0xa0, 0x30, 0x8f, 0xd7,
+                0xa1, 0x5e,
+            ],
+            [
+                0xa1, 0xf1, 0xeb, 0xbe, 0xd8, 0xdb, 0xc1, 0x53, 0xc0, 0xb8, 0x4a, 0xa6, 0x1f, 0xf0,
+                0x82, 0x39,
+            ],
+            [
+                0x3b, 0x62, 0xa9, 0xba, 0x62, 0x58, 0xf5, 0x61, 0x0f, 0x83, 0xe2, 0x64, 0xf3, 0x14,
+                0x97, 0xb4,
+            ],
+            [
+                0x26, 0x44, 0x99, 0x06, 0x0a, 0xd9, 0xba, 0xab, 0xc4, 0x7f, 0x8b, 0x02, 0xbb, 0x6d,
+                0x71, 0xed,
+            ],
+            [
+                0x00, 0x11, 0x0d, 0xc3, 0x78, 0x14, 0x69, 0x56, 0xc9, 0x54, 0x47, 0xd3, 0xf3, 0xd0,
+                0xfb, 0xba,
+            ],
+            [
+                0x01, 0x51, 0xc5, 0x68, 0x38, 0x6b, 0x66, 0x77, 0xa2, 0xb4, 0xdc, 0x6f, 0x81, 0xe5,
+                0xdc, 0x18,
+            ],
+            [
+                0xd6, 0x26, 0xb2, 0x66, 0x90, 0x5e, 0xf3, 0x58, 0x82, 0x63, 0x4d, 0xf6, 0x85, 0x32,
+                0xc1, 0x25,
+            ],
+            [
+                0x98, 0x69, 0xe2, 0x47, 0xe9, 0xc0, 0x8b, 0x10, 0xd0, 0x29, 0x93, 0x4f, 0xc4, 0xb9,
+                0x52, 0xf7,
+            ],
+            [
+                0x31, 0xfc, 0xef, 0xac, 0x66, 0xd7, 0xde, 0x9c, 0x7e, 0xc7, 0x48, 0x5f, 0xe4, 0x49,
+                0x49, 0x02,
+            ],
+            [
+                0x54, 0x93, 0xe9, 0x99, 0x33, 0xb0, 0xa8, 0x11, 0x7e, 0x08, 0xec, 0x0f, 0x97, 0xcf,
+                0xc3, 0xd9,
+            ],
+            [
+                0x6e, 0xe2, 0xa4, 0xca, 0x67, 0xb0, 0x54, 0xbb, 0xfd, 0x33, 0x15, 0xbf, 0x85, 0x23,
+                0x05, 0x77,
+            ],
+            [
+                0x47, 0x3d, 0x06, 0xe8, 0x73, 0x8d, 0xb8, 0x98, 0x54, 0xc0, 0x66, 0xc4, 0x7a, 0xe4,
+                0x77, 0x40,
+            ],
+            [
+                0xa4, 0x26, 0xe5, 0xe4, 0x23, 0xbf, 0x48, 0x85, 0x29, 0x4d, 0xa4, 0x81, 0xfe, 0xae,
+                0xf7, 0x23,
+            ],
+            [
+                0x78, 0x01, 0x77, 0x31, 0xcf, 0x65, 0xfa, 0xb0, 0x74, 0xd5, 0x20, 0x89, 0x52, 0x51,
+                0x2e, 0xb1,
+            ],
+            [
+                0x9e, 0x25, 0xfc, 0x83, 0x3f, 0x22, 0x90, 0x73, 0x3e, 0x93, 0x44, 0xa5, 0xe8, 0x38,
+                0x39, 0xeb,
+            ],
+            [
+                0x56, 0x8e, 0x49, 0x5a, 0xbe, 0x52, 0x5a, 0x21, 0x8a, 0x22, 0x14, 0xcd, 0x3e, 0x07,
+                0x1d, 0x12,
+            ],
+            [
+                0x4a, 0x29, 0xb5, 0x45, 0x52, 0xd1, 0x6b, 0x9a, 0x46, 0x9c, 0x10, 0x52, 0x8e, 0xff,
+                0x0a, 0xae,
+            ],
+            [
+                0xc9, 0xd1, 0x84, 0xdd, 0xd5, 0xa9, 0xf5, 0xe0, 0xcf, 0x8c, 0xe2, 0x9a, 0x9a, 0xbf,
+                0x69, 0x1c,
+            ],
+            [
+                0x2d, 0xb4, 0x79, 0xae, 0x78, 0xbd, 0x50, 0xd8, 0x88, 0x2a, 0x8a, 0x17, 0x8a, 0x61,
+                0x32, 0xad,
+            ],
+            [
+                0x8e, 0xce, 0x5f, 0x04, 0x2d, 0x5e, 0x44, 0x7b, 0x50, 0x51, 0xb9, 0xea, 0xcb, 0x8d,
+                0x8f, 0x6f,
+            ],
+            [
+                0x9c, 0x0b, 0x53, 0xb4, 0xb3, 0xc3, 0x07, 0xe8, 0x7e, 0xae, 0xe0, 0x86, 0x78, 0x14,
+                0x1f, 0x66,
+            ],
+            [
+                0xab, 0xf2, 0x48, 0xaf, 0x69, 0xa6, 0xea, 0xe4, 0xbf, 0xd3, 0xeb, 0x2f, 0x12, 0x9e,
+                0xeb, 0x94,
+            ],
+            [
+                0x06, 0x64, 0xda, 0x16, 0x68, 0x57, 0x4b, 0x88, 0xb9, 0x35, 0xf3, 0x02, 0x73, 0x58,
+                0xae, 0xf4,
+            ],
+            [
+                0xaa, 0x4b, 0x9d, 0xc4, 0xbf, 0x33, 0x7d, 0xe9, 0x0c, 0xd4, 0xfd, 0x3c, 0x46, 0x7c,
+                0x6a, 0xb7,
+            ],
+            [
+                0xea, 0x5c, 0x7f, 0x47, 0x1f, 0xaf, 0x6b, 0xde, 0x2b, 0x1a, 0xd7, 0xd4, 0x68, 0x6d,
+                0x22, 0x87,
+            ],
+            [
+                0x29, 0x39, 0xb0, 0x18, 0x32, 0x23, 0xfa, 0xfc, 0x17, 0x23, 0xde, 0x4f, 0x52, 0xc4,
+                0x3d, 0x35,
+            ],
+            [
+                0x7c, 0x39, 0x56, 0xca, 0x5e, 0xea, 0xfc, 0x3e, 0x36, 0x3e, 0x9d, 0x55, 0x65, 0x46,
+                0xeb, 0x68,
+            ],
+            [
+                0x77, 0xc6, 0x07, 0x71, 0x46, 0xf0, 0x1c, 0x32, 0xb6, 0xb6, 0x9d, 0x5f, 0x4e, 0xa9,
+                0xff, 0xcf,
+            ],
+            [
+                0x37, 0xa6, 0x98, 0x6c, 0xb8, 0x84, 0x7e, 0xdf, 0x09, 0x25, 0xf0, 0xf1, 0x30, 0x9b,
+                0x54, 0xde,
+            ],
+            [
+                0xa7, 0x05, 0xf0, 0xe6, 0x9d, 0xa9, 0xa8, 0xf9, 0x07, 0x24, 0x1a, 0x2e, 0x92, 0x3c,
+                0x8c, 0xc8,
+            ],
+            [
+                0x3d, 0xc4, 0x7d, 0x1f, 0x29, 0xc4, 0x48, 0x46, 0x1e, 0x9e, 0x76, 0xed, 0x90, 0x4f,
+                0x67, 0x11,
+            ],
+            [
+                0x0d, 0x62, 0xbf, 0x01, 0xe6, 0xfc, 0x0e, 0x1a, 0x0d, 0x3c, 0x47, 0x51, 0xc5, 0xd3,
+                0x69, 0x2b,
+            ],
+            [
+                0x8c, 0x03, 0x46, 0x8b, 0xca, 0x7c, 0x66, 0x9e, 0xe4, 0xfd, 0x5e, 0x08, 0x4b, 0xbe,
+                0xe7, 0xb5,
+            ],
+            [
+                0x52, 0x8a, 0x5b, 0xb9, 0x3b, 0xaf, 0x2c, 0x9c, 0x44, 0x73, 0xcc, 0xe5, 0xd0, 0xd2,
+                0x2b, 0xd9,
+            ],
+            [
+                0xdf, 0x6a, 0x30, 0x1e, 0x95, 0xc9, 0x5d, 0xad, 0x97, 0xae, 0x0c, 0xc8, 0xc6, 0x91,
+                0x3b, 0xd8,
+            ],
+            [
+                0x80, 0x11, 0x89, 0x90, 0x2c, 0x85, 0x7f, 0x39, 0xe7, 0x35, 0x91, 0x28, 0x5e, 0x70,
+                0xb6, 0xdb,
+            ],
+            [
+                0xe6, 0x17, 0x34, 0x6a, 0xc9, 0xc2, 0x31, 0xbb, 0x36, 0x50, 0xae, 0x34, 0xcc, 0xca,
+                0x0c, 0x5b,
+            ],
+            [
+                0x27, 0xd9, 0x34, 0x37, 0xef, 0xb7, 0x21, 0xaa, 0x40, 0x18, 0x21, 0xdc, 0xec, 0x5a,
+                0xdf, 0x89,
+            ],
+            [
+                0x89, 0x23, 0x7d, 0x9d, 0xed, 0x9c, 0x5e, 0x78, 0xd8, 0xb1, 0xc9, 0xb1, 0x66, 0xcc,
+                0x73, 0x42,
+            ],
+            [
+                0x4a, 0x6d, 0x80, 0x91, 0xbf, 0x5e, 0x7d, 0x65, 0x11, 0x89, 0xfa, 0x94, 0xa2, 0x50,
+                0xb1, 0x4c,
+            ],
+            [
+                0x0e, 0x33, 0xf9, 0x60, 0x55, 0xe7, 0xae, 0x89, 0x3f, 0xfc, 0x0e, 0x3d, 0xcf, 0x49,
+                0x29, 0x02,
+            ],
+            [
+                0xe6, 0x1c, 0x43, 0x2b, 0x72, 0x0b, 0x19, 0xd1, 0x8e, 0xc8, 0xd8, 0x4b, 0xdc, 0x63,
+                0x15, 0x1b,
+            ],
+            [
+                0xf7, 0xe5, 0xae, 0xf5, 0x49, 0xf7, 0x82, 0xcf, 0x37, 0x90, 0x55, 0xa6, 0x08, 0x26,
+                0x9b, 0x16,
+            ],
+            [
+                0x43, 0x8d, 0x03, 0x0f, 0xd0, 0xb7, 0xa5, 0x4f, 0xa8, 0x37, 0xf2, 0xad, 0x20, 0x1a,
+                0x64, 0x03,
+            ],
+            [
+                0xa5, 0x90, 0xd3, 0xee, 0x4f, 0xbf, 0x04, 0xe3, 0x24, 0x7e, 0x0d, 0x27, 0xf2, 0x86,
+                0x42, 0x3f,
+            ],
+            [
+                0x5f, 0xe2, 0xc1, 0xa1, 0x72, 0xfe, 0x93, 0xc4, 0xb1, 0x5c, 0xd3, 0x7c, 0xae, 0xf9,
+                0xf5, 0x38,
+            ],
+            [
+                0x2c, 0x97, 0x32, 0x5c, 0xbd, 0x06, 0xb3, 0x6e, 0xb2, 0x13, 0x3d, 0xd0, 0x8b, 0x3a,
+                0x01, 0x7c,
+            ],
+            [
+                0x92, 0xc8, 0x14, 0x22, 0x7a, 0x6b, 0xca, 0x94, 0x9f, 0xf0, 0x65, 0x9f, 0x00, 0x2a,
+                0xd3, 0x9e,
+            ],
+            [
+                0xdc, 0xe8, 0x50, 0x11, 0x0b, 0xd8, 0x32, 0x8c, 0xfb, 0xd5, 0x08, 0x41, 0xd6, 0x91,
+                0x1d, 0x87,
+            ],
+            [
+                0x67, 0xf1, 0x49, 0x84, 0xc7, 0xda, 0x79, 0x12, 0x48, 0xe3, 0x2b, 0xb5, 0x92, 0x25,
+                0x83, 0xda,
+            ],
+            [
+                0x19, 0x38, 0xf2, 0xcf, 0x72, 0xd5, 0x4e, 0xe9, 0x7e, 0x94, 0x16, 0x6f, 0xa9, 0x1d,
+                0x2a, 0x36,
+            ],
+            [
+                0x74, 0x48, 0x1e, 0x96, 0x46, 0xed, 0x49, 0xfe, 0x0f, 0x62, 0x24, 0x30, 0x16, 0x04,
+                0x69, 0x8e,
+            ],
+            [
+                0x57, 0xfc, 0xa5, 0xde, 0x98, 0xa9, 0xd6, 0xd8, 0x00, 0x64, 0x38, 0xd0, 0x58, 0x3d,
+                0x8a, 0x1d,
+            ],
+            [
+                0x9f, 0xec, 0xde, 0x1c, 0xef, 0xdc, 0x1c, 0xbe, 0xd4, 0x76, 0x36, 0x74, 0xd9, 0x57,
+                0x53, 0x59,
+            ],
+            [
+                0xe3, 0x04, 0x0c, 0x00, 0xeb, 0x28, 0xf1, 0x53, 0x66, 0xca, 0x73, 0xcb, 0xd8, 0x72,
+                0xe7, 0x40,
+            ],
+            [
+                0x76, 0x97, 0x00, 0x9a, 0x6a, 0x83, 0x1d, 0xfe, 0xcc, 0xa9, 0x1c, 0x59, 0x93, 0x67,
+                0x0f, 0x7a,
+            ],
+            [
+                0x58, 0x53, 0x54, 0x23, 0x21, 0xf5, 0x67, 0xa0, 0x05, 0xd5, 0x47, 0xa4, 0xf0, 0x47,
+                0x59, 0xbd,
+            ],
+            [
+                0x51, 0x50, 0xd1, 0x77, 0x2f, 0x50, 0x83, 0x4a, 0x50, 0x3e, 0x06, 0x9a, 0x97, 0x3f,
+                0xbd, 0x7c,
+            ],
+        ];
+        let k = Key([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+        for i in 0usize..maxlen {
+            let Digest(h) = shorthash(&m[..i], &k);
+            assert!(h == h_expecteds[i]);
+        }
+    }
+
+    #[test]
+    fn test_prefix_of_siphash24_differs() {
+        use crypto::shorthash::siphash24;
+        let k = gen_key();
+        let k24 = siphash24::Key::from_slice(k.as_ref()).unwrap();
+        let h = shorthash(b"some data", &k);
+        let h24 = siphash24::shorthash(b"some data", &k24);
+        assert!(&h.as_ref()[..siphash24::DIGESTBYTES] != h24.as_ref());
+    }
+
+    #[test]
+    fn test_hasher() {
+        use randombytes::randombytes;
+        let k = gen_key();
+        for i in 0..64usize {
+            let m = randombytes(i);
+            let mut hasher = SipHasher::new(&k);
+            for chunk in m.chunks(3) {
+                hasher.write(chunk);
+            }
+            let h = shorthash(&m, &k);
+            assert_eq!(hasher.digest(), h);
+            assert_eq!(&hasher.finish().to_le_bytes()[..], &h.0[..8]);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialisation() {
+        use randombytes::randombytes;
+        use test_utils::round_trip;
+        for i in 0..64usize {
+            let k = gen_key();
+            let m = randombytes(i);
+            let d = shorthash(&m[..], &k);
+            round_trip(k);
+            round_trip(d);
+        }
+    }
+}
@@ -55,8 +55,22 @@ impl SecretKey {
         }
         pk
     }
+
+    /// `seed()` extracts the `Seed` the secret key was generated from.
+    ///
+    /// Passing it to `keypair_from_seed()` gives back the full key pair, so it
+    /// is enough to store the `SEEDBYTES` byte seed instead of the secret key.
+    pub fn seed(&self) -> Seed {
+        let mut seed = Seed([0u8; SEEDBYTES]);
+        unsafe {
+            ffi::crypto_sign_ed25519_sk_to_seed(seed.0.as_mut_ptr(), self.0.as_ptr());
+        }
+        seed
+    }
 }
 
+/// Signs like `sign_detached()`, so that a `SecretKey` can be used wherever
+/// a `signature::Signer` is expected. Signing cannot fail.
 impl Signer<Signature> for SecretKey {
     fn try_sign(&self, msg: &[u8]) -> Result<Signature, Error> {
         Ok(sign_detached(msg, self))
@@ -68,6 +82,8 @@ new_type! {
     public PublicKey(PUBLICKEYBYTES);
 }
 
+/// Verifies like `verify_detached()`, so that a `PublicKey` can be used
+/// wherever a `signature::Verifier` is expected.
 impl Verifier<Signature> for PublicKey {
     fn verify(&self, msg: &[u8], sig: &Signature) -> Result<(), Error> {
         if verify_detached(sig, msg, self) {
@@ -85,6 +101,7 @@ impl Verifier<Signature> for PublicKey {
 /// called `sodiumoxide::init()` once before using any other function
 /// from sodiumoxide.
 pub fn gen_keypair() -> (PublicKey, SecretKey) {
+    ::ensure_init();
     let mut pk = PublicKey([0u8; PUBLICKEYBYTES]);
     let mut sk = SecretKey([0u8; SECRETKEYBYTES]);
     unsafe {
@@ -126,10 +143,32 @@ pub fn sign(m: &[u8], sk: &SecretKey) -> Vec<u8> {
     sm
 }
 
+/// `sign_into()` signs a message `m` using the signer's secret key `sk`, and writes the signed
+/// message to the start of `out`. It returns the number of bytes written, i.e.
+/// `m.len() + SIGNATUREBYTES`, or `Err(sodiumoxide::Error::InvalidLength)` if `out` is
+/// shorter than that.
+pub fn sign_into(out: &mut [u8], m: &[u8], sk: &SecretKey) -> Result<usize, ::Error> {
+    if out.len() < m.len() + SIGNATUREBYTES {
+        return Err(::Error::InvalidLength);
+    }
+    let mut smlen = 0;
+    unsafe {
+        ffi::crypto_sign_ed25519(
+            out.as_mut_ptr(),
+            &mut smlen,
+            m.as_ptr(),
+            m.len() as c_ulonglong,
+            sk.0.as_ptr(),
+        );
+    }
+    Ok(smlen as usize)
+}
+
 /// `verify()` verifies the signature in `sm` using the signer's public key `pk`.
 /// `verify()` returns the message `Ok(m)`.
-/// If the signature fails verification, `verify()` returns `Err(())`.
-pub fn verify(sm: &[u8], pk: &PublicKey) -> Result<Vec<u8>, ()> {
+/// If the signature fails verification, `verify()` returns
+/// `Err(sodiumoxide::Error::VerificationFailed)`.
+pub fn verify(sm: &[u8], pk: &PublicKey) -> Result<Vec<u8>, ::Error> {
     let mut m = vec![0u8; sm.len()];
     let mut mlen = 0;
     let ret = unsafe {
@@ -145,7 +184,7 @@ pub fn verify(sm: &[u8], pk: &PublicKey) -> Result<Vec<u8>, ()> {
         m.truncate(mlen as usize);
         Ok(m)
     } else {
-        Err(())
+        Err(::Error::VerificationFailed)
     }
 }
 
@@ -164,7 +203,7 @@ pub fn sign_detached(m: &[u8], sk: &SecretKey) -> Signature {
         );
     }
     assert_eq!(siglen, SIGNATUREBYTES as c_ulonglong);
-    Signature::new(sig)
+    Signature::from_bytes(&sig).expect("libsodium produced an invalid signature")
 }
 
 /// `verify_detached()` verifies the signature in `sig` against the message `m`
@@ -183,16 +222,22 @@ pub fn verify_detached(sig: &Signature, m: &[u8], pk: &PublicKey) -> bool {
 }
 
 /// State for multi-part (streaming) computation of signature.
+///
+/// The message is hashed with SHA-512 as it is passed to `update()`, and the
+/// hash is signed using `Ed25519ph` as specified in RFC 8032. These signatures
+/// differ from the ones produced by `sign_detached()` for the same message.
 #[derive(Copy, Clone)]
 pub struct State(ffi::crypto_sign_ed25519ph_state);
 
 impl State {
     /// `init()` initialize a streaming signing state.
     pub fn init() -> State {
-        let mut s = mem::MaybeUninit::uninit();
+        // The init function leaves the block buffer untouched, so start from
+        // zeroes to never read uninitialized memory, e.g. when copying the state.
+        let mut s = mem::MaybeUninit::zeroed();
         let state = unsafe {
-            ffi::crypto_sign_ed25519ph_init(s.as_mut_ptr());
-            s.assume_init() // s is definitely initialized
+            assert_eq!(ffi::crypto_sign_ed25519ph_init(s.as_mut_ptr()), 0);
+            s.assume_init()
         };
         State(state)
     }
@@ -220,10 +265,11 @@ impl State {
             );
         }
         assert_eq!(siglen, SIGNATUREBYTES as c_ulonglong);
-        Signature::new(sig)
+        Signature::from_bytes(&sig).expect("libsodium produced an invalid signature")
     }
 
-    /// `verify` verifies the signature in `sm` using the signer's public key `pk`.
+    /// `verify` verifies the signature `sig` of the data passed to `update()`
+    /// using the signer's public key `pk`.
     pub fn verify(&mut self, sig: &Signature, &PublicKey(ref pk): &PublicKey) -> bool {
         let mut sig = sig.to_bytes();
         let ret = unsafe {
@@ -246,10 +292,15 @@ impl Default for State {
     }
 }
 
+#[cfg(feature = "box")]
 use crate::crypto::box_;
 
 /// Converts a ed25519 [PublicKey]  into a curve25519 [box_::PublicKey]
-pub fn to_curve25519_pk(ed25519_pk: &PublicKey) -> Result<box_::PublicKey, ()> {
+///
+/// It returns `Err(::Error::InvalidEncoding)` if `ed25519_pk` is not a valid
+/// point.
+#[cfg(feature = "box")]
+pub fn to_curve25519_pk(ed25519_pk: &PublicKey) -> Result<box_::PublicKey, ::Error> {
     let mut x25519_pk = box_::PublicKey([0u8; box_::PUBLICKEYBYTES]);
 
     let ret = unsafe {
@@ -259,12 +310,13 @@ pub fn to_curve25519_pk(ed25519_pk: &PublicKey) -> Result<box_::PublicKey, ()> {
     if ret == 0 {
         Ok(x25519_pk)
     } else {
-        Err(())
+        Err(::Error::InvalidEncoding)
     }
 }
 
 /// Converts an ed25519 [SecretKey] into a curve25519 [box_::SecretKey]
-pub fn to_curve25519_sk(ed25519_sk: &SecretKey) -> Result<box_::SecretKey, ()> {
+#[cfg(feature = "box")]
+pub fn to_curve25519_sk(ed25519_sk: &SecretKey) -> Result<box_::SecretKey, ::Error> {
     let mut x25519_sk = box_::SecretKey([0u8; box_::SECRETKEYBYTES]);
 
     let ret = unsafe {
@@ -274,7 +326,29 @@ pub fn to_curve25519_sk(ed25519_sk: &SecretKey) -> Result<box_::SecretKey, ()> {
     if ret == 0 {
         Ok(x25519_sk)
     } else {
-        Err(())
+        Err(::Error::InvalidEncoding)
+    }
+}
+
+#[cfg(feature = "box")]
+impl PublicKey {
+    /// `to_curve25519()` converts the public key into a curve25519
+    /// [box_::PublicKey], see [to_curve25519_pk].
+    pub fn to_curve25519(&self) -> Result<box_::PublicKey, ::Error> {
+        to_curve25519_pk(self)
+    }
+}
+
+#[cfg(feature = "box")]
+impl SecretKey {
+    /// `to_curve25519()` converts the secret key into a curve25519
+    /// [box_::SecretKey], see [to_curve25519_sk].
+    ///
+    /// The result belongs to the public key returned by
+    /// [PublicKey::to_curve25519] for the matching public key, so one identity
+    /// key pair can also be used to open boxes.
+    pub fn to_curve25519(&self) -> Result<box_::SecretKey, ::Error> {
+        to_curve25519_sk(self)
     }
 }
 
@@ -301,6 +375,19 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_sign_into() {
+        let (pk, sk) = gen_keypair();
+        let mut out = [0; 4 + SIGNATUREBYTES];
+        assert_eq!(sign_into(&mut out, b"into", &sk), Ok(4 + SIGNATUREBYTES));
+        assert_eq!(&out[..], &sign(b"into", &sk)[..]);
+        assert_eq!(verify(&out, &pk), Ok(b"into".to_vec()));
+        assert_eq!(
+            sign_into(&mut out[..3 + SIGNATUREBYTES], b"into", &sk),
+            Err(::Error::InvalidLength)
+        );
+    }
+
     #[test]
     fn test_sign_verify_tamper() {
         use randombytes::randombytes;
@@ -310,7 +397,7 @@ mod test {
             let mut sm = sign(&m, &sk);
             for j in 0..sm.len() {
                 sm[j] ^= 0x20;
-                assert!(Err(()) == verify(&sm, &pk));
+                assert!(Err(::Error::VerificationFailed) == verify(&sm, &pk));
                 sm[j] ^= 0x20;
             }
         }
@@ -336,12 +423,55 @@ mod test {
             let mut sig = sign_detached(&m, &sk).to_bytes();
             for j in 0..SIGNATUREBYTES {
                 sig[j] ^= 0x20;
-                assert!(!verify_detached(&Signature::new(sig), &m, &pk));
+                // flipping one of the high bits of `S` already makes the
+                // signature unparseable
+                if let Ok(sig) = Signature::from_bytes(&sig) {
+                    assert!(!verify_detached(&sig, &m, &pk));
+                }
                 sig[j] ^= 0x20;
             }
         }
     }
 
+    #[test]
+    fn test_signature_traits() {
+        use randombytes::randombytes;
+
+        fn sign_generic<S: Signer<Signature>>(signer: &S, m: &[u8]) -> Signature {
+            signer.sign(m)
+        }
+        fn verify_generic<V: Verifier<Signature>>(verifier: &V, m: &[u8], sig: &Signature) -> bool {
+            verifier.verify(m, sig).is_ok()
+        }
+
+        for i in 0..64usize {
+            let (pk, sk) = gen_keypair();
+            let m = randombytes(i);
+            let sig = sign_generic(&sk, &m);
+            assert_eq!(sig, sign_detached(&m, &sk));
+            assert!(verify_generic(&pk, &m, &sig));
+
+            let (other_pk, _) = gen_keypair();
+            assert!(!verify_generic(&other_pk, &m, &sig));
+            let mut m = m;
+            m.push(0);
+            assert!(!verify_generic(&pk, &m, &sig));
+        }
+    }
+
+    #[test]
+    fn test_secret_key_seed() {
+        for _ in 0..32usize {
+            let (pk, sk) = gen_keypair();
+            let seed = sk.seed();
+            assert_eq!(sk.public_key(), pk);
+            let (pk2, sk2) = keypair_from_seed(&seed);
+            assert_eq!(pk2, pk);
+            assert_eq!(sk2, sk);
+            assert_eq!(sk2.seed(), seed);
+        }
+    }
+
     #[test]
     fn test_sign_verify_seed() {
         use randombytes::{randombytes, randombytes_into};
@@ -369,7 +499,7 @@ mod test {
             let mut sm = sign(&m, &sk);
             for j in 0..sm.len() {
                 sm[j] ^= 0x20;
-                assert!(Err(()) == verify(&sm, &pk));
+                assert!(Err(::Error::VerificationFailed) == verify(&sm, &pk));
                 sm[j] ^= 0x20;
             }
         }
@@ -448,6 +578,21 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_streaming_not_interchangeable() {
+        let (pk, sk) = gen_keypair();
+        let m = b"some data";
+        let mut state = State::init();
+        state.update(m);
+        let ph_sig = state.finalize(&sk);
+        assert!(!verify_detached(&ph_sig, m, &pk));
+
+        let sig = sign_detached(m, &sk);
+        let mut state = State::init();
+        state.update(m);
+        assert!(!state.verify(&sig, &pk));
+    }
+
     #[test]
     fn test_streaming_empty_sign() {
         let (pk, sk) = gen_keypair();
@@ -547,6 +692,7 @@ mod test {
         assert!(validator_state.verify(&sig, &pk));
     }
 
+    #[cfg(feature = "box")]
     #[test]
     fn test_convert_keys() {
         let (pk, sk) = gen_keypair();
@@ -554,6 +700,35 @@ mod test {
         let _pk2 = to_curve25519_pk(&pk).unwrap();
         let _sk2 = to_curve25519_sk(&sk).unwrap();
     }
+
+    #[cfg(feature = "box")]
+    #[test]
+    fn test_convert_keys_methods() {
+        for _ in 0..32usize {
+            let (pk, sk) = gen_keypair();
+            let box_pk = pk.to_curve25519().unwrap();
+            let box_sk = sk.to_curve25519().unwrap();
+            assert_eq!(box_pk, to_curve25519_pk(&pk).unwrap());
+            assert_eq!(box_sk, to_curve25519_sk(&sk).unwrap());
+            assert_eq!(box_sk.public_key(), box_pk);
+
+            let (other_pk, other_sk) = box_::gen_keypair();
+            let n = box_::gen_nonce();
+            let c = box_::seal(b"some data", &n, &box_pk, &other_sk);
+            assert_eq!(
+                box_::open(&c, &n, &other_pk, &box_sk),
+                Ok(b"some data".to_vec())
+            );
+        }
+    }
+
+    #[cfg(feature = "box")]
+    #[test]
+    fn test_convert_invalid_pk() {
+        // not a point on the curve
+        let pk = PublicKey([0xff; PUBLICKEYBYTES]);
+        assert!(pk.to_curve25519().is_err());
+    }
 }
 
 #[cfg(feature = "benchmarks")]
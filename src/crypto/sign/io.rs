@@ -0,0 +1,315 @@
+//! Streaming signing and verification on top of `std::io`.
+//!
+//! A [`SigningWriter`] signs everything written through it with the `ed25519ph`
+//! streaming interface and appends the detached signature as a
+//! [`SIGNATUREBYTES`](super::ed25519::SIGNATUREBYTES) byte trailer when it is
+//! finished. A [`VerifyingReader`] reads such a stream back, returning only
+//! the body and checking the trailer once the underlying reader reaches EOF.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::sign;
+//! use std::io::{Read, Write};
+//!
+//! let (pk, sk) = sign::gen_keypair();
+//!
+//! let mut writer = sign::SigningWriter::new(Vec::new(), &sk);
+//! writer.write_all(b"some data").unwrap();
+//! let signed = writer.finish().unwrap();
+//!
+//! let mut reader = sign::VerifyingReader::new(&signed[..], &pk);
+//! let mut body = Vec::new();
+//! reader.read_to_end(&mut body).unwrap();
+//! assert!(reader.is_verified());
+//! assert_eq!(body, b"some data");
+//! ```
+
+use super::ed25519::{PublicKey, SecretKey, Signature, State, SIGNATUREBYTES};
+use std::cmp;
+use std::io::{self, Read, Write};
+
+/// `SigningWriter` forwards all bytes to an inner writer while computing an
+/// `ed25519ph` signature over them.
+///
+/// The signature is only written out by [`finish()`](SigningWriter::finish);
+/// dropping a `SigningWriter` leaves the stream without a trailer.
+#[derive(Debug)]
+pub struct SigningWriter<W: Write> {
+    inner: W,
+    state: State,
+    sk: SecretKey,
+}
+
+impl<W: Write> SigningWriter<W> {
+    /// `new()` wraps `inner` so that everything written to it is signed with
+    /// the secret key `sk`.
+    pub fn new(inner: W, sk: &SecretKey) -> SigningWriter<W> {
+        SigningWriter {
+            inner,
+            state: State::init(),
+            sk: sk.clone(),
+        }
+    }
+
+    /// `finish()` writes the signature of all bytes written so far as a
+    /// trailer, flushes the inner writer and returns it.
+    pub fn finish(self) -> io::Result<W> {
+        let SigningWriter {
+            mut inner,
+            state,
+            sk,
+        } = self;
+        let sig = state.finalize(&sk);
+        inner.write_all(&sig.to_bytes())?;
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+
+impl<W: Write> Write for SigningWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.state.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// `VerifyingReader` reads a stream produced by a [`SigningWriter`], returning
+/// the body and holding back the trailing signature.
+///
+/// Once the inner reader reaches EOF the signature is verified: a valid
+/// signature makes `read()` return `Ok(0)` as usual, an invalid or missing one
+/// makes it return an error of kind `io::ErrorKind::InvalidData`.
+///
+/// WARNING: bytes are returned before the signature has been checked. They
+/// must not be trusted until EOF has been reached without an error, or
+/// equivalently until [`is_verified()`](VerifyingReader::is_verified) returns
+/// `true`.
+#[derive(Debug)]
+pub struct VerifyingReader<R: Read> {
+    inner: R,
+    state: State,
+    pk: PublicKey,
+    // Bytes read from `inner` that have not been returned yet. The last
+    // `SIGNATUREBYTES` are held back since they might be the trailer.
+    pending: Vec<u8>,
+    verified: Option<bool>,
+}
+
+impl<R: Read> VerifyingReader<R> {
+    /// `new()` wraps `inner` so that its trailing signature is checked against
+    /// the public key `pk`.
+    pub fn new(inner: R, pk: &PublicKey) -> VerifyingReader<R> {
+        VerifyingReader {
+            inner,
+            state: State::init(),
+            pk: *pk,
+            pending: Vec::with_capacity(2 * SIGNATUREBYTES),
+            verified: None,
+        }
+    }
+
+    /// `is_verified()` returns `true` once EOF has been reached and the
+    /// trailer is a valid signature of the body. It returns `false` before
+    /// EOF and if verification failed.
+    pub fn is_verified(&self) -> bool {
+        self.verified == Some(true)
+    }
+
+    /// `into_inner()` returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn verify_trailer(&mut self) -> bool {
+        if self.pending.len() != SIGNATUREBYTES {
+            return false;
+        }
+        match Signature::from_bytes(&self.pending) {
+            Ok(sig) => self.state.verify(&sig, &self.pk),
+            Err(_) => false,
+        }
+    }
+}
+
+impl<R: Read> Read for VerifyingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            match self.verified {
+                Some(true) => return Ok(0),
+                Some(false) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "invalid signature trailer",
+                    ))
+                }
+                None => {}
+            }
+
+            if self.pending.len() > SIGNATUREBYTES {
+                let n = cmp::min(buf.len(), self.pending.len() - SIGNATUREBYTES);
+                buf[..n].copy_from_slice(&self.pending[..n]);
+                self.state.update(&buf[..n]);
+                self.pending.drain(..n);
+                return Ok(n);
+            }
+
+            let old_len = self.pending.len();
+            self.pending
+                .resize(old_len + cmp::max(buf.len(), SIGNATUREBYTES), 0);
+            match self.inner.read(&mut self.pending[old_len..]) {
+                Ok(0) => {
+                    self.pending.truncate(old_len);
+                    let verified = self.verify_trailer();
+                    self.verified = Some(verified);
+                }
+                Ok(n) => self.pending.truncate(old_len + n),
+                Err(e) => {
+                    self.pending.truncate(old_len);
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::ed25519::gen_keypair;
+    use super::*;
+    use randombytes::randombytes;
+
+    // Reads at most one byte at a time from the wrapped reader.
+    struct OneByteReader<R: Read>(R);
+
+    impl<R: Read> Read for OneByteReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = cmp::min(buf.len(), 1);
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    fn sign_stream(m: &[u8], sk: &SecretKey) -> Vec<u8> {
+        let mut writer = SigningWriter::new(Vec::new(), sk);
+        for chunk in m.chunks(7) {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    fn read_one_byte_at_a_time<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut byte = [0u8; 1];
+        loop {
+            match reader.read(&mut byte)? {
+                0 => return Ok(out),
+                _ => out.push(byte[0]),
+            }
+        }
+    }
+
+    const SIZES: [usize; 10] = [0, 1, 63, 64, 65, 127, 128, 129, 1000, 4097];
+
+    #[test]
+    fn test_signing_writer_trailer() {
+        let (pk, sk) = gen_keypair();
+        let m = randombytes(100);
+        let signed = sign_stream(&m, &sk);
+        assert_eq!(signed.len(), m.len() + SIGNATUREBYTES);
+        assert_eq!(&signed[..m.len()], &m[..]);
+
+        let sig = Signature::from_bytes(&signed[m.len()..]).unwrap();
+        let mut state = State::init();
+        state.update(&m);
+        assert!(state.verify(&sig, &pk));
+    }
+
+    #[test]
+    fn test_verifying_reader_round_trip() {
+        let (pk, sk) = gen_keypair();
+        for &size in SIZES.iter() {
+            let m = randombytes(size);
+            let signed = sign_stream(&m, &sk);
+
+            let mut reader = VerifyingReader::new(&signed[..], &pk);
+            let mut body = Vec::new();
+            reader.read_to_end(&mut body).unwrap();
+            assert!(reader.is_verified());
+            assert_eq!(body, m);
+        }
+    }
+
+    #[test]
+    fn test_verifying_reader_short_reads() {
+        let (pk, sk) = gen_keypair();
+        for &size in SIZES.iter() {
+            let m = randombytes(size);
+            let signed = sign_stream(&m, &sk);
+
+            // short reads from the inner reader
+            let mut reader = VerifyingReader::new(OneByteReader(&signed[..]), &pk);
+            let mut body = Vec::new();
+            reader.read_to_end(&mut body).unwrap();
+            assert!(reader.is_verified());
+            assert_eq!(body, m);
+
+            // short reads by the caller
+            let mut reader = VerifyingReader::new(&signed[..], &pk);
+            assert_eq!(read_one_byte_at_a_time(&mut reader).unwrap(), m);
+            assert!(reader.is_verified());
+
+            // both
+            let mut reader = VerifyingReader::new(OneByteReader(&signed[..]), &pk);
+            assert_eq!(read_one_byte_at_a_time(&mut reader).unwrap(), m);
+            assert!(reader.is_verified());
+        }
+    }
+
+    #[test]
+    fn test_verifying_reader_tamper() {
+        let (pk, sk) = gen_keypair();
+        for &size in SIZES[..8].iter() {
+            let m = randombytes(size);
+            let mut signed = sign_stream(&m, &sk);
+            for i in 0..signed.len() {
+                signed[i] ^= 0x20;
+                let mut reader = VerifyingReader::new(OneByteReader(&signed[..]), &pk);
+                let mut body = Vec::new();
+                let err = reader.read_to_end(&mut body).unwrap_err();
+                assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+                assert!(!reader.is_verified());
+                signed[i] ^= 0x20;
+            }
+        }
+    }
+
+    #[test]
+    fn test_verifying_reader_wrong_key() {
+        let (_, sk) = gen_keypair();
+        let (pk, _) = gen_keypair();
+        let signed = sign_stream(b"some data", &sk);
+        let mut reader = VerifyingReader::new(&signed[..], &pk);
+        let mut body = Vec::new();
+        assert!(reader.read_to_end(&mut body).is_err());
+        assert!(!reader.is_verified());
+    }
+
+    #[test]
+    fn test_verifying_reader_truncated() {
+        let (pk, sk) = gen_keypair();
+        let signed = sign_stream(b"some data", &sk);
+        for len in 0..signed.len() {
+            let mut reader = VerifyingReader::new(&signed[..len], &pk);
+            let mut body = Vec::new();
+            assert!(reader.read_to_end(&mut body).is_err());
+            assert!(!reader.is_verified());
+        }
+    }
+}
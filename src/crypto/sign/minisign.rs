@@ -0,0 +1,452 @@
+//! Signatures and public keys in the [minisign](https://jedisct1.github.io/minisign/)
+//! format.
+//!
+//! Files signed with [`sign()`] can be verified with `minisign -V`, and
+//! signatures made by the `minisign` tool can be checked with [`verify()`].
+//!
+//! A public key file consists of an untrusted comment line and the Base64
+//! encoding of
+//!
+//! ```text
+//! "Ed" || key_id || public_key
+//! ```
+//!
+//! A signature file consists of four lines: an untrusted comment, the Base64
+//! encoding of
+//!
+//! ```text
+//! "ED" || key_id || ed25519(BLAKE2b-512(m))
+//! ```
+//!
+//! a trusted comment, and the Base64 encoding of a global signature over the
+//! first signature and the trusted comment. Only the trusted comment is
+//! authenticated. Legacy signatures, which sign `m` directly and start with
+//! `"Ed"`, are accepted by `verify()` too.
+//!
+//! The `key_id` is a random [`KeyId`] that lets tools pick the right key; it
+//! is checked by `verify()`, but it is not a fingerprint of the key.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::sign::{self, minisign};
+//!
+//! let (pk, sk) = sign::gen_keypair();
+//! let pk = minisign::PublicKey::new(minisign::gen_key_id(), pk);
+//!
+//! let sig = minisign::sign(b"some data", &sk, &pk.key_id, "file:data.txt");
+//! let encoded = sig.encode();
+//!
+//! let sig = minisign::Signature::decode(&encoded).unwrap();
+//! assert!(minisign::verify(&sig, b"some data", &pk).is_ok());
+//! assert_eq!(sig.trusted_comment, "file:data.txt");
+//!
+//! let pk = minisign::PublicKey::decode(&pk.encode()).unwrap();
+//! ```
+
+use super::ed25519::{self, sign_detached, verify_detached, SecretKey, SIGNATUREBYTES};
+use base64::{self, Variant};
+use ffi;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use randombytes::randombytes_into;
+
+/// Number of bytes in a `KeyId`.
+pub const KEYIDBYTES: usize = 8;
+
+const PREHASHBYTES: usize = 64;
+
+const ALG_PURE: &[u8; 2] = b"Ed";
+const ALG_PREHASHED: &[u8; 2] = b"ED";
+
+const UNTRUSTED_PREFIX: &str = "untrusted comment: ";
+const TRUSTED_PREFIX: &str = "trusted comment: ";
+
+new_type! {
+    /// `KeyId` identifying a minisign key pair.
+    public KeyId(KEYIDBYTES);
+}
+
+impl KeyId {
+    /// `to_hex()` returns the key id as shown by the `minisign` tool, i.e. as
+    /// an uppercase hexadecimal little-endian integer.
+    pub fn to_hex(&self) -> String {
+        let mut be = self.0;
+        be.reverse();
+        ::hex::encode(be).to_uppercase()
+    }
+}
+
+/// `gen_key_id()` randomly generates a `KeyId` for a new key pair.
+///
+/// THREAD SAFETY: `gen_key_id()` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+pub fn gen_key_id() -> KeyId {
+    let mut id = KeyId([0; KEYIDBYTES]);
+    randombytes_into(&mut id.0);
+    id
+}
+
+/// A minisign public key: an `ed25519` public key and its `KeyId`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey {
+    /// Identifier of the key pair.
+    pub key_id: KeyId,
+    /// The `ed25519` public key.
+    pub key: ed25519::PublicKey,
+}
+
+impl PublicKey {
+    /// `new()` pairs the public key `key` with its `KeyId` `key_id`.
+    pub fn new(key_id: KeyId, key: ed25519::PublicKey) -> PublicKey {
+        PublicKey { key_id, key }
+    }
+
+    /// `encode()` returns the contents of a minisign public key file.
+    pub fn encode(&self) -> String {
+        let mut bin = Vec::with_capacity(2 + KEYIDBYTES + ed25519::PUBLICKEYBYTES);
+        bin.extend_from_slice(ALG_PURE);
+        bin.extend_from_slice(&self.key_id.0);
+        bin.extend_from_slice(&self.key.0);
+        format!(
+            "{}minisign public key {}\n{}\n",
+            UNTRUSTED_PREFIX,
+            self.key_id.to_hex(),
+            base64::encode(&bin, Variant::Original)
+        )
+    }
+
+    /// `decode()` parses a minisign public key file, or just its Base64 line
+    /// as passed to `minisign -P`.
+    ///
+    /// It returns `Err(Error::InvalidEncoding)` if `s` is not a minisign
+    /// `ed25519` public key.
+    pub fn decode(s: &str) -> Result<PublicKey, ::Error> {
+        let mut lines = Lines::new(s);
+        let mut line = lines.next()?;
+        if line.starts_with(UNTRUSTED_PREFIX) {
+            line = lines.next()?;
+        }
+        lines.end()?;
+        let bin = decode_base64(line, 2 + KEYIDBYTES + ed25519::PUBLICKEYBYTES)?;
+        if &bin[..2] != ALG_PURE {
+            return Err(::Error::InvalidEncoding);
+        }
+        Ok(PublicKey {
+            key_id: KeyId::from_slice(&bin[2..2 + KEYIDBYTES]).unwrap(),
+            key: ed25519::PublicKey::from_slice(&bin[2 + KEYIDBYTES..]).unwrap(),
+        })
+    }
+}
+
+/// A minisign signature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    /// Free-form comment, not covered by any signature.
+    pub untrusted_comment: String,
+    /// Whether `signature` covers the `BLAKE2b-512` hash of the message
+    /// rather than the message itself.
+    pub prehashed: bool,
+    /// Identifier of the key pair that made the signature.
+    pub key_id: KeyId,
+    /// Signature of the message.
+    pub signature: ed25519::Signature,
+    /// Comment covered by `global_signature`, e.g. a file name and timestamp.
+    pub trusted_comment: String,
+    /// Signature of `signature` followed by `trusted_comment`.
+    pub global_signature: ed25519::Signature,
+}
+
+impl Signature {
+    /// `encode()` returns the contents of a minisign signature file.
+    pub fn encode(&self) -> String {
+        let mut bin = Vec::with_capacity(2 + KEYIDBYTES + SIGNATUREBYTES);
+        bin.extend_from_slice(if self.prehashed {
+            ALG_PREHASHED
+        } else {
+            ALG_PURE
+        });
+        bin.extend_from_slice(&self.key_id.0);
+        bin.extend_from_slice(self.signature.as_ref());
+        format!(
+            "{}{}\n{}\n{}{}\n{}\n",
+            UNTRUSTED_PREFIX,
+            self.untrusted_comment,
+            base64::encode(&bin, Variant::Original),
+            TRUSTED_PREFIX,
+            self.trusted_comment,
+            base64::encode(self.global_signature.as_ref(), Variant::Original)
+        )
+    }
+
+    /// `decode()` parses a minisign signature file.
+    ///
+    /// It returns `Err(Error::InvalidEncoding)` if `s` is not a minisign
+    /// `ed25519` signature. The signatures are not verified, see `verify()`.
+    pub fn decode(s: &str) -> Result<Signature, ::Error> {
+        let mut lines = Lines::new(s);
+        let untrusted_comment = strip_prefix(lines.next()?, UNTRUSTED_PREFIX)?;
+        let bin = decode_base64(lines.next()?, 2 + KEYIDBYTES + SIGNATUREBYTES)?;
+        let trusted_comment = strip_prefix(lines.next()?, TRUSTED_PREFIX)?;
+        let global = decode_base64(lines.next()?, SIGNATUREBYTES)?;
+        lines.end()?;
+
+        let prehashed = match &bin[..2] {
+            alg if alg == ALG_PREHASHED => true,
+            alg if alg == ALG_PURE => false,
+            _ => return Err(::Error::InvalidEncoding),
+        };
+        let signature = ed25519::Signature::from_bytes(&bin[2 + KEYIDBYTES..])
+            .map_err(|_| ::Error::InvalidEncoding)?;
+        let global_signature =
+            ed25519::Signature::from_bytes(&global).map_err(|_| ::Error::InvalidEncoding)?;
+        Ok(Signature {
+            untrusted_comment: untrusted_comment.to_string(),
+            prehashed,
+            key_id: KeyId::from_slice(&bin[2..2 + KEYIDBYTES]).unwrap(),
+            signature,
+            trusted_comment: trusted_comment.to_string(),
+            global_signature,
+        })
+    }
+}
+
+fn prehash(m: &[u8]) -> [u8; PREHASHBYTES] {
+    let mut h = [0u8; PREHASHBYTES];
+    unsafe {
+        ffi::crypto_generichash_blake2b(
+            h.as_mut_ptr(),
+            PREHASHBYTES,
+            m.as_ptr(),
+            m.len() as u64,
+            ::std::ptr::null(),
+            0,
+        );
+    }
+    h
+}
+
+fn global_message(signature: &ed25519::Signature, trusted_comment: &str) -> Vec<u8> {
+    let mut m = Vec::with_capacity(SIGNATUREBYTES + trusted_comment.len());
+    m.extend_from_slice(signature.as_ref());
+    m.extend_from_slice(trusted_comment.as_bytes());
+    m
+}
+
+/// `sign()` signs the message `m` with the secret key `sk` of the key pair
+/// `key_id`, in the prehashed format used by default by `minisign`, and
+/// authenticates `trusted_comment` along with it.
+///
+/// The comments must not contain line breaks, or `encode()` will not produce
+/// a valid signature file.
+pub fn sign(m: &[u8], sk: &SecretKey, key_id: &KeyId, trusted_comment: &str) -> Signature {
+    let signature = sign_detached(&prehash(m), sk);
+    let global_signature = sign_detached(&global_message(&signature, trusted_comment), sk);
+    Signature {
+        untrusted_comment: "signature from minisign secret key".to_string(),
+        prehashed: true,
+        key_id: *key_id,
+        signature,
+        trusted_comment: trusted_comment.to_string(),
+        global_signature,
+    }
+}
+
+/// `verify()` checks that `sig` is a signature of the message `m`, including
+/// its trusted comment, made by the key pair of `pk`.
+///
+/// It returns `Err(Error::VerificationFailed)` if `sig` was made by another
+/// key pair, or if the message or the trusted comment have been modified.
+pub fn verify(sig: &Signature, m: &[u8], pk: &PublicKey) -> Result<(), ::Error> {
+    if sig.key_id != pk.key_id {
+        return Err(::Error::VerificationFailed);
+    }
+    let valid = if sig.prehashed {
+        verify_detached(&sig.signature, &prehash(m), &pk.key)
+    } else {
+        verify_detached(&sig.signature, m, &pk.key)
+    };
+    let global = global_message(&sig.signature, &sig.trusted_comment);
+    if !valid || !verify_detached(&sig.global_signature, &global, &pk.key) {
+        return Err(::Error::VerificationFailed);
+    }
+    Ok(())
+}
+
+/// Iterates over the lines of a file, accepting `\n` and `\r\n` line endings
+/// and a missing final line break.
+struct Lines<'a>(::std::str::Split<'a, char>);
+
+impl<'a> Lines<'a> {
+    fn new(s: &'a str) -> Lines<'a> {
+        Lines(s.split('\n'))
+    }
+
+    fn next(&mut self) -> Result<&'a str, ::Error> {
+        match self.0.next() {
+            Some(line) => Ok(line.trim_end_matches('\r')),
+            None => Err(::Error::InvalidEncoding),
+        }
+    }
+
+    fn end(&mut self) -> Result<(), ::Error> {
+        match self.0.next() {
+            None => Ok(()),
+            Some(line) if line.trim().is_empty() && self.0.next().is_none() => Ok(()),
+            Some(_) => Err(::Error::InvalidEncoding),
+        }
+    }
+}
+
+fn strip_prefix<'a>(line: &'a str, prefix: &str) -> Result<&'a str, ::Error> {
+    match line.get(..prefix.len()) {
+        Some(start) if start == prefix => Ok(&line[prefix.len()..]),
+        _ => Err(::Error::InvalidEncoding),
+    }
+}
+
+fn decode_base64(line: &str, len: usize) -> Result<Vec<u8>, ::Error> {
+    match base64::decode(line.trim(), Variant::Original) {
+        Ok(ref bin) if bin.len() == len => Ok(bin.clone()),
+        _ => Err(::Error::InvalidEncoding),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::ed25519::{gen_keypair, keypair_from_seed, Seed};
+    use super::*;
+
+    #[test]
+    fn test_sign_verify() {
+        let (pk, sk) = gen_keypair();
+        let pk = PublicKey::new(gen_key_id(), pk);
+        let sig = sign(b"some data", &sk, &pk.key_id, "timestamp:1 file:data");
+        let sig = Signature::decode(&sig.encode()).unwrap();
+        assert!(sig.prehashed);
+        assert_eq!(verify(&sig, b"some data", &pk), Ok(()));
+        assert_eq!(
+            verify(&sig, b"some datb", &pk),
+            Err(::Error::VerificationFailed)
+        );
+
+        let mut forged = sig.clone();
+        forged.trusted_comment = "timestamp:2 file:data".to_string();
+        assert_eq!(
+            verify(&forged, b"some data", &pk),
+            Err(::Error::VerificationFailed)
+        );
+
+        // the untrusted comment is not authenticated
+        let mut relabelled = sig.clone();
+        relabelled.untrusted_comment = "anything".to_string();
+        assert_eq!(verify(&relabelled, b"some data", &pk), Ok(()));
+
+        let other = PublicKey::new(gen_key_id(), pk.key);
+        assert_eq!(
+            verify(&sig, b"some data", &other),
+            Err(::Error::VerificationFailed)
+        );
+    }
+
+    #[test]
+    fn test_verify_minisign_tool() {
+        // signature of "test" made by the minisign tool, from the test suite
+        // of the `minisign-verify` crate
+        let pk =
+            PublicKey::decode("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3").unwrap();
+        assert_eq!(pk.key_id.to_hex(), "E7620F1842B4E81F");
+        let sig = Signature::decode(
+            "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1633700835\tfile:test\tprehashed
+wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==
+",
+        )
+        .unwrap();
+        assert!(sig.prehashed);
+        assert_eq!(verify(&sig, b"test", &pk), Ok(()));
+    }
+
+    #[test]
+    fn test_verify_legacy() {
+        let (pk, sk) = gen_keypair();
+        let pk = PublicKey::new(gen_key_id(), pk);
+        let signature = sign_detached(b"some data", &sk);
+        let sig = Signature {
+            untrusted_comment: String::new(),
+            prehashed: false,
+            key_id: pk.key_id,
+            signature,
+            trusted_comment: "legacy".to_string(),
+            global_signature: sign_detached(&global_message(&signature, "legacy"), &sk),
+        };
+        let encoded = sig.encode();
+        let b64 = encoded.lines().nth(1).unwrap();
+        assert!(base64::decode(b64, Variant::Original)
+            .unwrap()
+            .starts_with(b"Ed"));
+        let sig = Signature::decode(&encoded).unwrap();
+        assert!(!sig.prehashed);
+        assert_eq!(verify(&sig, b"some data", &pk), Ok(()));
+    }
+
+    #[test]
+    fn test_public_key_encoding() {
+        let (pk, _) = keypair_from_seed(&Seed([0x42; 32]));
+        let pk = PublicKey::new(KeyId([1, 2, 3, 4, 5, 6, 7, 0xab]), pk);
+        let encoded = pk.encode();
+        let mut lines = encoded.lines();
+        assert_eq!(
+            lines.next(),
+            Some("untrusted comment: minisign public key AB07060504030201")
+        );
+        let b64 = lines.next().unwrap();
+        assert_eq!(lines.next(), None);
+
+        assert_eq!(PublicKey::decode(&encoded), Ok(pk.clone()));
+        assert_eq!(PublicKey::decode(b64), Ok(pk.clone()));
+        assert_eq!(
+            PublicKey::decode(&encoded.replace('\n', "\r\n")),
+            Ok(pk.clone())
+        );
+        let bin = base64::decode(b64, Variant::Original).unwrap();
+        assert_eq!(&bin[..2], b"Ed");
+        assert_eq!(&bin[2..10], &pk.key_id.0);
+        assert_eq!(&bin[10..], &pk.key.0);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let (pk, sk) = gen_keypair();
+        let pk = PublicKey::new(gen_key_id(), pk);
+        let encoded = sign(b"m", &sk, &pk.key_id, "c").encode();
+        let lines: Vec<&str> = encoded.lines().collect();
+
+        // missing or extra lines
+        let truncated = lines[..3].join("\n");
+        assert_eq!(Signature::decode(&truncated), Err(::Error::InvalidEncoding));
+        let extra = format!("{}garbage\n", encoded);
+        assert_eq!(Signature::decode(&extra), Err(::Error::InvalidEncoding));
+        // comment lines swapped
+        let swapped = [lines[2], lines[1], lines[0], lines[3]].join("\n");
+        assert_eq!(Signature::decode(&swapped), Err(::Error::InvalidEncoding));
+        // unknown algorithm
+        let mut bin = base64::decode(lines[1], Variant::Original).unwrap();
+        bin[0] = b'X';
+        let bad_alg = [
+            lines[0],
+            &base64::encode(&bin, Variant::Original),
+            lines[2],
+            lines[3],
+        ]
+        .join("\n");
+        assert_eq!(Signature::decode(&bad_alg), Err(::Error::InvalidEncoding));
+
+        assert_eq!(
+            PublicKey::decode(&pk.encode()[..20]),
+            Err(::Error::InvalidEncoding)
+        );
+        assert_eq!(PublicKey::decode(lines[1]), Err(::Error::InvalidEncoding));
+    }
+}
@@ -40,5 +40,57 @@
 //! let signature = sign::sign_detached(data_to_sign, &sk);
 //! assert!(sign::verify_detached(&signature, data_to_sign, &pk));
 //! ```
+//!
+//! # Example (streaming signatures)
+//! `State` implements the prehashed `Ed25519ph` variant, so a large message
+//! can be signed chunk by chunk without keeping it in memory. Its signatures
+//! can only be verified with a `State`, not with `verify_detached()`.
+//!
+//! ```
+//! use sodiumoxide::crypto::sign;
+//! let (pk, sk) = sign::gen_keypair();
+//!
+//! let mut state = sign::State::init();
+//! state.update(b"some ");
+//! state.update(b"data");
+//! let signature = state.finalize(&sk);
+//!
+//! let mut state = sign::State::init();
+//! state.update(b"some data");
+//! assert!(state.verify(&signature, &pk));
+//! ```
+//!
+//! # Example (signature traits)
+//! `SecretKey` and `PublicKey` implement the `Signer` and `Verifier` traits of
+//! the [signature](https://crates.io/crates/signature) crate, which are
+//! re-exported here, so they can be passed to code that is generic over
+//! signature schemes.
+//! ```
+//! use sodiumoxide::crypto::sign::{self, Signature, Signer, Verifier};
+//!
+//! fn sign_token<S: Signer<Signature>>(signer: &S, token: &[u8]) -> Signature {
+//!     signer.sign(token)
+//! }
+//!
+//! let (pk, sk) = sign::gen_keypair();
+//! let signature = sign_token(&sk, b"some token");
+//! assert!(pk.verify(b"some token", &signature).is_ok());
+//! ```
+//!
+//! # Key formats
+//! Keys can be exchanged with other software in the
+//! [`minisign`](minisign/index.html) and [`openssh`](openssh/index.html)
+//! formats, and as PKCS#8 and `SubjectPublicKeyInfo` documents as used by
+//! OpenSSL, see `SecretKey::to_pkcs8_pem()` and
+//! `PublicKey::to_public_key_pem()`.
 pub use self::ed25519::*;
+pub use crypto::pkcs8::{PrivateKeyDer, PrivateKeyPem};
 pub mod ed25519;
+pub mod minisign;
+pub mod openssh;
+mod pkcs8;
+
+#[cfg(feature = "std")]
+pub use self::io::{SigningWriter, VerifyingReader};
+#[cfg(feature = "std")]
+pub mod io;
@@ -0,0 +1,197 @@
+//! Sender-authenticated sealed boxes
+//!
+//! # Purpose
+//! A sealed box lets anybody encrypt a message to a recipient, but the
+//! recipient learns nothing about who sent it. A signed seal additionally
+//! signs the message with the sender's `sign` key, so that the recipient can
+//! check which sender produced it without an online handshake.
+//!
+//! # Algorithm Details
+//! The sender computes an `ed25519` detached signature over
+//!
+//! ```text
+//! DOMAIN || recipient_pk || m
+//! ```
+//!
+//! where `DOMAIN` is the 26 byte string `"sodiumoxide.signedseal.v1\0"` and
+//! `recipient_pk` is the 32 byte `box_` public key of the recipient. Binding the
+//! recipient key into the signature prevents the recipient from opening the
+//! message and re-sealing it to somebody else in the sender's name.
+//!
+//! The ciphertext is then
+//!
+//! ```text
+//! sealedbox::seal(signature || m, recipient_pk)
+//! ```
+//!
+//! so it is `SEALBYTES + SIGNATUREBYTES` bytes longer than the message.
+//!
+//! NOTE: the recipient can prove to third parties that the sender signed the
+//! message for them; signed seals are not deniable.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::{box_, sign, signedseal};
+//!
+//! let (sender_pk, sender_sk) = sign::gen_keypair();
+//! let (recipient_pk, recipient_sk) = box_::gen_keypair();
+//!
+//! let c = signedseal::seal(b"some data", &sender_sk, &recipient_pk);
+//! let m = signedseal::open(&c, &recipient_pk, &recipient_sk, &sender_pk).unwrap();
+//! assert_eq!(m, b"some data");
+//! ```
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+
+use crypto::box_;
+use crypto::sealedbox;
+use crypto::sign;
+use Error;
+
+/// Domain separator prepended to every signed message.
+pub const DOMAIN: &[u8] = b"sodiumoxide.signedseal.v1\0";
+
+/// Number of additional bytes in a ciphertext compared to the corresponding
+/// plaintext.
+pub const SEALBYTES: usize = sealedbox::SEALBYTES + sign::SIGNATUREBYTES;
+
+fn signed_message(m: &[u8], recipient_pk: &box_::PublicKey) -> Vec<u8> {
+    let mut sm = Vec::with_capacity(DOMAIN.len() + box_::PUBLICKEYBYTES + m.len());
+    sm.extend_from_slice(DOMAIN);
+    sm.extend_from_slice(&recipient_pk.0);
+    sm.extend_from_slice(m);
+    sm
+}
+
+/// `seal()` signs the message `m` with the sender's secret key `sender_sk` and
+/// encrypts it together with the signature for the recipient whose public key
+/// is `recipient_pk`. It returns a ciphertext whose length is
+/// `SEALBYTES + m.len()`.
+pub fn seal(m: &[u8], sender_sk: &sign::SecretKey, recipient_pk: &box_::PublicKey) -> Vec<u8> {
+    let sig = sign::sign_detached(&signed_message(m, recipient_pk), sender_sk);
+    let mut pt = Vec::with_capacity(sign::SIGNATUREBYTES + m.len());
+    pt.extend_from_slice(&sig.to_bytes());
+    pt.extend_from_slice(m);
+    sealedbox::seal(&pt, recipient_pk)
+}
+
+/// `open()` decrypts the ciphertext `c` using the recipient's key pair
+/// `(recipient_pk, recipient_sk)` and verifies that it was signed by the sender
+/// whose public key is `expected_sender`, for this recipient.
+///
+/// It returns the message if both decryption and verification succeed,
+/// `Err(Error::InvalidLength)` if `c` is too short to hold a signature, and
+/// `Err(Error::VerificationFailed)` otherwise.
+pub fn open(
+    c: &[u8],
+    recipient_pk: &box_::PublicKey,
+    recipient_sk: &box_::SecretKey,
+    expected_sender: &sign::PublicKey,
+) -> Result<Vec<u8>, Error> {
+    let pt = sealedbox::open(c, recipient_pk, recipient_sk)?;
+    if pt.len() < sign::SIGNATUREBYTES {
+        return Err(Error::InvalidLength);
+    }
+    let (sig, m) = pt.split_at(sign::SIGNATUREBYTES);
+    let sig = sign::Signature::from_bytes(sig).map_err(|_| Error::VerificationFailed)?;
+    if sign::verify_detached(&sig, &signed_message(m, recipient_pk), expected_sender) {
+        Ok(m.to_vec())
+    } else {
+        Err(Error::VerificationFailed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex;
+    use randombytes::randombytes;
+
+    #[test]
+    fn test_seal_open() {
+        let (sender_pk, sender_sk) = sign::gen_keypair();
+        let (recipient_pk, recipient_sk) = box_::gen_keypair();
+        for i in 0..256usize {
+            let m = randombytes(i);
+            let c = seal(&m, &sender_sk, &recipient_pk);
+            assert_eq!(c.len(), m.len() + SEALBYTES);
+            let opened = open(&c, &recipient_pk, &recipient_sk, &sender_pk);
+            assert_eq!(Ok(m), opened);
+        }
+    }
+
+    #[test]
+    fn test_seal_open_tamper() {
+        let (sender_pk, sender_sk) = sign::gen_keypair();
+        let (recipient_pk, recipient_sk) = box_::gen_keypair();
+        for i in 0..32usize {
+            let m = randombytes(i);
+            let mut c = seal(&m, &sender_sk, &recipient_pk);
+            for j in 0..c.len() {
+                c[j] ^= 0x20;
+                assert!(open(&c, &recipient_pk, &recipient_sk, &sender_pk).is_err());
+                c[j] ^= 0x20;
+            }
+        }
+    }
+
+    #[test]
+    fn test_open_wrong_sender() {
+        let (_, sender_sk) = sign::gen_keypair();
+        let (other_pk, _) = sign::gen_keypair();
+        let (recipient_pk, recipient_sk) = box_::gen_keypair();
+        let c = seal(b"some data", &sender_sk, &recipient_pk);
+        assert!(open(&c, &recipient_pk, &recipient_sk, &other_pk).is_err());
+    }
+
+    #[test]
+    fn test_open_resealed() {
+        let (sender_pk, sender_sk) = sign::gen_keypair();
+        let (alice_pk, alice_sk) = box_::gen_keypair();
+        let (bob_pk, bob_sk) = box_::gen_keypair();
+
+        // alice opens a message meant for her and forwards it to bob
+        let c = seal(b"some data", &sender_sk, &alice_pk);
+        let pt = sealedbox::open(&c, &alice_pk, &alice_sk).unwrap();
+        let resealed = sealedbox::seal(&pt, &bob_pk);
+        assert!(open(&resealed, &bob_pk, &bob_sk, &sender_pk).is_err());
+    }
+
+    #[test]
+    fn test_open_short_plaintext() {
+        let (sender_pk, _) = sign::gen_keypair();
+        let (recipient_pk, recipient_sk) = box_::gen_keypair();
+        for i in 0..sign::SIGNATUREBYTES {
+            let c = sealedbox::seal(&randombytes(i), &recipient_pk);
+            assert!(open(&c, &recipient_pk, &recipient_sk, &sender_pk).is_err());
+        }
+        assert!(open(&[], &recipient_pk, &recipient_sk, &sender_pk).is_err());
+    }
+
+    #[test]
+    fn test_layout_fixture() {
+        let sign_seed = sign::Seed([0x01; sign::SEEDBYTES]);
+        let box_seed = box_::Seed([0x02; box_::SEEDBYTES]);
+        let (sender_pk, sender_sk) = sign::keypair_from_seed(&sign_seed);
+        let (recipient_pk, recipient_sk) = box_::keypair_from_seed(&box_seed);
+        let m = b"signed seal fixture";
+
+        let c = seal(m, &sender_sk, &recipient_pk);
+        let pt = sealedbox::open(&c, &recipient_pk, &recipient_sk).unwrap();
+        assert_eq!(&pt[sign::SIGNATUREBYTES..], &m[..]);
+
+        let sig = sign::Signature::from_bytes(&pt[..sign::SIGNATUREBYTES]).unwrap();
+        let mut sm = b"sodiumoxide.signedseal.v1\0".to_vec();
+        sm.extend_from_slice(&recipient_pk.0);
+        sm.extend_from_slice(m);
+        assert!(sign::verify_detached(&sig, &sm, &sender_pk));
+
+        // ed25519 signatures are deterministic
+        assert_eq!(
+            hex::encode(&pt[..sign::SIGNATUREBYTES]),
+            "90f251d84c7365f87be06595a0700285d1b441689456e454084f3dd71286b779\
+             04dda9965df4d7f8a41fe47381f3bc2dde57087b643fcb8a137766371cdc6d08"
+        );
+    }
+}
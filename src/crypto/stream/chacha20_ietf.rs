@@ -0,0 +1,91 @@
+//! `crypto_stream_chacha20_ietf` (ChaCha20 as specified in RFC 8439)
+//!
+//! The IETF variant uses a 96-bit nonce and a 32-bit block counter, so the
+//! initial counter taken by `stream_xor_ic()` and `stream_ic()` is a `u32`.
+//! A single (key, nonce) pair can therefore encrypt at most 2^32 blocks of
+//! 64 bytes, i.e. 256 GB.
+//!
+//! Since the block counter addresses 64-byte blocks, the keystream byte at
+//! offset `pos` can be reached directly by starting at block `pos / 64`:
+//!
+//! ```
+//! use sodiumoxide::crypto::stream::chacha20_ietf;
+//!
+//! let key = chacha20_ietf::gen_key();
+//! let nonce = chacha20_ietf::gen_nonce();
+//! let c = chacha20_ietf::stream_xor(&[0u8; 1000], &nonce, &key);
+//!
+//! // decrypt the bytes 640..1000 without processing the first ten blocks
+//! let tail = chacha20_ietf::stream_xor_ic(&c[640..], &nonce, 10, &key);
+//! assert_eq!(&tail[..], &[0u8; 360][..]);
+//! ```
+
+use crypto::nonce::gen_random_nonce;
+use ffi::{
+    crypto_stream_chacha20_ietf, crypto_stream_chacha20_ietf_KEYBYTES,
+    crypto_stream_chacha20_ietf_NONCEBYTES, crypto_stream_chacha20_ietf_xor,
+    crypto_stream_chacha20_ietf_xor_ic,
+};
+
+stream_module!(
+    crypto_stream_chacha20_ietf,
+    crypto_stream_chacha20_ietf_xor,
+    crypto_stream_chacha20_ietf_xor_ic,
+    crypto_stream_chacha20_ietf_KEYBYTES as usize,
+    crypto_stream_chacha20_ietf_NONCEBYTES as usize,
+    u32
+);
+
+/// `gen_nonce` randomly generates a nonce
+///
+/// WARNING: the 96-bit nonces of this construction are too short for random
+/// nonces to be used with a single key for a large number of messages.
+///
+/// THREAD SAFETY: `gen_nonce()` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+pub fn gen_nonce() -> Nonce {
+    gen_random_nonce()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex;
+
+    #[test]
+    fn test_vector_1() {
+        // https://tools.ietf.org/html/rfc8439#section-2.4.2
+        let key = Key([
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+            0x1c, 0x1d, 0x1e, 0x1f,
+        ]);
+        let nonce = Nonce([
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x4a, 0x00, 0x00, 0x00, 0x00,
+        ]);
+        let m = b"Ladies and Gentlemen of the class of '99: If I could offer you only one \
+                  tip for the future, sunscreen would be it.";
+        let expected = "6e2e359a2568f98041ba0728dd0d6981e97e7aec1d4360c20a27afccfd9fae0b\
+                        f91b65c5524733ab8f593dabcd62b3571639d624e65152ab8f530c359f0861d8\
+                        07ca0dbf500d6a6156a38e088a22b65e52bc514d16ccf806818ce91ab7793736\
+                        5af90bbf74a35be6b40b8eedf2785e42874d";
+        let c = stream_xor_ic(m, &nonce, 1, &key);
+        assert_eq!(hex::encode(&c), expected);
+
+        let mut c2 = m.to_vec();
+        stream_xor_ic_inplace(&mut c2, &nonce, 1, &key);
+        assert_eq!(c, c2);
+
+        let mut keystream = stream_ic(m.len(), &nonce, 1, &key);
+        for (k, x) in keystream.iter_mut().zip(m.iter()) {
+            *k ^= *x;
+        }
+        assert_eq!(keystream, c);
+    }
+
+    #[test]
+    fn test_nonce_length() {
+        assert_eq!(96 / 8, gen_nonce().as_ref().len());
+    }
+}
@@ -44,15 +44,16 @@
 //! # Alternate primitives
 //! NaCl supports the following secret-key encryption functions:
 //!
-//! --------------------------------------------------------------
-//! |`crypto_stream`           |primitive   |KEYBYTES |NONCEBYTES|
-//! |--------------------------|------------|---------|----------|
-//! |`crypto_stream_chacha20`  |Chacha20/20 |32       |8         |
-//! |`crypto_stream_salsa20`   |Salsa20/20  |32       |8         |
-//! |`crypto_stream_xsalsa20`  |XSalsa20/20 |32       |24        |
-//! |`crypto_stream_xchacha20` |XChacha20/20|32       |24        |
-//!
-//! Beware that several of these primitives have 8-byte nonces. For those
+//! -------------------------------------------------------------------
+//! |`crypto_stream`                |primitive   |KEYBYTES |NONCEBYTES|
+//! |-------------------------------|------------|---------|----------|
+//! |`crypto_stream_chacha20`       |Chacha20/20 |32       |8         |
+//! |`crypto_stream_chacha20_ietf`  |Chacha20/20 |32       |12        |
+//! |`crypto_stream_salsa20`        |Salsa20/20  |32       |8         |
+//! |`crypto_stream_xsalsa20`       |XSalsa20/20 |32       |24        |
+//! |`crypto_stream_xchacha20`      |XChacha20/20|32       |24        |
+//!
+//! Beware that several of these primitives have 8-byte or 12-byte nonces. For those
 //! primitives it is no longer true that randomly generated nonces have negligible
 //! risk of collision. Callers who are unable to count 1, 2, 3..., and who insist
 //! on using these primitives, are advised to use a randomly derived key for each
@@ -97,6 +98,7 @@ pub use self::xsalsa20::*;
 #[macro_use]
 mod stream_macros;
 pub mod chacha20;
+pub mod chacha20_ietf;
 pub mod salsa20;
 pub mod xchacha20;
 pub mod xsalsa20;
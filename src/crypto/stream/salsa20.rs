@@ -15,7 +15,7 @@ stream_module!(
     crypto_stream_salsa20_NONCEBYTES as usize
 );
 
-#[cfg(test)]
+#[cfg(all(test, feature = "hash"))]
 mod test {
     use super::*;
 
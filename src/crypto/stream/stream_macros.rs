@@ -3,6 +3,14 @@ macro_rules! stream_module (($stream_name:ident,
                              $xor_ic_name:ident,
                              $keybytes:expr,
                              $noncebytes:expr) => (
+stream_module!($stream_name, $xor_name, $xor_ic_name, $keybytes, $noncebytes, u64);
+);
+                            ($stream_name:ident,
+                             $xor_name:ident,
+                             $xor_ic_name:ident,
+                             $keybytes:expr,
+                             $noncebytes:expr,
+                             $ic_type:ty) => (
 
 #[cfg(not(feature = "std"))] use prelude::*;
 use libc::c_ulonglong;
@@ -91,6 +99,22 @@ pub fn stream_xor_inplace(m: &mut [u8],
     }
 }
 
+/// `stream_ic()` produces a `len`-byte stream `c` as a function of a
+/// secret key `k` and a nonce `n`, starting at the block counter `ic`.
+///
+/// `stream_ic(len, n, 0, k)` is the same as `stream(len, n, k)`, and in general
+/// `stream_ic()` returns the keystream that `stream_xor_ic()` xors with the
+/// message, so it can be used to seek into a stream without computing the
+/// blocks that precede `ic`.
+pub fn stream_ic(len: usize,
+                 n: &Nonce,
+                 ic: $ic_type,
+                 k: &Key) -> Vec<u8> {
+    let mut c = vec![0u8; len];
+    stream_xor_ic_inplace(&mut c, n, ic, k);
+    c
+}
+
 /// `stream_xor_ic()` encrypts a message `m` using a secret key `k` and a nonce `n`,
 /// it is similar to `stream_xor()` but allows the caller to set the value of the initial
 /// block counter `ic`.
@@ -100,7 +124,7 @@ pub fn stream_xor_inplace(m: &mut [u8],
 /// Consequently `stream_xor()` can also be used to decrypt.
 pub fn stream_xor_ic(m: &[u8],
                      n: &Nonce,
-                     ic: u64,
+                     ic: $ic_type,
                      k: &Key) -> Vec<u8> {
     unsafe {
         let mut c = vec![0u8; m.len()];
@@ -108,7 +132,7 @@ pub fn stream_xor_ic(m: &[u8],
                      m.as_ptr(),
                      m.len() as c_ulonglong,
                      n.0.as_ptr(),
-                     ic as u64,
+                     ic,
                      k.0.as_ptr());
         c
     }
@@ -124,14 +148,14 @@ pub fn stream_xor_ic(m: &[u8],
 /// Consequently `stream_xor_ic_inplace()` can also be used to decrypt.
 pub fn stream_xor_ic_inplace(m: &mut [u8],
                              n: &Nonce,
-                             ic: u64,
+                             ic: $ic_type,
                              k: &Key) {
     unsafe {
         $xor_ic_name(m.as_mut_ptr(),
                      m.as_ptr(),
                      m.len() as c_ulonglong,
                      n.0.as_ptr(),
-                     ic as u64,
+                     ic,
                      k.0.as_ptr());
     }
 }
@@ -202,6 +226,18 @@ mod test_m {
         }
     }
 
+    #[test]
+    fn test_stream_ic() {
+        for i in 0..16usize {
+            let k = gen_key();
+            let n = gen_random_nonce();
+            let s = stream(64 * 16, &n, &k);
+            assert_eq!(stream_ic(64 * 16, &n, 0, &k), s);
+            let s_ic = stream_ic(64 * (16 - i), &n, i as _, &k);
+            assert_eq!(&s_ic[..], &s[64 * i..]);
+        }
+    }
+
     #[test]
     fn test_stream_xor_ic_inplace() {
         use randombytes::randombytes;
@@ -29,6 +29,32 @@ pub fn gen_nonce() -> Nonce {
 #[cfg(test)]
 mod test {
     use super::*;
+    use hex;
+
+    #[test]
+    fn test_vector_1() {
+        // regression vector computed with libsodium 1.0.18, using the key and
+        // nonce of draft-irtf-cfrg-xchacha-03, section A.3.2
+        let key = Key([
+            0x80, 0x81, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89, 0x8a, 0x8b, 0x8c, 0x8d,
+            0x8e, 0x8f, 0x90, 0x91, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0x9b,
+            0x9c, 0x9d, 0x9e, 0x9f,
+        ]);
+        let nonce = Nonce([
+            0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d,
+            0x4e, 0x4f, 0x50, 0x51, 0x52, 0x53, 0x54, 0x55, 0x56, 0x57,
+        ]);
+        let expected = "7b191f80f361f099094f6f4b8fb97df847cc6873a8f2b190dd73807183f907d5\
+                        a1cb27385b00329f7ddc127059d6882551a120e7631352e9b0381572e950155a\
+                        f10c73f45bf0f45afb1277d3f6ae9d553247726e05449ceccabaf50c42550dc8\
+                        003c107d2b6d9f7d31d3e1496e935e5ac111aa14ac3ba470aee497577d66943d";
+        let output = stream(128, &nonce, &key);
+        assert_eq!(hex::encode(&output), expected);
+
+        // seeking to the second block with `stream_xor_ic()` gives the same keystream
+        let output_ic = stream_xor_ic(&[0u8; 64], &nonce, 1, &key);
+        assert_eq!(&output_ic[..], &output[64..]);
+    }
 
     #[test]
     fn test_nonce_length() {
@@ -30,6 +30,7 @@ pub fn gen_nonce() -> Nonce {
 mod test {
     use super::*;
 
+    #[cfg(feature = "hash")]
     #[test]
     fn test_vector_1() {
         // corresponding to tests/stream.c and tests/stream5.cpp from NaCl
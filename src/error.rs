@@ -0,0 +1,70 @@
+//! Error type
+
+use std::fmt;
+
+/// `Error` describes why an operation failed.
+///
+/// More variants may be added in the future, so `match` statements on
+/// `Error` should include a wildcard arm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Error {
+    /// The sodium library or a state could not be initialized.
+    InitFailed,
+    /// A ciphertext, authentication tag or signature did not verify.
+    VerificationFailed,
+    /// An input was too short or too long to be valid.
+    InvalidLength,
+    /// A string was not validly encoded, e.g. contained non-hex characters.
+    InvalidEncoding,
+    /// A message was longer than the primitive can process in one call.
+    MessageTooLong,
+    /// A parameter, e.g. a cost limit, was outside of its valid range.
+    InvalidParameter,
+    /// The operation is not available, e.g. AES-GCM on a CPU without AES-NI.
+    Unsupported,
+    /// A stream was used after it had been finalized.
+    Finalized,
+    /// Memory could not be allocated, locked or protected.
+    OutOfMemory,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(match *self {
+            Error::InitFailed => "initialization failed",
+            Error::VerificationFailed => "verification failed",
+            Error::InvalidLength => "invalid length",
+            Error::InvalidEncoding => "invalid encoding",
+            Error::MessageTooLong => "message too long",
+            Error::InvalidParameter => "invalid parameter",
+            Error::Unsupported => "unsupported operation",
+            Error::Finalized => "stream already finalized",
+            Error::OutOfMemory => "memory allocation or protection failed",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for Error {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            format!("{}", Error::VerificationFailed),
+            "verification failed"
+        );
+        assert_eq!(format!("{}", Error::InitFailed), "initialization failed");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_std_error() {
+        use std::error::Error as StdError;
+        let e: Box<dyn StdError> = Box::new(Error::Unsupported);
+        assert_eq!(e.to_string(), "unsupported operation");
+    }
+}
@@ -0,0 +1,495 @@
+//! Guarded heap allocations for secrets
+//!
+//! [`SecretBox`] and [`SecretVec`] store their contents in memory allocated
+//! with `sodium_malloc()`. Such allocations are placed right before a guard
+//! page, preceded by another guard page and a canary, and are locked with
+//! `mlock()` so that they are not swapped to disk. Accessing memory past the
+//! end of the allocation crashes the process instead of silently reading or
+//! overwriting other data, and the memory is zeroed out when it is freed.
+//!
+//! Guarded allocations are much slower than regular ones and each of them
+//! takes up at least three or four virtual memory pages, so they should only
+//! be used for long-lived secrets such as keys.
+//!
+//! The key newtypes of this crate can be moved into guarded memory with their
+//! `into_guarded()` method.
+//!
+//! The protection of guarded memory can be changed with the scoped guards
+//! [`NoAccess`], [`ReadOnly`] and [`ReadWrite`], so that secrets are
+//! inaccessible except during the brief moments they are actually used.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::crypto::secretbox;
+//!
+//! let key = secretbox::gen_key();
+//! let guarded = key.into_guarded().unwrap();
+//! let key = secretbox::Key(*guarded);
+//! # let _ = key;
+//! ```
+
+use ffi;
+use libc::{c_int, c_void};
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::slice;
+use Error;
+
+unsafe fn guarded_alloc(count: usize, size: usize) -> Result<*mut u8, Error> {
+    if ::init().is_err() {
+        return Err(Error::InitFailed);
+    }
+    let p = ffi::sodium_allocarray(count, size) as *mut u8;
+    if p.is_null() {
+        Err(Error::OutOfMemory)
+    } else {
+        Ok(p)
+    }
+}
+
+/// `SecretBox` holds a single value of type `T` in guarded memory.
+///
+/// `T` is restricted to `Copy` types, as the contents are never dropped but
+/// simply wiped when the `SecretBox` goes out of scope.
+///
+/// NOTE: `new()` copies its argument into guarded memory, but cannot erase
+/// the original. Prefer `new_with()`, `from_ref()` or `into_guarded()` to avoid leaving
+/// copies behind.
+pub struct SecretBox<T: Copy> {
+    ptr: *mut T,
+    phantom: PhantomData<T>,
+}
+
+unsafe impl<T: Copy + Send> Send for SecretBox<T> {}
+unsafe impl<T: Copy + Sync> Sync for SecretBox<T> {}
+
+impl<T: Copy> SecretBox<T> {
+    /// `new()` allocates guarded memory and moves `value` into it.
+    ///
+    /// It returns `Err(Error::OutOfMemory)` if the allocation fails.
+    pub fn new(value: T) -> Result<SecretBox<T>, Error> {
+        SecretBox::from_ref(&value)
+    }
+
+    /// `from_ref()` allocates guarded memory and copies `*value` into it.
+    ///
+    /// It returns `Err(Error::OutOfMemory)` if the allocation fails.
+    pub fn from_ref(value: &T) -> Result<SecretBox<T>, Error> {
+        let ptr = unsafe { guarded_alloc(1, size_of::<T>())? as *mut T };
+        // `size_of::<T>()` is a multiple of the alignment of `T`, and
+        // `sodium_malloc()` places the allocation right before a page
+        // boundary, so `ptr` is suitably aligned.
+        unsafe { ptr::copy_nonoverlapping(value, ptr, 1) };
+        Ok(SecretBox {
+            ptr,
+            phantom: PhantomData,
+        })
+    }
+
+    /// `new_with()` allocates guarded memory holding `T::default()` and lets
+    /// `f` initialize it in place.
+    ///
+    /// It returns `Err(Error::OutOfMemory)` if the allocation fails.
+    pub fn new_with<F: FnOnce(&mut T)>(f: F) -> Result<SecretBox<T>, Error>
+    where
+        T: Default,
+    {
+        let mut b = SecretBox::new(T::default())?;
+        f(&mut b);
+        Ok(b)
+    }
+}
+
+impl<T: Copy> SecretBox<T> {
+    /// `noaccess()` makes the contents inaccessible until the returned guard
+    /// is dropped. See [`NoAccess`].
+    ///
+    /// It returns `Err(Error::OutOfMemory)` if the protection could not be changed.
+    pub fn noaccess(&mut self) -> Result<NoAccess<'_, T>, Error> {
+        unsafe { NoAccess::new(self.ptr) }
+    }
+
+    /// `readonly()` makes the contents read-only until the returned guard is
+    /// dropped. See [`ReadOnly`].
+    ///
+    /// It returns `Err(Error::OutOfMemory)` if the protection could not be changed.
+    pub fn readonly(&mut self) -> Result<ReadOnly<'_, T>, Error> {
+        unsafe { ReadOnly::new(self.ptr, ffi::sodium_mprotect_readwrite) }
+    }
+}
+
+impl<T: Copy> Deref for SecretBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl<T: Copy> DerefMut for SecretBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.ptr }
+    }
+}
+
+impl<T: Copy> Drop for SecretBox<T> {
+    fn drop(&mut self) {
+        // sodium_free() zeroes out the memory before releasing it
+        unsafe { ffi::sodium_free(self.ptr as *mut _) }
+    }
+}
+
+impl<T: Copy> fmt::Debug for SecretBox<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        // Hide secrets from debug output.
+        write!(formatter, "SecretBox(****)")
+    }
+}
+
+/// `SecretVec` holds a fixed-length sequence of values of type `T` in
+/// guarded memory. It dereferences to a slice.
+///
+/// Unlike `Vec`, a `SecretVec` cannot grow, since reallocating would leave
+/// copies of its contents behind.
+pub struct SecretVec<T: Copy> {
+    ptr: *mut T,
+    len: usize,
+    phantom: PhantomData<T>,
+}
+
+unsafe impl<T: Copy + Send> Send for SecretVec<T> {}
+unsafe impl<T: Copy + Sync> Sync for SecretVec<T> {}
+
+impl<T: Copy> SecretVec<T> {
+    /// `new()` allocates guarded memory for `len` elements, all set to
+    /// `value`.
+    ///
+    /// It returns `Err(Error::OutOfMemory)` if the allocation fails.
+    pub fn new(len: usize, value: T) -> Result<SecretVec<T>, Error> {
+        let ptr = unsafe { guarded_alloc(len, size_of::<T>())? as *mut T };
+        for i in 0..len {
+            unsafe { ptr::write(ptr.add(i), value) };
+        }
+        Ok(SecretVec {
+            ptr,
+            len,
+            phantom: PhantomData,
+        })
+    }
+
+    /// `from_slice()` allocates guarded memory and copies `s` into it.
+    ///
+    /// It returns `Err(Error::OutOfMemory)` if the allocation fails.
+    pub fn from_slice(s: &[T]) -> Result<SecretVec<T>, Error> {
+        let ptr = unsafe { guarded_alloc(s.len(), size_of::<T>())? as *mut T };
+        unsafe { ptr::copy_nonoverlapping(s.as_ptr(), ptr, s.len()) };
+        Ok(SecretVec {
+            ptr,
+            len: s.len(),
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl<T: Copy> SecretVec<T> {
+    fn as_mut_ptr(&mut self) -> *mut [T] {
+        &mut **self as *mut [T]
+    }
+
+    /// `noaccess()` makes the contents inaccessible until the returned guard
+    /// is dropped. See [`NoAccess`].
+    ///
+    /// It returns `Err(Error::OutOfMemory)` if the protection could not be changed.
+    pub fn noaccess(&mut self) -> Result<NoAccess<'_, [T]>, Error> {
+        unsafe { NoAccess::new(self.as_mut_ptr()) }
+    }
+
+    /// `readonly()` makes the contents read-only until the returned guard is
+    /// dropped. See [`ReadOnly`].
+    ///
+    /// It returns `Err(Error::OutOfMemory)` if the protection could not be changed.
+    pub fn readonly(&mut self) -> Result<ReadOnly<'_, [T]>, Error> {
+        unsafe { ReadOnly::new(self.as_mut_ptr(), ffi::sodium_mprotect_readwrite) }
+    }
+}
+
+impl<T: Copy> Deref for SecretVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<T: Copy> DerefMut for SecretVec<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl<T: Copy> Drop for SecretVec<T> {
+    fn drop(&mut self) {
+        // sodium_free() zeroes out the memory before releasing it
+        unsafe { ffi::sodium_free(self.ptr as *mut _) }
+    }
+}
+
+impl<T: Copy> fmt::Debug for SecretVec<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        // Hide secrets from debug output.
+        write!(formatter, "SecretVec(****)")
+    }
+}
+
+type Mprotect = unsafe extern "C" fn(*mut c_void) -> c_int;
+
+unsafe fn mprotect<T: ?Sized>(ptr: *mut T, f: Mprotect) -> Result<(), Error> {
+    if f(ptr as *mut c_void) == 0 {
+        Ok(())
+    } else {
+        Err(Error::OutOfMemory)
+    }
+}
+
+/// `NoAccess` is a scoped guard that makes guarded memory inaccessible.
+///
+/// Any access to the memory while the guard is alive crashes the process.
+/// The contents can be temporarily exposed again with `readonly()` and
+/// `readwrite()`, which restore the `NoAccess` protection when their guard is
+/// dropped. Dropping the `NoAccess` guard makes the memory readable and
+/// writable again.
+///
+/// # Example
+/// ```
+/// use sodiumoxide::guarded::SecretBox;
+///
+/// let mut key = SecretBox::new([0x42u8; 32]).unwrap();
+/// let mut hidden = key.noaccess().unwrap();
+/// // ... the key cannot be read, even by accident ...
+/// {
+///     let k = hidden.readonly().unwrap();
+///     assert_eq!(k[0], 0x42);
+/// }
+/// // ... inaccessible again ...
+/// ```
+pub struct NoAccess<'a, T: 'a + ?Sized> {
+    ptr: *mut T,
+    phantom: PhantomData<&'a mut T>,
+}
+
+impl<'a, T: 'a + ?Sized> NoAccess<'a, T> {
+    unsafe fn new(ptr: *mut T) -> Result<NoAccess<'a, T>, Error> {
+        mprotect(ptr, ffi::sodium_mprotect_noaccess)?;
+        Ok(NoAccess {
+            ptr,
+            phantom: PhantomData,
+        })
+    }
+
+    /// `readonly()` makes the contents read-only until the returned guard is
+    /// dropped, after which they are inaccessible again.
+    ///
+    /// It returns `Err(Error::OutOfMemory)` if the protection could not be changed.
+    pub fn readonly(&mut self) -> Result<ReadOnly<'_, T>, Error> {
+        unsafe { ReadOnly::new(self.ptr, ffi::sodium_mprotect_noaccess) }
+    }
+
+    /// `readwrite()` makes the contents readable and writable until the
+    /// returned guard is dropped, after which they are inaccessible again.
+    ///
+    /// It returns `Err(Error::OutOfMemory)` if the protection could not be changed.
+    pub fn readwrite(&mut self) -> Result<ReadWrite<'_, T>, Error> {
+        unsafe { ReadWrite::new(self.ptr, ffi::sodium_mprotect_noaccess) }
+    }
+}
+
+impl<'a, T: 'a + ?Sized> Drop for NoAccess<'a, T> {
+    fn drop(&mut self) {
+        // If this fails the memory stays inaccessible, and accessing it
+        // crashes; sodium_free() still releases it correctly.
+        let _ = unsafe { mprotect(self.ptr, ffi::sodium_mprotect_readwrite) };
+    }
+}
+
+/// `ReadOnly` is a scoped guard that makes guarded memory read-only. It
+/// dereferences to the contents.
+///
+/// Writing to the memory while the guard is alive crashes the process.
+/// Dropping the guard restores the previous protection.
+pub struct ReadOnly<'a, T: 'a + ?Sized> {
+    ptr: *mut T,
+    restore: Mprotect,
+    phantom: PhantomData<&'a mut T>,
+}
+
+impl<'a, T: 'a + ?Sized> ReadOnly<'a, T> {
+    unsafe fn new(ptr: *mut T, restore: Mprotect) -> Result<ReadOnly<'a, T>, Error> {
+        mprotect(ptr, ffi::sodium_mprotect_readonly)?;
+        Ok(ReadOnly {
+            ptr,
+            restore,
+            phantom: PhantomData,
+        })
+    }
+
+    /// `readwrite()` makes the contents readable and writable until the
+    /// returned guard is dropped, after which they are read-only again.
+    ///
+    /// It returns `Err(Error::OutOfMemory)` if the protection could not be changed.
+    pub fn readwrite(&mut self) -> Result<ReadWrite<'_, T>, Error> {
+        unsafe { ReadWrite::new(self.ptr, ffi::sodium_mprotect_readonly) }
+    }
+}
+
+impl<'a, T: 'a + ?Sized> Deref for ReadOnly<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl<'a, T: 'a + ?Sized> Drop for ReadOnly<'a, T> {
+    fn drop(&mut self) {
+        let _ = unsafe { mprotect(self.ptr, self.restore) };
+    }
+}
+
+/// `ReadWrite` is a scoped guard that makes guarded memory readable and
+/// writable. It dereferences to the contents.
+///
+/// Dropping the guard restores the previous protection.
+pub struct ReadWrite<'a, T: 'a + ?Sized> {
+    ptr: *mut T,
+    restore: Mprotect,
+    phantom: PhantomData<&'a mut T>,
+}
+
+impl<'a, T: 'a + ?Sized> ReadWrite<'a, T> {
+    unsafe fn new(ptr: *mut T, restore: Mprotect) -> Result<ReadWrite<'a, T>, Error> {
+        mprotect(ptr, ffi::sodium_mprotect_readwrite)?;
+        Ok(ReadWrite {
+            ptr,
+            restore,
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl<'a, T: 'a + ?Sized> Deref for ReadWrite<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl<'a, T: 'a + ?Sized> DerefMut for ReadWrite<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.ptr }
+    }
+}
+
+impl<'a, T: 'a + ?Sized> Drop for ReadWrite<'a, T> {
+    fn drop(&mut self) {
+        let _ = unsafe { mprotect(self.ptr, self.restore) };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_secret_box() {
+        let mut b = SecretBox::new([0x42u8; 32]).unwrap();
+        assert_eq!(*b, [0x42u8; 32]);
+        b[0] = 0x24;
+        assert_eq!(b[0], 0x24);
+        assert_eq!(&b[1..], &[0x42u8; 31][..]);
+    }
+
+    #[test]
+    fn test_secret_box_alignment() {
+        let b = SecretBox::new(0x0102_0304_0506_0708u64).unwrap();
+        assert_eq!(
+            &*b as *const u64 as usize % ::std::mem::align_of::<u64>(),
+            0
+        );
+        assert_eq!(*b, 0x0102_0304_0506_0708);
+        let b = SecretBox::new([0u64; 3]).unwrap();
+        assert_eq!(
+            &*b as *const [u64; 3] as usize % ::std::mem::align_of::<u64>(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_secret_box_new_with() {
+        let b = SecretBox::<[u8; 16]>::new_with(|k| k.copy_from_slice(&[7; 16])).unwrap();
+        assert_eq!(*b, [7; 16]);
+    }
+
+    #[test]
+    fn test_secret_vec() {
+        let mut v = SecretVec::new(100, 0u8).unwrap();
+        assert_eq!(v.len(), 100);
+        assert!(v.iter().all(|b| *b == 0));
+        v[99] = 1;
+        assert_eq!(v[99], 1);
+
+        let v = SecretVec::from_slice(b"some data").unwrap();
+        assert_eq!(&v[..], b"some data");
+
+        let v = SecretVec::<u8>::from_slice(&[]).unwrap();
+        assert!(v.is_empty());
+    }
+
+    #[test]
+    fn test_debug_hides_contents() {
+        let b = SecretBox::new([0x42u8; 4]).unwrap();
+        assert_eq!(format!("{:?}", b), "SecretBox(****)");
+        let v = SecretVec::from_slice(&[0x42u8; 4]).unwrap();
+        assert_eq!(format!("{:?}", v), "SecretVec(****)");
+    }
+
+    #[test]
+    fn test_protection_guards() {
+        let mut b = SecretBox::new([1u8; 32]).unwrap();
+        {
+            let mut hidden = b.noaccess().unwrap();
+            {
+                let k = hidden.readonly().unwrap();
+                assert_eq!(*k, [1u8; 32]);
+            }
+            {
+                let mut k = hidden.readwrite().unwrap();
+                k[0] = 2;
+            }
+            let mut k = hidden.readonly().unwrap();
+            assert_eq!(k[0], 2);
+            k.readwrite().unwrap()[1] = 3;
+            assert_eq!(k[1], 3);
+        }
+        // read-write again once the guards are gone
+        b[2] = 4;
+        assert_eq!(&b[..3], &[2, 3, 4]);
+        assert_eq!(b.readonly().unwrap()[2], 4);
+    }
+
+    #[test]
+    fn test_protection_guards_vec() {
+        let mut v = SecretVec::from_slice(b"secret").unwrap();
+        {
+            let mut hidden = v.noaccess().unwrap();
+            assert_eq!(&*hidden.readonly().unwrap(), b"secret");
+            hidden.readwrite().unwrap()[0] = b'S';
+        }
+        assert_eq!(&*v.readonly().unwrap(), b"Secret");
+        v[1] = b'E';
+        assert_eq!(&v[..], b"SEcret");
+    }
+}
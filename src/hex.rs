@@ -1,8 +1,12 @@
 //! Libsodium hexadecimal encoding/decoding helper functions
+//!
+//! Unlike most hex codecs, these functions run in constant time for a given
+//! length, so they can be used to encode and decode keys.
 use ffi;
 #[cfg(not(feature = "std"))]
 use prelude::*;
 use std::ptr;
+use Error;
 
 /// Encodes byte sequence into a hexadecimal string.
 ///
@@ -31,11 +35,51 @@ fn encoded_len(len: usize) -> Option<usize> {
     len.checked_mul(2)?.checked_add(1)
 }
 
+/// Encodes `bin` into `out` without allocating, and returns the encoded
+/// prefix of `out`. `out` must hold at least `2 * bin.len() + 1` bytes.
+#[allow(dead_code)] // unused if every crypto module is disabled
+pub(crate) fn encode_to<'a>(bin: &[u8], out: &'a mut [u8]) -> &'a str {
+    let len = encoded_len(bin.len()).unwrap();
+    assert!(out.len() >= len);
+
+    // SAFETY: see `encode()`
+    unsafe {
+        ffi::sodium_bin2hex(out.as_mut_ptr() as *mut _, len, bin.as_ptr(), bin.len());
+        ::std::str::from_utf8_unchecked(&out[..len - 1])
+    }
+}
+
+/// Decodes `hex` into `out` without allocating, and returns the number of
+/// bytes written. Fails under the same conditions as `decode()`, or if `out`
+/// is too short.
+#[allow(dead_code)] // unused if every crypto module is disabled
+pub(crate) fn decode_to(hex: &[u8], out: &mut [u8]) -> Result<usize, Error> {
+    let mut bin_len = 0;
+    let mut hex_end = ptr::null();
+
+    // SAFETY: `sodium_hex2bin` writes at most `out.len()` bytes to `out`
+    unsafe {
+        let rc = ffi::sodium_hex2bin(
+            out.as_mut_ptr(),
+            out.len(),
+            hex.as_ptr() as *const _,
+            hex.len(),
+            ptr::null(),
+            &mut bin_len,
+            &mut hex_end,
+        );
+        if rc != 0 || hex_end != hex.as_ptr().add(hex.len()) as *const _ {
+            return Err(Error::InvalidEncoding);
+        }
+    }
+    Ok(bin_len)
+}
+
 /// Parses a hexadecimal string into a byte sequence.
 ///
-/// Fails if `hex.len()` is not even or
+/// Fails with `Error::InvalidEncoding` if `hex.len()` is not even or
 /// if `hex` contains characters not in [0-9a-fA-F].
-pub fn decode<T: AsRef<[u8]>>(hex: T) -> Result<Vec<u8>, ()> {
+pub fn decode<T: AsRef<[u8]>>(hex: T) -> Result<Vec<u8>, Error> {
     let hex = hex.as_ref();
     let mut bin = vec![0; decoded_len(hex.len())?];
     let mut bin_len = 0;
@@ -53,16 +97,67 @@ pub fn decode<T: AsRef<[u8]>>(hex: T) -> Result<Vec<u8>, ()> {
             ptr::null_mut(),
         );
         if rc != 0 {
-            return Err(());
+            return Err(Error::InvalidEncoding);
         }
         bin.truncate(bin_len);
         Ok(bin)
     }
 }
 
-fn decoded_len(len: usize) -> Result<usize, ()> {
+/// Parses a hexadecimal string into a byte sequence, skipping any of the
+/// characters in `ignore`, e.g. `":"` for `"de:ad:be:ef"` or `" \n"` for
+/// wrapped output.
+///
+/// Ignored characters may only appear between pairs of digits, never in the
+/// middle of one. Fails with `Error::InvalidEncoding` if `hex` contains
+/// characters that are neither hexadecimal digits nor in `ignore` or if the
+/// number of digits is odd, and with `Error::InvalidParameter` if `ignore`
+/// contains a nul byte.
+///
+/// # Example
+/// ```
+/// use sodiumoxide::hex;
+/// use sodiumoxide::Error;
+///
+/// assert_eq!(Ok(vec![0xde, 0xad, 0xbe, 0xef]), hex::decode_ignore("de:ad:be:ef", ":"));
+/// assert_eq!(Err(Error::InvalidEncoding), hex::decode_ignore("de:ad:be:ef", ""));
+/// ```
+pub fn decode_ignore<T: AsRef<[u8]>, I: AsRef<[u8]>>(hex: T, ignore: I) -> Result<Vec<u8>, Error> {
+    let hex = hex.as_ref();
+    let ignore = ignore.as_ref();
+    if ignore.contains(&0) {
+        return Err(Error::InvalidParameter);
+    }
+    let mut ignore_nul = Vec::with_capacity(ignore.len() + 1);
+    ignore_nul.extend_from_slice(ignore);
+    ignore_nul.push(0);
+
+    let mut bin = vec![0; hex.len() / 2];
+    let mut bin_len = 0;
+
+    // SAFETY: `ignore_nul` is nul-terminated. If `sodium_hex2bin` returns
+    // zero, it has consumed all of `hex` and written `bin_len` bytes to `bin`.
+    unsafe {
+        let rc = ffi::sodium_hex2bin(
+            bin.as_mut_ptr(),
+            bin.len(),
+            hex.as_ptr() as *const _,
+            hex.len(),
+            ignore_nul.as_ptr() as *const _,
+            &mut bin_len,
+            ptr::null_mut(),
+        );
+        if rc != 0 {
+            return Err(Error::InvalidEncoding);
+        }
+        bin.truncate(bin_len);
+        Ok(bin)
+    }
+}
+
+fn decoded_len(len: usize) -> Result<usize, Error> {
     if len % 2 != 0 {
-        return Err(());
+        return Err(Error::InvalidEncoding);
     }
 
     Ok(len / 2)
@@ -82,7 +177,40 @@ mod tests {
     fn test_decode() {
         assert_eq!(Ok(b"".to_vec()), decode(""));
         assert_eq!(Ok(b"foobar".to_vec()), decode("666F6F626172"));
-        assert_eq!(Err(()), decode("abc"));
-        assert_eq!(Err(()), decode("abxy"));
+        assert_eq!(Err(Error::InvalidEncoding), decode("abc"));
+        assert_eq!(Err(Error::InvalidEncoding), decode("abxy"));
+    }
+
+    #[test]
+    fn test_decode_ignore() {
+        assert_eq!(Ok(b"".to_vec()), decode_ignore("", ":"));
+        assert_eq!(Ok(b"".to_vec()), decode_ignore(":::", ":"));
+        assert_eq!(
+            Ok(b"foobar".to_vec()),
+            decode_ignore("66:6f:6f:62:61:72", ":")
+        );
+        assert_eq!(
+            Ok(b"foobar".to_vec()),
+            decode_ignore("666f 6f62\n6172\n", " \n")
+        );
+        assert_eq!(Ok(b"foobar".to_vec()), decode_ignore("666F6F626172", ""));
+        assert_eq!(Err(Error::InvalidEncoding), decode_ignore("66:6f", ""));
+        assert_eq!(Err(Error::InvalidEncoding), decode_ignore("6:66f", ":"));
+        assert_eq!(Err(Error::InvalidEncoding), decode_ignore("66:6", ":"));
+        assert_eq!(Err(Error::InvalidEncoding), decode_ignore("66-6f", ":"));
+        assert_eq!(Err(Error::InvalidParameter), decode_ignore("666f", "\0"));
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        use randombytes::randombytes;
+        for i in 0..64usize {
+            let bin = randombytes(i);
+            let hex = encode(&bin);
+            assert_eq!(hex.len(), 2 * i);
+            assert_eq!(Ok(bin.clone()), decode(&hex));
+            assert_eq!(Ok(bin.clone()), decode(hex.to_uppercase()));
+            assert_eq!(Ok(bin), decode_ignore(&hex, ":"));
+        }
     }
 }
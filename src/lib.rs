@@ -51,6 +51,16 @@
 //!  [`crypto::verify`](crypto/verify/index.html)
 //!
 //!  [`crypto::shorthash`](crypto/shorthash/index.html)
+//!
+//!  [`crypto::core`](crypto/core/index.html)
+//!
+//! # Guarded memory
+//!  [`guarded`](guarded/index.html)
+//!
+//! # Runtime information
+//!  [`runtime`](runtime/index.html)
+//!
+//!  [`version`](version/index.html)
 
 #![crate_name = "sodiumoxide"]
 #![crate_type = "lib"]
@@ -65,21 +75,43 @@
 
 extern crate libsodium_sys as ffi;
 
+#[cfg(feature = "aead-traits")]
+extern crate aead_traits;
+#[cfg(feature = "digest")]
+extern crate digest;
 extern crate ed25519;
 extern crate libc;
+#[cfg(feature = "rand_core")]
+extern crate rand_core;
+#[cfg(feature = "rayon")]
+extern crate rayon;
 #[cfg(any(test, feature = "serde"))]
 extern crate serde;
+#[cfg(feature = "subtle")]
+extern crate subtle;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "zeroize")]
+extern crate zeroize;
 #[cfg(not(feature = "std"))]
 #[macro_use]
 extern crate alloc;
 #[cfg(all(test, not(feature = "std")))]
 extern crate std;
 
+// Not every module is used by every selection of features.
 #[cfg(all(not(test), not(feature = "std")))]
+#[allow(unused_imports)]
 mod std {
-    pub use core::{cmp, fmt, hash, iter, mem, ops, ptr, slice, str};
+    pub use core::{
+        borrow, char, cmp, convert, fmt, hash, iter, marker, mem, ops, ptr, slice, str, sync,
+    };
 }
 
+mod error;
+pub use error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+
 #[cfg(not(feature = "std"))]
 mod prelude {
     pub use alloc::string::String;
@@ -92,45 +124,131 @@ mod prelude {
 /// functions (`gen_key`, `gen_keypair`, `gen_nonce`, `randombytes`, `randombytes_into`)
 /// thread-safe
 ///
-/// `init()` returns `Ok` if initialization succeeded and `Err` if it failed.
-pub fn init() -> Result<(), ()> {
+/// `init()` can be called any number of times, from any thread. Once it has
+/// succeeded, further calls return immediately.
+///
+/// With the `auto-init` feature, the functions that need the library to be
+/// initialized call `init()` themselves, so calling it explicitly is only
+/// needed to handle initialization failures.
+///
+/// `init()` returns `Ok` if initialization succeeded and
+/// `Err(Error::InitFailed)` if it failed.
+pub fn init() -> Result<(), Error> {
+    if INITIALIZED.load(Ordering::Acquire) {
+        return Ok(());
+    }
+    // sodium_init() is itself thread-safe, so concurrent first calls are
+    // fine; it returns 1 if another thread got there first.
     if unsafe { ffi::sodium_init() } >= 0 {
+        INITIALIZED.store(true, Ordering::Release);
         Ok(())
     } else {
-        Err(())
+        Err(Error::InitFailed)
     }
 }
 
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Called by the functions that need the library to be initialized, e.g. to
+/// generate random numbers, when the `auto-init` feature is enabled.
+/// Failures are ignored: libsodium still works uninitialized, only without
+/// the thread-safety and speed guarantees of `init()`.
+#[inline]
+fn ensure_init() {
+    #[cfg(feature = "auto-init")]
+    let _ = init();
+}
+
 #[macro_use]
+#[allow(unused_macros)] // not every kind of newtype is used by every feature set
 mod newtype_macros;
 pub mod base64;
+pub mod guarded;
 pub mod hex;
 pub mod padding;
 pub mod randombytes;
+pub mod runtime;
 pub mod utils;
 pub mod version;
 
+#[cfg(test)]
+mod async_test_utils;
 #[cfg(test)]
 mod test_utils;
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_init_idempotent() {
+        #[cfg(feature = "std")]
+        {
+            let threads: Vec<_> = (0..8).map(|_| std::thread::spawn(init)).collect();
+            for t in threads {
+                assert_eq!(t.join().unwrap(), Ok(()));
+            }
+        }
+        assert_eq!(init(), Ok(()));
+        assert_eq!(init(), Ok(()));
+        assert!(INITIALIZED.load(Ordering::Acquire));
+    }
+}
+
 /// Cryptographic functions
 pub mod crypto {
+    // declared first, so that its test macros are in scope for box_ and sign
+    #[cfg(any(feature = "box", feature = "sign"))]
+    #[allow(dead_code)] // each key type only uses its own object identifier
+    #[macro_use]
+    mod pkcs8;
+    #[cfg(feature = "aead")]
     pub mod aead;
+    #[cfg(feature = "auth")]
     pub mod auth;
+    #[cfg(feature = "box")]
     pub mod box_;
+    #[cfg(feature = "channel")]
+    pub mod channel;
+    #[cfg(feature = "core")]
+    pub mod core;
+    #[cfg(feature = "envelope")]
+    pub mod envelope;
+    #[cfg(feature = "generichash")]
     pub mod generichash;
+    #[cfg(feature = "hash")]
     pub mod hash;
+    #[cfg(feature = "kdf")]
     pub mod kdf;
+    #[cfg(feature = "kx")]
     pub mod kx;
+    #[cfg(feature = "merkle")]
+    pub mod merkle;
+    #[allow(dead_code)] // unused when no enabled primitive takes a nonce
     mod nonce;
+    #[cfg(feature = "onetimeauth")]
     pub mod onetimeauth;
+    #[cfg(feature = "otp")]
+    pub mod otp;
+    #[cfg(feature = "pwbox")]
+    pub mod pwbox;
+    #[cfg(feature = "pwhash")]
     pub mod pwhash;
+    #[cfg(feature = "scalarmult")]
     pub mod scalarmult;
+    #[cfg(feature = "sealedbox")]
     pub mod sealedbox;
+    #[cfg(feature = "secretbox")]
     pub mod secretbox;
+    #[cfg(feature = "secretstream")]
     pub mod secretstream;
+    #[cfg(feature = "shorthash")]
     pub mod shorthash;
+    #[cfg(feature = "sign")]
     pub mod sign;
+    #[cfg(feature = "signedseal")]
+    pub mod signedseal;
+    #[cfg(feature = "stream")]
     pub mod stream;
     pub mod verify;
 }
@@ -23,6 +23,43 @@ macro_rules! newtype_from_slice (($newtype:ident, $len:expr) => (
     }
     ));
 
+macro_rules! newtype_from_hex (($newtype:ident, $len:expr) => (
+    /// `from_hex()` creates an object from a hexadecimal string
+    ///
+    /// The string is decoded directly into the object using libsodium's
+    /// constant-time `sodium_hex2bin()`; both lower and upper case digits are
+    /// accepted.
+    ///
+    /// This function will fail and return `Err(Error::InvalidLength)` if the
+    /// string does not encode exactly as many bytes as the object holds, or
+    /// `Err(Error::InvalidEncoding)` if it contains characters not in
+    /// [0-9a-fA-F]. Any partially decoded bytes are zeroed out before
+    /// returning.
+    pub fn from_hex(hex: &str) -> Result<$newtype, ::Error> {
+        if hex.len() != 2 * $len {
+            return Err(::Error::InvalidLength);
+        }
+        // `n` is zeroed out on drop if decoding fails.
+        let mut n = $newtype([0; $len]);
+        let mut bin_len = 0;
+        let rc = unsafe {
+            ::ffi::sodium_hex2bin(
+                n.0.as_mut_ptr(),
+                $len,
+                hex.as_ptr() as *const _,
+                hex.len(),
+                ::std::ptr::null(),
+                &mut bin_len,
+                ::std::ptr::null_mut(),
+            )
+        };
+        if rc != 0 || bin_len != $len {
+            return Err(::Error::InvalidEncoding);
+        }
+        Ok(n)
+    }
+    ));
+
 macro_rules! newtype_traits (($newtype:ident, $len:expr) => (
     impl ::std::cmp::PartialEq for $newtype {
         fn eq(&self, other: &$newtype) -> bool {
@@ -32,12 +69,27 @@ macro_rules! newtype_traits (($newtype:ident, $len:expr) => (
     }
     impl ::std::cmp::Eq for $newtype {}
 
+    #[cfg(feature = "subtle")]
+    impl ::subtle::ConstantTimeEq for $newtype {
+        fn ct_eq(&self, other: &$newtype) -> ::subtle::Choice {
+            use utils::memcmp;
+            ::subtle::Choice::from(memcmp(&self.0, &other.0) as u8)
+        }
+    }
+
     #[cfg(feature = "serde")]
     impl ::serde::Serialize for $newtype {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
             where S: ::serde::Serializer
         {
-            serializer.serialize_bytes(&self.as_ref())
+            if serializer.is_human_readable() {
+                let mut buf = [0u8; 2 * $len + 1];
+                let res = serializer.serialize_str(::hex::encode_to(&self.0, &mut buf));
+                ::utils::memzero(&mut buf);
+                res
+            } else {
+                serializer.serialize_bytes(&self.as_ref())
+            }
         }
     }
 
@@ -50,17 +102,25 @@ macro_rules! newtype_traits (($newtype:ident, $len:expr) => (
             impl<'de> ::serde::de::Visitor<'de> for NewtypeVisitor {
                 type Value = $newtype;
                 fn expecting(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-                    write!(formatter, stringify!($newtype))
+                    write!(formatter, "{} ({} bytes, or hex)", stringify!($newtype), $len)
                 }
                 fn visit_seq<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
                     where V: ::serde::de::SeqAccess<'de>
                 {
                     let mut res = $newtype([0; $len]);
-                    for r in res.0.iter_mut() {
-                        if let Some(value) = visitor.next_element()? {
-                            *r = value;
+                    for (i, r) in res.0.iter_mut().enumerate() {
+                        match visitor.next_element()? {
+                            Some(value) => *r = value,
+                            None => return Err(::serde::de::Error::invalid_length(i, &self)),
                         }
                     }
+                    let mut len = $len;
+                    while visitor.next_element::<u8>()?.is_some() {
+                        len += 1;
+                    }
+                    if len != $len {
+                        return Err(::serde::de::Error::invalid_length(len, &self));
+                    }
                     Ok(res)
                 }
                 fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
@@ -68,8 +128,38 @@ macro_rules! newtype_traits (($newtype:ident, $len:expr) => (
                 {
                     $newtype::from_slice(v).ok_or(::serde::de::Error::invalid_length(v.len(), &self))
                 }
+                fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                    where E: ::serde::de::Error
+                {
+                    // `res` is zeroed out on drop if decoding fails.
+                    let mut res = $newtype([0; $len]);
+                    match ::hex::decode_to(v.as_bytes(), &mut res.0) {
+                        Ok(len) if len == $len => Ok(res),
+                        Ok(len) => Err(::serde::de::Error::invalid_length(len, &self)),
+                        // Do not echo the input, it may be a secret key.
+                        Err(_) => Err(::serde::de::Error::invalid_value(
+                            ::serde::de::Unexpected::Other("invalid hex string"), &self)),
+                    }
+                }
+            }
+            // Human-readable formats get a hex string, but arrays of bytes
+            // written by earlier versions are still accepted.
+            if deserializer.is_human_readable() {
+                deserializer.deserialize_any(NewtypeVisitor)
+            } else {
+                deserializer.deserialize_bytes(NewtypeVisitor)
             }
-            deserializer.deserialize_bytes(NewtypeVisitor)
+        }
+    }
+
+    /// Creates an object from a byte slice, like `from_slice()`.
+    ///
+    /// This fails with `Error::InvalidLength` if the length of the
+    /// byte-slice isn't equal to the length of the object.
+    impl<'a> ::std::convert::TryFrom<&'a [u8]> for $newtype {
+        type Error = ::Error;
+        fn try_from(bs: &'a [u8]) -> Result<$newtype, ::Error> {
+            $newtype::from_slice(bs).ok_or(::Error::InvalidLength)
         }
     }
 
@@ -130,7 +220,13 @@ macro_rules! newtype_traits (($newtype:ident, $len:expr) => (
     }
     ));
 
-macro_rules! public_newtype_traits (($newtype:ident) => (
+macro_rules! public_newtype_traits (($newtype:ident, $len:expr) => (
+    impl ::std::borrow::Borrow<[u8]> for $newtype {
+        #[inline]
+        fn borrow(&self) -> &[u8] {
+            &self.0
+        }
+    }
     impl ::std::cmp::PartialOrd for $newtype {
         #[inline]
         fn partial_cmp(&self,
@@ -165,6 +261,37 @@ macro_rules! public_newtype_traits (($newtype:ident) => (
             ::std::hash::Hash::hash(self.as_ref(), state)
         }
     }
+    /// Formats the object as a lower case hexadecimal string.
+    impl ::std::fmt::LowerHex for $newtype {
+        fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+            let mut buf = [0u8; 2 * $len + 1];
+            formatter.write_str(::hex::encode_to(&self.0, &mut buf))
+        }
+    }
+    /// Formats the object as a lower case hexadecimal string.
+    impl ::std::fmt::Display for $newtype {
+        fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+            ::std::fmt::LowerHex::fmt(self, formatter)
+        }
+    }
+    /// Parses an object from a hexadecimal string, in lower or upper case.
+    ///
+    /// This fails with `Error::InvalidLength` if the string does not encode
+    /// exactly as many bytes as the object holds, and `Error::InvalidEncoding`
+    /// if it contains characters not in [0-9a-fA-F].
+    impl ::std::str::FromStr for $newtype {
+        type Err = ::Error;
+        fn from_str(hex: &str) -> Result<$newtype, ::Error> {
+            if hex.len() != 2 * $len {
+                return Err(::Error::InvalidLength);
+            }
+            let mut n = $newtype([0; $len]);
+            match ::hex::decode_to(hex.as_bytes(), &mut n.0) {
+                Ok(len) if len == $len => Ok(n),
+                _ => Err(::Error::InvalidEncoding),
+            }
+        }
+    }
     ));
 
 /// Macro used for generating newtypes of byte-arrays
@@ -208,6 +335,24 @@ macro_rules! new_type {
         newtype_traits!($name, $bytes);
         impl $name {
             newtype_from_slice!($name, $bytes);
+            newtype_from_hex!($name, $bytes);
+
+            /// `expose_bytes()` returns the secret bytes, e.g. to pass them to
+            /// another library.
+            ///
+            /// Take care not to copy them somewhere that is not zeroed out
+            /// after use.
+            pub fn expose_bytes(&self) -> &[u8; $bytes] {
+                &self.0
+            }
+
+            /// `into_guarded()` moves the secret into guarded memory allocated
+            /// with `sodium_malloc()`, and wipes the original.
+            ///
+            /// It returns `Err(Error::OutOfMemory)` if the allocation fails.
+            pub fn into_guarded(self) -> Result<::guarded::SecretBox<[u8; $bytes]>, ::Error> {
+                ::guarded::SecretBox::from_ref(&self.0)
+            }
         }
         impl Drop for $name {
             fn drop(&mut self) {
@@ -215,6 +360,15 @@ macro_rules! new_type {
                 memzero(&mut self.0);
             }
         }
+        #[cfg(feature = "zeroize")]
+        impl ::zeroize::Zeroize for $name {
+            fn zeroize(&mut self) {
+                use utils::memzero;
+                memzero(&mut self.0);
+            }
+        }
+        #[cfg(feature = "zeroize")]
+        impl ::zeroize::ZeroizeOnDrop for $name {}
         impl ::std::fmt::Debug for $name {
             fn fmt(&self,
                    formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
@@ -232,14 +386,18 @@ macro_rules! new_type {
         pub struct $name(pub [u8; $bytes]);
         newtype_clone!($name);
         newtype_traits!($name, $bytes);
-        public_newtype_traits!($name);
+        public_newtype_traits!($name, $bytes);
         impl $name {
             newtype_from_slice!($name, $bytes);
         }
         impl ::std::fmt::Debug for $name {
             fn fmt(&self,
                    formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-                write!(formatter, "{}({:?})", stringify!($name), self.as_ref())
+                write!(formatter, "{}(", stringify!($name))?;
+                for b in self.as_ref() {
+                    write!(formatter, "{:02x}", b)?;
+                }
+                write!(formatter, ")")
             }
         }
         );
@@ -252,7 +410,7 @@ macro_rules! new_type {
         pub struct $name(pub [u8; $bytes]);
         newtype_clone!($name);
         newtype_traits!($name, $bytes);
-        public_newtype_traits!($name);
+        public_newtype_traits!($name, $bytes);
         impl $name {
             newtype_from_slice!($name, $bytes);
 
@@ -281,6 +439,29 @@ macro_rules! new_type {
                 increment_le(&mut self.0);
             }
 
+            /// `add_le()` treats the nonce as an unsigned little-endian number and
+            /// returns a version of it advanced by `n`, e.g. to skip the nonces of
+            /// `n` messages in a counter-based scheme.
+            ///
+            /// WARNING: like `increment_le()`, this wraps around silently on overflow.
+            /// It is the caller's responsibility that no nonce value is used twice.
+            pub fn add_le(&self, n: u64) -> $name {
+                let mut res = *self;
+                res.add_le_inplace(n);
+                res
+            }
+
+            /// `add_le_inplace()` advances the nonce by `n` in place, with the same
+            /// caveats as `add_le()`.
+            pub fn add_le_inplace(&mut self, n: u64) {
+                use utils::add_le;
+                let mut y = [0u8; $bytes];
+                let n = n.to_le_bytes();
+                let len = ::std::cmp::min($bytes, n.len());
+                y[..len].copy_from_slice(&n[..len]);
+                add_le(&mut self.0, &y).unwrap();
+            }
+
         }
         impl crate::crypto::nonce::Nonce for $name {
             type Bytes = [u8; $bytes];
@@ -291,7 +472,11 @@ macro_rules! new_type {
         impl ::std::fmt::Debug for $name {
             fn fmt(&self,
                    formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-                write!(formatter, "{}({:?})", stringify!($name), self.as_ref())
+                write!(formatter, "{}(", stringify!($name))?;
+                for b in self.as_ref() {
+                    write!(formatter, "{:02x}", b)?;
+                }
+                write!(formatter, ")")
             }
         }
         );
@@ -18,13 +18,20 @@
 //! side channels for a given length & <block size mask> value.
 
 use ffi;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use Error;
 
 /// The `pad()` function adds padding data to a buffer buf whose original size is `unpadded_buflen`
 /// in order to extend its total length to a multiple of blocksize.
 ///
-/// The function returns `Err(())` if the padded buffer length would exceed `max_buflen`, or if the
-/// block size is 0. It returns a result containing the new padded length upon success.
-pub fn pad(buf: &mut [u8], unpadded_buflen: usize, blocksize: usize) -> Result<usize, ()> {
+/// The function returns `Err(Error::InvalidLength)` if the padded buffer length would exceed
+/// `max_buflen`, or `Err(Error::InvalidParameter)` if the block size is 0. It returns a result
+/// containing the new padded length upon success.
+pub fn pad(buf: &mut [u8], unpadded_buflen: usize, blocksize: usize) -> Result<usize, Error> {
+    if blocksize == 0 {
+        return Err(Error::InvalidParameter);
+    }
     let mut padded_buflen_p: usize = 0;
     unsafe {
         if 0 == ffi::sodium_pad(
@@ -36,14 +43,20 @@ pub fn pad(buf: &mut [u8], unpadded_buflen: usize, blocksize: usize) -> Result<u
         ) {
             Ok(padded_buflen_p)
         } else {
-            Err(())
+            Err(Error::InvalidLength)
         }
     }
 }
 
 /// The `unpad()` function computes the original, unpadded length of a message previously padded
 /// using [`pad()`]. The original length is returned upon success.
-pub fn unpad(buf: &[u8], padded_buflen: usize, blocksize: usize) -> Result<usize, ()> {
+///
+/// The function returns `Err(Error::InvalidEncoding)` if the padding is invalid, or
+/// `Err(Error::InvalidParameter)` if the block size is 0.
+pub fn unpad(buf: &[u8], padded_buflen: usize, blocksize: usize) -> Result<usize, Error> {
+    if blocksize == 0 {
+        return Err(Error::InvalidParameter);
+    }
     let mut unpadded_buflen_p: usize = 0;
     unsafe {
         if 0 == ffi::sodium_unpad(
@@ -54,11 +67,57 @@ pub fn unpad(buf: &[u8], padded_buflen: usize, blocksize: usize) -> Result<usize
         ) {
             Ok(unpadded_buflen_p)
         } else {
-            Err(())
+            Err(Error::InvalidEncoding)
+        }
+    }
+}
+
+/// `pad_vec()` pads the message in `buf` in place, growing the vector so that
+/// its length becomes the next multiple of `blocksize`. At least one byte of
+/// padding is always added.
+///
+/// The function fails like [`pad()`], leaving `buf` unchanged, if the block
+/// size is 0 or the padded length would overflow.
+///
+/// # Example
+/// ```
+/// use sodiumoxide::padding;
+///
+/// let mut buf = b"some data".to_vec();
+/// padding::pad_vec(&mut buf, 16).unwrap();
+/// assert_eq!(buf.len(), 16);
+/// padding::unpad_vec(&mut buf, 16).unwrap();
+/// assert_eq!(buf, b"some data");
+/// ```
+pub fn pad_vec(buf: &mut Vec<u8>, blocksize: usize) -> Result<(), Error> {
+    let unpadded_buflen = buf.len();
+    let max_buflen = unpadded_buflen
+        .checked_add(blocksize)
+        .ok_or(Error::InvalidLength)?;
+    buf.resize(max_buflen, 0);
+    match pad(buf, unpadded_buflen, blocksize) {
+        Ok(padded_buflen) => {
+            buf.truncate(padded_buflen);
+            Ok(())
+        }
+        Err(e) => {
+            buf.truncate(unpadded_buflen);
+            Err(e)
         }
     }
 }
 
+/// `unpad_vec()` removes the padding added by [`pad_vec()`] from `buf`,
+/// truncating the vector to the original message length.
+///
+/// The function fails like [`unpad()`], leaving `buf` unchanged, if the
+/// padding is invalid for the given block size.
+pub fn unpad_vec(buf: &mut Vec<u8>, blocksize: usize) -> Result<(), Error> {
+    let unpadded_buflen = unpad(buf, buf.len(), blocksize)?;
+    buf.truncate(unpadded_buflen);
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -95,7 +154,41 @@ mod test {
     pub fn test_on_bad_length() {
         const BUF_LEN: usize = 32;
         let mut buf = vec![0u8; BUF_LEN];
-        assert_eq!(Err(()), pad(&mut buf, BUF_LEN, BUF_LEN));
-        assert_eq!(Err(()), unpad(&buf, BUF_LEN, BUF_LEN));
+        assert_eq!(Err(Error::InvalidLength), pad(&mut buf, BUF_LEN, BUF_LEN));
+        assert_eq!(Err(Error::InvalidEncoding), unpad(&buf, BUF_LEN, BUF_LEN));
+        assert_eq!(Err(Error::InvalidParameter), unpad(&buf, BUF_LEN, 0));
+    }
+
+    #[test]
+    pub fn test_pad_vec_unpad_vec() {
+        use randombytes::randombytes;
+        for blocksize in 1..40usize {
+            for len in 0..100usize {
+                let m = randombytes(len);
+                let mut buf = m.clone();
+                pad_vec(&mut buf, blocksize).unwrap();
+                assert_eq!(buf.len() % blocksize, 0);
+                assert!(buf.len() > len && buf.len() <= len + blocksize);
+                assert_eq!(Ok(len), unpad(&buf, buf.len(), blocksize));
+                unpad_vec(&mut buf, blocksize).unwrap();
+                assert_eq!(buf, m);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_pad_vec_bad_blocksize() {
+        let mut buf = b"some data".to_vec();
+        assert_eq!(Err(Error::InvalidParameter), pad_vec(&mut buf, 0));
+        assert_eq!(buf, b"some data");
+    }
+
+    #[test]
+    pub fn test_unpad_vec_invalid() {
+        let mut buf = vec![0u8; 32];
+        assert_eq!(Err(Error::InvalidEncoding), unpad_vec(&mut buf, 16));
+        assert_eq!(buf, vec![0u8; 32]);
+        let mut empty = Vec::new();
+        assert_eq!(Err(Error::InvalidEncoding), unpad_vec(&mut empty, 16));
     }
 }
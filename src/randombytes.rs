@@ -1,8 +1,15 @@
 //! Cryptographic random number generation.
 
 use ffi;
+use libc::{c_char, c_void};
 #[cfg(not(feature = "std"))]
 use prelude::*;
+use std::mem;
+use std::ptr;
+use std::slice;
+use std::str;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use Error;
 
 /// The number of seed bytes to use for the deterministic RNG functions
 /// [`randombytes_buf_deterministic()`] and
@@ -15,6 +22,7 @@ pub const SEEDBYTES: usize = ffi::randombytes_SEEDBYTES as usize;
 /// called `sodiumoxide::init()` once before using any other function
 /// from sodiumoxide.
 pub fn randombytes(size: usize) -> Vec<u8> {
+    ::ensure_init();
     unsafe {
         let mut buf = vec![0u8; size];
         ffi::randombytes_buf(buf.as_mut_ptr() as *mut _, size);
@@ -28,6 +36,7 @@ pub fn randombytes(size: usize) -> Vec<u8> {
 /// called `sodiumoxide::init()` once before using any other function
 /// from sodiumoxide.
 pub fn randombytes_into(buf: &mut [u8]) {
+    ::ensure_init();
     unsafe {
         ffi::randombytes_buf(buf.as_mut_ptr() as *mut _, buf.len());
     }
@@ -43,9 +52,269 @@ pub fn randombytes_into(buf: &mut [u8]) {
 /// called `sodiumoxide::init()` once before using any other function
 /// from sodiumoxide.
 pub fn randombytes_uniform(upper_bound: u32) -> u32 {
+    ::ensure_init();
     unsafe { ffi::randombytes_uniform(upper_bound) }
 }
 
+/// `uniform()` returns an unpredictable value between 0 and `upper_bound`
+/// (excluded), e.g. an index, a port number or a die roll. It is a shorter
+/// name for [`randombytes_uniform()`] and, unlike `randombytes() % n`, it
+/// does not favour the low end of the range. An `upper_bound` < 2 leaves only
+/// a single element to be chosen, namely 0.
+///
+/// THREAD SAFETY: `uniform()` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+pub fn uniform(upper_bound: u32) -> u32 {
+    randombytes_uniform(upper_bound)
+}
+
+/// `uniform_u64()` returns an unpredictable value between 0 and `upper_bound`
+/// (excluded). Like [`randombytes_uniform()`] it guarantees a uniform
+/// distribution of the possible output values, using rejection sampling
+/// instead of a biased modulo reduction. An `upper_bound` < 2 leaves only a
+/// single element to be chosen, namely 0.
+///
+/// THREAD SAFETY: `uniform_u64()` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+pub fn uniform_u64(upper_bound: u64) -> u64 {
+    if upper_bound < 2 {
+        return 0;
+    }
+    // 2^64 mod upper_bound: values below this would make the low end of the
+    // range more likely, so they are rejected.
+    let min = upper_bound.wrapping_neg() % upper_bound;
+    loop {
+        let mut buf = [0u8; 8];
+        randombytes_into(&mut buf);
+        let r = u64::from_le_bytes(buf);
+        if r >= min {
+            return r % upper_bound;
+        }
+    }
+}
+
+/// `shuffle()` randomly permutes the elements of `xs` in place using the
+/// Fisher-Yates algorithm. Every permutation is equally likely.
+///
+/// THREAD SAFETY: `shuffle()` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+pub fn shuffle<T>(xs: &mut [T]) {
+    for i in (1..xs.len()).rev() {
+        let j = uniform_u64(i as u64 + 1) as usize;
+        xs.swap(i, j);
+    }
+}
+
+/// `choose()` returns a reference to a uniformly chosen element of `xs`, or
+/// `None` if `xs` is empty.
+///
+/// THREAD SAFETY: `choose()` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+pub fn choose<T>(xs: &[T]) -> Option<&T> {
+    if xs.is_empty() {
+        return None;
+    }
+    xs.get(uniform_u64(xs.len() as u64) as usize)
+}
+
+/// The entropy source installed by `set_implementation()`, stored as a
+/// `fn(&mut [u8])` pointer. Null until an implementation is installed.
+static FILL: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+fn custom_fill(buf: &mut [u8]) {
+    let f = FILL.load(Ordering::Acquire);
+    if f.is_null() {
+        // only reachable if libsodium calls us before `FILL` is set, and
+        // there is no sensible value to return
+        abort();
+    }
+    // `FILL` only ever holds pointers stored by `set_implementation()`
+    let f: fn(&mut [u8]) = unsafe { mem::transmute(f) };
+    call_without_unwinding(|| f(buf));
+}
+
+// `custom_fill()` is called from C, so a panic in the user's function must
+// not unwind out of it.
+#[cfg(feature = "std")]
+fn call_without_unwinding<F: FnOnce()>(f: F) {
+    if ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(f)).is_err() {
+        abort();
+    }
+}
+
+#[cfg(not(feature = "std"))]
+fn call_without_unwinding<F: FnOnce()>(f: F) {
+    let guard = AbortOnUnwind;
+    f();
+    mem::forget(guard);
+}
+
+#[cfg(feature = "std")]
+fn abort() -> ! {
+    ::std::process::abort()
+}
+
+#[cfg(not(feature = "std"))]
+fn abort() -> ! {
+    // a panic while unwinding aborts
+    let _guard = AbortOnUnwind;
+    panic!("randombytes implementation failed")
+}
+
+#[cfg(not(feature = "std"))]
+struct AbortOnUnwind;
+
+#[cfg(not(feature = "std"))]
+impl Drop for AbortOnUnwind {
+    fn drop(&mut self) {
+        panic!("panic in a randombytes implementation");
+    }
+}
+
+unsafe extern "C" fn custom_implementation_name() -> *const c_char {
+    b"sodiumoxide\0".as_ptr() as *const c_char
+}
+
+unsafe extern "C" fn custom_random() -> u32 {
+    let mut buf = [0u8; 4];
+    custom_fill(&mut buf);
+    u32::from_le_bytes(buf)
+}
+
+unsafe extern "C" fn custom_buf(buf: *mut c_void, size: usize) {
+    if size > 0 {
+        custom_fill(slice::from_raw_parts_mut(buf as *mut u8, size));
+    }
+}
+
+static CUSTOM_IMPLEMENTATION: ffi::randombytes_implementation = ffi::randombytes_implementation {
+    implementation_name: Some(custom_implementation_name),
+    random: Some(custom_random),
+    // libsodium derives the remaining functions from `random` and `buf`
+    stir: None,
+    uniform: None,
+    buf: Some(custom_buf),
+    close: None,
+};
+
+/// `set_implementation()` replaces the source of randomness used by libsodium,
+/// and thus by every function of this crate, with `fill`. `fill` must fill its
+/// argument entirely with unpredictable bytes, e.g. from a hardware TRNG on
+/// targets without `/dev/urandom`.
+///
+/// It returns `Err(Error::Unsupported)` if [`init()`](::init) has already been
+/// called, since random numbers may have been generated with the default
+/// implementation by then, and `Err(Error::InitFailed)` if libsodium rejects
+/// the implementation. A panic in `fill` aborts the process.
+///
+/// # Safety
+///
+/// libsodium's random number generator is global and not synchronized, so
+/// `set_implementation()` must not run concurrently with any other function
+/// of this crate, including `init()`. With the `auto-init` feature, that
+/// means it must be called before any other function of this crate. A weak
+/// `fill` function compromises all the keys and nonces generated by this
+/// crate.
+///
+/// # Example
+/// ```no_run
+/// fn hardware_trng(buf: &mut [u8]) {
+///     // read from the device ...
+/// #   let _ = buf;
+/// }
+///
+/// unsafe {
+///     sodiumoxide::randombytes::set_implementation(hardware_trng).unwrap();
+/// }
+/// sodiumoxide::init().unwrap();
+/// ```
+pub unsafe fn set_implementation(fill: fn(&mut [u8])) -> Result<(), Error> {
+    if ::INITIALIZED.load(Ordering::Acquire) {
+        return Err(Error::Unsupported);
+    }
+    FILL.store(fill as *mut (), Ordering::Release);
+    let ret = ffi::randombytes_set_implementation(
+        &CUSTOM_IMPLEMENTATION as *const _ as *mut ffi::randombytes_implementation,
+    );
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(Error::InitFailed)
+    }
+}
+
+/// `implementation_name()` returns the name of the random number generator
+/// currently used by libsodium, e.g. `"sysrandom"`, or `"sodiumoxide"` after
+/// `set_implementation()` has been called.
+pub fn implementation_name() -> &'static str {
+    unsafe {
+        let name = ffi::randombytes_implementation_name();
+        let len = ::libc::strlen(name);
+        str::from_utf8(slice::from_raw_parts(name as *const u8, len)).unwrap_or("")
+    }
+}
+
+/// `SodiumRng` is a handle to libsodium's cryptographically secure random
+/// number generator, for use with libraries that are generic over
+/// [`rand_core::RngCore`](https://docs.rs/rand_core/0.6/rand_core/trait.RngCore.html).
+///
+/// It holds no state: every instance draws from the same generator as
+/// [`randombytes()`], including an implementation installed with
+/// [`set_implementation()`].
+///
+/// This type is only available with the `rand_core` feature.
+///
+/// THREAD SAFETY: `SodiumRng` is thread-safe provided that you have
+/// called `sodiumoxide::init()` once before using any other function
+/// from sodiumoxide.
+///
+/// # Example
+/// ```
+/// # extern crate rand_core;
+/// # extern crate sodiumoxide;
+/// use rand_core::RngCore;
+/// use sodiumoxide::randombytes::SodiumRng;
+///
+/// # fn main() {
+/// let mut buf = [0u8; 16];
+/// SodiumRng.fill_bytes(&mut buf);
+/// let _ = SodiumRng.next_u64();
+/// # }
+/// ```
+#[cfg(feature = "rand_core")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SodiumRng;
+
+#[cfg(feature = "rand_core")]
+impl ::rand_core::RngCore for SodiumRng {
+    fn next_u32(&mut self) -> u32 {
+        ::ensure_init();
+        unsafe { ffi::randombytes_random() }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0u8; 8];
+        randombytes_into(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        randombytes_into(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ::rand_core::Error> {
+        randombytes_into(dest);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rand_core")]
+impl ::rand_core::CryptoRng for SodiumRng {}
+
 new_type! {
     /// `Seed` bytes for the deterministic random functions
     secret Seed(SEEDBYTES);
@@ -68,6 +337,21 @@ new_type! {
 ///
 /// This function is mainly useful for writing tests, and was introduced in libsodium 1.0.12. Under
 /// the hood, it uses the ChaCha20 stream cipher. Up to 256 GB can be produced with a single seed.
+///
+/// The output only depends on the seed, not on the platform, and requesting fewer bytes returns a
+/// prefix of the longer output. It is only stable across libsodium 1.0.16 and later: earlier
+/// versions used the original ChaCha20 rather than the IETF variant and produce a different
+/// sequence for the same seed.
+///
+/// # Example
+/// ```
+/// use sodiumoxide::randombytes::{randombytes_buf_deterministic, Seed, SEEDBYTES};
+///
+/// let seed = Seed([7; SEEDBYTES]);
+/// let a = randombytes_buf_deterministic(64, &seed);
+/// let b = randombytes_buf_deterministic(16, &seed);
+/// assert_eq!(&a[..16], &b[..]);
+/// ```
 pub fn randombytes_buf_deterministic(size: usize, seed: &Seed) -> Vec<u8> {
     unsafe {
         let mut buf = vec![0u8; size];
@@ -112,6 +396,177 @@ mod test {
         assert!(randombytes_uniform(7) < 7);
     }
 
+    #[test]
+    fn test_uniform() {
+        ::init().unwrap();
+
+        assert_eq!(uniform(0), 0);
+        assert_eq!(uniform(1), 0);
+        for _ in 0..1000 {
+            assert!(uniform(7) < 7);
+            assert!(uniform(!0) < !0);
+        }
+
+        const BUCKETS: usize = 6;
+        const TRIALS: usize = 60_000;
+        let mut counts = [0usize; BUCKETS];
+        for _ in 0..TRIALS {
+            counts[uniform(BUCKETS as u32) as usize] += 1;
+        }
+        // 5 degrees of freedom; a chi-square above 30 has p < 1e-5.
+        assert!(chi_square(&counts, TRIALS) < 30.0);
+    }
+
+    #[test]
+    fn test_uniform_u64_small_bounds() {
+        ::init().unwrap();
+
+        assert_eq!(uniform_u64(0), 0);
+        assert_eq!(uniform_u64(1), 0);
+        for _ in 0..1000 {
+            assert!(uniform_u64(7) < 7);
+            assert!(uniform_u64(!0) < !0);
+            assert!(uniform_u64((1 << 63) + 1) < (1 << 63) + 1);
+        }
+    }
+
+    #[test]
+    fn test_uniform_u64_distribution() {
+        ::init().unwrap();
+
+        const BUCKETS: usize = 10;
+        const TRIALS: usize = 100_000;
+        let mut counts = [0usize; BUCKETS];
+        for _ in 0..TRIALS {
+            counts[uniform_u64(BUCKETS as u64) as usize] += 1;
+        }
+        // 9 degrees of freedom; a chi-square above 40 has p < 1e-5.
+        assert!(chi_square(&counts, TRIALS) < 40.0);
+    }
+
+    #[test]
+    fn test_shuffle_edge_cases() {
+        ::init().unwrap();
+
+        let mut empty: [u8; 0] = [];
+        shuffle(&mut empty);
+        let mut single = [42u8];
+        shuffle(&mut single);
+        assert_eq!(single, [42]);
+
+        let mut xs: Vec<usize> = (0..100).collect();
+        shuffle(&mut xs);
+        xs.sort();
+        assert_eq!(xs, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_shuffle_distribution() {
+        ::init().unwrap();
+
+        // All 24 permutations of a 4-element slice should be equally likely.
+        // Permutations are indexed by reading them as base-4 numbers.
+        const TRIALS: usize = 48_000;
+        let mut counts = [0usize; 256];
+        for _ in 0..TRIALS {
+            let mut xs = [0usize, 1, 2, 3];
+            shuffle(&mut xs);
+            counts[xs.iter().fold(0, |acc, x| acc * 4 + x)] += 1;
+        }
+        let counts: Vec<usize> = counts.iter().cloned().filter(|&c| c != 0).collect();
+        assert_eq!(counts.len(), 24);
+        // 23 degrees of freedom; a chi-square above 70 has p < 1e-6.
+        assert!(chi_square(&counts, TRIALS) < 70.0);
+    }
+
+    #[test]
+    fn test_choose() {
+        ::init().unwrap();
+
+        let empty: [u8; 0] = [];
+        assert_eq!(choose(&empty), None);
+        assert_eq!(choose(&[42u8]), Some(&42));
+
+        const TRIALS: usize = 40_000;
+        let xs = [0usize, 1, 2, 3];
+        let mut counts = [0usize; 4];
+        for _ in 0..TRIALS {
+            counts[*choose(&xs).unwrap()] += 1;
+        }
+        // 3 degrees of freedom; a chi-square above 30 has p < 1e-5.
+        assert!(chi_square(&counts, TRIALS) < 30.0);
+    }
+
+    fn chi_square(counts: &[usize], trials: usize) -> f64 {
+        let expected = trials as f64 / counts.len() as f64;
+        counts
+            .iter()
+            .map(|&c| {
+                let d = c as f64 - expected;
+                d * d / expected
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_custom_implementation() {
+        use std::sync::atomic::AtomicUsize;
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        fn counting_fill(buf: &mut [u8]) {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            for (i, b) in buf.iter_mut().enumerate() {
+                *b = i as u8 + 1;
+            }
+        }
+
+        // Only exercise the callbacks: installing the implementation would
+        // make every other test in this binary use `counting_fill`.
+        FILL.store(counting_fill as *mut (), Ordering::Release);
+        let mut buf = [0u8; 64];
+        unsafe {
+            custom_buf(buf.as_mut_ptr() as *mut c_void, buf.len());
+            assert_eq!(custom_random(), u32::from_le_bytes([1, 2, 3, 4]));
+        }
+        assert_eq!(buf[0], 1);
+        assert_eq!(buf[63], 64);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_set_implementation_after_init() {
+        fn fill(_: &mut [u8]) {
+            unreachable!()
+        }
+
+        ::init().unwrap();
+        assert_eq!(unsafe { set_implementation(fill) }, Err(Error::Unsupported));
+        assert_ne!(implementation_name(), "sodiumoxide");
+    }
+
+    #[cfg(feature = "rand_core")]
+    #[test]
+    fn test_sodium_rng() {
+        use rand_core::{CryptoRng, RngCore};
+        ::init().unwrap();
+
+        fn generic<R: RngCore + CryptoRng>(rng: &mut R) -> [u8; 32] {
+            let mut buf = [0u8; 32];
+            rng.fill_bytes(&mut buf);
+            buf
+        }
+        let mut rng = SodiumRng;
+        assert_ne!(generic(&mut rng), generic(&mut rng));
+        assert_ne!(
+            (rng.next_u64(), rng.next_u64()),
+            (rng.next_u64(), rng.next_u64())
+        );
+        let mut buf = [0u8; 64];
+        rng.try_fill_bytes(&mut buf).unwrap();
+        assert!(buf.iter().any(|b| *b != 0));
+        assert!((0..64).any(|_| rng.next_u32() != rng.next_u32()));
+    }
+
     #[test]
     fn test_randombytes_buf_deterministic() {
         ::init().unwrap();
@@ -122,6 +577,31 @@ mod test {
         assert_eq!(res_1, res_2);
     }
 
+    #[test]
+    fn test_randombytes_buf_deterministic_vector() {
+        // regression vectors computed with libsodium 1.0.18
+        use hex;
+        ::init().unwrap();
+
+        let seed = Seed([0u8; SEEDBYTES]);
+        assert_eq!(
+            hex::encode(randombytes_buf_deterministic(32, &seed)),
+            "a11f8f12d0876f736d2d8fd26e14c2de88f163e3271710c676c7384cb3621279"
+        );
+
+        let mut seed = Seed([0u8; SEEDBYTES]);
+        for (i, b) in seed.0.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        let mut buf = [0u8; 32];
+        randombytes_buf_deterministic_into(&mut buf, &seed);
+        assert_eq!(
+            hex::encode(buf),
+            "0d8e6cc68715648926732e7ea73250cfaf2d58422083904c841a8ba33b986111"
+        );
+        assert_eq!(&randombytes_buf_deterministic(1000, &seed)[..32], &buf[..]);
+    }
+
     #[test]
     fn test_randombytes_buf_deterministic_into() {
         ::init().unwrap();
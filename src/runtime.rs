@@ -0,0 +1,81 @@
+//! Runtime CPU feature detection
+//!
+//! These functions report the CPU features that libsodium detected on the
+//! current machine, and thus which accelerated implementations it uses. They
+//! can be used to choose between primitives, e.g. to only use AES-GCM when
+//! `has_aesni()` and `has_pclmul()` are both true, or to print diagnostics.
+//!
+//! CPU features are only detected by [`init()`](../fn.init.html), so every
+//! function of this module calls it first, and returns `false` if
+//! initialization fails.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::runtime;
+//!
+//! if runtime::has_aesni() && runtime::has_pclmul() {
+//!     // AES-GCM is available and fast
+//! } else {
+//!     // fall back to ChaCha20-Poly1305
+//! }
+//! ```
+
+use ffi;
+
+macro_rules! runtime_has (($name:ident, $ffi_name:ident, $feature:expr) => (
+    #[doc = "Returns `true` if the CPU supports "]
+    #[doc = $feature]
+    #[doc = "."]
+    pub fn $name() -> bool {
+        ::init().is_ok() && unsafe { ffi::$ffi_name() == 1 }
+    }
+));
+
+runtime_has!(has_neon, sodium_runtime_has_neon, "ARM NEON");
+runtime_has!(has_sse2, sodium_runtime_has_sse2, "SSE2");
+runtime_has!(has_sse3, sodium_runtime_has_sse3, "SSE3");
+runtime_has!(has_ssse3, sodium_runtime_has_ssse3, "SSSE3");
+runtime_has!(has_sse41, sodium_runtime_has_sse41, "SSE4.1");
+runtime_has!(has_avx, sodium_runtime_has_avx, "AVX");
+runtime_has!(has_avx2, sodium_runtime_has_avx2, "AVX2");
+runtime_has!(has_avx512f, sodium_runtime_has_avx512f, "AVX-512F");
+runtime_has!(
+    has_pclmul,
+    sodium_runtime_has_pclmul,
+    "the PCLMULQDQ instruction"
+);
+runtime_has!(
+    has_aesni,
+    sodium_runtime_has_aesni,
+    "the AES-NI instruction set"
+);
+runtime_has!(
+    has_rdrand,
+    sodium_runtime_has_rdrand,
+    "the RDRAND instruction"
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+    #[test]
+    fn test_matches_std_detection() {
+        assert_eq!(has_sse2(), is_x86_feature_detected!("sse2"));
+        assert_eq!(has_sse3(), is_x86_feature_detected!("sse3"));
+        assert_eq!(has_ssse3(), is_x86_feature_detected!("ssse3"));
+        assert_eq!(has_sse41(), is_x86_feature_detected!("sse4.1"));
+        assert_eq!(has_aesni(), is_x86_feature_detected!("aes"));
+        assert_eq!(has_pclmul(), is_x86_feature_detected!("pclmulqdq"));
+        assert_eq!(has_rdrand(), is_x86_feature_detected!("rdrand"));
+        assert!(!has_neon());
+    }
+
+    #[cfg(feature = "aead")]
+    #[test]
+    fn test_aes256gcm_availability() {
+        use crypto::aead::aes256gcm;
+        assert_eq!(aes256gcm::is_available(), has_aesni() && has_pclmul());
+    }
+}
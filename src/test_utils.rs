@@ -24,3 +24,22 @@ where
     let decoded_value = Deserialize::deserialize(&mut de).unwrap();
     assert_eq!(value, decoded_value);
 }
+
+// Checks that decoding the JSON array of `len` bytes as a `T` fails
+pub fn rejects_length<T>(len: usize)
+where
+    T: DeserializeOwned,
+{
+    let json = serde_json::to_string(&vec![0u8; len]).unwrap();
+    assert!(serde_json::from_str::<T>(&json).is_err());
+}
+
+// Encodes `value` using JSON
+pub fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+// Decodes a `T` from JSON
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, ()> {
+    serde_json::from_str(json).map_err(|_| ())
+}
@@ -1,5 +1,9 @@
 //! Libsodium utility functions
 use ffi;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use Error;
 
 /// `memzero()` tries to effectively zero out the data in `x` even if
 /// optimizations are being applied to the code.
@@ -30,12 +34,12 @@ pub fn memcmp(x: &[u8], y: &[u8]) -> bool {
 ///
 /// Operating system might limit the amount of memory a process can `mlock()`.
 /// This function can fail if `mlock()` fails to lock the memory.
-pub fn mlock(x: &mut [u8]) -> Result<(), ()> {
+pub fn mlock(x: &mut [u8]) -> Result<(), Error> {
     let ret = unsafe { ffi::sodium_mlock(x.as_mut_ptr() as *mut _, x.len()) };
     if ret == 0 {
         Ok(())
     } else {
-        Err(())
+        Err(Error::OutOfMemory)
     }
 }
 
@@ -43,7 +47,7 @@ pub fn mlock(x: &mut [u8]) -> Result<(), ()> {
 ///
 /// `munlock()` overwrites the region with zeros before unlocking it, so it
 /// doesn't have to be done before calling this function.
-pub fn munlock(x: &mut [u8]) -> Result<(), ()> {
+pub fn munlock(x: &mut [u8]) -> Result<(), Error> {
     let ret = unsafe {
         // sodium_munlock() internally calls sodium_memzero() to clear memory
         // region.
@@ -52,7 +56,75 @@ pub fn munlock(x: &mut [u8]) -> Result<(), ()> {
     if ret == 0 {
         Ok(())
     } else {
-        Err(())
+        Err(Error::OutOfMemory)
+    }
+}
+
+/// `MemLock` keeps a memory region locked with `mlock()` for as long as it
+/// is alive, so that long-lived key material is not swapped to disk.
+///
+/// When a `MemLock` goes out of scope the region is unlocked with `munlock()`,
+/// which also zeroes it out. The locked bytes can be accessed through the
+/// guard, which dereferences to `[u8]`.
+///
+/// # Example
+/// ```
+/// use sodiumoxide::utils::MemLock;
+///
+/// let mut key = [0u8; 32];
+/// {
+///     let mut locked = MemLock::new(&mut key).unwrap();
+///     locked.copy_from_slice(&[0x42; 32]);
+///     // use the key
+///     assert_eq!(locked[0], 0x42);
+/// }
+/// // the key has been zeroed out when the guard was dropped
+/// assert_eq!(key, [0u8; 32]);
+/// ```
+#[must_use]
+pub struct MemLock<'a> {
+    region: &'a mut [u8],
+}
+
+impl<'a> MemLock<'a> {
+    /// `new()` locks the memory region `x` and returns a guard that unlocks
+    /// it again when dropped.
+    ///
+    /// Like `mlock()`, this fails if the operating system refuses to lock
+    /// the region, e.g. because its limit on locked memory has been reached.
+    pub fn new(x: &'a mut [u8]) -> Result<MemLock<'a>, Error> {
+        mlock(x)?;
+        Ok(MemLock { region: x })
+    }
+}
+
+impl<'a> fmt::Debug for MemLock<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        // Hide secrets from debug output.
+        write!(formatter, "MemLock(****)")
+    }
+}
+
+impl<'a> Deref for MemLock<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.region
+    }
+}
+
+impl<'a> DerefMut for MemLock<'a> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.region
+    }
+}
+
+impl<'a> Drop for MemLock<'a> {
+    fn drop(&mut self) {
+        if munlock(self.region).is_err() {
+            // the region could not be unlocked, but it must still be wiped
+            memzero(self.region);
+        }
     }
 }
 
@@ -73,21 +145,56 @@ pub fn increment_le(x: &mut [u8]) {
 /// `add_le()` treats `x` and `y` as unsigned little-endian numbers and adds `y` to `x`
 /// modulo 2^(8*len) in constant time.
 ///
-/// `add_le()` will return Err<()> if the length of `x` is not equal to the length of `y`.
+/// `add_le()` will return `Err(Error::InvalidLength)` if the length of `x` is not equal to the
+/// length of `y`.
 ///
 /// WARNING: When used for incrementing nonces it is the caller's responsibility to ensure
 /// that any given nonce value is used only once.
 /// If the caller does not do that the cryptographic primitives in sodiumoxide
 /// will not uphold any security guarantees (i.e. they may break)
-pub fn add_le(x: &mut [u8], y: &[u8]) -> Result<(), ()> {
+pub fn add_le(x: &mut [u8], y: &[u8]) -> Result<(), Error> {
     if x.len() == y.len() {
         unsafe {
             ffi::sodium_add(x.as_mut_ptr(), y.as_ptr(), x.len());
         }
         Ok(())
     } else {
-        Err(())
+        Err(Error::InvalidLength)
+    }
+}
+
+/// `sub_le()` treats `x` and `y` as unsigned little-endian numbers and subtracts `y` from `x`
+/// modulo 2^(8*len) in constant time.
+///
+/// `sub_le()` will return `Err(Error::InvalidLength)` if the length of `x` is not equal to the
+/// length of `y`.
+pub fn sub_le(x: &mut [u8], y: &[u8]) -> Result<(), Error> {
+    if x.len() == y.len() {
+        unsafe {
+            ffi::sodium_sub(x.as_mut_ptr(), y.as_ptr(), x.len());
+        }
+        Ok(())
+    } else {
+        Err(Error::InvalidLength)
+    }
+}
+
+/// `compare_le()` treats `x` and `y` as unsigned little-endian numbers and compares them in
+/// constant time, e.g. to check that a received nonce is larger than the last one seen.
+///
+/// `compare_le()` will return `Err(Error::InvalidLength)` if the length of `x` is not equal to the
+/// length of `y`.
+pub fn compare_le(x: &[u8], y: &[u8]) -> Result<Ordering, Error> {
+    if x.len() != y.len() {
+        return Err(Error::InvalidLength);
     }
+    let r = unsafe { ffi::sodium_compare(x.as_ptr(), y.as_ptr(), x.len()) };
+    Ok(r.cmp(&0))
+}
+
+/// `is_zero()` returns `true` if all the bytes of `x` are zero, in constant time.
+pub fn is_zero(x: &[u8]) -> bool {
+    unsafe { ffi::sodium_is_zero(x.as_ptr(), x.len()) == 1 }
 }
 
 #[cfg(test)]
@@ -234,6 +341,62 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_sub_le() {
+        let mut x = [41, 3, 3, 4, 5];
+        let y = [42, 0, 0, 0, 0];
+        assert!(sub_le(&mut x, &y).is_ok());
+        assert_eq!(x, [255, 2, 3, 4, 5]);
+
+        let mut x = [41, 0, 0, 0, 0];
+        assert!(sub_le(&mut x, &y).is_ok());
+        assert_eq!(x, [255, 255, 255, 255, 255]);
+
+        let mut x = [1u8; 4];
+        assert!(sub_le(&mut x, &y).is_err());
+        assert_eq!(x, [1u8; 4]);
+    }
+
+    #[test]
+    fn test_add_sub_le_inverse() {
+        use randombytes::randombytes;
+        for i in 1..64 {
+            let x = randombytes(i);
+            let y = randombytes(i);
+            let mut z = x.clone();
+            add_le(&mut z, &y).unwrap();
+            sub_le(&mut z, &y).unwrap();
+            assert_eq!(x, z);
+        }
+    }
+
+    #[test]
+    fn test_compare_le() {
+        assert_eq!(compare_le(&[1, 2, 3], &[1, 2, 3]), Ok(Ordering::Equal));
+        // the last byte is the most significant one
+        assert_eq!(compare_le(&[255, 2, 3], &[0, 2, 4]), Ok(Ordering::Less));
+        assert_eq!(compare_le(&[0, 2, 4], &[255, 2, 3]), Ok(Ordering::Greater));
+        assert_eq!(compare_le(&[], &[]), Ok(Ordering::Equal));
+        assert_eq!(compare_le(&[1, 2], &[1, 2, 3]), Err(Error::InvalidLength));
+
+        let mut x = [255, 255, 0, 7];
+        let y = x;
+        increment_le(&mut x);
+        assert_eq!(compare_le(&x, &y), Ok(Ordering::Greater));
+        assert_eq!(compare_le(&y, &x), Ok(Ordering::Less));
+    }
+
+    #[test]
+    fn test_is_zero() {
+        assert!(is_zero(&[]));
+        for i in 1..64 {
+            let mut x = vec![0u8; i];
+            assert!(is_zero(&x));
+            x[i - 1] = 1;
+            assert!(!is_zero(&x));
+        }
+    }
+
     #[test]
     fn test_mlock_munlock() {
         #[cfg(not(feature = "std"))]
@@ -247,6 +410,25 @@ mod test {
         assert_ne!(&x, t);
     }
 
+    #[test]
+    fn test_memlock_guard() {
+        let mut x = [0u8; 64];
+        {
+            let mut locked = MemLock::new(&mut x).unwrap();
+            assert_eq!(locked.len(), 64);
+            locked.copy_from_slice(&[0x42; 64]);
+            assert!(locked.iter().all(|b| *b == 0x42));
+        }
+        assert_eq!(&x[..], &[0u8; 64][..]);
+    }
+
+    #[test]
+    fn test_memlock_debug() {
+        let mut x = [0x42u8; 16];
+        let locked = MemLock::new(&mut x).unwrap();
+        assert_eq!(format!("{:?}", locked), "MemLock(****)");
+    }
+
     #[cfg(unix)]
     #[test]
     fn test_mlock_fail() {
@@ -1,9 +1,28 @@
 //! Libsodium version functions
+//!
+//! NOTE: the major and minor numbers are those of the library ABI, not of
+//! the release: libsodium 1.0.18 is library version 10.3.
+//!
+//! # Example
+//! ```
+//! use sodiumoxide::version;
+//!
+//! // refuse to run against a libsodium older than the bindings
+//! version::require_at_least(version::LIBRARY_VERSION_MAJOR, version::LIBRARY_VERSION_MINOR)
+//!     .expect("libsodium is too old");
+//! ```
 
 use ffi;
 use libc;
 use std::slice;
 use std::str;
+use Error;
+
+/// Major library version of the libsodium these bindings were generated for.
+pub const LIBRARY_VERSION_MAJOR: usize = ffi::SODIUM_LIBRARY_VERSION_MAJOR as usize;
+
+/// Minor library version of the libsodium these bindings were generated for.
+pub const LIBRARY_VERSION_MINOR: usize = ffi::SODIUM_LIBRARY_VERSION_MINOR as usize;
 
 /// `version_string()` returns the version string from libsodium.
 pub fn version_string() -> &'static str {
@@ -26,6 +45,19 @@ pub fn version_minor() -> usize {
     unsafe { ffi::sodium_library_version_minor() as usize }
 }
 
+/// `require_at_least()` checks that the libsodium in use, which may be a
+/// shared library chosen at runtime, has at least the library version
+/// `major.minor`.
+///
+/// It returns `Err(Error::Unsupported)` if the library is older.
+pub fn require_at_least(major: usize, minor: usize) -> Result<(), Error> {
+    if (version_major(), version_minor()) >= (major, minor) {
+        Ok(())
+    } else {
+        Err(Error::Unsupported)
+    }
+}
+
 #[cfg(test)]
 mod test {
     #[test]
@@ -33,4 +65,15 @@ mod test {
         use version::version_string;
         assert!(!version_string().is_empty());
     }
+
+    #[test]
+    fn test_require_at_least() {
+        use version::*;
+        let (major, minor) = (version_major(), version_minor());
+        assert_eq!(require_at_least(0, 0), Ok(()));
+        assert_eq!(require_at_least(major, minor), Ok(()));
+        assert_eq!(require_at_least(major - 1, minor + 1), Ok(()));
+        assert_eq!(require_at_least(major, minor + 1), Err(Error::Unsupported));
+        assert_eq!(require_at_least(major + 1, 0), Err(Error::Unsupported));
+    }
 }